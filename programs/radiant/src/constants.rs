@@ -10,6 +10,20 @@ pub const BPS_DENOMINATOR: u64 = 10_000;
 /// Index scale factor (1e18) for compound interest tracking
 pub const INDEX_ONE: u128 = 1_000_000_000_000_000_000; // 10^18
 
+/// Cumulative index level at which refresh_reserve emits a RebaseNeeded alert (100x)
+///
+/// Index growth horizon (continuous compounding):
+/// - 100% APR: 100x after ~4.6 years, 1000x after ~6.9 years
+/// - 20% APR: 100x after ~23 years, 1000x after ~35 years
+/// - 5% APR: 100x after ~92 years, 1000x after ~138 years
+pub const INDEX_REBASE_THRESHOLD: u128 = 100 * INDEX_ONE;
+
+/// Hard cap on cumulative indexes (1000x)
+/// Keeps `amount * index` position math well inside u128 for any realistic amount.
+/// Indexes are clamped here and accrual stops until the reserve is rebased;
+/// instructions that accrue inline keep working.
+pub const MAX_CUMULATIVE_INDEX: u128 = 1_000 * INDEX_ONE;
+
 /// USD value scale factor (1e6) for price calculations
 pub const USD_DECIMALS: u8 = 6;
 pub const USD_SCALE: u128 = 1_000_000; // 10^6
@@ -17,6 +31,19 @@ pub const USD_SCALE: u128 = 1_000_000; // 10^6
 /// Seconds per year (for interest rate calculations)
 pub const SECONDS_PER_YEAR: u64 = 31_536_000; // 365 * 24 * 60 * 60
 
+/// Seconds per day (for interest previews)
+pub const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Maximum index growth applied by a single accrual (10%, scaled by INDEX_ONE)
+///
+/// Each accrual applies simple interest over the elapsed time, so one
+/// refresh after a long idle gap books the whole period in a single step.
+/// Capping the factor bounds the interest any single refresh can book:
+/// e.g. at 100% APR the cap is hit after ~36.5 days without a refresh, and
+/// interest beyond it is forfeited rather than charged at once. Regularly
+/// refreshed reserves never reach it.
+pub const MAX_COMPOUND_FACTOR_PER_REFRESH: u128 = INDEX_ONE / 10;
+
 /// Slots per year (approximate, ~400ms per slot)
 pub const SLOTS_PER_YEAR: u64 = 78_840_000; // 31_536_000 / 0.4
 
@@ -39,6 +66,9 @@ pub const VAULT_SEED: &[u8] = b"vault";
 /// Seed prefix for Reserve fee receiver PDA
 pub const FEE_RECEIVER_SEED: &[u8] = b"fee_receiver";
 
+/// Seed prefix for whitelisted depositor PDA
+pub const WHITELISTED_DEPOSITOR_SEED: &[u8] = b"whitelisted_depositor";
+
 // ============================================================================
 // DEFAULT VALUES
 // ============================================================================
@@ -83,6 +113,10 @@ pub const MAX_OBLIGATION_DEPOSITS: usize = 8;
 /// Maximum number of borrows per obligation
 pub const MAX_OBLIGATION_BORROWS: usize = 8;
 
+/// Maximum remaining accounts accepted by refresh_obligation
+/// Each position passes its reserve, oracle and optional quote oracle
+pub const MAX_REFRESH_REMAINING_ACCOUNTS: usize = 3 * (MAX_OBLIGATION_DEPOSITS + MAX_OBLIGATION_BORROWS);
+
 /// Maximum LTV allowed (95% = 9500 BPS)
 pub const MAX_LTV_BPS: u16 = 9_500;
 
@@ -92,6 +126,15 @@ pub const MAX_LIQUIDATION_THRESHOLD_BPS: u16 = 9_800;
 /// Maximum liquidation bonus (25% = 2500 BPS)
 pub const MAX_LIQUIDATION_BONUS_BPS: u16 = 2_500;
 
+/// Maximum liquidation hysteresis band (5% = 500 BPS of health)
+pub const MAX_LIQUIDATION_HYSTERESIS_BPS: u16 = 500;
+
+/// Maximum borrow capacity buffer (1% = 100 BPS of allowed borrow value)
+pub const MAX_BORROW_CAPACITY_BUFFER_BPS: u16 = 100;
+
+/// Maximum opt-in negative supply rate (10% per year = 1000 BPS)
+pub const MAX_NEGATIVE_SUPPLY_RATE_BPS: u16 = 1_000;
+
 /// Maximum reserve factor (50% = 5000 BPS)
 pub const MAX_RESERVE_FACTOR_BPS: u16 = 5_000;
 
@@ -99,10 +142,23 @@ pub const MAX_RESERVE_FACTOR_BPS: u16 = 5_000;
 /// ~60 seconds at 400ms per slot
 pub const MAX_ORACLE_STALENESS_SLOTS: u64 = 150;
 
+/// Maximum price move between obligation refreshes (10x either direction)
+/// Larger moves are treated as bad oracle data and the refresh is rejected
+pub const MAX_PRICE_MOVE_MULTIPLIER: u64 = 10;
+
+/// Slots since the last refresh after which the price move check is skipped
+/// A genuine move beyond the multiplier blocks refreshes for at most this long
+/// ~10 minutes
+pub const PRICE_MOVE_CHECK_WINDOW_SLOTS: u64 = 1500;
+
 /// Maximum staleness for reserve refresh (slots)
 /// ~10 minutes
 pub const MAX_RESERVE_STALENESS_SLOTS: u64 = 1_500;
 
+/// Maximum staleness for obligation refresh (slots)
+/// ~60 seconds, matching oracle staleness
+pub const MAX_OBLIGATION_STALENESS_SLOTS: u64 = 150;
+
 // ============================================================================
 // HEALTH FACTOR
 // ============================================================================
@@ -114,6 +170,9 @@ pub const HEALTH_FACTOR_ONE: u64 = 10_000;
 /// User cannot borrow if it would drop health below this
 pub const MIN_HEALTH_FACTOR_AFTER_BORROW: u64 = 10_000;
 
+/// Upper bound for a reserve's minimum health factor to borrow (5.0 = 50000)
+pub const MAX_RESERVE_MIN_HEALTH_FACTOR_BPS: u16 = 50_000;
+
 // ============================================================================
 // MINIMUM AMOUNTS
 // ============================================================================
@@ -124,5 +183,21 @@ pub const MIN_DEPOSIT_AMOUNT: u64 = 1_000;
 /// Minimum borrow amount (to prevent dust attacks)
 pub const MIN_BORROW_AMOUNT: u64 = 1_000;
 
+/// Vault surplus treated as rounding dust, as a fraction of one whole token
+/// (1 / 10^6 of a token). Any surplus up to the bound qualifies, including a
+/// donation that small; larger surpluses are left for the authority's sweep_surplus.
+pub const ROUNDING_DUST_DIVISOR: u64 = 1_000_000;
+
+/// Floor on the rounding dust bound (native units) for low-decimal tokens
+pub const MIN_ROUNDING_DUST: u64 = 10;
+
 /// Minimum collateral value in USD to open a borrow position ($10)
 pub const MIN_COLLATERAL_VALUE_USD: u128 = 10 * USD_SCALE;
+
+/// Maximum USD value of a single obligation position ($1 trillion)
+/// Larger positions are rejected so refresh_obligation sums and the health
+/// math stay far below u128 overflow
+pub const MAX_POSITION_VALUE_USD: u128 = 1_000_000_000_000 * USD_SCALE;
+
+/// Maximum total deposited or borrowed USD value of an obligation
+pub const MAX_OBLIGATION_VALUE_USD: u128 = MAX_POSITION_VALUE_USD * MAX_OBLIGATION_DEPOSITS as u128;
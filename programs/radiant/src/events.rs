@@ -14,6 +14,30 @@ pub struct LendingMarketInitialized {
     pub liquidation_bonus_bps: u16,
 }
 
+/// Emitted when lending market config is updated
+#[event]
+pub struct LendingMarketUpdated {
+    pub lending_market: Pubkey,
+    pub close_factor_bps: u16,
+    pub liquidation_bonus_bps: u16,
+    pub protocol_fee_bps: u16,
+    pub allow_looping: bool,
+    pub max_liquidations_per_slot: u8,
+    pub max_obligation_borrow_usd: u64,
+    pub route_liquidation_fees_to_reserve: bool,
+    pub min_config_update_interval_slots: u64,
+    pub liquidation_hysteresis_bps: u16,
+    pub require_refresh_in_transaction: bool,
+    pub max_liquidation_refresh_age_slots: u64,
+    pub emit_action_blocked_events: bool,
+    pub borrow_capacity_buffer_bps: u16,
+    pub borrow_warning_health_bps: u16,
+    pub liquidation_bonus_decay_extra_bps: u16,
+    pub liquidation_bonus_decay_slots: u64,
+    pub max_obligation_deposits: u8,
+    pub max_obligation_borrows: u8,
+}
+
 /// Emitted when emergency mode is toggled
 #[event]
 pub struct EmergencyModeChanged {
@@ -22,6 +46,64 @@ pub struct EmergencyModeChanged {
     pub timestamp: i64,
 }
 
+/// Emitted when liquidations are halted or resumed
+#[event]
+pub struct LiquidationsHaltedChanged {
+    pub lending_market: Pubkey,
+    pub halt_liquidations: bool,
+    pub timestamp: i64,
+}
+
+/// Emitted when creation of new obligations is allowed or paused
+#[event]
+pub struct AllowNewObligationsChanged {
+    pub lending_market: Pubkey,
+    pub allow_new_obligations: bool,
+    pub timestamp: i64,
+}
+
+/// User action reported by ActionBlocked
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockedAction {
+    Deposit,
+    Borrow,
+}
+
+/// Reason reported by ActionBlocked
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockReason {
+    /// The lending market is in emergency mode
+    EmergencyMode,
+    /// The action is disabled on the reserve
+    ReserveDisabled,
+}
+
+/// Emitted when a deposit or borrow is rejected by emergency mode or a
+/// disabled reserve (only when the market's emit_action_blocked_events is set)
+///
+/// The transaction still fails; the event is read from its logs.
+#[event]
+pub struct ActionBlocked {
+    pub lending_market: Pubkey,
+    pub reserve: Pubkey,
+    pub owner: Pubkey,
+    pub action: BlockedAction,
+    pub reason: BlockReason,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted by market_solvency with the market-wide collateralization ratio
+#[event]
+pub struct MarketSolvency {
+    pub lending_market: Pubkey,
+    pub total_collateral_usd: u128,
+    pub total_debt_usd: u128,
+    pub collateralization_ratio_bps: u64,
+    pub solvent: bool,
+    pub timestamp: i64,
+}
+
 // ============================================================================
 // RESERVE EVENTS
 // ============================================================================
@@ -46,6 +128,15 @@ pub struct ReserveConfigUpdated {
     pub borrow_limit: u64,
 }
 
+/// Emitted when a depositor is added to or removed from a reserve's whitelist
+#[event]
+pub struct DepositorWhitelistUpdated {
+    pub reserve: Pubkey,
+    pub depositor: Pubkey,
+    pub whitelisted: bool,
+    pub timestamp: i64,
+}
+
 /// Emitted when a reserve is refreshed (interest accrued)
 #[event]
 pub struct ReserveRefreshed {
@@ -56,6 +147,33 @@ pub struct ReserveRefreshed {
     pub current_supply_rate_bps: u64,
     pub total_deposits: u64,
     pub total_borrows: u64,
+    pub depositor_count: u32,
+    pub borrower_count: u32,
+    pub index_divergence_bps: u64,
+    pub lifetime_borrow_interest_paid: u128,
+    pub last_borrow_timestamp: i64,
+    pub last_deposit_timestamp: i64,
+    pub lifetime_liquidation_repaid: u128,
+    pub lifetime_liquidation_seized: u128,
+    pub liquidation_count: u32,
+    pub timestamp: i64,
+}
+
+/// Emitted when a reserve has outstanding borrows but zero deposits
+#[event]
+pub struct BorrowsWithoutDeposits {
+    pub reserve: Pubkey,
+    pub total_borrows: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a reserve's cumulative index crosses the rebase alert threshold
+#[event]
+pub struct RebaseNeeded {
+    pub reserve: Pubkey,
+    pub cumulative_borrow_index: u128,
+    pub cumulative_supply_index: u128,
+    pub max_cumulative_index: u128,
     pub timestamp: i64,
 }
 
@@ -69,6 +187,27 @@ pub struct ObligationInitialized {
     pub lending_market: Pubkey,
     pub obligation: Pubkey,
     pub owner: Pubkey,
+    pub obligation_id: u8,
+}
+
+/// Emitted when an obligation's positions are sorted by reserve
+#[event]
+pub struct ObligationNormalized {
+    pub obligation: Pubkey,
+    pub version: u8,
+    pub reordered: bool,
+    pub timestamp: i64,
+}
+
+/// Emitted when a version 1 market, reserve or obligation is rewritten in
+/// the current layout
+#[event]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+    pub data_len: u32,
+    pub timestamp: i64,
 }
 
 /// Emitted when an obligation is refreshed
@@ -80,6 +219,30 @@ pub struct ObligationRefreshed {
     pub allowed_borrow_value_usd: u128,
     pub unhealthy_borrow_value_usd: u128,
     pub health_factor: Option<u64>,
+    pub net_equity_usd: i128,
+    pub timestamp: i64,
+}
+
+/// Emitted when an obligation is transferred to a new owner
+#[event]
+pub struct ObligationTransferred {
+    pub lending_market: Pubkey,
+    pub old_obligation: Pubkey,
+    pub new_obligation: Pubkey,
+    pub old_owner: Pubkey,
+    pub new_owner: Pubkey,
+    pub deposits_count: u8,
+    pub borrows_count: u8,
+    pub timestamp: i64,
+}
+
+/// Emitted when a deposit is enabled or disabled as collateral
+#[event]
+pub struct DepositCollateralChanged {
+    pub obligation: Pubkey,
+    pub reserve: Pubkey,
+    pub owner: Pubkey,
+    pub use_as_collateral: bool,
     pub timestamp: i64,
 }
 
@@ -96,6 +259,7 @@ pub struct DepositEvent {
     pub owner: Pubkey,
     pub amount: u64,
     pub new_deposit_amount: u64,
+    pub depositor_count: u32,
     pub timestamp: i64,
 }
 
@@ -108,6 +272,7 @@ pub struct WithdrawEvent {
     pub owner: Pubkey,
     pub amount: u64,
     pub remaining_deposit: u64,
+    pub depositor_count: u32,
     pub timestamp: i64,
 }
 
@@ -119,9 +284,12 @@ pub struct BorrowEvent {
     pub obligation: Pubkey,
     pub owner: Pubkey,
     pub amount: u64,
+    pub utilization_fee: u64,
+    pub estimated_daily_interest: u64,
     pub new_borrow_amount: u64,
     pub new_utilization_bps: u64,
     pub new_borrow_rate_bps: u64,
+    pub borrower_count: u32,
     pub timestamp: i64,
 }
 
@@ -134,9 +302,26 @@ pub struct RepayEvent {
     pub payer: Pubkey,
     pub owner: Pubkey,
     pub amount: u64,
+    pub principal_repaid: u64,
+    pub interest_repaid: u64,
     pub remaining_borrow: u64,
     pub new_utilization_bps: u64,
     pub new_borrow_rate_bps: u64,
+    pub borrower_count: u32,
+    pub timestamp: i64,
+}
+
+/// Emitted when a borrow leaves health below the market's warning threshold
+/// but above the minimum required to borrow
+#[event]
+pub struct RiskyBorrow {
+    pub lending_market: Pubkey,
+    pub reserve: Pubkey,
+    pub obligation: Pubkey,
+    pub owner: Pubkey,
+    pub health_factor: u64,
+    pub warning_health_bps: u16,
+    pub min_health_factor: u64,
     pub timestamp: i64,
 }
 
@@ -157,6 +342,13 @@ pub struct LiquidationEvent {
     pub collateral_seized: u64,
     pub liquidation_bonus: u64,
     pub protocol_fee: u64,
+    pub protocol_fee_bps: u16,
+    pub liquidation_bonus_bps: u16,
+    pub repay_reserve_borrower_count: u32,
+    pub collateral_reserve_depositor_count: u32,
+    pub repay_reserve_lifetime_liquidation_repaid: u128,
+    pub collateral_reserve_lifetime_liquidation_seized: u128,
+    pub residual_bad_debt: u64,
     pub timestamp: i64,
 }
 
@@ -169,6 +361,60 @@ pub struct LiquidationEvent {
 pub struct ProtocolFeesCollected {
     pub reserve: Pubkey,
     pub amount: u64,
+    pub amount_usd: u128,
+    pub recipient: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Emitted when treasury tokens are seeded into a reserve as protocol-owned liquidity
+#[event]
+pub struct ProtocolLiquiditySeeded {
+    pub reserve: Pubkey,
+    pub amount: u64,
+    pub protocol_owned_deposits: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when protocol-owned liquidity is withdrawn to the treasury
+#[event]
+pub struct ProtocolLiquidityWithdrawn {
+    pub reserve: Pubkey,
+    pub amount: u64,
+    pub protocol_owned_deposits: u64,
+    pub recipient: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Emitted when reserve totals are reconciled against obligations
+#[event]
+pub struct ReserveReconciled {
+    pub reserve: Pubkey,
+    pub obligations_count: u32,
+    pub stored_deposits: u64,
+    pub computed_deposits: u64,
+    pub stored_borrows: u64,
+    pub computed_borrows: u64,
+    pub repaired: bool,
+    pub timestamp: i64,
+}
+
+/// Emitted when untracked tokens are swept from a reserve vault
+#[event]
+pub struct SurplusSwept {
+    pub reserve: Pubkey,
+    pub amount: u64,
+    pub vault_balance: u64,
+    pub expected_vault_balance: u64,
     pub recipient: Pubkey,
     pub timestamp: i64,
 }
+
+/// Emitted when vault rounding dust is credited to protocol fees
+#[event]
+pub struct RoundingDustSwept {
+    pub reserve: Pubkey,
+    pub amount: u64,
+    pub vault_balance: u64,
+    pub accumulated_protocol_fees: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,79 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{LendingMarket, Reserve, WhitelistedDepositor};
+use crate::events::DepositorWhitelistUpdated;
+
+/// Accounts for whitelisting a depositor on a permissioned reserve
+#[derive(Accounts)]
+pub struct AddWhitelistedDepositor<'info> {
+    /// Authority of the lending market (must sign, pays for the entry)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// The lending market
+    #[account(
+        has_one = authority,
+        seeds = [LendingMarket::SEED_PREFIX, authority.key().as_ref()],
+        bump = lending_market.bump
+    )]
+    pub lending_market: Account<'info, LendingMarket>,
+
+    /// The reserve to whitelist the depositor on
+    #[account(
+        constraint = reserve.lending_market == lending_market.key() @ WhitelistError::InvalidReserve
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    /// The depositor to whitelist
+    /// CHECK: Any account may be whitelisted
+    pub depositor: UncheckedAccount<'info>,
+
+    /// The allow-list entry to create
+    /// PDA: ["whitelisted_depositor", reserve, depositor]
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + WhitelistedDepositor::INIT_SPACE,
+        seeds = [WhitelistedDepositor::SEED_PREFIX, reserve.key().as_ref(), depositor.key().as_ref()],
+        bump
+    )]
+    pub whitelisted_depositor: Account<'info, WhitelistedDepositor>,
+
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Whitelist a depositor on a reserve
+///
+/// Only enforced while the reserve is `permissioned`; entries can be added
+/// before the flag is turned on.
+///
+/// # Arguments
+/// * `ctx` - The context containing all accounts
+pub fn handler(ctx: Context<AddWhitelistedDepositor>) -> Result<()> {
+    let clock = Clock::get()?;
+    let entry = &mut ctx.accounts.whitelisted_depositor;
+
+    entry.bump = ctx.bumps.whitelisted_depositor;
+    entry.reserve = ctx.accounts.reserve.key();
+    entry.depositor = ctx.accounts.depositor.key();
+    entry.added_slot = clock.slot;
+
+    emit!(DepositorWhitelistUpdated {
+        reserve: entry.reserve,
+        depositor: entry.depositor,
+        whitelisted: true,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Depositor {} whitelisted on reserve {}", entry.depositor, entry.reserve);
+
+    Ok(())
+}
+
+/// Depositor whitelist errors
+#[error_code]
+pub enum WhitelistError {
+    #[msg("Reserve does not belong to this lending market")]
+    InvalidReserve,
+}
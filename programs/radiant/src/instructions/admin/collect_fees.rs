@@ -3,6 +3,7 @@ use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 
 use crate::state::{LendingMarket, Reserve};
 use crate::events::ProtocolFeesCollected;
+use crate::oracle::{get_reserve_price_usd, token_amount_to_usd, usd_to_token_amount};
 
 /// Accounts for collecting accumulated protocol fees
 #[derive(Accounts)]
@@ -13,7 +14,6 @@ pub struct CollectFees<'info> {
     /// The lending market
     #[account(
         has_one = authority,
-        has_one = treasury,
         seeds = [LendingMarket::SEED_PREFIX, authority.key().as_ref()],
         bump = lending_market.bump
     )]
@@ -42,24 +42,68 @@ pub struct CollectFees<'info> {
     )]
     pub treasury_token_account: Account<'info, TokenAccount>,
 
-    /// Treasury account (must match lending_market.treasury)
-    /// CHECK: Validated by has_one constraint on lending_market
+    /// Treasury account (reserve.fee_treasury if set, else lending_market.treasury)
+    /// CHECK: Validated against the reserve's fee treasury
+    #[account(
+        constraint = treasury.key() == reserve.fee_treasury_or(lending_market.treasury) @ CollectFeesError::InvalidTreasury
+    )]
     pub treasury: UncheckedAccount<'info>,
 
-    /// Token program
+    /// Token program (must match the reserve's pinned token program)
+    #[account(
+        constraint = token_program.key() == reserve.token_program_id @ CollectFeesError::InvalidTokenProgram,
+    )]
     pub token_program: Program<'info, Token>,
+
+    /// Reserve oracle, required to collect a USD amount
+    /// CHECK: Validated against reserve.oracle in the oracle helper
+    pub oracle: Option<UncheckedAccount<'info>>,
+
+    /// Quote oracle, required with the oracle if the reserve is priced through a non-USD feed
+    /// CHECK: Validated against reserve.price_quote_oracle in the oracle helper
+    pub quote_oracle: Option<UncheckedAccount<'info>>,
 }
 
 /// Collect accumulated protocol fees from a reserve
 ///
-/// Transfers accumulated fees from the reserve vault to the treasury.
+/// Transfers accumulated fees from the reserve vault to the treasury:
+/// the reserve's fee_treasury when set, otherwise the market treasury.
 /// Only the lending market authority can call this.
 ///
 /// # Arguments
 /// * `ctx` - The context containing all accounts
 /// * `amount` - Amount of fees to collect (0 = collect all)
-pub fn handler(ctx: Context<CollectFees>, amount: u64) -> Result<()> {
+/// * `amount_usd` - Optional USD value to collect instead (scaled by 10^6),
+///   converted at the oracle price; `amount` is ignored when set
+pub fn handler(ctx: Context<CollectFees>, amount: u64, amount_usd: Option<u64>) -> Result<()> {
     let reserve = &mut ctx.accounts.reserve;
+    let clock = Clock::get()?;
+
+    // Price the fees when an oracle is given (required for a USD amount)
+    let price_usd = match &ctx.accounts.oracle {
+        Some(oracle) => Some(get_reserve_price_usd(
+            reserve,
+            &oracle.to_account_info(),
+            ctx.accounts.quote_oracle.as_ref().map(|quote_oracle| quote_oracle.as_ref()),
+            clock.slot,
+        )?),
+        None => None,
+    };
+    let amount = match amount_usd {
+        Some(amount_usd) => {
+            let price_usd = price_usd.ok_or(CollectFeesError::MissingOracle)?;
+            let native_amount = usd_to_token_amount(amount_usd as u128, price_usd, reserve.token_decimals)?;
+            require!(native_amount > 0, CollectFeesError::AmountTooSmall);
+            native_amount
+        }
+        None => amount,
+    };
+
+    // Re-check vault invariants before moving funds
+    require!(
+        reserve.validate_accounts(&reserve.key(), &ctx.accounts.reserve_vault, None),
+        CollectFeesError::InvalidReserveAccounts
+    );
 
     // Get available fees
     let available_fees = reserve.liquidity.accumulated_protocol_fees;
@@ -97,8 +141,13 @@ pub fn handler(ctx: Context<CollectFees>, amount: u64) -> Result<()> {
         .checked_sub(collect_amount)
         .ok_or(CollectFeesError::MathOverflow)?;
 
+    // USD value of what was collected (0 without an oracle)
+    let collected_usd = match price_usd {
+        Some(price_usd) => token_amount_to_usd(collect_amount, price_usd, reserve.token_decimals)?,
+        None => 0,
+    };
+
     // Update timestamp
-    let clock = Clock::get()?;
     reserve.last_update_slot = clock.slot;
     reserve.last_update_timestamp = clock.unix_timestamp;
 
@@ -106,6 +155,7 @@ pub fn handler(ctx: Context<CollectFees>, amount: u64) -> Result<()> {
     emit!(ProtocolFeesCollected {
         reserve: reserve.key(),
         amount: collect_amount,
+        amount_usd: collected_usd,
         recipient: ctx.accounts.treasury.key(),
         timestamp: clock.unix_timestamp,
     });
@@ -125,15 +175,30 @@ pub enum CollectFeesError {
     #[msg("Invalid vault account")]
     InvalidVault,
 
+    #[msg("Token program does not match the reserve's token program")]
+    InvalidTokenProgram,
+
+    #[msg("Reserve token accounts failed validation")]
+    InvalidReserveAccounts,
+
     #[msg("Token mint mismatch")]
     InvalidTokenMint,
 
+    #[msg("Treasury does not match the reserve's fee treasury")]
+    InvalidTreasury,
+
     #[msg("Treasury token account owner mismatch")]
     InvalidTreasuryOwner,
 
     #[msg("No fees to collect")]
     NoFeesToCollect,
 
+    #[msg("Oracle account is required to collect a USD amount")]
+    MissingOracle,
+
+    #[msg("USD amount converts to zero tokens")]
+    AmountTooSmall,
+
     #[msg("Math overflow")]
     MathOverflow,
 }
@@ -63,7 +63,7 @@ pub fn handler(
     let lending_market = &mut ctx.accounts.lending_market;
 
     // Set version for future upgrades
-    lending_market.version = 1;
+    lending_market.version = LendingMarket::CURRENT_VERSION;
 
     // Store bump for PDA verification
     lending_market.bump = ctx.bumps.lending_market;
@@ -99,9 +99,56 @@ pub fn handler(
     );
     lending_market.protocol_fee_bps = protocol_fee;
 
+    // Liquidations enabled
+    lending_market.halt_liquidations = false;
+
     // No reserves yet
     lending_market.reserves_count = 0;
 
+    // Single-asset looping is opt-in
+    lending_market.allow_looping = false;
+
+    // No per-slot liquidation limit by default
+    lending_market.max_liquidations_per_slot = 0;
+
+    // Liquidation fees go to the reserve fee receiver by default
+    lending_market.route_liquidation_fees_to_reserve = false;
+
+    // No reserve config update rate limit by default
+    lending_market.min_config_update_interval_slots = 0;
+
+    // No per-obligation borrow cap by default
+    lending_market.max_obligation_borrow_usd = 0;
+
+    // New obligations allowed by default
+    lending_market.allow_new_obligations = true;
+
+    // No liquidation hysteresis by default
+    lending_market.liquidation_hysteresis_bps = 0;
+
+    // Same-transaction refresh before borrow is opt-in
+    lending_market.require_refresh_in_transaction = false;
+
+    // Liquidations accept the general obligation staleness window by default
+    lending_market.max_liquidation_refresh_age_slots = 0;
+
+    // Blocked-action events are opt-in
+    lending_market.emit_action_blocked_events = false;
+
+    // No borrow capacity buffer by default
+    lending_market.borrow_capacity_buffer_bps = 0;
+
+    // No risky borrow warnings by default
+    lending_market.borrow_warning_health_bps = 0;
+
+    // No liquidation bonus decay by default
+    lending_market.liquidation_bonus_decay_extra_bps = 0;
+    lending_market.liquidation_bonus_decay_slots = 0;
+
+    // Obligations may use the full position caps by default
+    lending_market.max_obligation_deposits = 0;
+    lending_market.max_obligation_borrows = 0;
+
     // Initialize padding to zeros
     lending_market._padding = [0u8; 128];
 
@@ -1,5 +1,5 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Mint, Token, TokenAccount};
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
 
 use crate::state::{
     LendingMarket,
@@ -7,10 +7,16 @@ use crate::state::{
     ReserveConfig,
     ReserveLiquidity,
     InterestRateConfig,
+    InterestModel,
+    RateKink,
+    MAX_EXTRA_RATE_KINKS,
 };
 use crate::constants::{
     INDEX_ONE,
     MAX_RESERVES,
+    MAX_LTV_BPS,
+    MAX_LIQUIDATION_THRESHOLD_BPS,
+    MAX_RESERVE_FACTOR_BPS,
     VAULT_SEED,
     FEE_RECEIVER_SEED,
     DEFAULT_OPTIMAL_UTILIZATION_BPS,
@@ -18,6 +24,7 @@ use crate::constants::{
     DEFAULT_SLOPE1_BPS,
     DEFAULT_SLOPE2_BPS,
     DEFAULT_RESERVE_FACTOR_BPS,
+    MAX_ORACLE_STALENESS_SLOTS,
 };
 use crate::events::ReserveInitialized;
 
@@ -79,6 +86,14 @@ pub struct InitializeReserve<'info> {
     /// CHECK: Validated in handler (must be valid Pyth account)
     pub oracle: UncheckedAccount<'info>,
 
+    /// Authority's token account funding the optional seed deposit
+    #[account(
+        mut,
+        constraint = authority_token_account.mint == token_mint.key() @ ReserveError::InvalidTokenMint,
+        constraint = authority_token_account.owner == authority.key() @ ReserveError::InvalidTokenOwner
+    )]
+    pub authority_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
     /// Token program
     pub token_program: Program<'info, Token>,
 
@@ -104,8 +119,87 @@ pub struct InitializeReserveParams {
     /// Optional: Maximum borrow limit (0 = unlimited)
     pub borrow_limit: Option<u64>,
 
+    /// Optional: Maximum utilization in BPS reachable by borrows (0 = no cap)
+    pub max_utilization_bps: Option<u16>,
+
+    /// Optional: Share of deposits in BPS kept free for withdrawals (default 0)
+    pub withdrawal_reserve_bps: Option<u16>,
+
+    /// Optional: Utilization in BPS above which the utilization fee applies
+    pub utilization_fee_threshold_bps: Option<u16>,
+
+    /// Optional: Utilization fee in BPS at 100% utilization (0 = disabled)
+    pub utilization_fee_max_bps: Option<u16>,
+
+    /// Optional: Maximum single deposit size (0 = unlimited)
+    pub max_single_deposit: Option<u64>,
+
+    /// Optional: Maximum single borrow size (0 = unlimited)
+    pub max_single_borrow: Option<u64>,
+
+    /// Optional: Skip accrual over periods with borrows disabled (default false)
+    pub reset_accrual_on_borrow_enable: Option<bool>,
+
+    /// Optional: Enable flash loans (default false)
+    pub flash_loans_enabled: Option<bool>,
+
+    /// Optional: Restrict deposits to whitelisted depositors (default false)
+    pub permissioned: Option<bool>,
+
+    /// Optional: Allow liquidators to seize this collateral (default true)
+    pub seizable: Option<bool>,
+
     /// Optional: Interest rate config (uses defaults if not provided)
     pub interest_rate_config: Option<InterestRateConfigParams>,
+
+    /// Optional: E-mode category (0 = none)
+    pub emode_category: Option<u8>,
+
+    /// Optional: LTV in BPS when in e-mode
+    pub emode_ltv_bps: Option<u16>,
+
+    /// Optional: Liquidation threshold in BPS when in e-mode
+    pub emode_liquidation_threshold_bps: Option<u16>,
+
+    /// Optional: Liquidation bonus in BPS for same-category liquidations
+    pub emode_liquidation_bonus_bps: Option<u16>,
+
+    /// Optional: Minimum health factor in BPS after borrowing this asset (0 = market default)
+    pub min_health_factor_for_borrow_bps: Option<u16>,
+
+    /// Optional: Maximum oracle price age in slots (uses global default if not provided)
+    pub max_oracle_staleness_slots: Option<u32>,
+
+    /// Optional: LTV haircut in BPS for recently deposited collateral (0 = none)
+    pub initial_borrow_ltv_haircut_bps: Option<u16>,
+
+    /// Optional: Slots after a deposit during which the LTV haircut applies
+    pub initial_borrow_haircut_slots: Option<u64>,
+
+    /// Optional: Slots after a deposit before it backs borrows (default 0)
+    pub collateral_activation_delay_slots: Option<u64>,
+
+    /// Optional: Liquidation protocol fee override in BPS (uses market fee if not provided)
+    pub liquidation_protocol_fee_bps: Option<u16>,
+
+    /// Optional: Close factor override in BPS for this debt (uses market close factor if not provided)
+    pub close_factor_bps: Option<u16>,
+
+    /// Optional: Oracle converting the main oracle's quote asset to USD
+    /// (for assets that only have a non-USD price feed)
+    pub price_quote_oracle: Option<Pubkey>,
+
+    /// Optional: Treasury for this reserve's fees (uses market treasury if not provided)
+    pub fee_treasury: Option<Pubkey>,
+
+    /// Optional: Seed deposit from the authority, locked in the reserve forever
+    /// Requires `authority_token_account`
+    pub initial_seed_deposit: Option<u64>,
+
+    /// Optional: Interest accrual model (default compound)
+    /// Fixed for the reserve's lifetime: switching would reprice every
+    /// outstanding borrow
+    pub interest_model: Option<InterestModel>,
 }
 
 /// Interest rate configuration parameters
@@ -116,6 +210,9 @@ pub struct InterestRateConfigParams {
     pub slope1_bps: u16,
     pub slope2_bps: u16,
     pub reserve_factor_bps: u16,
+    pub low_util_threshold_bps: u16,
+    pub low_util_supply_subsidy_bps: u16,
+    pub extra_kinks: Option<[RateKink; MAX_EXTRA_RATE_KINKS]>,
 }
 
 /// Initialize a new reserve (asset pool)
@@ -123,6 +220,10 @@ pub struct InterestRateConfigParams {
 /// Creates a new liquidity pool for a specific token.
 /// Each token can only have one reserve per lending market.
 ///
+/// An optional seed deposit establishes a non-zero baseline for
+/// total_deposits. It is not credited to any obligation and stays locked,
+/// so the first real depositor cannot manipulate the supply index.
+///
 /// # Arguments
 /// * `ctx` - The context containing all accounts
 /// * `params` - Reserve configuration parameters
@@ -136,12 +237,24 @@ pub fn handler(
         ReserveError::InvalidLtvThreshold
     );
 
-    // Validate liquidation threshold <= 100%
+    // Validate LTV <= protocol maximum
     require!(
-        params.liquidation_threshold_bps <= 10000,
+        params.ltv_bps <= MAX_LTV_BPS,
+        ReserveError::InvalidLtv
+    );
+
+    // Validate liquidation threshold <= protocol maximum
+    require!(
+        params.liquidation_threshold_bps <= MAX_LIQUIDATION_THRESHOLD_BPS,
         ReserveError::InvalidLiquidationThreshold
     );
 
+    // Validate borrow limit <= deposit limit when both are set
+    require!(
+        Reserve::validate_limits(params.deposit_limit.unwrap_or(0), params.borrow_limit.unwrap_or(0)),
+        ReserveError::BorrowLimitAboveDepositLimit
+    );
+
     // Check max reserves limit
     require!(
         ctx.accounts.lending_market.reserves_count < MAX_RESERVES,
@@ -152,7 +265,7 @@ pub fn handler(
     let clock = Clock::get()?;
 
     // Basic info
-    reserve.version = 1;
+    reserve.version = Reserve::CURRENT_VERSION;
     reserve.bump = ctx.bumps.reserve;
     reserve.lending_market = ctx.accounts.lending_market.key();
     reserve.token_mint = ctx.accounts.token_mint.key();
@@ -161,13 +274,19 @@ pub fn handler(
     // Token accounts
     reserve.token_vault = ctx.accounts.token_vault.key();
     reserve.fee_receiver = ctx.accounts.fee_receiver.key();
+    reserve.token_program_id = ctx.accounts.token_program.key();
 
     // Oracle
     reserve.oracle = ctx.accounts.oracle.key();
+    reserve.price_quote_oracle = params.price_quote_oracle;
 
     // Timestamps
     reserve.last_update_slot = clock.slot;
     reserve.last_update_timestamp = clock.unix_timestamp;
+    reserve.last_config_update_slot = 0;
+
+    // Fee routing
+    reserve.fee_treasury = params.fee_treasury;
 
     // Configuration
     let interest_config = params.interest_rate_config
@@ -177,6 +296,9 @@ pub fn handler(
             slope1_bps: c.slope1_bps,
             slope2_bps: c.slope2_bps,
             reserve_factor_bps: c.reserve_factor_bps,
+            low_util_threshold_bps: c.low_util_threshold_bps,
+            low_util_supply_subsidy_bps: c.low_util_supply_subsidy_bps,
+            extra_kinks: c.extra_kinks.unwrap_or_default(),
         })
         .unwrap_or(InterestRateConfig {
             optimal_utilization_bps: DEFAULT_OPTIMAL_UTILIZATION_BPS,
@@ -184,15 +306,49 @@ pub fn handler(
             slope1_bps: DEFAULT_SLOPE1_BPS,
             slope2_bps: DEFAULT_SLOPE2_BPS,
             reserve_factor_bps: DEFAULT_RESERVE_FACTOR_BPS,
+            low_util_threshold_bps: 0,
+            low_util_supply_subsidy_bps: 0,
+            extra_kinks: [RateKink::default(); MAX_EXTRA_RATE_KINKS],
         });
 
+    // Validate reserve factor <= protocol maximum
+    require!(
+        interest_config.reserve_factor_bps <= MAX_RESERVE_FACTOR_BPS,
+        ReserveError::InvalidReserveFactor
+    );
+
     reserve.config = ReserveConfig {
         ltv_bps: params.ltv_bps,
         liquidation_threshold_bps: params.liquidation_threshold_bps,
         deposit_limit: params.deposit_limit.unwrap_or(0),
         borrow_limit: params.borrow_limit.unwrap_or(0),
+        max_utilization_bps: params.max_utilization_bps.unwrap_or(0),
+        withdrawal_reserve_bps: params.withdrawal_reserve_bps.unwrap_or(0),
+        utilization_fee_threshold_bps: params.utilization_fee_threshold_bps.unwrap_or(0),
+        utilization_fee_max_bps: params.utilization_fee_max_bps.unwrap_or(0),
+        max_single_deposit: params.max_single_deposit.unwrap_or(0),
+        max_single_borrow: params.max_single_borrow.unwrap_or(0),
         deposits_enabled: true,
         borrows_enabled: true,
+        reset_accrual_on_borrow_enable: params.reset_accrual_on_borrow_enable.unwrap_or(false),
+        flash_loans_enabled: params.flash_loans_enabled.unwrap_or(false),
+        permissioned: params.permissioned.unwrap_or(false),
+        seizable: params.seizable.unwrap_or(true),
+        emode_category: params.emode_category.unwrap_or(0),
+        emode_ltv_bps: params.emode_ltv_bps.unwrap_or(0),
+        emode_liquidation_threshold_bps: params.emode_liquidation_threshold_bps.unwrap_or(0),
+        emode_liquidation_bonus_bps: params.emode_liquidation_bonus_bps.unwrap_or(0),
+        min_health_factor_for_borrow_bps: params.min_health_factor_for_borrow_bps.unwrap_or(0),
+        max_oracle_staleness_slots: params
+            .max_oracle_staleness_slots
+            .unwrap_or(MAX_ORACLE_STALENESS_SLOTS as u32),
+        initial_borrow_ltv_haircut_bps: params.initial_borrow_ltv_haircut_bps.unwrap_or(0),
+        initial_borrow_haircut_slots: params.initial_borrow_haircut_slots.unwrap_or(0),
+        collateral_activation_delay_slots: params.collateral_activation_delay_slots.unwrap_or(0),
+        liquidation_protocol_fee_bps: params.liquidation_protocol_fee_bps,
+        close_factor_bps: params.close_factor_bps,
+        // Negative supply rates are only enabled deliberately via update_reserve_config
+        negative_supply_rate_bps: 0,
         interest_rate_config: interest_config,
     };
 
@@ -211,10 +367,49 @@ pub fn handler(
         cumulative_supply_index: INDEX_ONE,  // Start at 1.0 (10^18)
         current_borrow_rate_bps: 0,
         current_supply_rate_bps: 0,
+        locked_seed_deposit: 0,
+        depositor_count: 0,
+        borrower_count: 0,
+        lifetime_borrow_interest_paid: 0,
+        protocol_owned_deposits: 0,
+        protocol_deposit_index_snapshot: INDEX_ONE,
+        last_borrow_timestamp: 0,
+        last_deposit_timestamp: 0,
+        lifetime_liquidation_repaid: 0,
+        lifetime_liquidation_seized: 0,
+        liquidation_count: 0,
+        borrow_interest_remainder: 0,
     };
 
+    // Interest model, fixed from here on
+    reserve.interest_model = params.interest_model.unwrap_or_default();
+    reserve.total_borrow_principal = 0;
+
     // Initialize padding
-    reserve._padding = [0u8; 128];
+    reserve._padding = [0u8; 119];
+
+    // Lock the optional seed deposit in the vault
+    let seed_deposit = params.initial_seed_deposit.unwrap_or(0);
+    if seed_deposit > 0 {
+        let authority_token_account = ctx.accounts.authority_token_account
+            .as_ref()
+            .ok_or(ReserveError::MissingSeedTokenAccount)?;
+
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: authority_token_account.to_account_info(),
+                to: ctx.accounts.token_vault.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            },
+        );
+        token::transfer(transfer_ctx, seed_deposit)?;
+
+        reserve.liquidity.total_deposits = seed_deposit;
+        reserve.liquidity.locked_seed_deposit = seed_deposit;
+
+        msg!("Locked seed deposit: {}", seed_deposit);
+    }
 
     // Increment reserves count
     ctx.accounts.lending_market.reserves_count += 1;
@@ -243,12 +438,30 @@ pub enum ReserveError {
     #[msg("LTV must be less than liquidation threshold")]
     InvalidLtvThreshold,
 
-    #[msg("Liquidation threshold must be <= 10000 bps (100%)")]
+    #[msg("LTV must be <= 9500 bps (95%)")]
+    InvalidLtv,
+
+    #[msg("Liquidation threshold must be <= 9800 bps (98%)")]
     InvalidLiquidationThreshold,
 
+    #[msg("Reserve factor must be <= 5000 bps (50%)")]
+    InvalidReserveFactor,
+
+    #[msg("Borrow limit must not exceed deposit limit when both are set")]
+    BorrowLimitAboveDepositLimit,
+
     #[msg("Maximum number of reserves reached")]
     MaxReservesReached,
 
     #[msg("Invalid reserve configuration")]
     InvalidReserveConfig,
+
+    #[msg("Token mint mismatch")]
+    InvalidTokenMint,
+
+    #[msg("Token account owner mismatch")]
+    InvalidTokenOwner,
+
+    #[msg("Seed deposit requires the authority token account")]
+    MissingSeedTokenAccount,
 }
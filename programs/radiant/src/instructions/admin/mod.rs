@@ -1,11 +1,29 @@
 pub mod initialize_lending_market;
 pub mod initialize_reserve;
 pub mod update_reserve_config;
+pub mod update_lending_market;
 pub mod set_emergency_mode;
+pub mod set_liquidations_halted;
+pub mod set_allow_new_obligations;
 pub mod collect_fees;
+pub mod sweep_surplus;
+pub mod reconcile_reserve;
+pub mod seed_protocol_liquidity;
+pub mod withdraw_protocol_liquidity;
+pub mod add_whitelisted_depositor;
+pub mod remove_whitelisted_depositor;
 
 pub use initialize_lending_market::*;
 pub use initialize_reserve::*;
 pub use update_reserve_config::*;
+pub use update_lending_market::*;
 pub use set_emergency_mode::*;
+pub use set_liquidations_halted::*;
+pub use set_allow_new_obligations::*;
 pub use collect_fees::*;
+pub use sweep_surplus::*;
+pub use reconcile_reserve::*;
+pub use seed_protocol_liquidity::*;
+pub use withdraw_protocol_liquidity::*;
+pub use add_whitelisted_depositor::*;
+pub use remove_whitelisted_depositor::*;
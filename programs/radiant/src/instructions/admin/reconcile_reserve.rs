@@ -0,0 +1,188 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{LendingMarket, Obligation, Reserve};
+use crate::events::ReserveReconciled;
+use crate::instructions::permissionless::refresh_reserve::accrue_interest;
+use crate::math::mul_div_floor;
+
+/// Accounts for reconciling reserve totals against obligations
+///
+/// remaining_accounts must contain the obligations to sum (each at most once).
+#[derive(Accounts)]
+pub struct ReconcileReserve<'info> {
+    /// Authority of the lending market (must sign)
+    pub authority: Signer<'info>,
+
+    /// The lending market
+    #[account(
+        has_one = authority,
+        seeds = [LendingMarket::SEED_PREFIX, authority.key().as_ref()],
+        bump = lending_market.bump
+    )]
+    pub lending_market: Account<'info, LendingMarket>,
+
+    /// The reserve to reconcile
+    #[account(
+        mut,
+        constraint = reserve.lending_market == lending_market.key() @ ReconcileReserveError::InvalidReserve
+    )]
+    pub reserve: Account<'info, Reserve>,
+}
+
+/// Reconcile a reserve's totals against the sum of obligation positions
+///
+/// Sums the index-adjusted deposits and borrows of every obligation passed
+/// in remaining_accounts for this reserve, plus the locked seed deposit and
+/// protocol-owned deposits,
+/// and emits the computed totals next to the stored ones. Interest is
+/// accrued first so stored and computed totals use the same indexes.
+/// Stored totals (and the borrow principal) are only overwritten when
+/// `repair` is set; in that case
+/// the obligations passed must hold exactly `depositor_count` deposits and
+/// `borrower_count` borrows in the reserve, so a partial set cannot wipe
+/// out the positions that were left out.
+///
+/// # Arguments
+/// * `ctx` - The context containing all accounts
+/// * `repair` - Overwrite stored totals with the computed ones
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ReconcileReserve<'info>>,
+    repair: bool,
+) -> Result<()> {
+    let lending_market_key = ctx.accounts.lending_market.key();
+    let reserve = &mut ctx.accounts.reserve;
+    let reserve_key = reserve.key();
+    let clock = Clock::get()?;
+
+    // Bring indexes and totals to this slot before comparing
+    accrue_interest(reserve, clock.slot, clock.unix_timestamp)?;
+
+    let current_supply_index = reserve.liquidity.cumulative_supply_index;
+    let current_borrow_index = reserve.liquidity.cumulative_borrow_index;
+
+    // Seed and protocol-owned deposits are part of total_deposits but owned
+    // by no obligation
+    let protocol_deposits = reserve
+        .current_protocol_owned_deposits()
+        .ok_or(ReconcileReserveError::MathOverflow)?;
+    let mut computed_deposits =
+        reserve.liquidity.locked_seed_deposit as u128 + protocol_deposits as u128;
+    let mut computed_borrows: u128 = 0;
+    let mut computed_principal: u128 = 0;
+    let mut depositors_seen: u32 = 0;
+    let mut borrowers_seen: u32 = 0;
+    let mut seen: Vec<Pubkey> = Vec::with_capacity(ctx.remaining_accounts.len());
+
+    for obligation_info in ctx.remaining_accounts.iter() {
+        require!(
+            !seen.contains(obligation_info.key),
+            ReconcileReserveError::DuplicateObligation
+        );
+        seen.push(obligation_info.key());
+
+        require_keys_eq!(
+            *obligation_info.owner,
+            crate::ID,
+            ReconcileReserveError::InvalidObligation
+        );
+        let obligation = Obligation::try_deserialize(&mut &obligation_info.try_borrow_data()?[..])?;
+        require_keys_eq!(
+            obligation.lending_market,
+            lending_market_key,
+            ReconcileReserveError::InvalidObligation
+        );
+
+        // Deposits are credited to users: round down
+        if let Some(index) = obligation.find_deposit(&reserve_key) {
+            let deposit = &obligation.deposits[index];
+            depositors_seen = depositors_seen.saturating_add(1);
+            let amount = if deposit.supply_index_snapshot > 0 {
+                mul_div_floor(
+                    deposit.deposited_amount as u128,
+                    current_supply_index,
+                    deposit.supply_index_snapshot,
+                )
+                .ok_or(ReconcileReserveError::MathOverflow)?
+            } else {
+                deposit.deposited_amount as u128
+            };
+            computed_deposits = computed_deposits
+                .checked_add(amount)
+                .ok_or(ReconcileReserveError::MathOverflow)?;
+        }
+
+        // Debt is owed by users: round up
+        if let Some(index) = obligation.find_borrow(&reserve_key) {
+            let borrow = &obligation.borrows[index];
+            borrowers_seen = borrowers_seen.saturating_add(1);
+            let amount = borrow
+                .current_amount(current_borrow_index, reserve.interest_model)
+                .ok_or(ReconcileReserveError::MathOverflow)?;
+            computed_borrows = computed_borrows
+                .checked_add(amount as u128)
+                .ok_or(ReconcileReserveError::MathOverflow)?;
+            computed_principal = computed_principal
+                .checked_add(borrow.principal as u128)
+                .ok_or(ReconcileReserveError::MathOverflow)?;
+        }
+    }
+
+    let computed_deposits = u64::try_from(computed_deposits)
+        .map_err(|_| ReconcileReserveError::MathOverflow)?;
+    let computed_borrows = u64::try_from(computed_borrows)
+        .map_err(|_| ReconcileReserveError::MathOverflow)?;
+    let computed_principal = u64::try_from(computed_principal)
+        .map_err(|_| ReconcileReserveError::MathOverflow)?;
+
+    let stored_deposits = reserve.liquidity.total_deposits;
+    let stored_borrows = reserve.liquidity.total_borrows;
+
+    if repair {
+        require!(
+            depositors_seen == reserve.liquidity.depositor_count
+                && borrowers_seen == reserve.liquidity.borrower_count,
+            ReconcileReserveError::IncompleteObligationSet
+        );
+        reserve.liquidity.total_deposits = computed_deposits;
+        reserve.liquidity.total_borrows = computed_borrows;
+        reserve.total_borrow_principal = computed_principal;
+    }
+
+    // Emit event
+    emit!(ReserveReconciled {
+        reserve: reserve_key,
+        obligations_count: seen.len() as u32,
+        stored_deposits,
+        computed_deposits,
+        stored_borrows,
+        computed_borrows,
+        repaired: repair,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Reserve reconciled: {}", reserve.token_mint);
+    msg!("Deposits: stored {}, computed {}", stored_deposits, computed_deposits);
+    msg!("Borrows: stored {}, computed {}", stored_borrows, computed_borrows);
+    msg!("Positions: {} deposits, {} borrows", depositors_seen, borrowers_seen);
+
+    Ok(())
+}
+
+/// Reconcile reserve errors
+#[error_code]
+pub enum ReconcileReserveError {
+    #[msg("Reserve does not belong to this lending market")]
+    InvalidReserve,
+
+    #[msg("Obligation account is invalid or belongs to another lending market")]
+    InvalidObligation,
+
+    #[msg("Obligation passed more than once")]
+    DuplicateObligation,
+
+    #[msg("Obligations passed do not cover every position in the reserve")]
+    IncompleteObligationSet,
+
+    #[msg("Math overflow")]
+    MathOverflow,
+}
@@ -0,0 +1,62 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{LendingMarket, Reserve, WhitelistedDepositor};
+use crate::events::DepositorWhitelistUpdated;
+use super::add_whitelisted_depositor::WhitelistError;
+
+/// Accounts for removing a depositor from a reserve's whitelist
+#[derive(Accounts)]
+pub struct RemoveWhitelistedDepositor<'info> {
+    /// Authority of the lending market (must sign, receives the rent)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// The lending market
+    #[account(
+        has_one = authority,
+        seeds = [LendingMarket::SEED_PREFIX, authority.key().as_ref()],
+        bump = lending_market.bump
+    )]
+    pub lending_market: Account<'info, LendingMarket>,
+
+    /// The reserve to remove the depositor from
+    #[account(
+        constraint = reserve.lending_market == lending_market.key() @ WhitelistError::InvalidReserve
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    /// The allow-list entry to close
+    #[account(
+        mut,
+        close = authority,
+        seeds = [
+            WhitelistedDepositor::SEED_PREFIX,
+            reserve.key().as_ref(),
+            whitelisted_depositor.depositor.as_ref(),
+        ],
+        bump = whitelisted_depositor.bump
+    )]
+    pub whitelisted_depositor: Account<'info, WhitelistedDepositor>,
+}
+
+/// Remove a depositor from a reserve's whitelist
+///
+/// Blocks further deposits while the reserve is permissioned. The
+/// depositor's existing position is unaffected and can still be withdrawn.
+///
+/// # Arguments
+/// * `ctx` - The context containing all accounts
+pub fn handler(ctx: Context<RemoveWhitelistedDepositor>) -> Result<()> {
+    let entry = &ctx.accounts.whitelisted_depositor;
+
+    emit!(DepositorWhitelistUpdated {
+        reserve: entry.reserve,
+        depositor: entry.depositor,
+        whitelisted: false,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Depositor {} removed from reserve {}", entry.depositor, entry.reserve);
+
+    Ok(())
+}
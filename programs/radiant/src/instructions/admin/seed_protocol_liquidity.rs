@@ -0,0 +1,158 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::state::{LendingMarket, Reserve};
+use crate::events::ProtocolLiquiditySeeded;
+use crate::instructions::permissionless::refresh_reserve::accrue_interest;
+
+/// Accounts for seeding protocol-owned liquidity into a reserve
+#[derive(Accounts)]
+pub struct SeedProtocolLiquidity<'info> {
+    /// Authority of the lending market (must sign)
+    pub authority: Signer<'info>,
+
+    /// Treasury (must sign, owns the source tokens)
+    pub treasury: Signer<'info>,
+
+    /// The lending market
+    #[account(
+        has_one = authority,
+        has_one = treasury,
+        seeds = [LendingMarket::SEED_PREFIX, authority.key().as_ref()],
+        bump = lending_market.bump
+    )]
+    pub lending_market: Account<'info, LendingMarket>,
+
+    /// The reserve receiving the liquidity
+    #[account(
+        mut,
+        constraint = reserve.lending_market == lending_market.key() @ ProtocolLiquidityError::InvalidReserve
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    /// Reserve's token vault (destination)
+    #[account(
+        mut,
+        constraint = reserve_vault.key() == reserve.token_vault @ ProtocolLiquidityError::InvalidVault
+    )]
+    pub reserve_vault: Account<'info, TokenAccount>,
+
+    /// Treasury token account (source)
+    #[account(
+        mut,
+        constraint = treasury_token_account.mint == reserve.token_mint @ ProtocolLiquidityError::InvalidTokenMint,
+        constraint = treasury_token_account.owner == treasury.key() @ ProtocolLiquidityError::InvalidTreasuryOwner
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+
+    /// Token program (must match the reserve's pinned token program)
+    #[account(
+        constraint = token_program.key() == reserve.token_program_id @ ProtocolLiquidityError::InvalidTokenProgram,
+    )]
+    pub token_program: Program<'info, Token>,
+}
+
+/// Deposit treasury tokens into a reserve as protocol-owned liquidity
+///
+/// The tokens increase total_deposits (and so available liquidity) and earn
+/// supply interest like any other deposit, but are tracked in
+/// protocol_owned_deposits rather than an obligation, so they are never
+/// collateral and can never be liquidated. Interest is accrued first so the
+/// new liquidity only earns from this slot on.
+///
+/// # Arguments
+/// * `ctx` - The context containing all accounts
+/// * `amount` - Amount of tokens to deposit
+pub fn handler(ctx: Context<SeedProtocolLiquidity>, amount: u64) -> Result<()> {
+    require!(amount > 0, ProtocolLiquidityError::AmountZero);
+
+    let reserve = &mut ctx.accounts.reserve;
+    let clock = Clock::get()?;
+
+    accrue_interest(reserve, clock.slot, clock.unix_timestamp)?;
+
+    // Check deposit limit if set
+    let new_total_deposits = reserve.liquidity.total_deposits
+        .checked_add(amount)
+        .ok_or(ProtocolLiquidityError::MathOverflow)?;
+    require!(
+        reserve.config.deposit_limit == 0 || new_total_deposits <= reserve.config.deposit_limit,
+        ProtocolLiquidityError::DepositLimitExceeded
+    );
+
+    // Transfer tokens from treasury to vault
+    let transfer_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.treasury_token_account.to_account_info(),
+            to: ctx.accounts.reserve_vault.to_account_info(),
+            authority: ctx.accounts.treasury.to_account_info(),
+        },
+    );
+    token::transfer(transfer_ctx, amount)?;
+
+    // Roll accrued interest into the position and re-snapshot the index
+    let new_protocol_deposits = reserve
+        .current_protocol_owned_deposits()
+        .ok_or(ProtocolLiquidityError::MathOverflow)?
+        .checked_add(amount)
+        .ok_or(ProtocolLiquidityError::MathOverflow)?;
+    reserve.liquidity.protocol_owned_deposits = new_protocol_deposits;
+    reserve.liquidity.protocol_deposit_index_snapshot = reserve.liquidity.cumulative_supply_index;
+    reserve.liquidity.total_deposits = new_total_deposits;
+
+    // Update interest rates based on new utilization
+    let utilization_bps = reserve.calculate_utilization_bps();
+    let borrow_rate = reserve.config.interest_rate_config.calculate_borrow_rate(utilization_bps);
+    let supply_rate = reserve.config.interest_rate_config.calculate_supply_rate(borrow_rate, utilization_bps);
+
+    reserve.liquidity.current_borrow_rate_bps = borrow_rate;
+    reserve.liquidity.current_supply_rate_bps = supply_rate;
+
+    // Emit event
+    emit!(ProtocolLiquiditySeeded {
+        reserve: reserve.key(),
+        amount,
+        protocol_owned_deposits: new_protocol_deposits,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Seeded {} protocol liquidity into reserve {}", amount, reserve.token_mint);
+    msg!("Protocol-owned deposits: {}", new_protocol_deposits);
+
+    Ok(())
+}
+
+/// Protocol liquidity errors
+#[error_code]
+pub enum ProtocolLiquidityError {
+    #[msg("Reserve does not belong to this lending market")]
+    InvalidReserve,
+
+    #[msg("Invalid vault account")]
+    InvalidVault,
+
+    #[msg("Token program does not match the reserve's token program")]
+    InvalidTokenProgram,
+
+    #[msg("Token mint mismatch")]
+    InvalidTokenMint,
+
+    #[msg("Treasury token account owner mismatch")]
+    InvalidTreasuryOwner,
+
+    #[msg("Amount must be greater than zero")]
+    AmountZero,
+
+    #[msg("Deposit would exceed reserve deposit limit")]
+    DepositLimitExceeded,
+
+    #[msg("Amount exceeds protocol-owned deposits")]
+    InsufficientProtocolDeposits,
+
+    #[msg("Insufficient liquidity in reserve")]
+    InsufficientLiquidity,
+
+    #[msg("Math overflow")]
+    MathOverflow,
+}
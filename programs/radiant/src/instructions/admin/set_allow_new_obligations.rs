@@ -0,0 +1,57 @@
+use anchor_lang::prelude::*;
+
+use crate::state::LendingMarket;
+use crate::events::AllowNewObligationsChanged;
+
+/// Accounts for allowing or pausing new obligations
+#[derive(Accounts)]
+pub struct SetAllowNewObligations<'info> {
+    /// Authority of the lending market (must sign)
+    pub authority: Signer<'info>,
+
+    /// The lending market to update
+    #[account(
+        mut,
+        has_one = authority,
+        seeds = [LendingMarket::SEED_PREFIX, authority.key().as_ref()],
+        bump = lending_market.bump
+    )]
+    pub lending_market: Account<'info, LendingMarket>,
+}
+
+/// Allow or pause creation of new obligations
+///
+/// For a controlled launch: while paused, initialize_obligation is rejected
+/// but existing obligations can still deposit, borrow, repay and withdraw.
+///
+/// # Arguments
+/// * `ctx` - The context containing all accounts
+/// * `allowed` - true to allow new obligations, false to pause them
+pub fn handler(
+    ctx: Context<SetAllowNewObligations>,
+    allowed: bool,
+) -> Result<()> {
+    let lending_market = &mut ctx.accounts.lending_market;
+    let clock = Clock::get()?;
+
+    let previous_state = lending_market.allow_new_obligations;
+    lending_market.allow_new_obligations = allowed;
+
+    // Emit event
+    emit!(AllowNewObligationsChanged {
+        lending_market: lending_market.key(),
+        allow_new_obligations: allowed,
+        timestamp: clock.unix_timestamp,
+    });
+
+    if allowed {
+        msg!("New obligations allowed");
+    } else {
+        msg!("NEW OBLIGATIONS PAUSED");
+    }
+
+    msg!("Previous state: {}", previous_state);
+    msg!("New state: {}", lending_market.allow_new_obligations);
+
+    Ok(())
+}
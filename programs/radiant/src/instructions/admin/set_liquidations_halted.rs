@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+
+use crate::state::LendingMarket;
+use crate::events::LiquidationsHaltedChanged;
+
+/// Accounts for halting or resuming liquidations
+#[derive(Accounts)]
+pub struct SetLiquidationsHalted<'info> {
+    /// Authority of the lending market (must sign)
+    pub authority: Signer<'info>,
+
+    /// The lending market to update
+    #[account(
+        mut,
+        has_one = authority,
+        seeds = [LendingMarket::SEED_PREFIX, authority.key().as_ref()],
+        bump = lending_market.bump
+    )]
+    pub lending_market: Account<'info, LendingMarket>,
+}
+
+/// Halt or resume liquidations
+///
+/// Emergency mode keeps liquidations enabled to protect the protocol, but
+/// during an oracle manipulation incident liquidations can themselves be
+/// the attack. This switch freezes liquidations independently of
+/// emergency mode; all other operations are unaffected.
+///
+/// # Arguments
+/// * `ctx` - The context containing all accounts
+/// * `halted` - true to halt liquidations, false to resume
+pub fn handler(
+    ctx: Context<SetLiquidationsHalted>,
+    halted: bool,
+) -> Result<()> {
+    let lending_market = &mut ctx.accounts.lending_market;
+    let clock = Clock::get()?;
+
+    let previous_state = lending_market.halt_liquidations;
+    lending_market.halt_liquidations = halted;
+
+    // Emit event
+    emit!(LiquidationsHaltedChanged {
+        lending_market: lending_market.key(),
+        halt_liquidations: halted,
+        timestamp: clock.unix_timestamp,
+    });
+
+    if halted {
+        msg!("LIQUIDATIONS HALTED");
+    } else {
+        msg!("Liquidations resumed");
+    }
+
+    msg!("Previous state: {}", previous_state);
+    msg!("New state: {}", lending_market.halt_liquidations);
+
+    Ok(())
+}
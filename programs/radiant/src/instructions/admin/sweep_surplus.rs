@@ -0,0 +1,129 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::state::{LendingMarket, Reserve};
+use crate::events::SurplusSwept;
+
+/// Accounts for sweeping untracked tokens from a reserve vault
+#[derive(Accounts)]
+pub struct SweepSurplus<'info> {
+    /// Authority of the lending market (must sign)
+    pub authority: Signer<'info>,
+
+    /// The lending market
+    #[account(
+        has_one = authority,
+        has_one = treasury,
+        seeds = [LendingMarket::SEED_PREFIX, authority.key().as_ref()],
+        bump = lending_market.bump
+    )]
+    pub lending_market: Account<'info, LendingMarket>,
+
+    /// The reserve whose vault holds the surplus
+    #[account(
+        mut,
+        constraint = reserve.lending_market == lending_market.key() @ SweepSurplusError::InvalidReserve
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    /// Reserve's token vault (source of surplus)
+    #[account(
+        mut,
+        constraint = reserve_vault.key() == reserve.token_vault @ SweepSurplusError::InvalidVault
+    )]
+    pub reserve_vault: Account<'info, TokenAccount>,
+
+    /// Treasury token account (destination for surplus)
+    /// Must be owned by the treasury and match reserve's token mint
+    #[account(
+        mut,
+        constraint = treasury_token_account.mint == reserve.token_mint @ SweepSurplusError::InvalidTokenMint,
+        constraint = treasury_token_account.owner == treasury.key() @ SweepSurplusError::InvalidTreasuryOwner
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+
+    /// Treasury account (must match lending_market.treasury)
+    /// CHECK: Validated by has_one constraint on lending_market
+    pub treasury: UncheckedAccount<'info>,
+
+    /// Token program (must match the reserve's pinned token program)
+    #[account(
+        constraint = token_program.key() == reserve.token_program_id @ SweepSurplusError::InvalidTokenProgram,
+    )]
+    pub token_program: Program<'info, Token>,
+}
+
+/// Sweep untracked tokens from a reserve vault to the treasury
+///
+/// Tokens sent directly to the vault (donations, mistaken transfers)
+/// raise its balance above `expected_vault_balance()`. Only that
+/// difference is transferred; tracked deposits, borrows and fees are
+/// left untouched. Only the lending market authority can call this.
+///
+/// # Arguments
+/// * `ctx` - The context containing all accounts
+pub fn handler(ctx: Context<SweepSurplus>) -> Result<()> {
+    let reserve = &ctx.accounts.reserve;
+
+    let vault_balance = ctx.accounts.reserve_vault.amount;
+    let expected_vault_balance = reserve.expected_vault_balance();
+
+    let surplus = vault_balance.saturating_sub(expected_vault_balance);
+    require!(surplus > 0, SweepSurplusError::NoSurplus);
+
+    // Transfer surplus from vault to treasury using PDA signer
+    let seeds = &[
+        Reserve::SEED_PREFIX,
+        reserve.lending_market.as_ref(),
+        reserve.token_mint.as_ref(),
+        &[reserve.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    let transfer_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.reserve_vault.to_account_info(),
+            to: ctx.accounts.treasury_token_account.to_account_info(),
+            authority: reserve.to_account_info(),
+        },
+        signer_seeds,
+    );
+    token::transfer(transfer_ctx, surplus)?;
+
+    // Emit event
+    emit!(SurplusSwept {
+        reserve: reserve.key(),
+        amount: surplus,
+        vault_balance,
+        expected_vault_balance,
+        recipient: ctx.accounts.treasury.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Swept {} surplus tokens from reserve {}", surplus, reserve.token_mint);
+
+    Ok(())
+}
+
+/// Sweep surplus errors
+#[error_code]
+pub enum SweepSurplusError {
+    #[msg("Reserve does not belong to this lending market")]
+    InvalidReserve,
+
+    #[msg("Invalid vault account")]
+    InvalidVault,
+
+    #[msg("Token program does not match the reserve's token program")]
+    InvalidTokenProgram,
+
+    #[msg("Token mint mismatch")]
+    InvalidTokenMint,
+
+    #[msg("Treasury token account owner mismatch")]
+    InvalidTreasuryOwner,
+
+    #[msg("Vault holds no surplus above tracked balances")]
+    NoSurplus,
+}
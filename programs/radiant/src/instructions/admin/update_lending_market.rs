@@ -0,0 +1,289 @@
+use anchor_lang::prelude::*;
+
+use crate::state::LendingMarket;
+use crate::constants::{
+    MAX_BORROW_CAPACITY_BUFFER_BPS,
+    MAX_LIQUIDATION_BONUS_BPS,
+    MAX_LIQUIDATION_HYSTERESIS_BPS,
+    MAX_OBLIGATION_BORROWS,
+    MAX_OBLIGATION_DEPOSITS,
+    MAX_OBLIGATION_STALENESS_SLOTS,
+    MAX_RESERVE_MIN_HEALTH_FACTOR_BPS,
+};
+use crate::events::LendingMarketUpdated;
+
+/// Accounts for updating lending market configuration
+#[derive(Accounts)]
+pub struct UpdateLendingMarket<'info> {
+    /// Authority of the lending market (must sign)
+    pub authority: Signer<'info>,
+
+    /// The lending market to update
+    #[account(
+        mut,
+        has_one = authority,
+        seeds = [LendingMarket::SEED_PREFIX, authority.key().as_ref()],
+        bump = lending_market.bump
+    )]
+    pub lending_market: Account<'info, LendingMarket>,
+}
+
+/// Parameters for updating lending market config
+/// All fields are optional - only provided fields will be updated
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct UpdateLendingMarketParams {
+    /// New close factor in BPS
+    pub close_factor_bps: Option<u16>,
+
+    /// New liquidation bonus in BPS
+    pub liquidation_bonus_bps: Option<u16>,
+
+    /// New protocol fee in BPS
+    pub protocol_fee_bps: Option<u16>,
+
+    /// Allow/disallow single-asset looping
+    pub allow_looping: Option<bool>,
+
+    /// New maximum liquidations per obligation per slot (0 = unlimited)
+    pub max_liquidations_per_slot: Option<u8>,
+
+    /// Route liquidation protocol fees into reserve accumulated fees
+    pub route_liquidation_fees_to_reserve: Option<bool>,
+
+    /// New minimum slots between reserve config updates (0 = no limit)
+    pub min_config_update_interval_slots: Option<u64>,
+
+    /// New maximum borrowed value per obligation in USD, scaled by 10^6 (0 = unlimited)
+    pub max_obligation_borrow_usd: Option<u64>,
+
+    /// New liquidation hysteresis band in health BPS (0 = disabled)
+    pub liquidation_hysteresis_bps: Option<u16>,
+
+    /// Require a same-transaction refresh_obligation before borrow
+    pub require_refresh_in_transaction: Option<bool>,
+
+    /// New maximum obligation refresh age for liquidation in slots
+    /// (0 = general staleness window)
+    pub max_liquidation_refresh_age_slots: Option<u64>,
+
+    /// Emit ActionBlocked events for deposits and borrows rejected by
+    /// emergency mode or a disabled reserve
+    pub emit_action_blocked_events: Option<bool>,
+
+    /// New borrow capacity buffer in BPS of allowed borrow value
+    pub borrow_capacity_buffer_bps: Option<u16>,
+
+    /// New health factor in BPS below which borrows emit RiskyBorrow (0 = off)
+    pub borrow_warning_health_bps: Option<u16>,
+
+    /// New extra liquidation bonus in BPS at the start of the decay
+    pub liquidation_bonus_decay_extra_bps: Option<u16>,
+
+    /// New liquidation bonus decay period in slots (0 = no decay bonus)
+    pub liquidation_bonus_decay_slots: Option<u64>,
+
+    /// New maximum deposit positions per obligation (0 = hard cap)
+    pub max_obligation_deposits: Option<u8>,
+
+    /// New maximum borrow positions per obligation (0 = hard cap)
+    pub max_obligation_borrows: Option<u8>,
+}
+
+/// Update lending market configuration
+///
+/// Allows admin to modify market-wide parameters.
+/// Only provided fields will be updated.
+///
+/// # Arguments
+/// * `ctx` - The context containing all accounts
+/// * `params` - Optional configuration updates
+pub fn handler(
+    ctx: Context<UpdateLendingMarket>,
+    params: UpdateLendingMarketParams,
+) -> Result<()> {
+    let lending_market = &mut ctx.accounts.lending_market;
+
+    // Update liquidation parameters
+    if let Some(close_factor) = params.close_factor_bps {
+        require!(
+            LendingMarket::validate_close_factor(close_factor),
+            UpdateLendingMarketError::InvalidCloseFactor
+        );
+        lending_market.close_factor_bps = close_factor;
+    }
+
+    if let Some(liq_bonus) = params.liquidation_bonus_bps {
+        require!(
+            LendingMarket::validate_liquidation_bonus(liq_bonus),
+            UpdateLendingMarketError::InvalidLiquidationBonus
+        );
+        lending_market.liquidation_bonus_bps = liq_bonus;
+    }
+
+    if let Some(protocol_fee) = params.protocol_fee_bps {
+        require!(
+            protocol_fee <= 10000,
+            UpdateLendingMarketError::InvalidProtocolFee
+        );
+        lending_market.protocol_fee_bps = protocol_fee;
+    }
+
+    // Update flags
+    if let Some(allow_looping) = params.allow_looping {
+        lending_market.allow_looping = allow_looping;
+    }
+
+    // Update liquidation limits
+    if let Some(max_liquidations) = params.max_liquidations_per_slot {
+        lending_market.max_liquidations_per_slot = max_liquidations;
+    }
+
+    // Update liquidation fee routing
+    if let Some(route_to_reserve) = params.route_liquidation_fees_to_reserve {
+        lending_market.route_liquidation_fees_to_reserve = route_to_reserve;
+    }
+
+    // Update reserve config rate limit
+    if let Some(min_interval) = params.min_config_update_interval_slots {
+        lending_market.min_config_update_interval_slots = min_interval;
+    }
+
+    // Update borrow concentration limit
+    if let Some(max_borrow_usd) = params.max_obligation_borrow_usd {
+        lending_market.max_obligation_borrow_usd = max_borrow_usd;
+    }
+
+    // Update liquidation hysteresis
+    if let Some(hysteresis) = params.liquidation_hysteresis_bps {
+        require!(
+            hysteresis <= MAX_LIQUIDATION_HYSTERESIS_BPS,
+            UpdateLendingMarketError::InvalidLiquidationHysteresis
+        );
+        lending_market.liquidation_hysteresis_bps = hysteresis;
+    }
+
+    // Update borrow refresh ordering requirement
+    if let Some(require_refresh) = params.require_refresh_in_transaction {
+        lending_market.require_refresh_in_transaction = require_refresh;
+    }
+
+    // Update liquidation refresh window
+    if let Some(max_age) = params.max_liquidation_refresh_age_slots {
+        require!(
+            max_age <= MAX_OBLIGATION_STALENESS_SLOTS,
+            UpdateLendingMarketError::InvalidLiquidationRefreshAge
+        );
+        lending_market.max_liquidation_refresh_age_slots = max_age;
+    }
+
+    if let Some(emit_blocked) = params.emit_action_blocked_events {
+        lending_market.emit_action_blocked_events = emit_blocked;
+    }
+
+    // Update borrow capacity buffer
+    if let Some(buffer) = params.borrow_capacity_buffer_bps {
+        require!(
+            buffer <= MAX_BORROW_CAPACITY_BUFFER_BPS,
+            UpdateLendingMarketError::InvalidBorrowCapacityBuffer
+        );
+        lending_market.borrow_capacity_buffer_bps = buffer;
+    }
+
+    // Update risky borrow warning threshold
+    if let Some(warning_health) = params.borrow_warning_health_bps {
+        require!(
+            warning_health == 0
+                || (10000..=MAX_RESERVE_MIN_HEALTH_FACTOR_BPS).contains(&warning_health),
+            UpdateLendingMarketError::InvalidBorrowWarningHealth
+        );
+        lending_market.borrow_warning_health_bps = warning_health;
+    }
+
+    // Update liquidation bonus decay
+    if let Some(extra_bps) = params.liquidation_bonus_decay_extra_bps {
+        require!(
+            extra_bps <= MAX_LIQUIDATION_BONUS_BPS,
+            UpdateLendingMarketError::InvalidLiquidationBonusDecay
+        );
+        lending_market.liquidation_bonus_decay_extra_bps = extra_bps;
+    }
+
+    if let Some(decay_slots) = params.liquidation_bonus_decay_slots {
+        lending_market.liquidation_bonus_decay_slots = decay_slots;
+    }
+
+    // Update per-obligation position limits; existing positions are kept
+    if let Some(max_deposits) = params.max_obligation_deposits {
+        require!(
+            max_deposits as usize <= MAX_OBLIGATION_DEPOSITS,
+            UpdateLendingMarketError::InvalidObligationPositionLimit
+        );
+        lending_market.max_obligation_deposits = max_deposits;
+    }
+
+    if let Some(max_borrows) = params.max_obligation_borrows {
+        require!(
+            max_borrows as usize <= MAX_OBLIGATION_BORROWS,
+            UpdateLendingMarketError::InvalidObligationPositionLimit
+        );
+        lending_market.max_obligation_borrows = max_borrows;
+    }
+
+    // Emit event
+    emit!(LendingMarketUpdated {
+        lending_market: lending_market.key(),
+        close_factor_bps: lending_market.close_factor_bps,
+        liquidation_bonus_bps: lending_market.liquidation_bonus_bps,
+        protocol_fee_bps: lending_market.protocol_fee_bps,
+        allow_looping: lending_market.allow_looping,
+        max_liquidations_per_slot: lending_market.max_liquidations_per_slot,
+        max_obligation_borrow_usd: lending_market.max_obligation_borrow_usd,
+        route_liquidation_fees_to_reserve: lending_market.route_liquidation_fees_to_reserve,
+        min_config_update_interval_slots: lending_market.min_config_update_interval_slots,
+        liquidation_hysteresis_bps: lending_market.liquidation_hysteresis_bps,
+        require_refresh_in_transaction: lending_market.require_refresh_in_transaction,
+        max_liquidation_refresh_age_slots: lending_market.max_liquidation_refresh_age_slots,
+        emit_action_blocked_events: lending_market.emit_action_blocked_events,
+        borrow_capacity_buffer_bps: lending_market.borrow_capacity_buffer_bps,
+        borrow_warning_health_bps: lending_market.borrow_warning_health_bps,
+        liquidation_bonus_decay_extra_bps: lending_market.liquidation_bonus_decay_extra_bps,
+        liquidation_bonus_decay_slots: lending_market.liquidation_bonus_decay_slots,
+        max_obligation_deposits: lending_market.max_obligation_deposits,
+        max_obligation_borrows: lending_market.max_obligation_borrows,
+    });
+
+    msg!("Lending market config updated: {}", lending_market.key());
+
+    Ok(())
+}
+
+/// Errors for lending market config updates
+#[error_code]
+pub enum UpdateLendingMarketError {
+    #[msg("Close factor must be between 0 and 10000 bps (0-100%)")]
+    InvalidCloseFactor,
+
+    #[msg("Liquidation bonus must be between 0 and 2500 bps (0-25%)")]
+    InvalidLiquidationBonus,
+
+    #[msg("Protocol fee must be between 0 and 10000 bps (0-100%)")]
+    InvalidProtocolFee,
+
+    #[msg("Liquidation hysteresis must be <= 500 bps")]
+    InvalidLiquidationHysteresis,
+
+    #[msg("Liquidation refresh age must not exceed the obligation staleness window")]
+    InvalidLiquidationRefreshAge,
+
+    #[msg("Borrow capacity buffer must be <= 100 bps")]
+    InvalidBorrowCapacityBuffer,
+
+    #[msg("Borrow warning health must be 0 or between 10000 and 50000 bps")]
+    InvalidBorrowWarningHealth,
+
+    #[msg("Extra liquidation bonus must be <= 2500 bps")]
+    InvalidLiquidationBonusDecay,
+
+    #[msg("Obligation position limits must not exceed the hard caps of 8")]
+    InvalidObligationPositionLimit,
+}
@@ -1,7 +1,14 @@
 use anchor_lang::prelude::*;
 
-use crate::state::{LendingMarket, Reserve};
+use crate::state::{LendingMarket, RateKink, Reserve, MAX_EXTRA_RATE_KINKS};
+use crate::constants::{
+    MAX_LIQUIDATION_THRESHOLD_BPS,
+    MAX_LTV_BPS,
+    MAX_NEGATIVE_SUPPLY_RATE_BPS,
+    MAX_RESERVE_FACTOR_BPS,
+};
 use crate::events::ReserveConfigUpdated;
+use crate::instructions::permissionless::refresh_reserve::accrue_interest;
 
 /// Accounts for updating reserve configuration
 #[derive(Accounts)]
@@ -41,12 +48,88 @@ pub struct UpdateReserveConfigParams {
     /// New borrow limit (0 = unlimited)
     pub borrow_limit: Option<u64>,
 
+    /// Allow deposit/borrow limits below the reserve's current usage
+    pub force_limits: Option<bool>,
+
+    /// New maximum utilization in BPS reachable by borrows (0 = no cap)
+    pub max_utilization_bps: Option<u16>,
+
+    /// New share of deposits in BPS kept free for withdrawals
+    pub withdrawal_reserve_bps: Option<u16>,
+
+    /// New utilization fee threshold in BPS
+    pub utilization_fee_threshold_bps: Option<u16>,
+
+    /// New utilization fee in BPS at 100% utilization (0 = disabled)
+    pub utilization_fee_max_bps: Option<u16>,
+
+    /// New maximum single deposit size (0 = unlimited)
+    pub max_single_deposit: Option<u64>,
+
+    /// New maximum single borrow size (0 = unlimited)
+    pub max_single_borrow: Option<u64>,
+
     /// Enable/disable deposits
     pub deposits_enabled: Option<bool>,
 
     /// Enable/disable borrows
     pub borrows_enabled: Option<bool>,
 
+    /// Skip accrual over periods with borrows disabled
+    pub reset_accrual_on_borrow_enable: Option<bool>,
+
+    /// Enable/disable flash loans
+    pub flash_loans_enabled: Option<bool>,
+
+    /// Restrict deposits to whitelisted depositors
+    pub permissioned: Option<bool>,
+
+    /// Allow liquidators to seize this collateral
+    pub seizable: Option<bool>,
+
+    /// New e-mode category (0 = none)
+    pub emode_category: Option<u8>,
+
+    /// New e-mode LTV in BPS
+    pub emode_ltv_bps: Option<u16>,
+
+    /// New e-mode liquidation threshold in BPS
+    pub emode_liquidation_threshold_bps: Option<u16>,
+
+    /// New liquidation bonus in BPS for same-category liquidations
+    pub emode_liquidation_bonus_bps: Option<u16>,
+
+    /// New minimum health factor in BPS after borrowing this asset (0 = market default)
+    pub min_health_factor_for_borrow_bps: Option<u16>,
+
+    /// New maximum oracle price age in slots (0 = global default)
+    pub max_oracle_staleness_slots: Option<u32>,
+
+    /// New LTV haircut in BPS for recently deposited collateral (0 = none)
+    pub initial_borrow_ltv_haircut_bps: Option<u16>,
+
+    /// New number of slots after a deposit during which the LTV haircut applies
+    pub initial_borrow_haircut_slots: Option<u64>,
+
+    /// New number of slots after a deposit before it backs borrows
+    pub collateral_activation_delay_slots: Option<u64>,
+
+    /// New liquidation protocol fee override in BPS
+    /// Some(None) clears the override (falls back to the market fee)
+    pub liquidation_protocol_fee_bps: Option<Option<u16>>,
+
+    /// New close factor override in BPS for liquidating this debt
+    /// Some(None) clears the override (falls back to the market close factor)
+    pub close_factor_bps: Option<Option<u16>>,
+
+    /// New treasury for this reserve's collected fees
+    /// Some(None) clears the override (falls back to the market treasury)
+    pub fee_treasury: Option<Option<Pubkey>>,
+
+    /// New negative supply rate in BPS per year (0 = disabled)
+    /// Lets the supply index decrease; see ReserveConfig for the risks
+    pub negative_supply_rate_bps: Option<u16>,
+
     /// New optimal utilization in BPS
     pub optimal_utilization_bps: Option<u16>,
 
@@ -61,12 +144,24 @@ pub struct UpdateReserveConfigParams {
 
     /// New reserve factor in BPS
     pub reserve_factor_bps: Option<u16>,
+
+    /// New utilization threshold in BPS for the supply subsidy
+    pub low_util_threshold_bps: Option<u16>,
+
+    /// New low utilization supply subsidy in BPS (0 = disabled)
+    pub low_util_supply_subsidy_bps: Option<u16>,
+
+    /// New kinks above the optimal utilization (all unused = two-slope model)
+    pub extra_kinks: Option<[RateKink; MAX_EXTRA_RATE_KINKS]>,
 }
 
 /// Update reserve configuration
 ///
 /// Allows admin to modify reserve parameters.
 /// Only provided fields will be updated.
+/// Updates are rate-limited by the market's minimum update interval,
+/// except in emergency mode. Interest is accrued at the old parameters
+/// before the new ones apply.
 ///
 /// # Arguments
 /// * `ctx` - The context containing all accounts
@@ -75,7 +170,18 @@ pub fn handler(
     ctx: Context<UpdateReserveConfig>,
     params: UpdateReserveConfigParams,
 ) -> Result<()> {
+    let lending_market = &ctx.accounts.lending_market;
     let reserve = &mut ctx.accounts.reserve;
+    let clock = Clock::get()?;
+
+    // Rate-limit config changes; emergency mode bypasses the limit
+    let min_interval = lending_market.min_config_update_interval_slots;
+    if min_interval > 0 && reserve.last_config_update_slot > 0 && !lending_market.emergency_mode {
+        require!(
+            clock.slot.saturating_sub(reserve.last_config_update_slot) >= min_interval,
+            UpdateConfigError::ConfigUpdateTooSoon
+        );
+    }
 
     // Build new config with updates
     let mut new_config = reserve.config.clone();
@@ -96,7 +202,12 @@ pub fn handler(
     );
 
     require!(
-        new_config.liquidation_threshold_bps <= 10000,
+        new_config.ltv_bps <= MAX_LTV_BPS,
+        UpdateConfigError::InvalidLtv
+    );
+
+    require!(
+        new_config.liquidation_threshold_bps <= MAX_LIQUIDATION_THRESHOLD_BPS,
         UpdateConfigError::InvalidLiquidationThreshold
     );
 
@@ -109,6 +220,56 @@ pub fn handler(
         new_config.borrow_limit = borrow_limit;
     }
 
+    require!(
+        Reserve::validate_limits(new_config.deposit_limit, new_config.borrow_limit),
+        UpdateConfigError::BorrowLimitAboveDepositLimit
+    );
+
+    // Reject limits already violated by current usage unless forced
+    let deposit_limit_below_usage = new_config.deposit_limit > 0
+        && new_config.deposit_limit < reserve.liquidity.total_deposits;
+    let borrow_limit_below_usage = new_config.borrow_limit > 0
+        && new_config.borrow_limit < reserve.liquidity.total_borrows;
+
+    if deposit_limit_below_usage || borrow_limit_below_usage {
+        require!(
+            params.force_limits.unwrap_or(false),
+            UpdateConfigError::LimitBelowCurrentUsage
+        );
+        msg!("Warning: limits set below current usage (deposits {}, borrows {})",
+            reserve.liquidity.total_deposits,
+            reserve.liquidity.total_borrows
+        );
+    }
+
+    if let Some(max_utilization) = params.max_utilization_bps {
+        require!(max_utilization <= 10000, UpdateConfigError::InvalidMaxUtilization);
+        new_config.max_utilization_bps = max_utilization;
+    }
+
+    if let Some(withdrawal_reserve) = params.withdrawal_reserve_bps {
+        require!(withdrawal_reserve <= 10000, UpdateConfigError::InvalidWithdrawalReserve);
+        new_config.withdrawal_reserve_bps = withdrawal_reserve;
+    }
+
+    if let Some(fee_threshold) = params.utilization_fee_threshold_bps {
+        require!(fee_threshold <= 10000, UpdateConfigError::InvalidUtilizationFee);
+        new_config.utilization_fee_threshold_bps = fee_threshold;
+    }
+
+    if let Some(fee_max) = params.utilization_fee_max_bps {
+        require!(fee_max <= 10000, UpdateConfigError::InvalidUtilizationFee);
+        new_config.utilization_fee_max_bps = fee_max;
+    }
+
+    if let Some(max_single_deposit) = params.max_single_deposit {
+        new_config.max_single_deposit = max_single_deposit;
+    }
+
+    if let Some(max_single_borrow) = params.max_single_borrow {
+        new_config.max_single_borrow = max_single_borrow;
+    }
+
     // Update flags
     if let Some(deposits_enabled) = params.deposits_enabled {
         new_config.deposits_enabled = deposits_enabled;
@@ -118,6 +279,106 @@ pub fn handler(
         new_config.borrows_enabled = borrows_enabled;
     }
 
+    if let Some(reset_accrual) = params.reset_accrual_on_borrow_enable {
+        new_config.reset_accrual_on_borrow_enable = reset_accrual;
+    }
+
+    if let Some(flash_loans_enabled) = params.flash_loans_enabled {
+        new_config.flash_loans_enabled = flash_loans_enabled;
+    }
+
+    if let Some(permissioned) = params.permissioned {
+        new_config.permissioned = permissioned;
+    }
+
+    if let Some(seizable) = params.seizable {
+        new_config.seizable = seizable;
+    }
+
+    // Update e-mode parameters
+    if let Some(emode_category) = params.emode_category {
+        new_config.emode_category = emode_category;
+    }
+
+    if let Some(emode_ltv) = params.emode_ltv_bps {
+        new_config.emode_ltv_bps = emode_ltv;
+    }
+
+    if let Some(emode_liq_threshold) = params.emode_liquidation_threshold_bps {
+        new_config.emode_liquidation_threshold_bps = emode_liq_threshold;
+    }
+
+    if let Some(emode_liq_bonus) = params.emode_liquidation_bonus_bps {
+        new_config.emode_liquidation_bonus_bps = emode_liq_bonus;
+    }
+
+    require!(
+        Reserve::validate_emode_config(&new_config),
+        UpdateConfigError::InvalidEmodeConfig
+    );
+
+    // Update borrow health requirement
+    if let Some(min_health_factor) = params.min_health_factor_for_borrow_bps {
+        require!(
+            Reserve::validate_min_health_factor_for_borrow(min_health_factor),
+            UpdateConfigError::InvalidMinHealthFactor
+        );
+        new_config.min_health_factor_for_borrow_bps = min_health_factor;
+    }
+
+    // Update oracle staleness tolerance
+    if let Some(max_staleness) = params.max_oracle_staleness_slots {
+        new_config.max_oracle_staleness_slots = max_staleness;
+    }
+
+    // Update initial borrow haircut
+    if let Some(haircut) = params.initial_borrow_ltv_haircut_bps {
+        require!(
+            haircut <= 10000,
+            UpdateConfigError::InvalidInitialBorrowHaircut
+        );
+        new_config.initial_borrow_ltv_haircut_bps = haircut;
+    }
+
+    if let Some(haircut_slots) = params.initial_borrow_haircut_slots {
+        new_config.initial_borrow_haircut_slots = haircut_slots;
+    }
+
+    // Update collateral activation delay
+    if let Some(activation_delay) = params.collateral_activation_delay_slots {
+        new_config.collateral_activation_delay_slots = activation_delay;
+    }
+
+    // Update liquidation protocol fee override
+    if let Some(liquidation_protocol_fee) = params.liquidation_protocol_fee_bps {
+        require!(
+            liquidation_protocol_fee.unwrap_or(0) <= 10000,
+            UpdateConfigError::InvalidLiquidationProtocolFee
+        );
+        new_config.liquidation_protocol_fee_bps = liquidation_protocol_fee;
+    }
+
+    // Update close factor override
+    if let Some(close_factor) = params.close_factor_bps {
+        require!(
+            (1..=10000).contains(&close_factor.unwrap_or(10000)),
+            UpdateConfigError::InvalidCloseFactor
+        );
+        new_config.close_factor_bps = close_factor;
+    }
+
+    // Update negative supply rate (opt-in, lets the supply index decrease)
+    if let Some(negative_rate) = params.negative_supply_rate_bps {
+        require!(
+            negative_rate <= MAX_NEGATIVE_SUPPLY_RATE_BPS,
+            UpdateConfigError::InvalidNegativeSupplyRate
+        );
+        if negative_rate > 0 {
+            msg!("Warning: negative supply rate enabled: {} bps", negative_rate);
+        }
+        new_config.negative_supply_rate_bps = negative_rate;
+    }
+
     // Update interest rate config
     let mut new_ir_config = new_config.interest_rate_config;
 
@@ -139,10 +400,28 @@ pub fn handler(
     }
 
     if let Some(reserve_factor) = params.reserve_factor_bps {
-        require!(reserve_factor <= 10000, UpdateConfigError::InvalidReserveFactor);
+        require!(reserve_factor <= MAX_RESERVE_FACTOR_BPS, UpdateConfigError::InvalidReserveFactor);
         new_ir_config.reserve_factor_bps = reserve_factor;
     }
 
+    if let Some(low_util_threshold) = params.low_util_threshold_bps {
+        require!(low_util_threshold <= 10000, UpdateConfigError::InvalidLowUtilThreshold);
+        new_ir_config.low_util_threshold_bps = low_util_threshold;
+    }
+
+    if let Some(subsidy) = params.low_util_supply_subsidy_bps {
+        new_ir_config.low_util_supply_subsidy_bps = subsidy;
+    }
+
+    if let Some(extra_kinks) = params.extra_kinks {
+        new_ir_config.extra_kinks = extra_kinks;
+    }
+
+    require!(
+        new_ir_config.validate_rate_curve(),
+        UpdateConfigError::InvalidRateCurve
+    );
+
     new_config.interest_rate_config = new_ir_config;
 
     // Final validation
@@ -151,9 +430,34 @@ pub fn handler(
         UpdateConfigError::InvalidReserveConfig
     );
 
+    // Restart accrual when borrows are switched back on, if opted in,
+    // so the idle period is not charged retroactively on the next refresh.
+    // Otherwise settle interest up to now at the old parameters, so no part
+    // of the elapsed period is charged at the new rate.
+    let borrows_reenabled = !reserve.config.borrows_enabled && new_config.borrows_enabled;
+    if borrows_reenabled && new_config.reset_accrual_on_borrow_enable {
+        reserve.last_update_slot = clock.slot;
+        reserve.last_update_timestamp = clock.unix_timestamp;
+        msg!("Borrows enabled: interest accrual restarted at slot {}", clock.slot);
+    } else {
+        accrue_interest(reserve, clock.slot, clock.unix_timestamp)?;
+    }
+
     // Apply the new config
     reserve.config = new_config;
 
+    // Rates from here on follow the new parameters
+    let utilization_bps = reserve.calculate_utilization_bps();
+    let borrow_rate = reserve.config.interest_rate_config.calculate_borrow_rate(utilization_bps);
+    let supply_rate = reserve.config.interest_rate_config.calculate_supply_rate(borrow_rate, utilization_bps);
+    reserve.liquidity.current_borrow_rate_bps = borrow_rate;
+    reserve.liquidity.current_supply_rate_bps = supply_rate;
+
+    if let Some(fee_treasury) = params.fee_treasury {
+        reserve.fee_treasury = fee_treasury;
+    }
+    reserve.last_config_update_slot = clock.slot;
+
     // Emit event
     emit!(ReserveConfigUpdated {
         reserve: reserve.key(),
@@ -177,15 +481,60 @@ pub enum UpdateConfigError {
     #[msg("LTV must be less than liquidation threshold")]
     InvalidLtvThreshold,
 
-    #[msg("Liquidation threshold must be <= 10000 bps")]
+    #[msg("LTV must be <= 9500 bps")]
+    InvalidLtv,
+
+    #[msg("Liquidation threshold must be <= 9800 bps")]
     InvalidLiquidationThreshold,
 
     #[msg("Optimal utilization must be <= 10000 bps")]
     InvalidOptimalUtilization,
 
-    #[msg("Reserve factor must be <= 10000 bps")]
+    #[msg("Reserve factor must be <= 5000 bps")]
     InvalidReserveFactor,
 
+    #[msg("Liquidation protocol fee must be <= 10000 bps")]
+    InvalidLiquidationProtocolFee,
+
+    #[msg("Close factor override must be between 1 and 10000 bps")]
+    InvalidCloseFactor,
+
+    #[msg("Negative supply rate must be <= 1000 bps")]
+    InvalidNegativeSupplyRate,
+
+    #[msg("Interest rate curve must be continuous and non-decreasing")]
+    InvalidRateCurve,
+
+    #[msg("Reserve config was updated too recently")]
+    ConfigUpdateTooSoon,
+
+    #[msg("Limit is below current reserve usage; set force_limits to apply")]
+    LimitBelowCurrentUsage,
+
+    #[msg("Maximum utilization must be <= 10000 bps")]
+    InvalidMaxUtilization,
+
+    #[msg("Borrow limit must not exceed deposit limit when both are set")]
+    BorrowLimitAboveDepositLimit,
+
+    #[msg("Withdrawal reserve must be <= 10000 bps")]
+    InvalidWithdrawalReserve,
+
+    #[msg("Utilization fee parameters must be <= 10000 bps")]
+    InvalidUtilizationFee,
+
+    #[msg("Low utilization threshold must be <= 10000 bps")]
+    InvalidLowUtilThreshold,
+
+    #[msg("Initial borrow LTV haircut must be <= 10000 bps")]
+    InvalidInitialBorrowHaircut,
+
+    #[msg("Minimum health factor for borrow must be 0 or between 10000 and 50000 bps")]
+    InvalidMinHealthFactor,
+
+    #[msg("Invalid e-mode configuration")]
+    InvalidEmodeConfig,
+
     #[msg("Invalid reserve configuration")]
     InvalidReserveConfig,
 }
@@ -0,0 +1,137 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::state::{LendingMarket, Reserve};
+use crate::events::ProtocolLiquidityWithdrawn;
+use crate::instructions::permissionless::refresh_reserve::accrue_interest;
+use super::seed_protocol_liquidity::ProtocolLiquidityError;
+
+/// Accounts for withdrawing protocol-owned liquidity from a reserve
+#[derive(Accounts)]
+pub struct WithdrawProtocolLiquidity<'info> {
+    /// Authority of the lending market (must sign)
+    pub authority: Signer<'info>,
+
+    /// The lending market
+    #[account(
+        has_one = authority,
+        has_one = treasury,
+        seeds = [LendingMarket::SEED_PREFIX, authority.key().as_ref()],
+        bump = lending_market.bump
+    )]
+    pub lending_market: Account<'info, LendingMarket>,
+
+    /// The reserve holding the liquidity
+    #[account(
+        mut,
+        constraint = reserve.lending_market == lending_market.key() @ ProtocolLiquidityError::InvalidReserve
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    /// Reserve's token vault (source)
+    #[account(
+        mut,
+        constraint = reserve_vault.key() == reserve.token_vault @ ProtocolLiquidityError::InvalidVault
+    )]
+    pub reserve_vault: Account<'info, TokenAccount>,
+
+    /// Treasury token account (destination)
+    #[account(
+        mut,
+        constraint = treasury_token_account.mint == reserve.token_mint @ ProtocolLiquidityError::InvalidTokenMint,
+        constraint = treasury_token_account.owner == treasury.key() @ ProtocolLiquidityError::InvalidTreasuryOwner
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+
+    /// Treasury account (must match lending_market.treasury)
+    /// CHECK: Validated by has_one constraint on lending_market
+    pub treasury: UncheckedAccount<'info>,
+
+    /// Token program (must match the reserve's pinned token program)
+    #[account(
+        constraint = token_program.key() == reserve.token_program_id @ ProtocolLiquidityError::InvalidTokenProgram,
+    )]
+    pub token_program: Program<'info, Token>,
+}
+
+/// Withdraw protocol-owned liquidity (plus earned interest) to the treasury
+///
+/// Interest is accrued first so the withdrawal includes supply interest up
+/// to this slot. Only idle liquidity can be withdrawn; tokens lent out to
+/// borrowers stay in the reserve.
+///
+/// # Arguments
+/// * `ctx` - The context containing all accounts
+/// * `amount` - Amount to withdraw (0 = withdraw all)
+pub fn handler(ctx: Context<WithdrawProtocolLiquidity>, amount: u64) -> Result<()> {
+    let reserve = &mut ctx.accounts.reserve;
+    let clock = Clock::get()?;
+
+    accrue_interest(reserve, clock.slot, clock.unix_timestamp)?;
+
+    let current_protocol_deposits = reserve
+        .current_protocol_owned_deposits()
+        .ok_or(ProtocolLiquidityError::MathOverflow)?;
+    require!(current_protocol_deposits > 0, ProtocolLiquidityError::InsufficientProtocolDeposits);
+
+    // Determine amount to withdraw (0 = all)
+    let withdraw_amount = if amount == 0 { current_protocol_deposits } else { amount };
+    require!(
+        withdraw_amount <= current_protocol_deposits,
+        ProtocolLiquidityError::InsufficientProtocolDeposits
+    );
+    require!(
+        withdraw_amount <= reserve.available_liquidity(),
+        ProtocolLiquidityError::InsufficientLiquidity
+    );
+
+    // Transfer tokens from vault to treasury using PDA signer
+    let seeds = &[
+        Reserve::SEED_PREFIX,
+        reserve.lending_market.as_ref(),
+        reserve.token_mint.as_ref(),
+        &[reserve.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    let transfer_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.reserve_vault.to_account_info(),
+            to: ctx.accounts.treasury_token_account.to_account_info(),
+            authority: reserve.to_account_info(),
+        },
+        signer_seeds,
+    );
+    token::transfer(transfer_ctx, withdraw_amount)?;
+
+    // Update protocol position and reserve totals
+    let remaining_protocol_deposits = current_protocol_deposits - withdraw_amount;
+    reserve.liquidity.protocol_owned_deposits = remaining_protocol_deposits;
+    reserve.liquidity.protocol_deposit_index_snapshot = reserve.liquidity.cumulative_supply_index;
+    reserve.liquidity.total_deposits = reserve.liquidity.total_deposits
+        .checked_sub(withdraw_amount)
+        .ok_or(ProtocolLiquidityError::MathOverflow)?;
+
+    // Update interest rates based on new utilization
+    let utilization_bps = reserve.calculate_utilization_bps();
+    let borrow_rate = reserve.config.interest_rate_config.calculate_borrow_rate(utilization_bps);
+    let supply_rate = reserve.config.interest_rate_config.calculate_supply_rate(borrow_rate, utilization_bps);
+
+    reserve.liquidity.current_borrow_rate_bps = borrow_rate;
+    reserve.liquidity.current_supply_rate_bps = supply_rate;
+
+    // Emit event
+    emit!(ProtocolLiquidityWithdrawn {
+        reserve: reserve.key(),
+        amount: withdraw_amount,
+        protocol_owned_deposits: remaining_protocol_deposits,
+        recipient: ctx.accounts.treasury.key(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Withdrew {} protocol liquidity from reserve {}", withdraw_amount, reserve.token_mint);
+    msg!("Remaining protocol-owned deposits: {}", remaining_protocol_deposits);
+
+    Ok(())
+}
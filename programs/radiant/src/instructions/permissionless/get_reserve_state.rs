@@ -0,0 +1,104 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Reserve;
+use crate::oracle::get_reserve_price_usd;
+
+/// Accounts for reading a reserve's key metrics
+#[derive(Accounts)]
+pub struct GetReserveState<'info> {
+    /// The reserve to read
+    pub reserve: Account<'info, Reserve>,
+
+    /// Reserve oracle (optional; price is 0 when omitted)
+    /// CHECK: Validated against reserve.oracle when provided
+    #[account(
+        constraint = oracle.key() == reserve.oracle @ GetReserveStateError::InvalidOracle
+    )]
+    pub oracle: Option<UncheckedAccount<'info>>,
+
+    /// Quote oracle, required with the oracle if the reserve is priced through a non-USD feed
+    /// CHECK: Validated against reserve.price_quote_oracle in the oracle helper
+    pub quote_oracle: Option<UncheckedAccount<'info>>,
+}
+
+/// Snapshot of a reserve's key metrics, returned to the caller
+///
+/// A stable interface for composing programs; fields are only appended.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct ReserveState {
+    /// Current utilization in BPS
+    pub utilization_bps: u64,
+
+    /// Current borrow rate in BPS (annualized)
+    pub borrow_rate_bps: u64,
+
+    /// Current supply rate in BPS (annualized)
+    pub supply_rate_bps: u64,
+
+    /// Liquidity available to borrow or withdraw (native units)
+    pub available_liquidity: u64,
+
+    /// Total deposits (native units)
+    pub total_deposits: u64,
+
+    /// Total borrows (native units)
+    pub total_borrows: u64,
+
+    /// Oracle price in USD scaled by 10^6 (0 if no oracle was provided)
+    pub price_usd: u64,
+
+    /// Slot of the last reserve refresh
+    pub last_update_slot: u64,
+
+    /// Utilization ceiling implied by the borrow limit and utilization cap, in BPS
+    pub effective_max_utilization_bps: u64,
+
+    /// Borrow rate at the optimal utilization kink in BPS (annualized)
+    pub borrow_rate_at_kink_bps: u64,
+
+    /// Borrow rate at 100% utilization in BPS (annualized)
+    pub borrow_rate_at_full_bps: u64,
+}
+
+/// Read a reserve's key metrics
+///
+/// Returns a `ReserveState` via return data, so other programs can read
+/// reserve metrics through CPI without depending on the account layout.
+/// Rates reflect the last refresh. No state is written.
+///
+/// # Arguments
+/// * `ctx` - The context containing all accounts
+pub fn handler(ctx: Context<GetReserveState>) -> Result<ReserveState> {
+    let reserve = &ctx.accounts.reserve;
+
+    let price_usd = match &ctx.accounts.oracle {
+        Some(oracle) => get_reserve_price_usd(
+            reserve,
+            &oracle.to_account_info(),
+            ctx.accounts.quote_oracle.as_ref().map(|quote_oracle| quote_oracle.as_ref()),
+            Clock::get()?.slot,
+        )?,
+        None => 0,
+    };
+
+    Ok(ReserveState {
+        utilization_bps: reserve.calculate_utilization_bps(),
+        borrow_rate_bps: reserve.liquidity.current_borrow_rate_bps,
+        supply_rate_bps: reserve.liquidity.current_supply_rate_bps,
+        available_liquidity: reserve.available_liquidity(),
+        total_deposits: reserve.liquidity.total_deposits,
+        total_borrows: reserve.liquidity.total_borrows,
+        price_usd,
+        last_update_slot: reserve.last_update_slot,
+        effective_max_utilization_bps: reserve.effective_max_utilization_bps(),
+        borrow_rate_at_kink_bps: reserve.config.interest_rate_config.rate_at_kink(),
+        borrow_rate_at_full_bps: reserve.config.interest_rate_config.rate_at_full(),
+    })
+}
+
+/// Get reserve state errors
+#[error_code]
+pub enum GetReserveStateError {
+    #[msg("Invalid oracle account")]
+    InvalidOracle,
+}
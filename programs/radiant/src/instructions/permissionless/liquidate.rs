@@ -2,8 +2,10 @@ use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 
 use crate::state::{LendingMarket, Reserve, Obligation};
-use crate::constants::VAULT_SEED;
+use crate::constants::{VAULT_SEED, HEALTH_FACTOR_ONE, MAX_RESERVE_STALENESS_SLOTS};
 use crate::events::LiquidationEvent;
+use crate::math::{mul_bps_floor, mul_div_ceil, mul_div_floor, mul_div_floor_wide};
+use crate::instructions::permissionless::refresh_reserve::accrue_interest;
 
 /// Accounts for liquidating an unhealthy position
 #[derive(Accounts)]
@@ -13,6 +15,7 @@ pub struct Liquidate<'info> {
 
     /// The lending market
     #[account(
+        constraint = !lending_market.halt_liquidations @ LiquidateError::LiquidationsHalted,
         seeds = [LendingMarket::SEED_PREFIX, lending_market.authority.as_ref()],
         bump = lending_market.bump
     )]
@@ -58,6 +61,7 @@ pub struct Liquidate<'info> {
     pub collateral_vault: Box<Account<'info, TokenAccount>>,
 
     /// Fee receiver for protocol fees from liquidation
+    /// (unused when the market routes liquidation fees to the reserve)
     #[account(
         mut,
         constraint = collateral_fee_receiver.key() == collateral_reserve.fee_receiver @ LiquidateError::InvalidFeeReceiver,
@@ -81,7 +85,11 @@ pub struct Liquidate<'info> {
     )]
     pub liquidator_collateral_account: Box<Account<'info, TokenAccount>>,
 
-    /// Token program
+    /// Token program (must match the reserve's pinned token program)
+    #[account(
+        constraint = token_program.key() == repay_reserve.token_program_id @ LiquidateError::InvalidTokenProgram,
+        constraint = token_program.key() == collateral_reserve.token_program_id @ LiquidateError::InvalidTokenProgram,
+    )]
     pub token_program: Program<'info, Token>,
 }
 
@@ -92,6 +100,16 @@ pub struct Liquidate<'info> {
 /// 1. Repays part of the borrower's debt
 /// 2. Receives collateral worth more than the repayment (liquidation bonus)
 ///
+/// With a market hysteresis band, a liquidation may exceed the close factor
+/// to restore health to 1.0 + band, and a restored obligation cannot be
+/// liquidated again until its health falls below 1.0 - band.
+///
+/// With a market bonus decay, the bonus starts at base + extra when a refresh
+/// first finds the obligation liquidatable and falls linearly to the base.
+///
+/// Cached obligation values are reduced by the repaid debt and seized
+/// collateral; only refresh_obligation moves `last_update_slot`.
+///
 /// # Arguments
 /// * `ctx` - The context containing all accounts
 /// * `repay_amount` - Amount of debt to repay (in debt token units)
@@ -100,6 +118,41 @@ pub fn handler(ctx: Context<Liquidate>, repay_amount: u64) -> Result<()> {
     let repay_reserve = &mut ctx.accounts.repay_reserve;
     let collateral_reserve = &mut ctx.accounts.collateral_reserve;
     let obligation = &mut ctx.accounts.obligation;
+    let clock = Clock::get()?;
+
+    // Check reserves are not stale, so debt and collateral use current indexes
+    require!(
+        !repay_reserve.is_stale(clock.slot, MAX_RESERVE_STALENESS_SLOTS),
+        LiquidateError::ReserveStale
+    );
+    require!(
+        !collateral_reserve.is_stale(clock.slot, MAX_RESERVE_STALENESS_SLOTS),
+        LiquidateError::ReserveStale
+    );
+
+    // Accrue interest inline so debt and collateral include all interest to this slot
+    accrue_interest(repay_reserve, clock.slot, clock.unix_timestamp)?;
+    accrue_interest(collateral_reserve, clock.slot, clock.unix_timestamp)?;
+
+    // Re-check vault and fee receiver invariants before moving funds
+    require!(
+        repay_reserve.validate_accounts(&repay_reserve.key(), &ctx.accounts.repay_vault, None),
+        LiquidateError::InvalidReserveAccounts
+    );
+    require!(
+        collateral_reserve.validate_accounts(
+            &collateral_reserve.key(),
+            &ctx.accounts.collateral_vault,
+            Some(&ctx.accounts.collateral_fee_receiver),
+        ),
+        LiquidateError::InvalidReserveAccounts
+    );
+
+    // Liquidation is irreversible: require a recent obligation refresh
+    require!(
+        clock.slot.saturating_sub(obligation.last_update_slot) <= lending_market.liquidation_refresh_age_slots(),
+        LiquidateError::ObligationStale
+    );
 
     // Verify obligation is liquidatable (health factor <= 1.0)
     require!(
@@ -107,6 +160,18 @@ pub fn handler(ctx: Context<Liquidate>, repay_amount: u64) -> Result<()> {
         LiquidateError::ObligationHealthy
     );
 
+    // Don't re-liquidate a restored position until health drops below the band
+    require!(
+        !obligation.in_liquidation_hysteresis_band(lending_market.liquidation_hysteresis_bps),
+        LiquidateError::WithinHysteresisBand
+    );
+
+    // Limit liquidations per obligation per slot (spreads keepers across slots)
+    require!(
+        obligation.record_liquidation(clock.slot, lending_market.max_liquidations_per_slot),
+        LiquidateError::LiquidationLimitReached
+    );
+
     let repay_reserve_key = repay_reserve.key();
     let collateral_reserve_key = collateral_reserve.key();
 
@@ -120,41 +185,107 @@ pub fn handler(ctx: Context<Liquidate>, repay_amount: u64) -> Result<()> {
         .find_deposit(&collateral_reserve_key)
         .ok_or(LiquidateError::NoCollateralFound)?;
 
-    // Calculate current borrow amount with interest
+    // Calculate current borrow amount with interest (rounded up)
     let borrow = &obligation.borrows[borrow_index];
     let current_borrow_index = repay_reserve.liquidity.cumulative_borrow_index;
-    let current_borrow_amount = if borrow.borrow_index_snapshot > 0 {
-        (borrow.borrowed_amount as u128 * current_borrow_index / borrow.borrow_index_snapshot) as u64
-    } else {
-        borrow.borrowed_amount
+    let current_borrow_amount = borrow
+        .current_amount(current_borrow_index, repay_reserve.interest_model)
+        .ok_or(LiquidateError::MathOverflow)?;
+
+    // Calculate current deposit amount with interest (rounded down)
+    let deposit = &obligation.deposits[deposit_index];
+    require!(deposit.use_as_collateral, LiquidateError::NotCollateral);
+    require!(collateral_reserve.config.seizable, LiquidateError::CollateralNotSeizable);
+    let current_supply_index = collateral_reserve.liquidity.cumulative_supply_index;
+    let current_deposit_amount = deposit
+        .current_amount(current_supply_index)
+        .ok_or(LiquidateError::MathOverflow)?;
+
+    // Convert between the two assets at the prices of the recent refresh
+    require!(
+        borrow.market_price_usd > 0 && deposit.market_price_usd > 0,
+        LiquidateError::MissingPrice
+    );
+    let pair = LiquidationPair {
+        repay_price_usd: borrow.market_price_usd,
+        repay_decimals: repay_reserve.token_decimals,
+        collateral_price_usd: deposit.market_price_usd,
+        collateral_decimals: collateral_reserve.token_decimals,
+    };
+
+    // The market bonus starts higher for newly liquidatable obligations and
+    // decays to the base; same e-mode category pairs (e.g. stable-stable)
+    // are capped at the reduced e-mode bonus
+    let market_bonus_bps = lending_market
+        .decayed_liquidation_bonus_bps(obligation.unhealthy_slots(clock.slot));
+    let bonus_bps = collateral_reserve.config.liquidation_bonus_bps(
+        &repay_reserve.config,
+        market_bonus_bps,
+    );
+
+    // Estimate health after repaying a given amount, from cached USD values
+    let borrow_value_usd = borrow.market_value_usd;
+    let deposit_value_usd = deposit.market_value_usd;
+    let collateral_threshold_bps = collateral_reserve.config.liquidation_threshold_bps as u128;
+    let estimate_health_after = |repay: u64| -> Option<u64> {
+        let repay_value_usd = mul_div_floor(repay as u128, borrow_value_usd, current_borrow_amount as u128)?;
+        let seized = pair.collateral_for_repay(repay, bonus_bps)?.min(current_deposit_amount as u128);
+        let seized_value_usd = mul_div_floor(seized, deposit_value_usd, current_deposit_amount as u128)?;
+        let seized_unhealthy_usd = mul_div_floor(seized_value_usd, collateral_threshold_bps, 10000)?;
+        Some(obligation.health_after_liquidation(repay_value_usd, seized_unhealthy_usd))
     };
 
     // Calculate maximum repayable (close factor)
     // close_factor = 50% means can only repay half the debt at once
-    let max_repay = (current_borrow_amount as u128 * lending_market.close_factor_bps as u128 / 10000) as u64;
+    // Volatile debt may use a higher per-reserve close factor
+    let close_factor_bps = repay_reserve
+        .config
+        .effective_close_factor_bps(lending_market.close_factor_bps);
+    let close_factor_repay = mul_bps_floor(current_borrow_amount, close_factor_bps as u64)
+        .ok_or(LiquidateError::MathOverflow)?;
+
+    // Largest repayment whose bonus-inclusive seizure fits in the deposit
+    let max_seizable_repay = pair
+        .repay_for_collateral(current_deposit_amount, bonus_bps)
+        .ok_or(LiquidateError::MathOverflow)?;
+
+    // With hysteresis, a liquidation may go past the close factor far enough
+    // to restore health to 1.0 + band
+    let hysteresis_bps = lending_market.liquidation_hysteresis_bps;
+    let max_repay = if hysteresis_bps > 0 {
+        let target_health = HEALTH_FACTOR_ONE + hysteresis_bps as u64;
+        match min_repay_for_health(
+            current_borrow_amount.min(max_seizable_repay),
+            target_health,
+            &estimate_health_after,
+        ) {
+            Some(target_repay) => close_factor_repay.max(target_repay),
+            None => close_factor_repay,
+        }
+    } else {
+        close_factor_repay
+    };
 
     // Determine actual repay amount
-    let actual_repay = repay_amount.min(max_repay).min(current_borrow_amount);
-    require!(actual_repay > 0, LiquidateError::RepayAmountTooSmall);
-
-    // Calculate collateral to seize
-    // In production, this should use oracle prices for proper conversion
-    // collateral_value = repay_value * (1 + liquidation_bonus)
-    //
-    // Simplified calculation (assumes 1:1 price ratio):
-    // In production: collateral_amount = (repay_amount * repay_price / collateral_price) * (1 + bonus)
-    let bonus_bps = lending_market.liquidation_bonus_bps as u128;
-    let collateral_to_seize = (actual_repay as u128 * (10000 + bonus_bps) / 10000) as u64;
-
-    // Verify enough collateral to seize
-    let deposit = &obligation.deposits[deposit_index];
-    let current_supply_index = collateral_reserve.liquidity.cumulative_supply_index;
-    let current_deposit_amount = if deposit.supply_index_snapshot > 0 {
-        (deposit.deposited_amount as u128 * current_supply_index / deposit.supply_index_snapshot) as u64
+    let mut actual_repay = repay_amount.min(max_repay).min(current_borrow_amount);
+
+    // Calculate collateral to seize:
+    // collateral_amount = repay_amount * repay_price / collateral_price * (1 + bonus)
+    let collateral_with_bonus = pair
+        .collateral_for_repay(actual_repay, bonus_bps)
+        .ok_or(LiquidateError::MathOverflow)?;
+
+    // When the deposit can't cover repay plus bonus, seize all of it and
+    // reduce the repayment to what it covers
+    let collateral_to_seize = if collateral_with_bonus > current_deposit_amount as u128 {
+        actual_repay = max_seizable_repay;
+        current_deposit_amount
     } else {
-        deposit.deposited_amount
+        u64::try_from(collateral_with_bonus).map_err(|_| LiquidateError::MathOverflow)?
     };
+    require!(actual_repay > 0 && collateral_to_seize > 0, LiquidateError::RepayAmountTooSmall);
 
+    // Verify enough collateral to seize
     require!(
         collateral_to_seize <= current_deposit_amount,
         LiquidateError::InsufficientCollateral
@@ -172,9 +303,23 @@ pub fn handler(ctx: Context<Liquidate>, repay_amount: u64) -> Result<()> {
     token::transfer(transfer_repay_ctx, actual_repay)?;
 
     // 2. Calculate protocol fee and liquidator reward
-    let liquidation_bonus_amount = collateral_to_seize.saturating_sub(actual_repay);
-    let protocol_fee = (liquidation_bonus_amount as u128 * lending_market.protocol_fee_bps as u128 / 10000) as u64;
-    let liquidator_reward = collateral_to_seize.saturating_sub(protocol_fee);
+    // The bonus is the seized collateral above the repayment's value;
+    // checked so an inconsistent split errors instead of mis-paying
+    let repay_in_collateral = pair
+        .collateral_for_repay(actual_repay, 0)
+        .and_then(|amount| u64::try_from(amount).ok())
+        .ok_or(LiquidateError::MathOverflow)?;
+    let liquidation_bonus_amount = collateral_to_seize
+        .checked_sub(repay_in_collateral)
+        .ok_or(LiquidateError::MathOverflow)?;
+    let protocol_fee_bps = collateral_reserve
+        .config
+        .effective_liquidation_protocol_fee_bps(lending_market.protocol_fee_bps);
+    let protocol_fee = mul_bps_floor(liquidation_bonus_amount, protocol_fee_bps as u64)
+        .ok_or(LiquidateError::MathOverflow)?;
+    let liquidator_reward = collateral_to_seize
+        .checked_sub(protocol_fee)
+        .ok_or(LiquidateError::MathOverflow)?;
 
     // 3. Transfer collateral to liquidator (minus protocol fee) using PDA signer
     let seeds = &[
@@ -196,8 +341,15 @@ pub fn handler(ctx: Context<Liquidate>, repay_amount: u64) -> Result<()> {
     );
     token::transfer(transfer_collateral_ctx, liquidator_reward)?;
 
-    // 4. Transfer protocol fee to fee receiver
-    if protocol_fee > 0 {
+    // 4. Route protocol fee: keep it in the vault as reserve fees, or
+    //    transfer it to the fee receiver
+    if protocol_fee > 0 && lending_market.route_liquidation_fees_to_reserve {
+        collateral_reserve.liquidity.accumulated_protocol_fees = collateral_reserve
+            .liquidity
+            .accumulated_protocol_fees
+            .checked_add(protocol_fee)
+            .ok_or(LiquidateError::MathOverflow)?;
+    } else if protocol_fee > 0 {
         let transfer_fee_ctx = CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
             Transfer {
@@ -218,14 +370,62 @@ pub fn handler(ctx: Context<Liquidate>, repay_amount: u64) -> Result<()> {
     collateral_reserve.liquidity.total_deposits = collateral_reserve.liquidity.total_deposits
         .saturating_sub(collateral_to_seize);
 
+    // Track lifetime liquidation activity on both reserves
+    repay_reserve.liquidity.lifetime_liquidation_repaid = repay_reserve.liquidity.lifetime_liquidation_repaid
+        .checked_add(actual_repay as u128)
+        .ok_or(LiquidateError::MathOverflow)?;
+    repay_reserve.liquidity.liquidation_count = repay_reserve.liquidity.liquidation_count.saturating_add(1);
+    collateral_reserve.liquidity.lifetime_liquidation_seized = collateral_reserve.liquidity.lifetime_liquidation_seized
+        .checked_add(collateral_to_seize as u128)
+        .ok_or(LiquidateError::MathOverflow)?;
+    collateral_reserve.liquidity.liquidation_count = collateral_reserve.liquidity.liquidation_count.saturating_add(1);
+
+    // Keep cached values consistent until the next refresh: the repaid debt
+    // is removed rounding down and the seized collateral rounding up (at the
+    // higher of its base and e-mode parameters), so cached health never
+    // reads better than the position. last_update_slot is left untouched.
+    let repaid_value_usd = mul_div_floor(
+        actual_repay as u128,
+        obligation.borrows[borrow_index].market_value_usd,
+        current_borrow_amount as u128,
+    )
+    .ok_or(LiquidateError::MathOverflow)?;
+    let seized_value_usd = mul_div_ceil(
+        collateral_to_seize as u128,
+        obligation.deposits[deposit_index].market_value_usd,
+        current_deposit_amount as u128,
+    )
+    .ok_or(LiquidateError::MathOverflow)?
+    .min(obligation.deposits[deposit_index].market_value_usd);
+    let (seized_ltv_bps, seized_threshold_bps) = collateral_reserve.config.risk_params(true);
+
+    // The hysteresis band re-arms from the health this liquidation actually
+    // leaves, recomputed from the updated cached values
+    let health_after_liquidation = obligation
+        .apply_liquidation_to_cache(
+            borrow_index,
+            deposit_index,
+            repaid_value_usd,
+            seized_value_usd,
+            seized_ltv_bps,
+            seized_threshold_bps,
+        )
+        .ok_or(LiquidateError::MathOverflow)?;
+
     // Update obligation borrow
     let remaining_borrow = current_borrow_amount.saturating_sub(actual_repay);
+    let (principal_repaid, _) = obligation.borrows[borrow_index]
+        .split_repayment(current_borrow_amount, actual_repay);
+    repay_reserve.total_borrow_principal = repay_reserve.total_borrow_principal
+        .saturating_sub(principal_repaid);
     if remaining_borrow == 0 {
         obligation.borrows.remove(borrow_index);
+        repay_reserve.liquidity.borrower_count = repay_reserve.liquidity.borrower_count.saturating_sub(1);
     } else {
         let borrow = &mut obligation.borrows[borrow_index];
         borrow.borrowed_amount = remaining_borrow;
         borrow.borrow_index_snapshot = current_borrow_index;
+        borrow.principal = borrow.principal.saturating_sub(principal_repaid);
     }
 
     // Update obligation deposit (need to recalculate index after borrow removal might have shifted)
@@ -236,19 +436,25 @@ pub fn handler(ctx: Context<Liquidate>, repay_amount: u64) -> Result<()> {
     let remaining_deposit = current_deposit_amount.saturating_sub(collateral_to_seize);
     if remaining_deposit == 0 {
         obligation.deposits.remove(deposit_index);
+        collateral_reserve.liquidity.depositor_count = collateral_reserve.liquidity.depositor_count.saturating_sub(1);
     } else {
         let deposit = &mut obligation.deposits[deposit_index];
         deposit.deposited_amount = remaining_deposit;
         deposit.supply_index_snapshot = current_supply_index;
+        // Removed collateral comes out of the matured part first
+        deposit.pending_amount = deposit.pending_amount.min(remaining_deposit);
     }
 
-    // Update timestamps
-    let clock = Clock::get()?;
-    repay_reserve.last_update_slot = clock.slot;
-    repay_reserve.last_update_timestamp = clock.unix_timestamp;
-    collateral_reserve.last_update_slot = clock.slot;
-    collateral_reserve.last_update_timestamp = clock.unix_timestamp;
-    obligation.last_update_slot = clock.slot;
+    obligation.last_liquidation_health_bps = health_after_liquidation;
+
+    // All collateral seized with debt remaining: the rest of this borrow
+    // can no longer be liquidated. It stays on the obligation as bad debt
+    // for the protocol to write off.
+    let has_collateral_left = obligation
+        .deposits
+        .iter()
+        .any(|deposit| deposit.use_as_collateral);
+    let residual_bad_debt = if has_collateral_left { 0 } else { remaining_borrow };
 
     // Emit liquidation event
     emit!(LiquidationEvent {
@@ -262,6 +468,13 @@ pub fn handler(ctx: Context<Liquidate>, repay_amount: u64) -> Result<()> {
         collateral_seized: collateral_to_seize,
         liquidation_bonus: liquidation_bonus_amount,
         protocol_fee,
+        protocol_fee_bps,
+        liquidation_bonus_bps: bonus_bps,
+        repay_reserve_borrower_count: repay_reserve.liquidity.borrower_count,
+        collateral_reserve_depositor_count: collateral_reserve.liquidity.depositor_count,
+        repay_reserve_lifetime_liquidation_repaid: repay_reserve.liquidity.lifetime_liquidation_repaid,
+        collateral_reserve_lifetime_liquidation_seized: collateral_reserve.liquidity.lifetime_liquidation_seized,
+        residual_bad_debt,
         timestamp: clock.unix_timestamp,
     });
 
@@ -270,13 +483,102 @@ pub fn handler(ctx: Context<Liquidate>, repay_amount: u64) -> Result<()> {
     msg!("Total collateral seized: {} tokens", collateral_to_seize);
     msg!("Liquidator received: {} tokens", liquidator_reward);
     msg!("Protocol fee collected: {} tokens", protocol_fee);
+    if residual_bad_debt > 0 {
+        msg!("All collateral seized, residual bad debt: {} tokens", residual_bad_debt);
+    }
 
     Ok(())
 }
 
+/// Prices and decimals of the repaid and seized assets of a liquidation
+#[derive(Clone, Copy, Debug)]
+pub struct LiquidationPair {
+    /// Repaid token price in USD (scaled by 10^6 per whole token)
+    pub repay_price_usd: u64,
+
+    /// Repaid token decimals
+    pub repay_decimals: u8,
+
+    /// Collateral token price in USD (scaled by 10^6 per whole token)
+    pub collateral_price_usd: u64,
+
+    /// Collateral token decimals
+    pub collateral_decimals: u8,
+}
+
+impl LiquidationPair {
+    /// Collateral worth `repay` of debt plus `bonus_bps`, rounded down
+    ///
+    /// collateral = repay * repay_price * 10^collateral_decimals * (1 + bonus)
+    ///            / (collateral_price * 10^repay_decimals)
+    /// Returns None on overflow or a zero collateral price.
+    pub fn collateral_for_repay(&self, repay: u64, bonus_bps: u16) -> Option<u128> {
+        let repay_value = repay as u128 * self.repay_price_usd as u128;
+        let (up, down) = decimal_scales(self.repay_decimals, self.collateral_decimals)?;
+        let numerator_scale = up.checked_mul(10000 + bonus_bps as u128)?;
+        let denominator = (self.collateral_price_usd as u128)
+            .checked_mul(down)?
+            .checked_mul(10000)?;
+        mul_div_floor_wide(repay_value, numerator_scale, denominator)
+    }
+
+    /// Largest repayment whose bonus-inclusive seizure fits in `collateral`,
+    /// rounded down
+    /// Returns None on overflow or a zero repay price.
+    pub fn repay_for_collateral(&self, collateral: u64, bonus_bps: u16) -> Option<u64> {
+        let collateral_value = collateral as u128 * self.collateral_price_usd as u128;
+        let (up, down) = decimal_scales(self.collateral_decimals, self.repay_decimals)?;
+        let numerator_scale = up.checked_mul(10000)?;
+        let denominator = (self.repay_price_usd as u128)
+            .checked_mul(down)?
+            .checked_mul(10000 + bonus_bps as u128)?;
+        let repay = mul_div_floor_wide(collateral_value, numerator_scale, denominator)?;
+        Some(u64::try_from(repay).unwrap_or(u64::MAX))
+    }
+}
+
+/// Factors (multiply, divide) converting amounts from `from_decimals` to
+/// `to_decimals`; one of them is always 1, keeping intermediates small
+fn decimal_scales(from_decimals: u8, to_decimals: u8) -> Option<(u128, u128)> {
+    if to_decimals >= from_decimals {
+        Some((10u128.checked_pow((to_decimals - from_decimals) as u32)?, 1))
+    } else {
+        Some((1, 10u128.checked_pow((from_decimals - to_decimals) as u32)?))
+    }
+}
+
+/// Find the smallest repay amount (up to `max_repay`) whose estimated
+/// post-liquidation health reaches `target_health`
+///
+/// Returns None if even `max_repay` falls short, e.g. when the liquidation
+/// bonus makes each repayment worsen health.
+fn min_repay_for_health(
+    max_repay: u64,
+    target_health: u64,
+    estimate_health_after: &impl Fn(u64) -> Option<u64>,
+) -> Option<u64> {
+    if estimate_health_after(max_repay)? < target_health {
+        return None;
+    }
+
+    let (mut low, mut high) = (0u64, max_repay);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if estimate_health_after(mid)? >= target_health {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    Some(high)
+}
+
 /// Liquidation errors
 #[error_code]
 pub enum LiquidateError {
+    #[msg("Liquidations are halted")]
+    LiquidationsHalted,
+
     #[msg("Reserve does not belong to this lending market")]
     InvalidReserve,
 
@@ -286,9 +588,21 @@ pub enum LiquidateError {
     #[msg("Invalid vault account")]
     InvalidVault,
 
+    #[msg("Token program does not match the reserve's token program")]
+    InvalidTokenProgram,
+
     #[msg("Invalid fee receiver account")]
     InvalidFeeReceiver,
 
+    #[msg("Reserve token accounts failed validation")]
+    InvalidReserveAccounts,
+
+    #[msg("Obligation was not refreshed recently enough to liquidate")]
+    ObligationStale,
+
+    #[msg("Obligation is within the liquidation hysteresis band")]
+    WithinHysteresisBand,
+
     #[msg("Token mint mismatch")]
     InvalidTokenMint,
 
@@ -304,12 +618,151 @@ pub enum LiquidateError {
     #[msg("No collateral found for collateral reserve")]
     NoCollateralFound,
 
+    #[msg("Deposit is not used as collateral and cannot be seized")]
+    NotCollateral,
+
+    #[msg("Collateral reserve is not seizable, choose another collateral")]
+    CollateralNotSeizable,
+
+    #[msg("Liquidation limit for this obligation reached in the current slot")]
+    LiquidationLimitReached,
+
     #[msg("Repay amount too small")]
     RepayAmountTooSmall,
 
     #[msg("Insufficient collateral to seize")]
     InsufficientCollateral,
 
+    #[msg("Reserve data is stale, refresh required")]
+    ReserveStale,
+
+    #[msg("Obligation has no cached price for a position, refresh required")]
+    MissingPrice,
+
     #[msg("Math overflow")]
     MathOverflow,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const USDC_SOL: LiquidationPair = LiquidationPair {
+        repay_price_usd: 1_000_000,
+        repay_decimals: 6,
+        collateral_price_usd: 100_000_000,
+        collateral_decimals: 9,
+    };
+
+    const SAME_ASSET: LiquidationPair = LiquidationPair {
+        repay_price_usd: 1_000_000,
+        repay_decimals: 6,
+        collateral_price_usd: 1_000_000,
+        collateral_decimals: 6,
+    };
+
+    #[test]
+    fn seizure_converts_through_both_prices() {
+        // 100 USDC at a 5% bonus buys 1.05 SOL at $100
+        assert_eq!(USDC_SOL.collateral_for_repay(100_000_000, 500), Some(1_050_000_000));
+        assert_eq!(USDC_SOL.collateral_for_repay(100_000_000, 0), Some(1_000_000_000));
+        assert_eq!(SAME_ASSET.collateral_for_repay(1_000, 500), Some(1_050));
+    }
+
+    #[test]
+    fn max_repay_inverts_the_seizure() {
+        assert_eq!(USDC_SOL.repay_for_collateral(1_050_000_000, 500), Some(100_000_000));
+        assert_eq!(SAME_ASSET.repay_for_collateral(1_050, 500), Some(1_000));
+    }
+
+    #[test]
+    fn zero_prices_do_not_convert() {
+        let pair = LiquidationPair { collateral_price_usd: 0, ..USDC_SOL };
+        assert_eq!(pair.collateral_for_repay(100, 500), None);
+        let pair = LiquidationPair { repay_price_usd: 0, ..USDC_SOL };
+        assert_eq!(pair.repay_for_collateral(100, 500), None);
+    }
+
+    #[test]
+    fn large_repay_exceeds_u64_instead_of_wrapping() {
+        // The bonus-inclusive seizure of u64::MAX is reported in full, so the
+        // caller's u64 conversion errors rather than truncating
+        let seized = SAME_ASSET.collateral_for_repay(u64::MAX, 500).unwrap();
+        assert!(seized > u64::MAX as u128);
+        assert_eq!(seized, u64::MAX as u128 * 10500 / 10000);
+    }
+
+    #[test]
+    fn extreme_prices_overflow_to_none() {
+        let pair = LiquidationPair {
+            repay_price_usd: u64::MAX,
+            repay_decimals: 0,
+            collateral_price_usd: 1,
+            collateral_decimals: 18,
+        };
+        assert_eq!(pair.collateral_for_repay(u64::MAX, 500), None);
+        assert_eq!(pair.repay_for_collateral(u64::MAX, 500), None);
+    }
+
+    #[test]
+    fn large_amounts_across_decimals_convert_exactly() {
+        // 10^8 whole tokens of a $100k, 8-decimal asset repaid into a $1 stablecoin
+        let pair = LiquidationPair {
+            repay_price_usd: 100_000_000_000,
+            repay_decimals: 8,
+            collateral_price_usd: 1_000_000,
+            collateral_decimals: 6,
+        };
+        let repay = 10_000_000_000_000_000u64;
+        let seized = pair.collateral_for_repay(repay, 0).unwrap();
+        assert_eq!(seized, repay as u128 * 100_000 / 100);
+    }
+
+    #[test]
+    fn max_repay_near_u64_max_fits() {
+        let repay = SAME_ASSET.repay_for_collateral(u64::MAX, 500).unwrap();
+        assert_eq!(repay as u128, u64::MAX as u128 * 10000 / 10500);
+        assert!(SAME_ASSET.collateral_for_repay(repay, 500).unwrap() <= u64::MAX as u128);
+    }
+
+    #[test]
+    fn min_repay_search_finds_the_smallest_restoring_repay() {
+        let estimate = |repay: u64| Some(9_000 + repay);
+        assert_eq!(min_repay_for_health(5_000, 10_200, &estimate), Some(1_200));
+        assert_eq!(min_repay_for_health(1_000, 10_200, &estimate), None);
+    }
+
+    proptest! {
+        #[test]
+        fn max_seizable_repay_never_seizes_more_than_the_deposit(
+            collateral in 0u64..=u64::MAX / 2,
+            bonus_bps in 0u16..=2_000,
+            repay_price in 1u64..=1_000_000_000_000,
+            collateral_price in 1u64..=1_000_000_000_000,
+            repay_decimals in 0u8..=9,
+            collateral_decimals in 0u8..=9,
+        ) {
+            let pair = LiquidationPair {
+                repay_price_usd: repay_price,
+                repay_decimals,
+                collateral_price_usd: collateral_price,
+                collateral_decimals,
+            };
+            let repay = pair.repay_for_collateral(collateral, bonus_bps).unwrap();
+            if let Some(seized) = pair.collateral_for_repay(repay, bonus_bps) {
+                prop_assert!(seized <= collateral as u128);
+            }
+        }
+
+        #[test]
+        fn bonus_never_reduces_the_seizure(
+            repay in 0u64..=1_000_000_000_000_000,
+            bonus_bps in 0u16..=2_000,
+        ) {
+            let without = USDC_SOL.collateral_for_repay(repay, 0).unwrap();
+            let with = USDC_SOL.collateral_for_repay(repay, bonus_bps).unwrap();
+            prop_assert!(with >= without);
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{LendingMarket, Obligation};
+use crate::constants::MAX_OBLIGATION_STALENESS_SLOTS;
+use crate::events::MarketSolvency as MarketSolvencyEvent;
+use super::market_totals::sum_market_totals;
+
+/// Accounts for reporting a lending market's solvency
+///
+/// remaining_accounts follow the `MarketTotals` layout: for every reserve of
+/// the market, the reserve, its oracle and its quote oracle (if set). They
+/// are followed by the market's obligations with debt, each refreshed within
+/// MAX_OBLIGATION_STALENESS_SLOTS.
+#[derive(Accounts)]
+pub struct MarketSolvency<'info> {
+    /// The lending market
+    #[account(
+        seeds = [LendingMarket::SEED_PREFIX, lending_market.authority.as_ref()],
+        bump = lending_market.bump
+    )]
+    pub lending_market: Account<'info, LendingMarket>,
+}
+
+/// Report the market-wide collateralization ratio
+///
+/// Compares the collateral of borrowing obligations with total borrows
+/// across all reserves, in USD, and emits a `MarketSolvency` event. The
+/// market is solvent while collateral covers debt.
+///
+/// Debt comes from the reserves, which must all be passed. Collateral is
+/// the cached value of deposits enabled as collateral on the passed
+/// obligations; obligations without debt back nothing and are skipped.
+/// Leaving an obligation out can only understate collateral, so an
+/// incomplete list never reports a market as more solvent than it is.
+///
+/// # Arguments
+/// * `ctx` - The context containing all accounts
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, MarketSolvency<'info>>,
+) -> Result<()> {
+    let lending_market_key = ctx.accounts.lending_market.key();
+    let clock = Clock::get()?;
+
+    let mut remaining_accounts = ctx.remaining_accounts.iter();
+    let totals = sum_market_totals(
+        &ctx.accounts.lending_market,
+        &mut remaining_accounts,
+        clock.slot,
+    )?;
+
+    let mut total_collateral_usd: u128 = 0;
+    let mut seen: Vec<Pubkey> = Vec::new();
+    for obligation_info in remaining_accounts {
+        require!(
+            !seen.contains(obligation_info.key),
+            MarketSolvencyError::DuplicateObligation
+        );
+        seen.push(obligation_info.key());
+
+        require_keys_eq!(
+            *obligation_info.owner,
+            crate::ID,
+            MarketSolvencyError::InvalidObligation
+        );
+        let obligation = Obligation::try_deserialize(&mut &obligation_info.try_borrow_data()?[..])?;
+        require_keys_eq!(
+            obligation.lending_market,
+            lending_market_key,
+            MarketSolvencyError::InvalidObligation
+        );
+        require!(
+            clock.slot.saturating_sub(obligation.last_update_slot) <= MAX_OBLIGATION_STALENESS_SLOTS,
+            MarketSolvencyError::ObligationStale
+        );
+
+        if obligation.borrows.is_empty() {
+            continue;
+        }
+        for deposit in obligation.deposits.iter().filter(|deposit| deposit.use_as_collateral) {
+            total_collateral_usd = total_collateral_usd
+                .checked_add(deposit.market_value_usd)
+                .ok_or(MarketSolvencyError::MathOverflow)?;
+        }
+    }
+
+    // ratio = collateral / debt * 10000 (u64::MAX with no debt)
+    let collateralization_ratio_bps = match total_collateral_usd
+        .checked_mul(10000)
+        .ok_or(MarketSolvencyError::MathOverflow)?
+        .checked_div(totals.total_borrows_usd)
+    {
+        Some(ratio) => u64::try_from(ratio).unwrap_or(u64::MAX),
+        None => u64::MAX,
+    };
+    let solvent = total_collateral_usd >= totals.total_borrows_usd;
+
+    emit!(MarketSolvencyEvent {
+        lending_market: lending_market_key,
+        total_collateral_usd,
+        total_debt_usd: totals.total_borrows_usd,
+        collateralization_ratio_bps,
+        solvent,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Collateralization ratio: {} bps", collateralization_ratio_bps);
+    if !solvent {
+        msg!("MARKET UNDERCOLLATERALIZED");
+    }
+
+    Ok(())
+}
+
+/// Market solvency errors
+#[error_code]
+pub enum MarketSolvencyError {
+    #[msg("Obligation account is invalid or belongs to another lending market")]
+    InvalidObligation,
+
+    #[msg("Obligation passed more than once")]
+    DuplicateObligation,
+
+    #[msg("Obligation was not refreshed recently enough")]
+    ObligationStale,
+
+    #[msg("Math overflow")]
+    MathOverflow,
+}
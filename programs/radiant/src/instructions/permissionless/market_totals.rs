@@ -0,0 +1,162 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{LendingMarket, Reserve};
+use crate::oracle::{get_reserve_price_usd, token_amount_to_usd};
+
+/// Accounts for summing a lending market's deposits and borrows in USD
+///
+/// remaining_accounts must contain, for every reserve of the market (each once):
+/// - The reserve
+/// - The reserve's oracle
+/// - The reserve's quote oracle (only if `price_quote_oracle` is set)
+#[derive(Accounts)]
+pub struct MarketTotals<'info> {
+    /// The lending market
+    #[account(
+        seeds = [LendingMarket::SEED_PREFIX, lending_market.authority.as_ref()],
+        bump = lending_market.bump
+    )]
+    pub lending_market: Account<'info, LendingMarket>,
+}
+
+/// Market-wide totals, returned to the caller
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct MarketTotalsResult {
+    /// Sum of all reserve deposits in USD (scaled by 10^6)
+    pub total_deposits_usd: u128,
+
+    /// Sum of all reserve borrows in USD (scaled by 10^6)
+    pub total_borrows_usd: u128,
+
+    /// Number of reserves included
+    pub reserves_count: u8,
+}
+
+/// Sum every reserve's deposits and borrows in USD
+///
+/// Uses each reserve's stored totals as of its last refresh, priced through
+/// its oracle. Every reserve of the market must be passed so the totals are
+/// complete.
+///
+/// # Returns
+/// * The USD deposit and borrow totals for the market
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, MarketTotals<'info>>,
+) -> Result<MarketTotalsResult> {
+    let mut remaining_accounts = ctx.remaining_accounts.iter();
+    let totals = sum_market_totals(
+        &ctx.accounts.lending_market,
+        &mut remaining_accounts,
+        Clock::get()?.slot,
+    )?;
+    require!(
+        remaining_accounts.next().is_none(),
+        MarketTotalsError::UnexpectedAccounts
+    );
+
+    msg!("Market totals over {} reserves", totals.reserves_count);
+    msg!(
+        "Deposits: {} USD (6 dp), Borrows: {} USD (6 dp)",
+        totals.total_deposits_usd,
+        totals.total_borrows_usd
+    );
+
+    Ok(totals)
+}
+
+/// Sum the USD deposits and borrows of every reserve in `remaining_accounts`
+///
+/// Expects the [reserve, oracle, (quote oracle)] layout described on
+/// `MarketTotals` and requires every reserve of the market exactly once.
+/// Consumes only the reserve accounts, leaving any that follow to the caller.
+pub fn sum_market_totals<'a, 'info: 'a>(
+    lending_market: &Account<LendingMarket>,
+    remaining_accounts: &mut impl Iterator<Item = &'a AccountInfo<'info>>,
+    current_slot: u64,
+) -> Result<MarketTotalsResult> {
+    let lending_market_key = lending_market.key();
+
+    let mut total_deposits_usd: u128 = 0;
+    let mut total_borrows_usd: u128 = 0;
+    let mut seen: Vec<Pubkey> = Vec::with_capacity(lending_market.reserves_count as usize);
+
+    // Partial totals would understate the market
+    while seen.len() < lending_market.reserves_count as usize {
+        let reserve_info = remaining_accounts
+            .next()
+            .ok_or(MarketTotalsError::IncompleteReserves)?;
+        require!(
+            !seen.contains(reserve_info.key),
+            MarketTotalsError::DuplicateReserve
+        );
+        seen.push(reserve_info.key());
+
+        require_keys_eq!(
+            *reserve_info.owner,
+            crate::ID,
+            MarketTotalsError::InvalidReserveAccount
+        );
+        let reserve = Reserve::try_deserialize(&mut &reserve_info.try_borrow_data()?[..])?;
+        require_keys_eq!(
+            reserve.lending_market,
+            lending_market_key,
+            MarketTotalsError::InvalidReserveAccount
+        );
+
+        let oracle_info = remaining_accounts
+            .next()
+            .ok_or(MarketTotalsError::OracleNotFound)?;
+        let quote_oracle_info = if reserve.price_quote_oracle.is_some() {
+            remaining_accounts.next()
+        } else {
+            None
+        };
+        let price_usd = get_reserve_price_usd(&reserve, oracle_info, quote_oracle_info, current_slot)?;
+
+        let deposits_usd = token_amount_to_usd(
+            reserve.liquidity.total_deposits,
+            price_usd,
+            reserve.token_decimals,
+        )?;
+        let borrows_usd = token_amount_to_usd(
+            reserve.liquidity.total_borrows,
+            price_usd,
+            reserve.token_decimals,
+        )?;
+
+        total_deposits_usd = total_deposits_usd
+            .checked_add(deposits_usd)
+            .ok_or(MarketTotalsError::MathOverflow)?;
+        total_borrows_usd = total_borrows_usd
+            .checked_add(borrows_usd)
+            .ok_or(MarketTotalsError::MathOverflow)?;
+    }
+
+    Ok(MarketTotalsResult {
+        total_deposits_usd,
+        total_borrows_usd,
+        reserves_count: lending_market.reserves_count,
+    })
+}
+
+/// Market totals errors
+#[error_code]
+pub enum MarketTotalsError {
+    #[msg("Reserve account is invalid or belongs to another lending market")]
+    InvalidReserveAccount,
+
+    #[msg("Reserve passed more than once")]
+    DuplicateReserve,
+
+    #[msg("Every reserve of the lending market must be passed")]
+    IncompleteReserves,
+
+    #[msg("Oracle not found in remaining accounts")]
+    OracleNotFound,
+
+    #[msg("Remaining accounts include accounts beyond the market's reserves")]
+    UnexpectedAccounts,
+
+    #[msg("Math overflow")]
+    MathOverflow,
+}
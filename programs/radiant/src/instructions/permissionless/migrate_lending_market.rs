@@ -0,0 +1,87 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+use crate::state::LendingMarket;
+use crate::state::legacy::{grow_account, read_legacy_account, LendingMarketV1};
+use crate::events::AccountMigrated;
+
+/// Accounts for migrating a version 1 lending market
+#[derive(Accounts)]
+pub struct MigrateLendingMarket<'info> {
+    /// Pays the rent for the larger account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The version 1 lending market
+    /// CHECK: Owner, discriminator and layout are checked by read_legacy_account
+    #[account(mut)]
+    pub lending_market: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Rewrite a version 1 lending market in the current layout
+///
+/// Fields added since version 1 take the defaults of
+/// initialize_lending_market, so the market behaves as it did before the
+/// upgrade until the authority changes them. The account grows to the
+/// current size, with the payer covering the extra rent.
+///
+/// # Arguments
+/// * `ctx` - The context containing all accounts
+pub fn handler(ctx: Context<MigrateLendingMarket>) -> Result<()> {
+    let account = ctx.accounts.lending_market.to_account_info();
+    let data_len = 8 + LendingMarket::INIT_SPACE;
+    let legacy: LendingMarketV1 =
+        read_legacy_account(&account, LendingMarket::DISCRIMINATOR, data_len)?;
+
+    let lending_market = LendingMarket {
+        version: LendingMarket::CURRENT_VERSION,
+        bump: legacy.bump,
+        authority: legacy.authority,
+        treasury: legacy.treasury,
+        emergency_mode: legacy.emergency_mode,
+        halt_liquidations: false,
+        close_factor_bps: legacy.close_factor_bps,
+        liquidation_bonus_bps: legacy.liquidation_bonus_bps,
+        protocol_fee_bps: legacy.protocol_fee_bps,
+        reserves_count: legacy.reserves_count,
+        allow_looping: false,
+        max_liquidations_per_slot: 0,
+        route_liquidation_fees_to_reserve: false,
+        min_config_update_interval_slots: 0,
+        max_obligation_borrow_usd: 0,
+        allow_new_obligations: true,
+        liquidation_hysteresis_bps: 0,
+        require_refresh_in_transaction: false,
+        max_liquidation_refresh_age_slots: 0,
+        emit_action_blocked_events: false,
+        borrow_capacity_buffer_bps: 0,
+        borrow_warning_health_bps: 0,
+        liquidation_bonus_decay_extra_bps: 0,
+        liquidation_bonus_decay_slots: 0,
+        max_obligation_deposits: 0,
+        max_obligation_borrows: 0,
+        _padding: [0u8; 128],
+    };
+
+    grow_account(
+        &account,
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        data_len,
+    )?;
+    lending_market.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+
+    emit!(AccountMigrated {
+        account: account.key(),
+        from_version: legacy.version,
+        to_version: lending_market.version,
+        data_len: data_len as u32,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Lending market migrated to version {}", lending_market.version);
+
+    Ok(())
+}
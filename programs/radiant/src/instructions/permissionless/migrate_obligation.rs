@@ -0,0 +1,101 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+use crate::state::{Obligation, ObligationCollateral, ObligationLiquidity};
+use crate::state::legacy::{grow_account, read_legacy_account, ObligationV1};
+use crate::events::AccountMigrated;
+
+/// Accounts for migrating a version 1 obligation
+#[derive(Accounts)]
+pub struct MigrateObligation<'info> {
+    /// Pays the rent for the larger account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The version 1 obligation
+    /// CHECK: Owner, discriminator and layout are checked by read_legacy_account
+    #[account(mut)]
+    pub obligation: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Rewrite a version 1 obligation in the current layout
+///
+/// Version 1 obligations had a single obligation per owner, so the migrated
+/// obligation takes id 0. Every deposit keeps counting as collateral, and
+/// each borrow's principal starts at its stored amount. Positions are
+/// sorted by reserve and the obligation must be refreshed before use.
+/// The account grows to the current size, with the payer covering the
+/// extra rent.
+///
+/// # Arguments
+/// * `ctx` - The context containing all accounts
+pub fn handler(ctx: Context<MigrateObligation>) -> Result<()> {
+    let account = ctx.accounts.obligation.to_account_info();
+    let data_len = 8 + Obligation::INIT_SPACE;
+    let legacy: ObligationV1 = read_legacy_account(&account, Obligation::DISCRIMINATOR, data_len)?;
+
+    let mut obligation = Obligation {
+        version: Obligation::SORTED_VERSION,
+        bump: legacy.bump,
+        lending_market: legacy.lending_market,
+        owner: legacy.owner,
+        // Cached values predate the current pricing; force a refresh
+        last_update_slot: 0,
+        deposits: legacy.deposits.iter()
+            .map(|deposit| ObligationCollateral {
+                market_value_usd: deposit.market_value_usd,
+                pending_amount: 0,
+                ..ObligationCollateral::new(
+                    deposit.reserve,
+                    deposit.deposited_amount,
+                    deposit.supply_index_snapshot,
+                    0,
+                )
+            })
+            .collect(),
+        borrows: legacy.borrows.iter()
+            .map(|borrow| ObligationLiquidity {
+                market_value_usd: borrow.market_value_usd,
+                ..ObligationLiquidity::new(
+                    borrow.reserve,
+                    borrow.borrowed_amount,
+                    borrow.borrow_index_snapshot,
+                )
+            })
+            .collect(),
+        deposited_value_usd: legacy.deposited_value_usd,
+        borrowed_value_usd: legacy.borrowed_value_usd,
+        allowed_borrow_value_usd: legacy.allowed_borrow_value_usd,
+        unhealthy_borrow_value_usd: legacy.unhealthy_borrow_value_usd,
+        last_liquidation_slot: 0,
+        liquidations_in_slot: 0,
+        last_withdraw_slot: 0,
+        obligation_id: 0,
+        last_liquidation_health_bps: 0,
+        unhealthy_since_slot: 0,
+        _padding: [0u8; 64],
+    };
+    obligation.normalize();
+
+    grow_account(
+        &account,
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        data_len,
+    )?;
+    obligation.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+
+    emit!(AccountMigrated {
+        account: account.key(),
+        from_version: legacy.version,
+        to_version: obligation.version,
+        data_len: data_len as u32,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Obligation migrated to version {} for user: {}", obligation.version, obligation.owner);
+
+    Ok(())
+}
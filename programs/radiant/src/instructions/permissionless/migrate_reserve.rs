@@ -0,0 +1,166 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use anchor_spl::token::TokenAccount;
+
+use crate::state::{
+    InterestModel,
+    InterestRateConfig,
+    RateKink,
+    Reserve,
+    ReserveConfig,
+    ReserveLiquidity,
+    MAX_EXTRA_RATE_KINKS,
+};
+use crate::state::legacy::{grow_account, read_legacy_account, MigrationError, ReserveV1};
+use crate::constants::{INDEX_ONE, MAX_ORACLE_STALENESS_SLOTS};
+use crate::events::AccountMigrated;
+
+/// Accounts for migrating a version 1 reserve
+#[derive(Accounts)]
+pub struct MigrateReserve<'info> {
+    /// Pays the rent for the larger account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The version 1 reserve
+    /// CHECK: Owner, discriminator and layout are checked by read_legacy_account
+    #[account(mut)]
+    pub reserve: UncheckedAccount<'info>,
+
+    /// The reserve's token vault (checked against the legacy reserve)
+    pub token_vault: Account<'info, TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Rewrite a version 1 reserve in the current layout
+///
+/// Config fields added since version 1 take the defaults of
+/// initialize_reserve, and new statistics start from zero. The account
+/// grows to the current size, with the payer covering the extra rent.
+///
+/// Version 1 never credited supply interest to total_deposits, so it is
+/// rebuilt from the vault as `vault + total_borrows - protocol fees`: what
+/// depositors can withdraw under the current accounting. Position counters
+/// start at zero and only count positions opened after the migration.
+///
+/// # Arguments
+/// * `ctx` - The context containing all accounts
+pub fn handler(ctx: Context<MigrateReserve>) -> Result<()> {
+    let account = ctx.accounts.reserve.to_account_info();
+    let data_len = 8 + Reserve::INIT_SPACE;
+    let legacy: ReserveV1 = read_legacy_account(&account, Reserve::DISCRIMINATOR, data_len)?;
+
+    let token_vault = &ctx.accounts.token_vault;
+    require_keys_eq!(token_vault.key(), legacy.token_vault, MigrationError::InvalidTokenVault);
+
+    let total_deposits = token_vault.amount
+        .checked_add(legacy.liquidity.total_borrows)
+        .ok_or(MigrationError::MathOverflow)?
+        .saturating_sub(legacy.liquidity.accumulated_protocol_fees);
+
+    let legacy_rates = legacy.config.interest_rate_config;
+    let reserve = Reserve {
+        version: Reserve::CURRENT_VERSION,
+        bump: legacy.bump,
+        lending_market: legacy.lending_market,
+        token_mint: legacy.token_mint,
+        token_decimals: legacy.token_decimals,
+        token_vault: legacy.token_vault,
+        fee_receiver: legacy.fee_receiver,
+        oracle: legacy.oracle,
+        price_quote_oracle: None,
+        last_update_slot: legacy.last_update_slot,
+        last_update_timestamp: legacy.last_update_timestamp,
+        config: ReserveConfig {
+            ltv_bps: legacy.config.ltv_bps,
+            liquidation_threshold_bps: legacy.config.liquidation_threshold_bps,
+            deposit_limit: legacy.config.deposit_limit,
+            borrow_limit: legacy.config.borrow_limit,
+            max_utilization_bps: 0,
+            withdrawal_reserve_bps: 0,
+            utilization_fee_threshold_bps: 0,
+            utilization_fee_max_bps: 0,
+            max_single_deposit: 0,
+            max_single_borrow: 0,
+            deposits_enabled: legacy.config.deposits_enabled,
+            borrows_enabled: legacy.config.borrows_enabled,
+            reset_accrual_on_borrow_enable: false,
+            flash_loans_enabled: false,
+            permissioned: false,
+            seizable: true,
+            emode_category: 0,
+            emode_ltv_bps: 0,
+            emode_liquidation_threshold_bps: 0,
+            emode_liquidation_bonus_bps: 0,
+            min_health_factor_for_borrow_bps: 0,
+            max_oracle_staleness_slots: MAX_ORACLE_STALENESS_SLOTS as u32,
+            initial_borrow_ltv_haircut_bps: 0,
+            initial_borrow_haircut_slots: 0,
+            collateral_activation_delay_slots: 0,
+            liquidation_protocol_fee_bps: None,
+            close_factor_bps: None,
+            negative_supply_rate_bps: 0,
+            interest_rate_config: InterestRateConfig {
+                optimal_utilization_bps: legacy_rates.optimal_utilization_bps,
+                base_rate_bps: legacy_rates.base_rate_bps,
+                slope1_bps: legacy_rates.slope1_bps,
+                slope2_bps: legacy_rates.slope2_bps,
+                reserve_factor_bps: legacy_rates.reserve_factor_bps,
+                low_util_threshold_bps: 0,
+                low_util_supply_subsidy_bps: 0,
+                extra_kinks: [RateKink::default(); MAX_EXTRA_RATE_KINKS],
+            },
+        },
+        liquidity: ReserveLiquidity {
+            total_deposits,
+            total_borrows: legacy.liquidity.total_borrows,
+            accumulated_protocol_fees: legacy.liquidity.accumulated_protocol_fees,
+            cumulative_borrow_index: legacy.liquidity.cumulative_borrow_index,
+            cumulative_supply_index: legacy.liquidity.cumulative_supply_index,
+            current_borrow_rate_bps: legacy.liquidity.current_borrow_rate_bps,
+            current_supply_rate_bps: legacy.liquidity.current_supply_rate_bps,
+            locked_seed_deposit: 0,
+            depositor_count: 0,
+            borrower_count: 0,
+            lifetime_borrow_interest_paid: 0,
+            protocol_owned_deposits: 0,
+            protocol_deposit_index_snapshot: INDEX_ONE,
+            last_borrow_timestamp: 0,
+            last_deposit_timestamp: 0,
+            lifetime_liquidation_repaid: 0,
+            lifetime_liquidation_seized: 0,
+            liquidation_count: 0,
+            borrow_interest_remainder: 0,
+        },
+        last_config_update_slot: 0,
+        fee_treasury: None,
+        token_program_id: *token_vault.to_account_info().owner,
+        interest_model: InterestModel::Compound,
+        // Version 1 tracked no principal; migrated reserves compound, so
+        // this total never drives accrual
+        total_borrow_principal: legacy.liquidity.total_borrows,
+        _padding: [0u8; 119],
+    };
+
+    grow_account(
+        &account,
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        data_len,
+    )?;
+    reserve.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+
+    emit!(AccountMigrated {
+        account: account.key(),
+        from_version: legacy.version,
+        to_version: reserve.version,
+        data_len: data_len as u32,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Reserve migrated to version {}: {}", reserve.version, reserve.token_mint);
+    msg!("Total deposits rebuilt from the vault: {}", total_deposits);
+
+    Ok(())
+}
@@ -1,7 +1,25 @@
 pub mod refresh_reserve;
 pub mod refresh_obligation;
 pub mod liquidate;
+pub mod preview_debt;
+pub mod get_reserve_state;
+pub mod sweep_rounding_dust;
+pub mod market_totals;
+pub mod market_solvency;
+pub mod normalize_obligation;
+pub mod migrate_lending_market;
+pub mod migrate_reserve;
+pub mod migrate_obligation;
 
 pub use refresh_reserve::*;
 pub use refresh_obligation::*;
 pub use liquidate::*;
+pub use preview_debt::*;
+pub use get_reserve_state::*;
+pub use sweep_rounding_dust::*;
+pub use market_totals::*;
+pub use market_solvency::*;
+pub use normalize_obligation::*;
+pub use migrate_lending_market::*;
+pub use migrate_reserve::*;
+pub use migrate_obligation::*;
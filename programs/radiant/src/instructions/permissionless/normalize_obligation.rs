@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{LendingMarket, Obligation};
+use crate::events::ObligationNormalized;
+
+/// Accounts for normalizing an obligation's position layout
+#[derive(Accounts)]
+pub struct NormalizeObligation<'info> {
+    /// The lending market
+    #[account(
+        seeds = [LendingMarket::SEED_PREFIX, lending_market.authority.as_ref()],
+        bump = lending_market.bump
+    )]
+    pub lending_market: Account<'info, LendingMarket>,
+
+    /// The obligation to normalize
+    #[account(
+        mut,
+        constraint = obligation.lending_market == lending_market.key() @ NormalizeObligationError::InvalidObligation
+    )]
+    pub obligation: Account<'info, Obligation>,
+}
+
+/// Sort an obligation's deposits and borrows by reserve
+///
+/// Migrates obligations created before positions were kept sorted and
+/// bumps them to `Obligation::SORTED_VERSION`. Positions are only
+/// reordered, never changed, and running it again is a no-op. Clients
+/// must pass refresh_obligation's remaining accounts in the new order.
+///
+/// # Arguments
+/// * `ctx` - The context containing all accounts
+pub fn handler(ctx: Context<NormalizeObligation>) -> Result<()> {
+    let obligation = &mut ctx.accounts.obligation;
+
+    let reordered = obligation.normalize();
+
+    emit!(ObligationNormalized {
+        obligation: obligation.key(),
+        version: obligation.version,
+        reordered,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Obligation normalized (version {}, reordered: {})", obligation.version, reordered);
+
+    Ok(())
+}
+
+/// Normalize obligation errors
+#[error_code]
+pub enum NormalizeObligationError {
+    #[msg("Obligation does not belong to this lending market")]
+    InvalidObligation,
+}
@@ -0,0 +1,82 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{Obligation, Reserve};
+use crate::constants::SECONDS_PER_YEAR;
+use crate::instructions::permissionless::refresh_reserve::calculate_compound_factor;
+
+/// Accounts for previewing an obligation's debt
+#[derive(Accounts)]
+pub struct PreviewDebt<'info> {
+    /// The reserve the debt is owed to
+    pub reserve: Account<'info, Reserve>,
+
+    /// The borrower's obligation
+    #[account(
+        constraint = obligation.lending_market == reserve.lending_market @ PreviewDebtError::InvalidObligation
+    )]
+    pub obligation: Account<'info, Obligation>,
+}
+
+/// Preview the current debt of an obligation in a reserve
+///
+/// Projects the reserve's borrow index forward to the current time using
+/// the same accrual math as `refresh_reserve`, so interest accrued since
+/// the last refresh is included. No state is written.
+///
+/// # Arguments
+/// * `ctx` - The context containing all accounts
+///
+/// # Returns
+/// * The current debt including accrued interest (in native units)
+pub fn handler(ctx: Context<PreviewDebt>) -> Result<u64> {
+    let reserve = &ctx.accounts.reserve;
+    let obligation = &ctx.accounts.obligation;
+    let clock = Clock::get()?;
+
+    let borrow_index = obligation
+        .find_borrow(&reserve.key())
+        .ok_or(PreviewDebtError::NoBorrowFound)?;
+    let borrow = &obligation.borrows[borrow_index];
+
+    // Project the borrow index as refresh_reserve would at this slot
+    let mut current_borrow_index = reserve.liquidity.cumulative_borrow_index;
+    let slots_elapsed = clock.slot.saturating_sub(reserve.last_update_slot);
+    let time_elapsed = clock.unix_timestamp.saturating_sub(reserve.last_update_timestamp);
+
+    if slots_elapsed > 0 && reserve.liquidity.total_borrows > 0 && time_elapsed > 0 {
+        let time_elapsed_capped = time_elapsed.min(SECONDS_PER_YEAR as i64);
+        let borrow_compound_factor = calculate_compound_factor(
+            reserve.liquidity.current_borrow_rate_bps,
+            time_elapsed_capped as u64,
+        )?;
+
+        let borrow_index_growth = reserve
+            .borrow_index_growth(borrow_compound_factor)
+            .ok_or(PreviewDebtError::MathOverflow)?;
+        current_borrow_index = current_borrow_index
+            .checked_add(borrow_index_growth)
+            .ok_or(PreviewDebtError::MathOverflow)?;
+    }
+
+    // Debt is owed by the user: round up
+    let current_debt = borrow
+        .current_amount(current_borrow_index, reserve.interest_model)
+        .ok_or(PreviewDebtError::MathOverflow)?;
+
+    msg!("Current debt: {} (stored principal {})", current_debt, borrow.borrowed_amount);
+
+    Ok(current_debt)
+}
+
+/// Preview debt errors
+#[error_code]
+pub enum PreviewDebtError {
+    #[msg("Obligation does not belong to this lending market")]
+    InvalidObligation,
+
+    #[msg("No borrow found for this reserve")]
+    NoBorrowFound,
+
+    #[msg("Math overflow")]
+    MathOverflow,
+}
@@ -1,11 +1,28 @@
 use anchor_lang::prelude::*;
 
-use crate::state::{LendingMarket, Obligation};
-use crate::constants::USD_SCALE;
+use crate::state::{is_plausible_price_move, LendingMarket, Obligation, Reserve, ReserveConfig};
+use crate::constants::{
+    MAX_OBLIGATION_STALENESS_SLOTS,
+    MAX_OBLIGATION_VALUE_USD,
+    MAX_POSITION_VALUE_USD,
+    MAX_PRICE_MOVE_MULTIPLIER,
+    MAX_REFRESH_REMAINING_ACCOUNTS,
+    MAX_RESERVE_STALENESS_SLOTS,
+    USD_SCALE,
+};
 use crate::events::ObligationRefreshed;
+use crate::oracle::{get_reserve_price_usd, token_amount_to_usd};
+use crate::math::mul_div_floor;
 
 /// Accounts for refreshing an obligation
-/// Note: In production, you'd pass all deposit/borrow reserves as remaining_accounts
+///
+/// remaining_accounts must contain, for each deposit and then each borrow
+/// (in obligation order):
+/// - The position's reserve
+/// - The reserve's oracle
+/// - The reserve's quote oracle (only if `price_quote_oracle` is set)
+///
+/// No other accounts may be passed.
 #[derive(Accounts)]
 pub struct RefreshObligation<'info> {
     /// The lending market
@@ -21,89 +38,215 @@ pub struct RefreshObligation<'info> {
         constraint = obligation.lending_market == lending_market.key() @ RefreshObligationError::InvalidObligation
     )]
     pub obligation: Account<'info, Obligation>,
-    // In production, remaining_accounts would contain:
-    // - All deposit reserves (to get supply indexes and prices)
-    // - All borrow reserves (to get borrow indexes and prices)
+}
+
+/// Result of an obligation refresh, returned to the caller
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct ObligationRefreshResult {
+    /// Health factor in BPS (None = no debt)
+    pub health_factor: Option<u64>,
+
+    /// Slot after which the refreshed values should no longer be trusted
+    pub stale_after_slot: u64,
 }
 
 /// Refresh obligation state
 ///
 /// This permissionless instruction:
-/// 1. Updates deposit values with accrued interest
-/// 2. Updates borrow values with accrued interest
-/// 3. Recalculates USD values using oracle prices
+/// 1. Reads each position's reserve and oracle price from remaining_accounts
+/// 2. Recalculates USD values using oracle prices, rejecting implausible
+///    price moves since the last refresh (cached values are left untouched).
+///    Amounts include interest accrued since each position's index snapshot.
+/// 3. Applies each reserve's LTV and liquidation threshold
+///    (e-mode parameters when every position shares one nonzero category,
+///    LTV reduced by the initial borrow haircut for recently deposited
+///    amounts, and zero until the collateral activation delay has passed;
+///    a top-up never holds back the rest of the position)
 /// 4. Updates health factor cached values
 ///
 /// Anyone can call this to keep the obligation state fresh.
 /// Must be called before borrow, withdraw, or liquidate.
 ///
-/// Note: This is a simplified version. In production, you would:
-/// - Pass all deposit/borrow reserves as remaining_accounts
-/// - Read oracle prices for each asset
-/// - Calculate proper USD values
-pub fn handler(ctx: Context<RefreshObligation>) -> Result<()> {
+/// # Returns
+/// * The health factor and the slot at which it becomes stale
+pub fn handler(ctx: Context<RefreshObligation>) -> Result<ObligationRefreshResult> {
+    let lending_market_key = ctx.accounts.lending_market.key();
     let obligation = &mut ctx.accounts.obligation;
     let clock = Clock::get()?;
 
+    // Bound the work a caller can request; positions use at most 3 accounts each
+    require!(
+        ctx.remaining_accounts.len() <= MAX_REFRESH_REMAINING_ACCOUNTS,
+        RefreshObligationError::TooManyAccounts
+    );
+
+    let mut remaining_accounts = ctx.remaining_accounts.iter();
+    let slots_since_refresh = clock.slot.saturating_sub(obligation.last_update_slot);
+
     // Reset cached values
     let mut deposited_value_usd: u128 = 0;
     let mut borrowed_value_usd: u128 = 0;
     let mut allowed_borrow_value_usd: u128 = 0;
     let mut unhealthy_borrow_value_usd: u128 = 0;
 
-    // In production, you would iterate through remaining_accounts
-    // to get each reserve's current index and oracle price
-    //
-    // For now, we use a simplified approach where the reserves
-    // must be refreshed separately and we just update timestamps
+    // Collateral values (total and pending), configs and deposit slots,
+    // kept until the e-mode category is known
+    let mut deposit_risk: Vec<(u128, u128, ReserveConfig, u64)> = Vec::with_capacity(obligation.deposits.len());
+    let mut emode_categories: Vec<u8> =
+        Vec::with_capacity(obligation.deposits.len() + obligation.borrows.len());
 
     // Update each deposit's cached USD value
-    // Note: In production, this would use oracle prices
     for deposit in obligation.deposits.iter_mut() {
-        // Placeholder: In production, read from reserve account in remaining_accounts
-        // let reserve = get_reserve_from_remaining_accounts(deposit.reserve)?;
-        // let current_supply_index = reserve.liquidity.cumulative_supply_index;
-        // let price_usd = get_oracle_price(reserve.oracle)?;
+        let (reserve, price_usd) = load_reserve_price(
+            &mut remaining_accounts,
+            &deposit.reserve,
+            &lending_market_key,
+            clock.slot,
+        )?;
 
-        // Calculate current deposit value with interest
-        // current_amount = principal * (current_index / snapshot_index)
-        // For now, we just use the stored amount (without index update)
-        let deposit_amount = deposit.deposited_amount;
+        require!(
+            is_plausible_price_move(
+                deposit.market_price_usd,
+                price_usd,
+                MAX_PRICE_MOVE_MULTIPLIER,
+                slots_since_refresh,
+            ),
+            RefreshObligationError::SuspiciousPriceMove
+        );
 
-        // Placeholder USD value (in production: amount * price / 10^decimals)
-        // Using 1:1 ratio for simplicity - replace with oracle price
-        let deposit_usd = (deposit_amount as u128) * USD_SCALE / 1_000_000; // Assuming 6 decimals
+        // Include supply interest accrued since the deposit snapshot (round down)
+        let current_deposit_amount = if deposit.supply_index_snapshot > 0 {
+            let amount = mul_div_floor(
+                deposit.deposited_amount as u128,
+                reserve.liquidity.cumulative_supply_index,
+                deposit.supply_index_snapshot,
+            )
+            .ok_or(RefreshObligationError::MathOverflow)?;
+            u64::try_from(amount).map_err(|_| RefreshObligationError::MathOverflow)?
+        } else {
+            deposit.deposited_amount
+        };
 
-        deposit.market_value_usd = deposit_usd;
-        deposited_value_usd += deposit_usd;
+        // usd_value = amount * price / 10^decimals, bounded per position
+        let deposit_usd = token_amount_to_usd(
+            current_deposit_amount,
+            price_usd,
+            reserve.token_decimals,
+        )?;
+        require!(
+            deposit_usd <= MAX_POSITION_VALUE_USD,
+            RefreshObligationError::ValueTooLarge
+        );
 
-        // Calculate borrowing capacity (LTV)
-        // Placeholder: 80% LTV - in production, read from reserve config
-        let ltv_bps: u128 = 8000;
-        allowed_borrow_value_usd += deposit_usd * ltv_bps / 10000;
+        deposit.market_value_usd = deposit_usd;
+        deposit.market_price_usd = price_usd;
+        deposited_value_usd = deposited_value_usd
+            .checked_add(deposit_usd)
+            .ok_or(RefreshObligationError::MathOverflow)?;
 
-        // Calculate liquidation threshold value
-        // Placeholder: 85% threshold - in production, read from reserve config
-        let liq_threshold_bps: u128 = 8500;
-        unhealthy_borrow_value_usd += deposit_usd * liq_threshold_bps / 10000;
+        // Deposits disabled as collateral add no borrowing power or e-mode constraint
+        if deposit.use_as_collateral {
+            let pending_amount = deposit.pending_at(
+                current_deposit_amount,
+                clock.slot,
+                reserve.config.collateral_maturity_slots(),
+            );
+            let pending_usd = token_amount_to_usd(pending_amount, price_usd, reserve.token_decimals)?;
+            deposit_risk.push((deposit_usd, pending_usd, reserve.config, deposit.last_deposit_slot));
+            emode_categories.push(reserve.config.emode_category);
+        }
     }
 
     // Update each borrow's cached USD value
     for borrow in obligation.borrows.iter_mut() {
-        // Placeholder: In production, read from reserve account in remaining_accounts
-        // let reserve = get_reserve_from_remaining_accounts(borrow.reserve)?;
-        // let current_borrow_index = reserve.liquidity.cumulative_borrow_index;
-        // let price_usd = get_oracle_price(reserve.oracle)?;
+        let (reserve, price_usd) = load_reserve_price(
+            &mut remaining_accounts,
+            &borrow.reserve,
+            &lending_market_key,
+            clock.slot,
+        )?;
+
+        require!(
+            is_plausible_price_move(
+                borrow.market_price_usd,
+                price_usd,
+                MAX_PRICE_MOVE_MULTIPLIER,
+                slots_since_refresh,
+            ),
+            RefreshObligationError::SuspiciousPriceMove
+        );
 
-        // Calculate current borrow value with interest
-        // current_amount = principal * (current_index / snapshot_index)
-        let borrow_amount = borrow.borrowed_amount;
+        // Include borrow interest accrued since the borrow snapshot (round up)
+        let current_borrow_amount = borrow
+            .current_amount(reserve.liquidity.cumulative_borrow_index, reserve.interest_model)
+            .ok_or(RefreshObligationError::MathOverflow)?;
 
-        // Placeholder USD value (in production: amount * price / 10^decimals)
-        let borrow_usd = (borrow_amount as u128) * USD_SCALE / 1_000_000;
+        let borrow_usd = token_amount_to_usd(
+            current_borrow_amount,
+            price_usd,
+            reserve.token_decimals,
+        )?;
+        require!(
+            borrow_usd <= MAX_POSITION_VALUE_USD,
+            RefreshObligationError::ValueTooLarge
+        );
 
         borrow.market_value_usd = borrow_usd;
-        borrowed_value_usd += borrow_usd;
+        borrow.market_price_usd = price_usd;
+        borrowed_value_usd = borrowed_value_usd
+            .checked_add(borrow_usd)
+            .ok_or(RefreshObligationError::MathOverflow)?;
+
+        emode_categories.push(reserve.config.emode_category);
+    }
+
+    require!(
+        deposited_value_usd <= MAX_OBLIGATION_VALUE_USD && borrowed_value_usd <= MAX_OBLIGATION_VALUE_USD,
+        RefreshObligationError::ValueTooLarge
+    );
+
+    // Every account must belong to a position
+    require!(
+        remaining_accounts.next().is_none(),
+        RefreshObligationError::UnexpectedAccounts
+    );
+
+    // E-mode applies only when all collateral and debt share one nonzero category
+    let emode_active = shared_emode_category(&emode_categories).is_some();
+
+    for (deposit_usd, pending_usd, config, last_deposit_slot) in deposit_risk.iter() {
+        let (ltv_bps, liq_threshold_bps) = config.risk_params(emode_active);
+
+        // Recently deposited collateral borrows at a reduced LTV
+        let pending_ltv_bps = config.apply_initial_borrow_haircut(ltv_bps, *last_deposit_slot, clock.slot);
+
+        // Collateral still within its activation delay adds no borrowing power
+        let pending_ltv_bps = if config.is_collateral_active(*last_deposit_slot, clock.slot) {
+            pending_ltv_bps
+        } else {
+            0
+        };
+
+        // Calculate borrowing capacity (LTV), with only the pending part
+        // of the position held back
+        let mature_usd = deposit_usd.saturating_sub(*pending_usd);
+        let mature_allowed_usd = mul_div_floor(mature_usd, ltv_bps as u128, 10000)
+            .ok_or(RefreshObligationError::MathOverflow)?;
+        let pending_allowed_usd = mul_div_floor(*pending_usd, pending_ltv_bps as u128, 10000)
+            .ok_or(RefreshObligationError::MathOverflow)?;
+        let allowed_usd = mature_allowed_usd
+            .checked_add(pending_allowed_usd)
+            .ok_or(RefreshObligationError::MathOverflow)?;
+        allowed_borrow_value_usd = allowed_borrow_value_usd
+            .checked_add(allowed_usd)
+            .ok_or(RefreshObligationError::MathOverflow)?;
+
+        // Calculate liquidation threshold value
+        let unhealthy_usd = mul_div_floor(*deposit_usd, liq_threshold_bps as u128, 10000)
+            .ok_or(RefreshObligationError::MathOverflow)?;
+        unhealthy_borrow_value_usd = unhealthy_borrow_value_usd
+            .checked_add(unhealthy_usd)
+            .ok_or(RefreshObligationError::MathOverflow)?;
     }
 
     // Update cached values
@@ -115,8 +258,12 @@ pub fn handler(ctx: Context<RefreshObligation>) -> Result<()> {
     // Update timestamp
     obligation.last_update_slot = clock.slot;
 
+    // Fresh values reflect any prior withdrawal, unblocking borrows
+    obligation.last_withdraw_slot = 0;
+
     // Calculate health factor
     let health_factor = obligation.calculate_health_factor();
+    obligation.update_unhealthy_since(clock.slot);
 
     // Emit event
     emit!(ObligationRefreshed {
@@ -126,6 +273,7 @@ pub fn handler(ctx: Context<RefreshObligation>) -> Result<()> {
         allowed_borrow_value_usd,
         unhealthy_borrow_value_usd,
         health_factor,
+        net_equity_usd: obligation.net_equity_usd(),
         timestamp: clock.unix_timestamp,
     });
 
@@ -136,7 +284,75 @@ pub fn handler(ctx: Context<RefreshObligation>) -> Result<()> {
     );
     msg!("Health factor: {:?}", health_factor);
 
-    Ok(())
+    Ok(ObligationRefreshResult {
+        health_factor,
+        stale_after_slot: clock.slot.saturating_add(MAX_OBLIGATION_STALENESS_SLOTS),
+    })
+}
+
+/// Get the e-mode category shared by every position, if any
+/// Returns None for mixed categories, category 0, or no positions
+fn shared_emode_category(categories: &[u8]) -> Option<u8> {
+    let first = *categories.first()?;
+    if first != 0 && categories.iter().all(|category| *category == first) {
+        Some(first)
+    } else {
+        None
+    }
+}
+
+/// Load the next position's reserve and its USD price from remaining_accounts
+///
+/// Consumes the reserve account, its oracle, and its quote oracle when the
+/// reserve is priced through a non-USD feed. The reserve must have been
+/// refreshed within MAX_RESERVE_STALENESS_SLOTS.
+fn load_reserve_price<'a, 'info: 'a>(
+    remaining_accounts: &mut impl Iterator<Item = &'a AccountInfo<'info>>,
+    expected_reserve: &Pubkey,
+    lending_market: &Pubkey,
+    current_slot: u64,
+) -> Result<(Reserve, u64)> {
+    let reserve_info = remaining_accounts
+        .next()
+        .ok_or(RefreshObligationError::ReserveNotFound)?;
+
+    require_keys_eq!(
+        reserve_info.key(),
+        *expected_reserve,
+        RefreshObligationError::ReserveNotFound
+    );
+    require_keys_eq!(
+        *reserve_info.owner,
+        crate::ID,
+        RefreshObligationError::InvalidReserveAccount
+    );
+
+    let reserve = Reserve::try_deserialize(&mut &reserve_info.try_borrow_data()?[..])?;
+    require_keys_eq!(
+        reserve.lending_market,
+        *lending_market,
+        RefreshObligationError::InvalidReserveAccount
+    );
+    // Positions are scaled by the reserve's indexes, which an idle reserve
+    // leaves behind: stale debt would overstate health
+    require!(
+        !reserve.is_stale(current_slot, MAX_RESERVE_STALENESS_SLOTS),
+        RefreshObligationError::ReserveStale
+    );
+
+    let oracle_info = remaining_accounts
+        .next()
+        .ok_or(RefreshObligationError::OracleNotFound)?;
+
+    let quote_oracle_info = if reserve.price_quote_oracle.is_some() {
+        remaining_accounts.next()
+    } else {
+        None
+    };
+
+    let price_usd = get_reserve_price_usd(&reserve, oracle_info, quote_oracle_info, current_slot)?;
+
+    Ok((reserve, price_usd))
 }
 
 /// Refresh obligation errors
@@ -148,9 +364,30 @@ pub enum RefreshObligationError {
     #[msg("Reserve not found in remaining accounts")]
     ReserveNotFound,
 
+    #[msg("Reserve account is invalid or belongs to another lending market")]
+    InvalidReserveAccount,
+
+    #[msg("Oracle not found in remaining accounts")]
+    OracleNotFound,
+
     #[msg("Invalid oracle price")]
     InvalidOraclePrice,
 
+    #[msg("Oracle price moved implausibly since last refresh")]
+    SuspiciousPriceMove,
+
+    #[msg("Position or obligation USD value exceeds the supported range")]
+    ValueTooLarge,
+
+    #[msg("Too many remaining accounts")]
+    TooManyAccounts,
+
+    #[msg("Remaining accounts include accounts that match no position")]
+    UnexpectedAccounts,
+
+    #[msg("Reserve is stale and must be refreshed first")]
+    ReserveStale,
+
     #[msg("Math overflow")]
     MathOverflow,
 }
@@ -1,8 +1,12 @@
 use anchor_lang::prelude::*;
 
 use crate::state::{LendingMarket, Reserve};
-use crate::constants::{INDEX_ONE, SECONDS_PER_YEAR};
-use crate::events::ReserveRefreshed;
+use crate::constants::{
+    BPS_DENOMINATOR, INDEX_ONE, INDEX_REBASE_THRESHOLD, MAX_COMPOUND_FACTOR_PER_REFRESH, MAX_CUMULATIVE_INDEX,
+    SECONDS_PER_YEAR,
+};
+use crate::events::{BorrowsWithoutDeposits, RebaseNeeded, ReserveRefreshed};
+use crate::math::{mul_div_ceil, mul_div_floor, scale_index_floor};
 
 /// Accounts for refreshing a reserve
 #[derive(Accounts)]
@@ -21,12 +25,15 @@ pub struct RefreshReserve<'info> {
     )]
     pub reserve: Account<'info, Reserve>,
 
-    /// Pyth oracle price feed
-    /// CHECK: Validated against reserve.oracle
+    /// Pyth oracle price feed (optional)
+    /// Interest accrual depends only on utilization, so reserves can be
+    /// refreshed while their oracle is unavailable. Obligation refresh
+    /// still requires valid prices.
+    /// CHECK: Validated against reserve.oracle when provided
     #[account(
         constraint = oracle.key() == reserve.oracle @ RefreshReserveError::InvalidOracle
     )]
-    pub oracle: UncheckedAccount<'info>,
+    pub oracle: Option<UncheckedAccount<'info>>,
 }
 
 /// Refresh reserve state
@@ -34,28 +41,76 @@ pub struct RefreshReserve<'info> {
 /// This permissionless instruction:
 /// 1. Accrues interest based on time elapsed
 /// 2. Updates cumulative indexes
-/// 3. Recalculates interest rates based on utilization
+/// 3. Pays the low utilization supply subsidy from protocol fees
+/// 4. Charges the opt-in negative supply rate, if configured
+/// 5. Recalculates interest rates based on utilization
 ///
 /// Anyone can call this to keep the reserve state fresh.
 /// Must be called before any operation that depends on current state.
+/// The oracle account is optional: no price is read here.
 pub fn handler(ctx: Context<RefreshReserve>) -> Result<()> {
     let reserve = &mut ctx.accounts.reserve;
     let clock = Clock::get()?;
 
-    let current_slot = clock.slot;
     let current_timestamp = clock.unix_timestamp;
 
+    // Skip if already updated this slot
+    if !accrue_interest(reserve, clock.slot, current_timestamp)? {
+        return Ok(());
+    }
+
+    let utilization_bps = reserve.calculate_utilization_bps();
+    let borrow_rate = reserve.liquidity.current_borrow_rate_bps;
+    let supply_rate = reserve.liquidity.current_supply_rate_bps;
+
+    // Emit event
+    emit!(ReserveRefreshed {
+        reserve: reserve.key(),
+        cumulative_borrow_index: reserve.liquidity.cumulative_borrow_index,
+        cumulative_supply_index: reserve.liquidity.cumulative_supply_index,
+        current_borrow_rate_bps: borrow_rate,
+        current_supply_rate_bps: supply_rate,
+        total_deposits: reserve.liquidity.total_deposits,
+        total_borrows: reserve.liquidity.total_borrows,
+        depositor_count: reserve.liquidity.depositor_count,
+        borrower_count: reserve.liquidity.borrower_count,
+        index_divergence_bps: reserve.index_divergence_ratio(),
+        lifetime_borrow_interest_paid: reserve.liquidity.lifetime_borrow_interest_paid,
+        last_borrow_timestamp: reserve.liquidity.last_borrow_timestamp,
+        last_deposit_timestamp: reserve.liquidity.last_deposit_timestamp,
+        lifetime_liquidation_repaid: reserve.liquidity.lifetime_liquidation_repaid,
+        lifetime_liquidation_seized: reserve.liquidity.lifetime_liquidation_seized,
+        liquidation_count: reserve.liquidity.liquidation_count,
+        timestamp: current_timestamp,
+    });
+
+    msg!("Reserve refreshed: {}", reserve.token_mint);
+    msg!("Utilization: {} bps, Borrow rate: {} bps, Supply rate: {} bps",
+        utilization_bps, borrow_rate, supply_rate);
+
+    Ok(())
+}
+
+/// Accrue interest on a reserve up to the given slot and timestamp
+///
+/// Updates indexes, totals, protocol fees and rates, and marks the reserve
+/// fresh. Shared by `refresh_reserve` and instructions that accrue inline.
+/// Returns false if the reserve was already updated this slot.
+pub fn accrue_interest(
+    reserve: &mut Account<Reserve>,
+    current_slot: u64,
+    current_timestamp: i64,
+) -> Result<bool> {
     // Calculate time elapsed since last update
     let slots_elapsed = current_slot.saturating_sub(reserve.last_update_slot);
     let time_elapsed = current_timestamp.saturating_sub(reserve.last_update_timestamp);
 
-    // Skip if already updated this slot
     if slots_elapsed == 0 {
-        return Ok(());
+        return Ok(false);
     }
 
     // Only accrue interest if there are borrows
-    if reserve.liquidity.total_borrows > 0 && time_elapsed > 0 {
+    let borrow_compound_factor = if reserve.liquidity.total_borrows > 0 && time_elapsed > 0 {
         // Cap time elapsed to prevent extreme interest accrual (max 1 year)
         let time_elapsed_capped = time_elapsed.min(SECONDS_PER_YEAR as i64);
 
@@ -67,16 +122,38 @@ pub fn handler(ctx: Context<RefreshReserve>) -> Result<()> {
         // Calculate compound factor for borrow index
         // compound_factor = (rate_bps * time_elapsed) / (10000 * seconds_per_year)
         // We scale by INDEX_ONE for precision
-        let borrow_compound_factor = calculate_compound_factor(
+        let factor = calculate_compound_factor(
             borrow_rate_bps,
             time_elapsed_capped as u64,
         )?;
 
-        // Update borrow index: new_index = old_index * (1 + compound_factor)
+        // Stop accruing at the index cap rather than failing, so withdrawals
+        // and other paths that accrue inline never get stuck
+        factor.min(index_headroom_factor(reserve.liquidity.cumulative_borrow_index))
+    } else {
+        0
+    };
+
+    // Calculate interest earned, carrying the sub-unit remainder so reserve
+    // totals never run ahead of the debt actually owed
+    let (interest_earned, interest_remainder) = calculate_interest_earned(
+        reserve.interest_bearing_borrows(),
+        borrow_compound_factor,
+        reserve.liquidity.borrow_interest_remainder,
+    )?;
+    reserve.liquidity.borrow_interest_remainder = interest_remainder;
+
+    if borrow_compound_factor > 0 {
+        // Update borrow index: new_index = old_index * (1 + compound_factor),
+        // or old_index + compound_factor under simple interest
+        // Rounded up: borrowers never owe less than they should
+        let borrow_index_growth = reserve
+            .borrow_index_growth(borrow_compound_factor)
+            .ok_or(RefreshReserveError::MathOverflow)?;
         let new_borrow_index = reserve.liquidity.cumulative_borrow_index
-            .checked_mul(INDEX_ONE + borrow_compound_factor)
+            .checked_add(borrow_index_growth)
             .ok_or(RefreshReserveError::MathOverflow)?
-            / INDEX_ONE;
+            .min(MAX_CUMULATIVE_INDEX);
 
         // Sanity check: new index should not be less than old index (compound factor >= 0)
         require!(
@@ -84,21 +161,28 @@ pub fn handler(ctx: Context<RefreshReserve>) -> Result<()> {
             RefreshReserveError::InvalidIndexCalculation
         );
 
-        // Calculate interest earned
-        let interest_earned = calculate_interest_earned(
-            reserve.liquidity.total_borrows,
-            borrow_compound_factor,
-        )?;
-
         // Update total borrows with accrued interest
         reserve.liquidity.total_borrows = reserve.liquidity.total_borrows
             .checked_add(interest_earned)
             .ok_or(RefreshReserveError::MathOverflow)?;
 
+        // Track cumulative borrower interest
+        let lifetime_interest_before = reserve.liquidity.lifetime_borrow_interest_paid;
+        reserve.liquidity.lifetime_borrow_interest_paid = lifetime_interest_before
+            .checked_add(interest_earned as u128)
+            .ok_or(RefreshReserveError::MathOverflow)?;
+
         // Calculate protocol fees (reserve factor)
-        let protocol_fee = (interest_earned as u128
-            * reserve.config.interest_rate_config.reserve_factor_bps as u128
-            / 10000) as u64;
+        // Rounded up so the depositors' remainder is rounded down. The fee is
+        // taken on lifetime interest so that many small periods round once,
+        // rather than each one-unit period going entirely to the protocol
+        let reserve_factor_bps = reserve.config.interest_rate_config.reserve_factor_bps as u128;
+        let fee_on = |interest: u128| mul_div_ceil(interest, reserve_factor_bps, BPS_DENOMINATOR as u128);
+        let protocol_fee = fee_on(reserve.liquidity.lifetime_borrow_interest_paid)
+            .zip(fee_on(lifetime_interest_before))
+            .and_then(|(after, before)| u64::try_from(after.saturating_sub(before)).ok())
+            .ok_or(RefreshReserveError::MathOverflow)?
+            .min(interest_earned);
 
         reserve.liquidity.accumulated_protocol_fees = reserve.liquidity.accumulated_protocol_fees
             .checked_add(protocol_fee)
@@ -106,17 +190,28 @@ pub fn handler(ctx: Context<RefreshReserve>) -> Result<()> {
 
         // Update supply index (depositors earn interest minus protocol fee)
         let supply_interest = interest_earned.saturating_sub(protocol_fee);
+        // Rounded down: depositors never gain at the protocol's expense
         let supply_compound_factor = if reserve.liquidity.total_deposits > 0 {
-            (supply_interest as u128 * INDEX_ONE) / reserve.liquidity.total_deposits as u128
+            mul_div_floor(
+                supply_interest as u128,
+                INDEX_ONE,
+                reserve.liquidity.total_deposits as u128,
+            )
+            .ok_or(RefreshReserveError::MathOverflow)?
         } else {
             0
         };
 
+        let supply_index_growth = scale_index_floor(
+            reserve.liquidity.cumulative_supply_index,
+            supply_compound_factor,
+        )
+        .ok_or(RefreshReserveError::MathOverflow)?;
+        // Clamped at the cap: interest above it stays in the pool unallocated
         let new_supply_index = reserve.liquidity.cumulative_supply_index
-            .checked_add(
-                (reserve.liquidity.cumulative_supply_index * supply_compound_factor) / INDEX_ONE
-            )
-            .ok_or(RefreshReserveError::MathOverflow)?;
+            .checked_add(supply_index_growth)
+            .ok_or(RefreshReserveError::MathOverflow)?
+            .min(MAX_CUMULATIVE_INDEX);
 
         // Sanity check: new supply index should not be less than old index
         require!(
@@ -127,6 +222,52 @@ pub fn handler(ctx: Context<RefreshReserve>) -> Result<()> {
         // Apply new indexes
         reserve.liquidity.cumulative_borrow_index = new_borrow_index;
         reserve.liquidity.cumulative_supply_index = new_supply_index;
+
+        // Credit depositors' share of interest so total_deposits tracks
+        // what depositors can withdraw. Withdrawals include supply interest,
+        // so without this the last withdrawals would underflow the total.
+        // Utilization, rates and the deposit limit all measure this claim,
+        // and expected_vault_balance stays exact for sweep_surplus.
+        if reserve.liquidity.total_deposits > 0 {
+            reserve.liquidity.total_deposits = reserve.liquidity.total_deposits
+                .checked_add(supply_interest)
+                .ok_or(RefreshReserveError::MathOverflow)?;
+        }
+
+        // Alert operators well before the cap is reached
+        if new_borrow_index >= INDEX_REBASE_THRESHOLD || new_supply_index >= INDEX_REBASE_THRESHOLD {
+            emit!(RebaseNeeded {
+                reserve: reserve.key(),
+                cumulative_borrow_index: new_borrow_index,
+                cumulative_supply_index: new_supply_index,
+                max_cumulative_index: MAX_CUMULATIVE_INDEX,
+                timestamp: current_timestamp,
+            });
+            msg!("Reserve index above rebase threshold: {}", new_borrow_index);
+        }
+        if new_borrow_index == MAX_CUMULATIVE_INDEX {
+            msg!("Borrow index at cap, interest accrual paused until rebase");
+        }
+    }
+
+    // Subsidize depositors from protocol fees while utilization is low
+    if time_elapsed > 0 {
+        apply_supply_subsidy(reserve, time_elapsed.min(SECONDS_PER_YEAR as i64) as u64)?;
+    }
+
+    // Opt-in negative supply rate: the only path that lowers the supply index
+    if time_elapsed > 0 && reserve.config.negative_supply_rate_bps > 0 {
+        apply_negative_supply_rate(reserve, time_elapsed.min(SECONDS_PER_YEAR as i64) as u64)?;
+    }
+
+    // Flag broken accounting; utilization reads as 100% in this state
+    if reserve.has_borrows_without_deposits() {
+        emit!(BorrowsWithoutDeposits {
+            reserve: reserve.key(),
+            total_borrows: reserve.liquidity.total_borrows,
+            timestamp: current_timestamp,
+        });
+        msg!("Warning: reserve has {} borrowed with zero deposits", reserve.liquidity.total_borrows);
     }
 
     // Recalculate interest rates based on new utilization
@@ -141,50 +282,179 @@ pub fn handler(ctx: Context<RefreshReserve>) -> Result<()> {
     reserve.last_update_slot = current_slot;
     reserve.last_update_timestamp = current_timestamp;
 
-    // Emit event
-    emit!(ReserveRefreshed {
-        reserve: reserve.key(),
-        cumulative_borrow_index: reserve.liquidity.cumulative_borrow_index,
-        cumulative_supply_index: reserve.liquidity.cumulative_supply_index,
-        current_borrow_rate_bps: borrow_rate,
-        current_supply_rate_bps: supply_rate,
-        total_deposits: reserve.liquidity.total_deposits,
-        total_borrows: reserve.liquidity.total_borrows,
-        timestamp: current_timestamp,
-    });
+    Ok(true)
+}
 
-    msg!("Reserve refreshed: {}", reserve.token_mint);
-    msg!("Utilization: {} bps, Borrow rate: {} bps, Supply rate: {} bps",
-        utilization_bps, borrow_rate, supply_rate);
+/// Move the low utilization supply subsidy from protocol fees to depositors
+///
+/// subsidy = total_deposits * subsidy_rate * time / (10000 * seconds_per_year),
+/// rounded down and capped at the accumulated protocol fees. The rate is
+/// clamped so the supply rate never exceeds borrow_rate * utilization.
+fn apply_supply_subsidy(reserve: &mut Reserve, time_elapsed_seconds: u64) -> Result<()> {
+    let utilization_bps = reserve.calculate_utilization_bps();
+    let subsidy_rate_bps = reserve
+        .config
+        .interest_rate_config
+        .effective_supply_subsidy_rate(reserve.liquidity.current_borrow_rate_bps, utilization_bps);
+    if subsidy_rate_bps == 0 || reserve.liquidity.total_deposits == 0 {
+        return Ok(());
+    }
+
+    let rate_time = (subsidy_rate_bps as u128)
+        .checked_mul(time_elapsed_seconds as u128)
+        .ok_or(RefreshReserveError::MathOverflow)?;
+    let subsidy = mul_div_floor(
+        reserve.liquidity.total_deposits as u128,
+        rate_time,
+        10000u128 * SECONDS_PER_YEAR as u128,
+    )
+    .ok_or(RefreshReserveError::MathOverflow)?;
+
+    // Never pay out more than the fees available
+    let subsidy = u64::try_from(subsidy)
+        .unwrap_or(u64::MAX)
+        .min(reserve.liquidity.accumulated_protocol_fees);
+    if subsidy == 0 {
+        return Ok(());
+    }
+
+    // Grow the supply index by subsidy / total_deposits, rounded down
+    let subsidy_factor = mul_div_floor(
+        subsidy as u128,
+        INDEX_ONE,
+        reserve.liquidity.total_deposits as u128,
+    )
+    .ok_or(RefreshReserveError::MathOverflow)?;
+    let supply_index_growth = scale_index_floor(
+        reserve.liquidity.cumulative_supply_index,
+        subsidy_factor,
+    )
+    .ok_or(RefreshReserveError::MathOverflow)?;
+    let new_supply_index = reserve.liquidity.cumulative_supply_index
+        .checked_add(supply_index_growth)
+        .ok_or(RefreshReserveError::MathOverflow)?;
+
+    // No subsidy once it would push the supply index past the cap
+    if new_supply_index > MAX_CUMULATIVE_INDEX {
+        return Ok(());
+    }
+
+    reserve.liquidity.cumulative_supply_index = new_supply_index;
+    reserve.liquidity.accumulated_protocol_fees -= subsidy;
+    reserve.liquidity.total_deposits = reserve.liquidity.total_deposits
+        .checked_add(subsidy)
+        .ok_or(RefreshReserveError::MathOverflow)?;
+
+    msg!("Supply subsidy paid from protocol fees: {}", subsidy);
 
     Ok(())
 }
 
-/// Calculate compound factor for a given rate and time
-/// Returns the factor scaled by INDEX_ONE
-fn calculate_compound_factor(rate_bps: u64, time_elapsed_seconds: u64) -> Result<u128> {
-    // compound_factor = (rate_bps * time_elapsed) / (10000 * seconds_per_year) * INDEX_ONE
-    // Simplified: (rate_bps * time_elapsed * INDEX_ONE) / (10000 * SECONDS_PER_YEAR)
+/// Move the negative supply rate's charge from depositors to protocol fees
+///
+/// charge = total_deposits * negative_rate * time / (10000 * seconds_per_year),
+/// rounded down. The supply index shrinks by the same proportion but never
+/// below INDEX_ONE, and the charge is reduced to match when the floor binds.
+/// Total deposits and fees move by the same amount, so the expected vault
+/// balance is unchanged.
+fn apply_negative_supply_rate(reserve: &mut Reserve, time_elapsed_seconds: u64) -> Result<()> {
+    let total_deposits = reserve.liquidity.total_deposits;
+    let old_supply_index = reserve.liquidity.cumulative_supply_index;
+    if total_deposits == 0 || old_supply_index <= INDEX_ONE {
+        return Ok(());
+    }
 
-    let numerator = (rate_bps as u128)
+    let rate_time = (reserve.config.negative_supply_rate_bps as u128)
+        .checked_mul(time_elapsed_seconds as u128)
+        .ok_or(RefreshReserveError::MathOverflow)?;
+    let charge = mul_div_floor(
+        total_deposits as u128,
+        rate_time,
+        10000u128 * SECONDS_PER_YEAR as u128,
+    )
+    .ok_or(RefreshReserveError::MathOverflow)?;
+
+    // Shrink the index by charge / total_deposits, rounded up so depositors
+    // are never credited more than their reduced share
+    let index_reduction = mul_div_ceil(old_supply_index, charge, total_deposits as u128)
+        .ok_or(RefreshReserveError::MathOverflow)?;
+    let new_supply_index = old_supply_index.saturating_sub(index_reduction).max(INDEX_ONE);
+
+    // Recompute the charge from the applied reduction (floor may bind)
+    let charge = mul_div_floor(
+        total_deposits as u128,
+        old_supply_index - new_supply_index,
+        old_supply_index,
+    )
+    .ok_or(RefreshReserveError::MathOverflow)?;
+    let charge = u64::try_from(charge).map_err(|_| RefreshReserveError::MathOverflow)?;
+    if charge == 0 {
+        return Ok(());
+    }
+
+    reserve.liquidity.cumulative_supply_index = new_supply_index;
+    reserve.liquidity.total_deposits = total_deposits - charge;
+    reserve.liquidity.accumulated_protocol_fees = reserve.liquidity.accumulated_protocol_fees
+        .checked_add(charge)
+        .ok_or(RefreshReserveError::MathOverflow)?;
+
+    msg!("Negative supply rate charged to depositors: {}", charge);
+
+    Ok(())
+}
+
+/// Largest compound factor that keeps `index` within MAX_CUMULATIVE_INDEX
+/// (0 once the cap is reached)
+pub fn index_headroom_factor(index: u128) -> u128 {
+    if index >= MAX_CUMULATIVE_INDEX {
+        return 0;
+    }
+    mul_div_floor(MAX_CUMULATIVE_INDEX - index, INDEX_ONE, index).unwrap_or(u128::MAX)
+}
+
+/// Calculate compound factor for a given rate and time
+/// Returns the factor scaled by INDEX_ONE, rounded up (borrower-owed),
+/// capped at MAX_COMPOUND_FACTOR_PER_REFRESH
+pub fn calculate_compound_factor(rate_bps: u64, time_elapsed_seconds: u64) -> Result<u128> {
+    // compound_factor = (rate_bps * time_elapsed * INDEX_ONE) / (10000 * SECONDS_PER_YEAR)
+    let rate_time = (rate_bps as u128)
         .checked_mul(time_elapsed_seconds as u128)
-        .ok_or(RefreshReserveError::MathOverflow)?
-        .checked_mul(INDEX_ONE)
         .ok_or(RefreshReserveError::MathOverflow)?;
 
     let denominator = 10000u128 * SECONDS_PER_YEAR as u128;
 
-    Ok(numerator / denominator)
+    let factor = mul_div_ceil(rate_time, INDEX_ONE, denominator)
+        .ok_or(RefreshReserveError::MathOverflow)?;
+
+    // Bound the interest a single (possibly long-delayed) refresh can book
+    Ok(factor.min(MAX_COMPOUND_FACTOR_PER_REFRESH))
 }
 
 /// Calculate interest earned based on principal and compound factor
-fn calculate_interest_earned(principal: u64, compound_factor: u128) -> Result<u64> {
-    let interest = (principal as u128)
+///
+/// Reserve totals round down; the fraction of a native unit left over is
+/// returned (scaled by INDEX_ONE) and carried into the next accrual, so
+/// total_borrows tracks the index exactly over time without phantom debt.
+/// Per-position debt still rounds up when read through the index.
+///
+/// Returns (interest_earned, new_remainder).
+pub fn calculate_interest_earned(
+    principal: u64,
+    compound_factor: u128,
+    remainder: u128,
+) -> Result<(u64, u128)> {
+    if principal == 0 {
+        return Ok((0, 0));
+    }
+
+    let scaled_interest = (principal as u128)
         .checked_mul(compound_factor)
-        .ok_or(RefreshReserveError::MathOverflow)?
-        / INDEX_ONE;
+        .and_then(|interest| interest.checked_add(remainder))
+        .ok_or(RefreshReserveError::MathOverflow)?;
+    let interest = u64::try_from(scaled_interest / INDEX_ONE)
+        .map_err(|_| RefreshReserveError::MathOverflow)?;
 
-    Ok(interest as u64)
+    Ok((interest, scaled_interest % INDEX_ONE))
 }
 
 /// Refresh reserve errors
@@ -202,3 +472,121 @@ pub enum RefreshReserveError {
     #[msg("Math overflow")]
     MathOverflow,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::scale_index_ceil;
+    use proptest::prelude::*;
+
+    #[test]
+    fn sub_unit_interest_creates_no_phantom_debt() {
+        // 1000 units at a factor worth 0.001 units per refresh
+        let factor = INDEX_ONE / 1_000_000;
+        let mut remainder = 0;
+        let mut total_interest = 0;
+        for _ in 0..999 {
+            let (interest, next) = calculate_interest_earned(1000, factor, remainder).unwrap();
+            total_interest += interest;
+            remainder = next;
+        }
+        assert_eq!(total_interest, 0);
+
+        // The thousandth refresh completes one whole unit
+        let (interest, next) = calculate_interest_earned(1000, factor, remainder).unwrap();
+        assert_eq!(interest, 1);
+        assert_eq!(next, 0);
+    }
+
+    #[test]
+    fn compound_factor_is_linear_in_rate_and_time() {
+        // 10% APR over one day
+        let day = calculate_compound_factor(1000, 86_400).unwrap();
+        assert_eq!(day, mul_div_ceil(INDEX_ONE, 1000 * 86_400, 10000 * SECONDS_PER_YEAR as u128).unwrap());
+        assert_eq!(calculate_compound_factor(0, 86_400).unwrap(), 0);
+        assert_eq!(calculate_compound_factor(1000, 0).unwrap(), 0);
+    }
+
+    #[test]
+    fn compound_factor_is_capped_per_refresh() {
+        let year = calculate_compound_factor(50_000, SECONDS_PER_YEAR).unwrap();
+        assert_eq!(year, MAX_COMPOUND_FACTOR_PER_REFRESH);
+    }
+
+    #[test]
+    fn borrow_index_compounds_across_refreshes() {
+        // Two half-period accruals grow the index more than one full-period
+        // accrual: interest earns interest
+        let half = calculate_compound_factor(500, SECONDS_PER_YEAR / 2).unwrap();
+        let full = calculate_compound_factor(500, SECONDS_PER_YEAR).unwrap();
+
+        let once = INDEX_ONE + scale_index_ceil(INDEX_ONE, full).unwrap();
+        let mut twice = INDEX_ONE;
+        for _ in 0..2 {
+            twice += scale_index_ceil(twice, half).unwrap();
+        }
+        assert!(twice > once);
+    }
+
+    #[test]
+    fn zero_principal_clears_remainder() {
+        assert_eq!(calculate_interest_earned(0, INDEX_ONE, INDEX_ONE - 1).unwrap(), (0, 0));
+    }
+
+    proptest! {
+        #[test]
+        fn interest_and_remainder_conserve_scaled_value(
+            principal in 0u64..=u64::MAX / 4,
+            factor in 0u128..=INDEX_ONE,
+            remainder in 0u128..INDEX_ONE,
+        ) {
+            let (interest, next) = calculate_interest_earned(principal, factor, remainder).unwrap();
+            prop_assert!(next < INDEX_ONE);
+            if principal > 0 {
+                prop_assert_eq!(
+                    interest as u128 * INDEX_ONE + next,
+                    principal as u128 * factor + remainder
+                );
+            }
+        }
+
+        #[test]
+        fn split_accrual_matches_single_accrual(
+            principal in 1u64..=1_000_000_000_000,
+            factors in proptest::collection::vec(0u128..=INDEX_ONE / 100, 1..50),
+        ) {
+            let mut remainder = 0;
+            let mut split_interest: u128 = 0;
+            for factor in factors.iter() {
+                let (interest, next) = calculate_interest_earned(principal, *factor, remainder).unwrap();
+                split_interest += interest as u128;
+                remainder = next;
+            }
+
+            let total_factor: u128 = factors.iter().sum();
+            let (single_interest, single_remainder) =
+                calculate_interest_earned(principal, total_factor, 0).unwrap();
+            prop_assert_eq!(split_interest, single_interest as u128);
+            prop_assert_eq!(remainder, single_remainder);
+        }
+
+        #[test]
+        fn interest_never_exceeds_exact_value(
+            principal in 1u64..=1_000_000_000_000,
+            factors in proptest::collection::vec(0u128..=INDEX_ONE / 100, 1..50),
+        ) {
+            let mut remainder = 0;
+            let mut charged: u128 = 0;
+            for factor in factors.iter() {
+                let (interest, next) = calculate_interest_earned(principal, *factor, remainder).unwrap();
+                charged += interest as u128;
+                remainder = next;
+            }
+
+            // Charged interest is the exact value rounded down, never above it
+            let exact_scaled: u128 = factors.iter().map(|f| principal as u128 * f).sum();
+            prop_assert!(charged * INDEX_ONE <= exact_scaled);
+            prop_assert!(exact_scaled - charged * INDEX_ONE < INDEX_ONE);
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+
+use crate::state::{LendingMarket, Reserve};
+use crate::constants::{MIN_ROUNDING_DUST, ROUNDING_DUST_DIVISOR};
+use crate::events::RoundingDustSwept;
+use crate::instructions::permissionless::refresh_reserve::accrue_interest;
+
+/// Accounts for sweeping rounding dust into protocol fees
+#[derive(Accounts)]
+pub struct SweepRoundingDust<'info> {
+    /// The lending market
+    #[account(
+        seeds = [LendingMarket::SEED_PREFIX, lending_market.authority.as_ref()],
+        bump = lending_market.bump
+    )]
+    pub lending_market: Account<'info, LendingMarket>,
+
+    /// The reserve to clean up
+    #[account(
+        mut,
+        constraint = reserve.lending_market == lending_market.key() @ SweepRoundingDustError::InvalidReserve
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    /// Reserve's token vault
+    #[account(
+        constraint = reserve_vault.key() == reserve.token_vault @ SweepRoundingDustError::InvalidVault
+    )]
+    pub reserve_vault: Account<'info, TokenAccount>,
+}
+
+/// Accrue interest and credit vault rounding dust to protocol fees
+///
+/// Interest math rounds in the protocol's favour, so over time the vault
+/// holds slightly more than `expected_vault_balance()`. This permissionless
+/// instruction books that remainder as protocol fees without moving tokens.
+/// Only surpluses up to `max_rounding_dust` for the token's decimals qualify:
+/// anything larger is not rounding and is left for the authority's
+/// `sweep_surplus`. A donation within the bound is booked as fees too, which
+/// is why the bound is kept to a negligible value per token. Tracked
+/// deposits, borrows and fees are never reduced.
+///
+/// # Arguments
+/// * `ctx` - The context containing all accounts
+pub fn handler(ctx: Context<SweepRoundingDust>) -> Result<()> {
+    let reserve = &mut ctx.accounts.reserve;
+    let clock = Clock::get()?;
+
+    // Accrue first so the expected balance is current
+    accrue_interest(reserve, clock.slot, clock.unix_timestamp)?;
+
+    let vault_balance = ctx.accounts.reserve_vault.amount;
+    let dust = vault_balance.saturating_sub(reserve.expected_vault_balance());
+    require!(dust > 0, SweepRoundingDustError::NoDust);
+    require!(
+        dust <= max_rounding_dust(reserve.token_decimals),
+        SweepRoundingDustError::SurplusTooLarge
+    );
+
+    reserve.liquidity.accumulated_protocol_fees = reserve.liquidity.accumulated_protocol_fees
+        .checked_add(dust)
+        .ok_or(SweepRoundingDustError::MathOverflow)?;
+
+    // Emit event
+    emit!(RoundingDustSwept {
+        reserve: reserve.key(),
+        amount: dust,
+        vault_balance,
+        accumulated_protocol_fees: reserve.liquidity.accumulated_protocol_fees,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Swept {} rounding dust into fees for reserve {}", dust, reserve.token_mint);
+
+    Ok(())
+}
+
+/// Largest vault surplus treated as rounding dust for a token (native units)
+///
+/// One millionth of a whole token, but at least MIN_ROUNDING_DUST so
+/// low-decimal tokens can still clear a few units of rounding.
+pub fn max_rounding_dust(token_decimals: u8) -> u64 {
+    10u64
+        .checked_pow(token_decimals as u32)
+        .map(|one_token| one_token / ROUNDING_DUST_DIVISOR)
+        .unwrap_or(u64::MAX)
+        .max(MIN_ROUNDING_DUST)
+}
+
+/// Sweep rounding dust errors
+#[error_code]
+pub enum SweepRoundingDustError {
+    #[msg("Reserve does not belong to this lending market")]
+    InvalidReserve,
+
+    #[msg("Invalid vault account")]
+    InvalidVault,
+
+    #[msg("No rounding dust to sweep")]
+    NoDust,
+
+    #[msg("Vault surplus is too large to be rounding dust")]
+    SurplusTooLarge,
+
+    #[msg("Math overflow")]
+    MathOverflow,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dust_bound_scales_with_decimals() {
+        assert_eq!(max_rounding_dust(6), MIN_ROUNDING_DUST);
+        assert_eq!(max_rounding_dust(9), 1_000);
+        assert_eq!(max_rounding_dust(18), 1_000_000_000_000);
+    }
+
+    #[test]
+    fn dust_bound_has_a_floor_for_low_decimals() {
+        assert_eq!(max_rounding_dust(0), MIN_ROUNDING_DUST);
+        assert_eq!(max_rounding_dust(2), MIN_ROUNDING_DUST);
+    }
+
+    #[test]
+    fn dust_bound_is_negligible_per_token() {
+        for decimals in 7..=19u8 {
+            let one_token = 10u128.pow(decimals as u32);
+            assert!(max_rounding_dust(decimals) as u128 * ROUNDING_DUST_DIVISOR as u128 <= one_token);
+        }
+    }
+}
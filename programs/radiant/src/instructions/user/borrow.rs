@@ -1,9 +1,24 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions::{
+    self as sysvar_instructions,
+    load_current_index_checked,
+    load_instruction_at_checked,
+};
+use anchor_lang::Discriminator;
 use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 
-use crate::state::{LendingMarket, Reserve, Obligation, ObligationLiquidity};
-use crate::constants::{VAULT_SEED, MAX_OBLIGATION_BORROWS, MIN_BORROW_AMOUNT, MIN_HEALTH_FACTOR_AFTER_BORROW, MAX_RESERVE_STALENESS_SLOTS};
-use crate::events::BorrowEvent;
+use crate::state::{InterestModel, LendingMarket, Reserve, Obligation, ObligationLiquidity};
+use crate::constants::{
+    VAULT_SEED,
+    MIN_BORROW_AMOUNT,
+    MAX_RESERVE_STALENESS_SLOTS,
+    MAX_OBLIGATION_STALENESS_SLOTS,
+    SECONDS_PER_DAY,
+    SECONDS_PER_YEAR,
+};
+use crate::events::{ActionBlocked, BlockReason, BlockedAction, BorrowEvent, RiskyBorrow};
+use crate::oracle::get_reserve_price_usd;
+use crate::math::{mul_bps_ceil, mul_div_ceil, mul_div_floor};
 
 /// Accounts for borrowing tokens
 #[derive(Accounts)]
@@ -13,7 +28,6 @@ pub struct Borrow<'info> {
 
     /// The lending market
     #[account(
-        constraint = !lending_market.emergency_mode @ BorrowError::EmergencyModeActive,
         seeds = [LendingMarket::SEED_PREFIX, lending_market.authority.as_ref()],
         bump = lending_market.bump
     )]
@@ -22,8 +36,7 @@ pub struct Borrow<'info> {
     /// The reserve to borrow from
     #[account(
         mut,
-        constraint = reserve.lending_market == lending_market.key() @ BorrowError::InvalidReserve,
-        constraint = reserve.config.borrows_enabled @ BorrowError::BorrowsDisabled
+        constraint = reserve.lending_market == lending_market.key() @ BorrowError::InvalidReserve
     )]
     pub reserve: Account<'info, Reserve>,
 
@@ -32,7 +45,12 @@ pub struct Borrow<'info> {
         mut,
         constraint = obligation.lending_market == lending_market.key() @ BorrowError::InvalidObligation,
         constraint = obligation.owner == owner.key() @ BorrowError::InvalidObligationOwner,
-        seeds = [Obligation::SEED_PREFIX, lending_market.key().as_ref(), owner.key().as_ref()],
+        seeds = [
+            Obligation::SEED_PREFIX,
+            lending_market.key().as_ref(),
+            owner.key().as_ref(),
+            Obligation::id_seed(&obligation.obligation_id),
+        ],
         bump = obligation.bump
     )]
     pub obligation: Account<'info, Obligation>,
@@ -54,22 +72,73 @@ pub struct Borrow<'info> {
     )]
     pub user_token_account: Account<'info, TokenAccount>,
 
-    /// Token program
+    /// Oracle of the reserve being borrowed from
+    /// CHECK: Validated against reserve.oracle
+    #[account(
+        constraint = oracle.key() == reserve.oracle @ BorrowError::InvalidOracle
+    )]
+    pub oracle: UncheckedAccount<'info>,
+
+    /// Quote oracle, required if the reserve is priced through a non-USD feed
+    /// CHECK: Validated against reserve.price_quote_oracle in the oracle helper
+    pub quote_oracle: Option<UncheckedAccount<'info>>,
+
+    /// Instructions sysvar, required when the market requires a refresh
+    /// earlier in the same transaction
+    /// CHECK: Address constrained to the instructions sysvar
+    #[account(address = sysvar_instructions::ID @ BorrowError::InvalidInstructionsSysvar)]
+    pub instructions_sysvar: Option<UncheckedAccount<'info>>,
+
+    /// Token program (must match the reserve's pinned token program)
+    #[account(
+        constraint = token_program.key() == reserve.token_program_id @ BorrowError::InvalidTokenProgram,
+    )]
     pub token_program: Program<'info, Token>,
 }
 
 /// Borrow tokens from the reserve
 ///
-/// User must have sufficient collateral to cover the borrow.
+/// User must have sufficient collateral to cover the borrow, and the
+/// obligation must have been refreshed within MAX_OBLIGATION_STALENESS_SLOTS.
 /// The borrow amount is limited by:
-/// - User's borrowing capacity (collateral * LTV)
+/// - User's borrowing capacity (collateral * LTV), priced at the oracle
 /// - Available liquidity in the reserve
-/// - Reserve's borrow limit
+/// - Reserve's borrow limit and utilization cap
 ///
 /// # Arguments
 /// * `ctx` - The context containing all accounts
 /// * `amount` - Amount of tokens to borrow (in native units)
 pub fn handler(ctx: Context<Borrow>, amount: u64) -> Result<()> {
+    // Market and reserve gates, checked here rather than as account
+    // constraints so rejections can be reported
+    let blocked_reason = if ctx.accounts.lending_market.emergency_mode {
+        Some(BlockReason::EmergencyMode)
+    } else if !ctx.accounts.reserve.config.borrows_enabled {
+        Some(BlockReason::ReserveDisabled)
+    } else {
+        None
+    };
+
+    if let Some(reason) = blocked_reason {
+        if ctx.accounts.lending_market.emit_action_blocked_events {
+            emit!(ActionBlocked {
+                lending_market: ctx.accounts.lending_market.key(),
+                reserve: ctx.accounts.reserve.key(),
+                owner: ctx.accounts.owner.key(),
+                action: BlockedAction::Borrow,
+                reason,
+                amount,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+
+        msg!("Borrow blocked: {:?}", reason);
+        return match reason {
+            BlockReason::EmergencyMode => err!(BorrowError::EmergencyModeActive),
+            BlockReason::ReserveDisabled => err!(BorrowError::BorrowsDisabled),
+        };
+    }
+
     // Validate amount
     require!(amount > 0, BorrowError::AmountZero);
     require!(amount >= MIN_BORROW_AMOUNT, BorrowError::AmountTooSmall);
@@ -84,16 +153,72 @@ pub fn handler(ctx: Context<Borrow>, amount: u64) -> Result<()> {
         BorrowError::ReserveStale
     );
 
+    // Capacity is checked against cached values: require a recent refresh
+    require!(
+        clock.slot.saturating_sub(obligation.last_update_slot) <= MAX_OBLIGATION_STALENESS_SLOTS,
+        BorrowError::ObligationStale
+    );
+
+    // Optionally require refresh_obligation earlier in this transaction
+    if ctx.accounts.lending_market.require_refresh_in_transaction {
+        let instructions_sysvar = ctx.accounts.instructions_sysvar
+            .as_ref()
+            .ok_or(BorrowError::MissingInstructionsSysvar)?;
+        require!(
+            refreshed_earlier_in_transaction(instructions_sysvar, &obligation.key())?,
+            BorrowError::RefreshNotInTransaction
+        );
+    }
+
+    // Block withdraw-then-borrow on cached values until a refresh
+    require!(
+        !obligation.withdrawn_since_refresh(),
+        BorrowError::WithdrawCooldownActive
+    );
+
     // User must have deposits (collateral)
     require!(
         obligation.has_deposits(),
         BorrowError::NoCollateral
     );
 
+    // Reject circular positions: borrowing the asset that is the only collateral
+    let reserve_key = reserve.key();
+    require!(
+        ctx.accounts.lending_market.allow_looping
+            || !(obligation.deposits.len() == 1 && obligation.deposits[0].reserve == reserve_key),
+        BorrowError::CircularBorrow
+    );
+
+    // Check single borrow size if set
+    require!(
+        reserve.config.max_single_borrow == 0 || amount <= reserve.config.max_single_borrow,
+        BorrowError::SingleBorrowTooLarge
+    );
+
+    // Charge a one-time fee when the borrow pushes utilization past the
+    // threshold, scaled by how far past it goes; added to the debt and
+    // credited to protocol fees
+    let utilization_after_bps = if reserve.liquidity.total_deposits > 0 {
+        mul_div_floor(
+            reserve.liquidity.total_borrows as u128 + amount as u128,
+            10000,
+            reserve.liquidity.total_deposits as u128,
+        )
+        .ok_or(BorrowError::MathOverflow)? as u64
+    } else {
+        0
+    };
+    let utilization_fee = mul_bps_ceil(amount, reserve.config.utilization_fee_bps(utilization_after_bps))
+        .ok_or(BorrowError::MathOverflow)?;
+    let debt_amount = amount
+        .checked_add(utilization_fee)
+        .ok_or(BorrowError::MathOverflow)?;
+
     // Check borrow limit if set
     if reserve.config.borrow_limit > 0 {
         let new_total_borrows = reserve.liquidity.total_borrows
-            .checked_add(amount)
+            .checked_add(debt_amount)
             .ok_or(BorrowError::MathOverflow)?;
         require!(
             new_total_borrows <= reserve.config.borrow_limit,
@@ -101,22 +226,57 @@ pub fn handler(ctx: Context<Borrow>, amount: u64) -> Result<()> {
         );
     }
 
-    // Check available liquidity
-    let available_liquidity = reserve.available_liquidity();
+    // Check available liquidity, excluding the withdrawal reserve
+    let borrowable_liquidity = reserve.borrowable_liquidity();
     require!(
-        amount <= available_liquidity,
+        amount <= borrowable_liquidity,
         BorrowError::InsufficientLiquidity
     );
 
-    // Check borrowing capacity
-    // Note: In production, this should use oracle prices for proper USD calculations
-    // For now, we use the cached values from refresh_obligation
-    let remaining_capacity = obligation.remaining_borrow_capacity_usd();
+    // Check the utilization cap; limit and liquidity are checked above,
+    // so max_borrowable only binds here through the cap
     require!(
-        remaining_capacity > 0,
+        amount <= reserve.max_borrowable(),
+        BorrowError::UtilizationCapExceeded
+    );
+
+    // Check borrowing capacity against the USD value of the new debt,
+    // including the utilization fee added to it
+    // Debt is valued rounding up so dust borrows cannot slip through at zero
+    let price_usd = get_reserve_price_usd(
+        reserve,
+        &ctx.accounts.oracle.to_account_info(),
+        ctx.accounts.quote_oracle.as_ref().map(|oracle| oracle.as_ref()),
+        clock.slot,
+    )?;
+    let decimals_factor = 10u128
+        .checked_pow(reserve.token_decimals as u32)
+        .ok_or(BorrowError::MathOverflow)?;
+    let borrow_value_usd = mul_div_ceil(debt_amount as u128, price_usd as u128, decimals_factor)
+        .ok_or(BorrowError::MathOverflow)?;
+
+    // Part of the capacity is held back so rounding at the next refresh
+    // cannot leave a max borrow over capacity
+    let remaining_capacity = obligation.remaining_borrow_capacity_with_buffer_usd(
+        ctx.accounts.lending_market.borrow_capacity_buffer_bps,
+    );
+    require!(
+        borrow_value_usd <= remaining_capacity,
         BorrowError::InsufficientBorrowingCapacity
     );
 
+    // Check per-obligation borrow cap if set
+    let max_obligation_borrow_usd = ctx.accounts.lending_market.max_obligation_borrow_usd;
+    if max_obligation_borrow_usd > 0 {
+        let new_borrowed_value_usd = obligation.borrowed_value_usd
+            .checked_add(borrow_value_usd)
+            .ok_or(BorrowError::MathOverflow)?;
+        require!(
+            new_borrowed_value_usd <= max_obligation_borrow_usd as u128,
+            BorrowError::ObligationBorrowLimitExceeded
+        );
+    }
+
     // Verify vault has sufficient balance
     require!(
         ctx.accounts.token_vault.amount >= amount,
@@ -145,11 +305,16 @@ pub fn handler(ctx: Context<Borrow>, amount: u64) -> Result<()> {
 
     // Update reserve liquidity
     reserve.liquidity.total_borrows = reserve.liquidity.total_borrows
-        .checked_add(amount)
+        .checked_add(debt_amount)
+        .ok_or(BorrowError::MathOverflow)?;
+    reserve.liquidity.accumulated_protocol_fees = reserve.liquidity.accumulated_protocol_fees
+        .checked_add(utilization_fee)
+        .ok_or(BorrowError::MathOverflow)?;
+    reserve.total_borrow_principal = reserve.total_borrow_principal
+        .checked_add(debt_amount)
         .ok_or(BorrowError::MathOverflow)?;
 
     // Update obligation
-    let reserve_key = reserve.key();
     let current_borrow_index = reserve.liquidity.cumulative_borrow_index;
 
     // Check if user already has a borrow from this reserve
@@ -158,40 +323,75 @@ pub fn handler(ctx: Context<Borrow>, amount: u64) -> Result<()> {
         let borrow = &mut obligation.borrows[borrow_index];
 
         // Calculate current value with interest, then add new borrow
-        let current_borrow_amount = (borrow.borrowed_amount as u128 * current_borrow_index)
-            / borrow.borrow_index_snapshot;
+        let current_borrow_amount = match reserve.interest_model {
+            InterestModel::Compound => {
+                ((borrow.borrowed_amount as u128 * current_borrow_index)
+                    / borrow.borrow_index_snapshot) as u64
+            }
+            InterestModel::Simple => borrow
+                .current_amount(current_borrow_index, reserve.interest_model)
+                .ok_or(BorrowError::MathOverflow)?,
+        };
 
         let new_amount = current_borrow_amount
-            .checked_add(amount as u128)
+            .checked_add(debt_amount)
             .ok_or(BorrowError::MathOverflow)?;
 
         // Store new amount with current index as snapshot
-        borrow.borrowed_amount = new_amount as u64;
+        borrow.borrowed_amount = new_amount;
         borrow.borrow_index_snapshot = current_borrow_index;
+        borrow.principal = borrow.principal
+            .checked_add(debt_amount)
+            .ok_or(BorrowError::MathOverflow)?;
     } else {
-        // Create new borrow entry
+        // Create new borrow entry, within the market's position limit
         require!(
-            obligation.borrows.len() < MAX_OBLIGATION_BORROWS,
+            obligation.borrows.len() < ctx.accounts.lending_market.obligation_borrows_limit(),
             BorrowError::MaxBorrowsReached
         );
 
-        obligation.borrows.push(ObligationLiquidity::new(
+        obligation.insert_borrow(ObligationLiquidity::new(
             reserve_key,
-            amount,
+            debt_amount,
             current_borrow_index,
         ));
+
+        reserve.liquidity.borrower_count = reserve.liquidity.borrower_count.saturating_add(1);
     }
 
+    // Include the new debt in cached values until the next refresh
+    obligation.borrowed_value_usd = obligation.borrowed_value_usd
+        .checked_add(borrow_value_usd)
+        .ok_or(BorrowError::MathOverflow)?;
+
     // Validate final health factor after borrow
     // This ensures user maintains a safe distance from liquidation
+    // Volatile assets may require a larger buffer than the market minimum
+    let min_health_factor = reserve.config.min_health_factor_for_borrow();
     if obligation.borrowed_value_usd > 0 {
         let health_factor = obligation.calculate_health_factor();
         match health_factor {
             Some(hf) => {
                 require!(
-                    hf >= MIN_HEALTH_FACTOR_AFTER_BORROW,
+                    hf >= min_health_factor,
                     BorrowError::InsufficientHealthFactor
                 );
+
+                // Allowed, but flag borrows that land in the warning band
+                let warning_health_bps = ctx.accounts.lending_market.borrow_warning_health_bps;
+                if hf < warning_health_bps as u64 {
+                    emit!(RiskyBorrow {
+                        lending_market: ctx.accounts.lending_market.key(),
+                        reserve: reserve_key,
+                        obligation: obligation.key(),
+                        owner: ctx.accounts.owner.key(),
+                        health_factor: hf,
+                        warning_health_bps,
+                        min_health_factor,
+                        timestamp: clock.unix_timestamp,
+                    });
+                    msg!("Warning: borrow leaves health factor at {} bps", hf);
+                }
             },
             None => {
                 // No debt, should not happen here but safe
@@ -210,7 +410,7 @@ pub fn handler(ctx: Context<Borrow>, amount: u64) -> Result<()> {
     // Update timestamps
     reserve.last_update_slot = clock.slot;
     reserve.last_update_timestamp = clock.unix_timestamp;
-    obligation.last_update_slot = clock.slot;
+    reserve.liquidity.last_borrow_timestamp = clock.unix_timestamp;
 
     // Get new borrow amount for event
     let new_borrow_amount = if let Some(idx) = obligation.find_borrow(&reserve_key) {
@@ -219,6 +419,16 @@ pub fn handler(ctx: Context<Borrow>, amount: u64) -> Result<()> {
         0
     };
 
+    // Estimate interest on the new debt over the next day at the current rate
+    // (simple interest, rounded up; the actual rate moves with utilization)
+    let estimated_daily_interest = mul_div_ceil(
+        debt_amount as u128,
+        borrow_rate as u128 * SECONDS_PER_DAY as u128,
+        10000u128 * SECONDS_PER_YEAR as u128,
+    )
+    .and_then(|interest| u64::try_from(interest).ok())
+    .ok_or(BorrowError::MathOverflow)?;
+
     // Emit borrow event
     emit!(BorrowEvent {
         lending_market: ctx.accounts.lending_market.key(),
@@ -226,9 +436,12 @@ pub fn handler(ctx: Context<Borrow>, amount: u64) -> Result<()> {
         obligation: obligation.key(),
         owner: ctx.accounts.owner.key(),
         amount,
+        utilization_fee,
+        estimated_daily_interest,
         new_borrow_amount,
         new_utilization_bps: utilization_bps,
         new_borrow_rate_bps: borrow_rate,
+        borrower_count: reserve.liquidity.borrower_count,
         timestamp: clock.unix_timestamp,
     });
 
@@ -238,6 +451,32 @@ pub fn handler(ctx: Context<Borrow>, amount: u64) -> Result<()> {
     Ok(())
 }
 
+/// Check whether a refresh_obligation for `obligation` appears before the
+/// current instruction in this transaction
+fn refreshed_earlier_in_transaction(
+    instructions_sysvar: &AccountInfo,
+    obligation: &Pubkey,
+) -> Result<bool> {
+    let current_index = load_current_index_checked(instructions_sysvar)?;
+
+    for index in 0..current_index {
+        let instruction = load_instruction_at_checked(index as usize, instructions_sysvar)?;
+        let is_refresh_obligation = instruction.program_id == crate::ID
+            && instruction.data.starts_with(crate::instruction::RefreshObligation::DISCRIMINATOR);
+        // RefreshObligation accounts: [lending_market, obligation, ...]
+        let refreshes_obligation = instruction
+            .accounts
+            .get(1)
+            .is_some_and(|account| account.pubkey == *obligation);
+
+        if is_refresh_obligation && refreshes_obligation {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
 /// Borrow errors
 #[error_code]
 pub enum BorrowError {
@@ -259,6 +498,21 @@ pub enum BorrowError {
     #[msg("Invalid vault account")]
     InvalidVault,
 
+    #[msg("Token program does not match the reserve's token program")]
+    InvalidTokenProgram,
+
+    #[msg("Invalid oracle account")]
+    InvalidOracle,
+
+    #[msg("Invalid instructions sysvar account")]
+    InvalidInstructionsSysvar,
+
+    #[msg("Instructions sysvar is required by this lending market")]
+    MissingInstructionsSysvar,
+
+    #[msg("Borrow must be preceded by refresh_obligation in the same transaction")]
+    RefreshNotInTransaction,
+
     #[msg("Token mint mismatch")]
     InvalidTokenMint,
 
@@ -274,12 +528,24 @@ pub enum BorrowError {
     #[msg("No collateral deposited")]
     NoCollateral,
 
+    #[msg("Cannot borrow the same asset as the only collateral")]
+    CircularBorrow,
+
+    #[msg("Borrow amount exceeds the single borrow maximum")]
+    SingleBorrowTooLarge,
+
     #[msg("Borrow limit exceeded")]
     BorrowLimitExceeded,
 
+    #[msg("Obligation borrow value would exceed the market per-obligation limit")]
+    ObligationBorrowLimitExceeded,
+
     #[msg("Insufficient liquidity in reserve")]
     InsufficientLiquidity,
 
+    #[msg("Borrow would exceed the reserve utilization cap")]
+    UtilizationCapExceeded,
+
     #[msg("Insufficient borrowing capacity")]
     InsufficientBorrowingCapacity,
 
@@ -289,6 +555,12 @@ pub enum BorrowError {
     #[msg("Maximum borrows per obligation reached")]
     MaxBorrowsReached,
 
+    #[msg("Borrows are paused after a withdrawal until the obligation is refreshed")]
+    WithdrawCooldownActive,
+
+    #[msg("Obligation data is stale, refresh required")]
+    ObligationStale,
+
     #[msg("Reserve data is stale, refresh required")]
     ReserveStale,
 
@@ -1,12 +1,17 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 
-use crate::state::{LendingMarket, Reserve, Obligation, ObligationCollateral};
-use crate::constants::{VAULT_SEED, MAX_OBLIGATION_DEPOSITS, MIN_DEPOSIT_AMOUNT, MAX_RESERVE_STALENESS_SLOTS};
-use crate::events::DepositEvent;
+use crate::state::{LendingMarket, Reserve, Obligation, ObligationCollateral, WhitelistedDepositor};
+use crate::constants::{
+    VAULT_SEED, MAX_POSITION_VALUE_USD, MIN_DEPOSIT_AMOUNT, MAX_RESERVE_STALENESS_SLOTS,
+};
+use crate::events::{ActionBlocked, BlockReason, BlockedAction, DepositEvent};
+use crate::instructions::user::initialize_obligation::init_obligation;
+use crate::oracle::token_amount_to_usd;
 
 /// Accounts for depositing collateral
 #[derive(Accounts)]
+#[instruction(amount: u64, obligation_id: u8)]
 pub struct Deposit<'info> {
     /// User depositing collateral
     #[account(mut)]
@@ -14,7 +19,6 @@ pub struct Deposit<'info> {
 
     /// The lending market
     #[account(
-        constraint = !lending_market.emergency_mode @ DepositError::EmergencyModeActive,
         seeds = [LendingMarket::SEED_PREFIX, lending_market.authority.as_ref()],
         bump = lending_market.bump
     )]
@@ -23,18 +27,24 @@ pub struct Deposit<'info> {
     /// The reserve to deposit into
     #[account(
         mut,
-        constraint = reserve.lending_market == lending_market.key() @ DepositError::InvalidReserve,
-        constraint = reserve.config.deposits_enabled @ DepositError::DepositsDisabled
+        constraint = reserve.lending_market == lending_market.key() @ DepositError::InvalidReserve
     )]
     pub reserve: Account<'info, Reserve>,
 
-    /// User's obligation account
+    /// User's obligation account, created on first deposit when requested
+    /// PDA: ["obligation", lending_market, owner, obligation_id]
+    /// Owner and market of an existing obligation are checked in the handler
     #[account(
-        mut,
-        constraint = obligation.lending_market == lending_market.key() @ DepositError::InvalidObligation,
-        constraint = obligation.owner == owner.key() @ DepositError::InvalidObligationOwner,
-        seeds = [Obligation::SEED_PREFIX, lending_market.key().as_ref(), owner.key().as_ref()],
-        bump = obligation.bump
+        init_if_needed,
+        payer = owner,
+        space = 8 + Obligation::INIT_SPACE,
+        seeds = [
+            Obligation::SEED_PREFIX,
+            lending_market.key().as_ref(),
+            owner.key().as_ref(),
+            Obligation::id_seed(&obligation_id),
+        ],
+        bump
     )]
     pub obligation: Account<'info, Obligation>,
 
@@ -55,19 +65,106 @@ pub struct Deposit<'info> {
     )]
     pub token_vault: Account<'info, TokenAccount>,
 
-    /// Token program
+    /// Token program (must match the reserve's pinned token program)
+    #[account(
+        constraint = token_program.key() == reserve.token_program_id @ DepositError::InvalidTokenProgram,
+    )]
     pub token_program: Program<'info, Token>,
+
+    /// Owner's whitelist entry, required when the reserve is permissioned
+    #[account(
+        seeds = [WhitelistedDepositor::SEED_PREFIX, reserve.key().as_ref(), owner.key().as_ref()],
+        bump = whitelisted_depositor.bump
+    )]
+    pub whitelisted_depositor: Option<Account<'info, WhitelistedDepositor>>,
+
+    /// System program (pays for obligation creation on first deposit)
+    pub system_program: Program<'info, System>,
 }
 
 /// Deposit collateral into the reserve
 ///
 /// Transfers tokens from user to reserve vault and tracks the deposit
-/// in the user's obligation.
+/// in the user's obligation. With `create_obligation`, a missing obligation
+/// is created in the same instruction; otherwise it must already exist.
 ///
 /// # Arguments
 /// * `ctx` - The context containing all accounts
 /// * `amount` - Amount of tokens to deposit (in native units)
-pub fn handler(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+/// * `obligation_id` - Index of the owner's obligation to deposit into
+/// * `create_obligation` - Create the obligation if it does not exist yet
+pub fn handler(
+    ctx: Context<Deposit>,
+    amount: u64,
+    obligation_id: u8,
+    create_obligation: bool,
+) -> Result<()> {
+    // Market and reserve gates, checked here rather than as account
+    // constraints so rejections can be reported
+    let blocked_reason = if ctx.accounts.lending_market.emergency_mode {
+        Some(BlockReason::EmergencyMode)
+    } else if !ctx.accounts.reserve.config.deposits_enabled {
+        Some(BlockReason::ReserveDisabled)
+    } else {
+        None
+    };
+
+    if let Some(reason) = blocked_reason {
+        if ctx.accounts.lending_market.emit_action_blocked_events {
+            emit!(ActionBlocked {
+                lending_market: ctx.accounts.lending_market.key(),
+                reserve: ctx.accounts.reserve.key(),
+                owner: ctx.accounts.owner.key(),
+                action: BlockedAction::Deposit,
+                reason,
+                amount,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+
+        msg!("Deposit blocked: {:?}", reason);
+        return match reason {
+            BlockReason::EmergencyMode => err!(DepositError::EmergencyModeActive),
+            BlockReason::ReserveDisabled => err!(DepositError::DepositsDisabled),
+        };
+    }
+
+    // A zeroed owner means init_if_needed just created the account
+    if ctx.accounts.obligation.owner == Pubkey::default() {
+        require!(create_obligation, DepositError::ObligationNotInitialized);
+        require!(
+            ctx.accounts.lending_market.allow_new_obligations,
+            DepositError::NewObligationsPaused
+        );
+        init_obligation(
+            &mut ctx.accounts.obligation,
+            ctx.bumps.obligation,
+            ctx.accounts.lending_market.key(),
+            ctx.accounts.owner.key(),
+            obligation_id,
+        )?;
+    } else {
+        // Existing obligation: never reinitialize, only verify it
+        require_keys_eq!(
+            ctx.accounts.obligation.lending_market,
+            ctx.accounts.lending_market.key(),
+            DepositError::InvalidObligation
+        );
+        require_keys_eq!(
+            ctx.accounts.obligation.owner,
+            ctx.accounts.owner.key(),
+            DepositError::InvalidObligationOwner
+        );
+    }
+
+    // Permissioned pools only accept whitelisted depositors
+    if ctx.accounts.reserve.config.permissioned {
+        require!(
+            ctx.accounts.whitelisted_depositor.is_some(),
+            DepositError::DepositorNotWhitelisted
+        );
+    }
+
     // Validate amount
     require!(amount > 0, DepositError::AmountZero);
     require!(amount >= MIN_DEPOSIT_AMOUNT, DepositError::AmountTooSmall);
@@ -82,6 +179,12 @@ pub fn handler(ctx: Context<Deposit>, amount: u64) -> Result<()> {
         DepositError::ReserveStale
     );
 
+    // Check single deposit size if set
+    require!(
+        reserve.config.max_single_deposit == 0 || amount <= reserve.config.max_single_deposit,
+        DepositError::SingleDepositTooLarge
+    );
+
     // Check deposit limit if set
     if reserve.config.deposit_limit > 0 {
         let new_total = reserve.liquidity.total_deposits
@@ -108,6 +211,7 @@ pub fn handler(ctx: Context<Deposit>, amount: u64) -> Result<()> {
     reserve.liquidity.total_deposits = reserve.liquidity.total_deposits
         .checked_add(amount)
         .ok_or(DepositError::MathOverflow)?;
+    reserve.liquidity.last_deposit_timestamp = clock.unix_timestamp;
 
     // Update obligation
     let reserve_key = reserve.key();
@@ -125,28 +229,47 @@ pub fn handler(ctx: Context<Deposit>, amount: u64) -> Result<()> {
         let new_amount = current_amount
             .checked_add(amount as u128)
             .ok_or(DepositError::MathOverflow)?;
+        let new_amount = u64::try_from(new_amount).map_err(|_| DepositError::MathOverflow)?;
+
+        // Reject positions refresh_obligation could not value, using the
+        // price cached at the last refresh
+        if deposit.market_price_usd > 0 {
+            let new_value_usd = token_amount_to_usd(
+                new_amount,
+                deposit.market_price_usd,
+                reserve.token_decimals,
+            )?;
+            require!(
+                new_value_usd <= MAX_POSITION_VALUE_USD,
+                DepositError::ValueTooLarge
+            );
+        }
 
         // Store new amount with current index as snapshot
-        deposit.deposited_amount = new_amount as u64;
+        deposit.deposited_amount = new_amount;
         deposit.supply_index_snapshot = current_supply_index;
+        // Only the top-up waits out the haircut and activation delay
+        deposit.add_pending(amount, clock.slot, reserve.config.collateral_maturity_slots());
     } else {
-        // Create new deposit entry
+        // Create new deposit entry, within the market's position limit
         require!(
-            obligation.deposits.len() < MAX_OBLIGATION_DEPOSITS,
+            obligation.deposits.len() < ctx.accounts.lending_market.obligation_deposits_limit(),
             DepositError::MaxDepositsReached
         );
 
-        obligation.deposits.push(ObligationCollateral::new(
+        obligation.insert_deposit(ObligationCollateral::new(
             reserve_key,
             amount,
             current_supply_index,
+            clock.slot,
         ));
+
+        reserve.liquidity.depositor_count = reserve.liquidity.depositor_count.saturating_add(1);
     }
 
     // Update timestamp
     reserve.last_update_slot = clock.slot;
     reserve.last_update_timestamp = clock.unix_timestamp;
-    obligation.last_update_slot = clock.slot;
 
     // Get new deposit amount for event
     let new_deposit_amount = if let Some(idx) = obligation.find_deposit(&reserve_key) {
@@ -163,6 +286,7 @@ pub fn handler(ctx: Context<Deposit>, amount: u64) -> Result<()> {
         owner: ctx.accounts.owner.key(),
         amount,
         new_deposit_amount,
+        depositor_count: reserve.liquidity.depositor_count,
         timestamp: clock.unix_timestamp,
     });
 
@@ -183,12 +307,24 @@ pub enum DepositError {
     #[msg("Deposits are disabled for this reserve")]
     DepositsDisabled,
 
+    #[msg("Reserve is permissioned and the depositor is not whitelisted")]
+    DepositorNotWhitelisted,
+
     #[msg("Obligation does not belong to this lending market")]
     InvalidObligation,
 
     #[msg("Obligation owner mismatch")]
     InvalidObligationOwner,
 
+    #[msg("Deposit would exceed the maximum position USD value")]
+    ValueTooLarge,
+
+    #[msg("Obligation does not exist; pass create_obligation to create it")]
+    ObligationNotInitialized,
+
+    #[msg("New obligations are paused for this lending market")]
+    NewObligationsPaused,
+
     #[msg("Token mint mismatch")]
     InvalidTokenMint,
 
@@ -198,12 +334,18 @@ pub enum DepositError {
     #[msg("Invalid vault account")]
     InvalidVault,
 
+    #[msg("Token program does not match the reserve's token program")]
+    InvalidTokenProgram,
+
     #[msg("Deposit amount cannot be zero")]
     AmountZero,
 
     #[msg("Deposit amount too small")]
     AmountTooSmall,
 
+    #[msg("Deposit amount exceeds the single deposit maximum")]
+    SingleDepositTooLarge,
+
     #[msg("Deposit limit exceeded")]
     DepositLimitExceeded,
 
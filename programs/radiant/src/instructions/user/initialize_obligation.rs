@@ -5,6 +5,7 @@ use crate::events::ObligationInitialized;
 
 /// Accounts for initializing a user's obligation
 #[derive(Accounts)]
+#[instruction(obligation_id: u8)]
 pub struct InitializeObligation<'info> {
     /// User who owns this obligation
     #[account(mut)]
@@ -12,18 +13,24 @@ pub struct InitializeObligation<'info> {
 
     /// The lending market
     #[account(
+        constraint = lending_market.allow_new_obligations @ InitializeObligationError::NewObligationsPaused,
         seeds = [LendingMarket::SEED_PREFIX, lending_market.authority.as_ref()],
         bump = lending_market.bump
     )]
     pub lending_market: Account<'info, LendingMarket>,
 
     /// The obligation account to initialize
-    /// PDA: ["obligation", lending_market, owner]
+    /// PDA: ["obligation", lending_market, owner, obligation_id]
     #[account(
         init,
         payer = owner,
         space = 8 + Obligation::INIT_SPACE,
-        seeds = [Obligation::SEED_PREFIX, lending_market.key().as_ref(), owner.key().as_ref()],
+        seeds = [
+            Obligation::SEED_PREFIX,
+            lending_market.key().as_ref(),
+            owner.key().as_ref(),
+            Obligation::id_seed(&obligation_id),
+        ],
         bump
     )]
     pub obligation: Account<'info, Obligation>,
@@ -35,22 +42,42 @@ pub struct InitializeObligation<'info> {
 /// Initialize a user's obligation account
 ///
 /// An obligation tracks a user's deposits and borrows in the lending market.
-/// Each user has one obligation per lending market.
+/// A user may hold several obligations per lending market, one per
+/// `obligation_id`, to keep the risk of separate strategies isolated.
 ///
 /// # Arguments
 /// * `ctx` - The context containing all accounts
-pub fn handler(ctx: Context<InitializeObligation>) -> Result<()> {
-    let obligation = &mut ctx.accounts.obligation;
+/// * `obligation_id` - Index of this obligation among the owner's obligations
+pub fn handler(ctx: Context<InitializeObligation>, obligation_id: u8) -> Result<()> {
+    init_obligation(
+        &mut ctx.accounts.obligation,
+        ctx.bumps.obligation,
+        ctx.accounts.lending_market.key(),
+        ctx.accounts.owner.key(),
+        obligation_id,
+    )
+}
 
-    // Set version
-    obligation.version = 1;
+/// Set up a freshly created obligation account and emit ObligationInitialized
+///
+/// Shared by initialize_obligation and deposit's first-deposit creation.
+pub fn init_obligation(
+    obligation: &mut Account<Obligation>,
+    bump: u8,
+    lending_market: Pubkey,
+    owner: Pubkey,
+    obligation_id: u8,
+) -> Result<()> {
+    // Set version (new obligations start with sorted, empty positions)
+    obligation.version = Obligation::SORTED_VERSION;
 
     // Store bump for PDA verification
-    obligation.bump = ctx.bumps.obligation;
+    obligation.bump = bump;
 
     // Link to lending market and owner
-    obligation.lending_market = ctx.accounts.lending_market.key();
-    obligation.owner = ctx.accounts.owner.key();
+    obligation.lending_market = lending_market;
+    obligation.owner = owner;
+    obligation.obligation_id = obligation_id;
 
     // Set last update slot
     let clock = Clock::get()?;
@@ -66,6 +93,15 @@ pub fn handler(ctx: Context<InitializeObligation>) -> Result<()> {
     obligation.allowed_borrow_value_usd = 0;
     obligation.unhealthy_borrow_value_usd = 0;
 
+    // No liquidations yet
+    obligation.last_liquidation_slot = 0;
+    obligation.liquidations_in_slot = 0;
+
+    // No withdrawals yet
+    obligation.last_withdraw_slot = 0;
+    obligation.last_liquidation_health_bps = 0;
+    obligation.unhealthy_since_slot = 0;
+
     // Initialize padding
     obligation._padding = [0u8; 64];
 
@@ -74,9 +110,17 @@ pub fn handler(ctx: Context<InitializeObligation>) -> Result<()> {
         lending_market: obligation.lending_market,
         obligation: obligation.key(),
         owner: obligation.owner,
+        obligation_id,
     });
 
-    msg!("Obligation initialized for user: {}", obligation.owner);
+    msg!("Obligation {} initialized for user: {}", obligation_id, obligation.owner);
 
     Ok(())
 }
+
+/// Initialize obligation errors
+#[error_code]
+pub enum InitializeObligationError {
+    #[msg("New obligations are paused for this lending market")]
+    NewObligationsPaused,
+}
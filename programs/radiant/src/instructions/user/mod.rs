@@ -3,9 +3,15 @@ pub mod deposit;
 pub mod withdraw;
 pub mod borrow;
 pub mod repay;
+pub mod repay_to_health;
+pub mod transfer_obligation;
+pub mod set_deposit_collateral;
 
 pub use initialize_obligation::*;
 pub use deposit::*;
 pub use withdraw::*;
 pub use borrow::*;
 pub use repay::*;
+pub use repay_to_health::*;
+pub use transfer_obligation::*;
+pub use set_deposit_collateral::*;
@@ -1,14 +1,18 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::set_return_data;
 use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 
-use crate::state::{LendingMarket, Reserve, Obligation};
+use crate::state::{InterestModel, LendingMarket, Reserve, Obligation};
 use crate::constants::{VAULT_SEED, MAX_RESERVE_STALENESS_SLOTS};
 use crate::events::RepayEvent;
+use crate::math::mul_div_floor;
 
 /// Accounts for repaying borrowed tokens
 #[derive(Accounts)]
 pub struct Repay<'info> {
     /// User repaying the loan (can be anyone, not just the borrower)
+    /// Receives the obligation's rent if it is closed
+    #[account(mut)]
     pub payer: Signer<'info>,
 
     /// The lending market
@@ -29,7 +33,12 @@ pub struct Repay<'info> {
     #[account(
         mut,
         constraint = obligation.lending_market == lending_market.key() @ RepayError::InvalidObligation,
-        seeds = [Obligation::SEED_PREFIX, lending_market.key().as_ref(), obligation.owner.as_ref()],
+        seeds = [
+            Obligation::SEED_PREFIX,
+            lending_market.key().as_ref(),
+            obligation.owner.as_ref(),
+            Obligation::id_seed(&obligation.obligation_id),
+        ],
         bump = obligation.bump
     )]
     pub obligation: Account<'info, Obligation>,
@@ -51,7 +60,10 @@ pub struct Repay<'info> {
     )]
     pub token_vault: Account<'info, TokenAccount>,
 
-    /// Token program
+    /// Token program (must match the reserve's pinned token program)
+    #[account(
+        constraint = token_program.key() == reserve.token_program_id @ RepayError::InvalidTokenProgram,
+    )]
     pub token_program: Program<'info, Token>,
 }
 
@@ -60,12 +72,43 @@ pub struct Repay<'info> {
 /// Anyone can repay on behalf of a borrower.
 /// If amount is 0 or greater than debt, repays full debt.
 ///
+/// Repay never advances the obligation's refresh slot, so it cannot extend
+/// the freshness window liquidate relies on. Instead the cached USD debt is
+/// reduced by the repaid share at the last refreshed price, so a liquidation
+/// in the same slot sees the post-repay health: a tiny repay changes health
+/// only by its own size and cannot block a legitimate liquidation.
+/// If `close_obligation` is set and the obligation is left with no
+/// deposits or borrows, it is closed and its rent refunded to the owner
+/// (the payer must be the owner in that case).
+///
 /// # Arguments
 /// * `ctx` - The context containing all accounts
 /// * `amount` - Amount to repay (in native units), 0 = repay all
-pub fn handler(ctx: Context<Repay>, amount: u64) -> Result<()> {
-    let reserve = &mut ctx.accounts.reserve;
-    let obligation = &mut ctx.accounts.obligation;
+/// * `close_obligation` - Close the obligation if it becomes empty
+pub fn handler(ctx: Context<Repay>, amount: u64, close_obligation: bool) -> Result<()> {
+    process_repay(ctx.accounts, amount)?;
+
+    let obligation = &ctx.accounts.obligation;
+    if close_obligation && !obligation.has_deposits() && !obligation.has_borrows() {
+        require_keys_eq!(
+            ctx.accounts.payer.key(),
+            obligation.owner,
+            RepayError::CloseRequiresOwner
+        );
+
+        msg!("Closing empty obligation: {}", obligation.key());
+        ctx.accounts.obligation.close(ctx.accounts.payer.to_account_info())?;
+    }
+
+    Ok(())
+}
+
+/// Repay logic shared by `repay` and `repay_to_health`
+///
+/// Returns the amount actually repaid.
+pub fn process_repay(accounts: &mut Repay, amount: u64) -> Result<u64> {
+    let reserve = &mut accounts.reserve;
+    let obligation = &mut accounts.obligation;
     let reserve_key = reserve.key();
     let clock = Clock::get()?;
 
@@ -76,18 +119,22 @@ pub fn handler(ctx: Context<Repay>, amount: u64) -> Result<()> {
     );
 
     // Find user's borrow in this reserve
-    let borrow_index = obligation
-        .find_borrow(&reserve_key)
-        .ok_or(RepayError::NoBorrowFound)?;
+    let Some(borrow_index) = obligation.find_borrow(&reserve_key) else {
+        report_borrowed_reserves(obligation, &reserve_key);
+        return err!(RepayError::NoBorrowFound);
+    };
 
     let current_borrow_index = reserve.liquidity.cumulative_borrow_index;
 
     // Calculate current borrow value with accrued interest
     let borrow = &obligation.borrows[borrow_index];
-    let current_borrow_amount = if borrow.borrow_index_snapshot > 0 {
-        (borrow.borrowed_amount as u128 * current_borrow_index / borrow.borrow_index_snapshot) as u64
-    } else {
-        borrow.borrowed_amount
+    let current_borrow_amount = match reserve.interest_model {
+        InterestModel::Compound if borrow.borrow_index_snapshot > 0 => {
+            (borrow.borrowed_amount as u128 * current_borrow_index / borrow.borrow_index_snapshot) as u64
+        }
+        _ => borrow
+            .current_amount(current_borrow_index, reserve.interest_model)
+            .ok_or(RepayError::MathOverflow)?,
     };
 
     require!(current_borrow_amount > 0, RepayError::NothingToRepay);
@@ -99,36 +146,56 @@ pub fn handler(ctx: Context<Repay>, amount: u64) -> Result<()> {
         amount
     };
 
+    // Split repayment into principal and accrued interest (interest first)
+    let (principal_repaid, interest_repaid) = obligation.borrows[borrow_index]
+        .split_repayment(current_borrow_amount, repay_amount);
+
     // Transfer tokens from payer to vault
     let transfer_ctx = CpiContext::new(
-        ctx.accounts.token_program.to_account_info(),
+        accounts.token_program.to_account_info(),
         Transfer {
-            from: ctx.accounts.payer_token_account.to_account_info(),
-            to: ctx.accounts.token_vault.to_account_info(),
-            authority: ctx.accounts.payer.to_account_info(),
+            from: accounts.payer_token_account.to_account_info(),
+            to: accounts.token_vault.to_account_info(),
+            authority: accounts.payer.to_account_info(),
         },
     );
     token::transfer(transfer_ctx, repay_amount)?;
 
     // Update reserve liquidity
+    // Saturating: position debts round up, so their sum may exceed the
+    // rounded-down reserve total by a few native units
     reserve.liquidity.total_borrows = reserve.liquidity.total_borrows
-        .checked_sub(repay_amount)
-        .ok_or(RepayError::MathOverflow)?;
+        .saturating_sub(repay_amount);
+    reserve.total_borrow_principal = reserve.total_borrow_principal
+        .saturating_sub(principal_repaid);
 
     // Calculate remaining borrow after repayment
     let remaining_borrow = current_borrow_amount
         .checked_sub(repay_amount)
         .ok_or(RepayError::MathOverflow)?;
 
+    // Reduce the cached USD debt by the repaid share (rounded down, so the
+    // remaining debt is never understated) until the next refresh
+    let repaid_value_usd = mul_div_floor(
+        obligation.borrows[borrow_index].market_value_usd,
+        repay_amount as u128,
+        current_borrow_amount as u128,
+    )
+    .ok_or(RepayError::MathOverflow)?;
+    obligation.borrowed_value_usd = obligation.borrowed_value_usd.saturating_sub(repaid_value_usd);
+
     // Update or remove obligation borrow
     if remaining_borrow == 0 {
         // Remove the borrow entry
         obligation.borrows.remove(borrow_index);
+        reserve.liquidity.borrower_count = reserve.liquidity.borrower_count.saturating_sub(1);
     } else {
         // Update the borrow with remaining amount
         let borrow = &mut obligation.borrows[borrow_index];
         borrow.borrowed_amount = remaining_borrow;
         borrow.borrow_index_snapshot = current_borrow_index;
+        borrow.principal = borrow.principal.saturating_sub(principal_repaid);
+        borrow.market_value_usd = borrow.market_value_usd.saturating_sub(repaid_value_usd);
     }
 
     // Update interest rates based on new utilization
@@ -142,19 +209,21 @@ pub fn handler(ctx: Context<Repay>, amount: u64) -> Result<()> {
     // Update timestamps
     reserve.last_update_slot = clock.slot;
     reserve.last_update_timestamp = clock.unix_timestamp;
-    obligation.last_update_slot = clock.slot;
 
     // Emit repay event
     emit!(RepayEvent {
-        lending_market: ctx.accounts.lending_market.key(),
+        lending_market: accounts.lending_market.key(),
         reserve: reserve_key,
         obligation: obligation.key(),
-        payer: ctx.accounts.payer.key(),
+        payer: accounts.payer.key(),
         owner: obligation.owner,
         amount: repay_amount,
+        principal_repaid,
+        interest_repaid,
         remaining_borrow,
         new_utilization_bps: utilization_bps,
         new_borrow_rate_bps: borrow_rate,
+        borrower_count: reserve.liquidity.borrower_count,
         timestamp: clock.unix_timestamp,
     });
 
@@ -162,7 +231,31 @@ pub fn handler(ctx: Context<Repay>, amount: u64) -> Result<()> {
     msg!("Remaining debt: {}", remaining_borrow);
     msg!("New utilization: {} bps, Borrow rate: {} bps", utilization_bps, borrow_rate);
 
-    Ok(())
+    Ok(repay_amount)
+}
+
+/// Tell the caller which reserves the obligation actually owes
+///
+/// Logs the borrowed reserves (and a hint when the given reserve is only a
+/// deposit) and sets them as return data, borsh-encoded as `Vec<Pubkey>`,
+/// so simulations of a failed repay show where the debt is.
+fn report_borrowed_reserves(obligation: &Obligation, reserve: &Pubkey) {
+    let borrowed_reserves = obligation.borrowed_reserves();
+
+    if obligation.find_deposit(reserve).is_some() {
+        msg!("Reserve {} is a deposit of this obligation, not a borrow", reserve);
+    }
+    if borrowed_reserves.is_empty() {
+        msg!("Obligation has no borrows");
+    } else {
+        for borrowed_reserve in borrowed_reserves.iter() {
+            msg!("Obligation borrows from reserve {}", borrowed_reserve);
+        }
+    }
+
+    if let Ok(data) = borrowed_reserves.try_to_vec() {
+        set_return_data(&data);
+    }
 }
 
 /// Repay errors
@@ -177,6 +270,9 @@ pub enum RepayError {
     #[msg("Invalid vault account")]
     InvalidVault,
 
+    #[msg("Token program does not match the reserve's token program")]
+    InvalidTokenProgram,
+
     #[msg("Token mint mismatch")]
     InvalidTokenMint,
 
@@ -192,6 +288,9 @@ pub enum RepayError {
     #[msg("Reserve data is stale, refresh required")]
     ReserveStale,
 
+    #[msg("Only the obligation owner can close it on repay")]
+    CloseRequiresOwner,
+
     #[msg("Math overflow")]
     MathOverflow,
 }
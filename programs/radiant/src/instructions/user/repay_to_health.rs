@@ -0,0 +1,85 @@
+use anchor_lang::prelude::*;
+
+// Glob import brings in the Accounts-generated modules needed to nest Repay
+use crate::instructions::user::repay::*;
+use crate::math::mul_div_ceil;
+use crate::oracle::get_reserve_price_usd;
+
+/// Accounts for repaying down to a target health factor
+#[derive(Accounts)]
+pub struct RepayToHealth<'info> {
+    /// Standard repay accounts
+    pub repay: Repay<'info>,
+
+    /// Oracle of the reserve being repaid
+    /// CHECK: Validated against reserve.oracle
+    #[account(
+        constraint = oracle.key() == repay.reserve.oracle @ RepayToHealthError::InvalidOracle
+    )]
+    pub oracle: UncheckedAccount<'info>,
+
+    /// Quote oracle, required if the reserve is priced through a non-USD feed
+    /// CHECK: Validated against reserve.price_quote_oracle in the oracle helper
+    pub quote_oracle: Option<UncheckedAccount<'info>>,
+}
+
+/// Repay exactly enough debt to reach a target health factor
+///
+/// Computes the USD repayment from the obligation's cached values,
+/// converts it to native units at the repay reserve's oracle price,
+/// caps it at the full debt and executes the repay.
+/// The obligation should be refreshed in the same transaction.
+///
+/// # Arguments
+/// * `ctx` - The context containing all accounts
+/// * `target_health_bps` - Desired health factor after repay (1.0 = 10000)
+///
+/// # Returns
+/// * The amount actually repaid (in native units)
+pub fn handler(ctx: Context<RepayToHealth>, target_health_bps: u64) -> Result<u64> {
+    let clock = Clock::get()?;
+
+    let repay_value_usd = ctx.accounts.repay.obligation
+        .repay_to_target_health(target_health_bps)
+        .ok_or(RepayToHealthError::InvalidTargetHealth)?;
+    require!(repay_value_usd > 0, RepayToHealthError::TargetHealthAlreadyMet);
+
+    // Convert USD to native units, rounding up so the target is reached
+    let reserve = &ctx.accounts.repay.reserve;
+    let price_usd = get_reserve_price_usd(
+        reserve,
+        &ctx.accounts.oracle.to_account_info(),
+        ctx.accounts.quote_oracle.as_ref().map(|oracle| oracle.as_ref()),
+        clock.slot,
+    )?;
+
+    let decimals_factor = 10u128
+        .checked_pow(reserve.token_decimals as u32)
+        .ok_or(RepayToHealthError::MathOverflow)?;
+    let repay_amount = mul_div_ceil(repay_value_usd, decimals_factor, price_usd as u128)
+        .ok_or(RepayToHealthError::MathOverflow)?;
+
+    // Amounts at or above the debt repay it in full
+    let repay_amount = u64::try_from(repay_amount).unwrap_or(u64::MAX);
+    let repaid = process_repay(&mut ctx.accounts.repay, repay_amount)?;
+
+    msg!("Repaid {} tokens to reach target health {} bps", repaid, target_health_bps);
+
+    Ok(repaid)
+}
+
+/// Repay to health errors
+#[error_code]
+pub enum RepayToHealthError {
+    #[msg("Invalid oracle account")]
+    InvalidOracle,
+
+    #[msg("Target health factor must be greater than zero")]
+    InvalidTargetHealth,
+
+    #[msg("Obligation is already at or above the target health factor")]
+    TargetHealthAlreadyMet,
+
+    #[msg("Math overflow")]
+    MathOverflow,
+}
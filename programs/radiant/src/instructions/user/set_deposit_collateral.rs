@@ -0,0 +1,156 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{LendingMarket, Reserve, Obligation};
+use crate::constants::{MAX_OBLIGATION_STALENESS_SLOTS, MIN_HEALTH_FACTOR_AFTER_BORROW};
+use crate::events::DepositCollateralChanged;
+use crate::math::{health_factor_bps, mul_div_ceil};
+
+/// Accounts for enabling or disabling a deposit as collateral
+#[derive(Accounts)]
+pub struct SetDepositCollateral<'info> {
+    /// Owner of the obligation
+    pub owner: Signer<'info>,
+
+    /// The lending market
+    #[account(
+        seeds = [LendingMarket::SEED_PREFIX, lending_market.authority.as_ref()],
+        bump = lending_market.bump
+    )]
+    pub lending_market: Account<'info, LendingMarket>,
+
+    /// The reserve of the deposit
+    #[account(
+        constraint = reserve.lending_market == lending_market.key() @ SetDepositCollateralError::InvalidReserve
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    /// User's obligation account
+    #[account(
+        mut,
+        constraint = obligation.lending_market == lending_market.key() @ SetDepositCollateralError::InvalidObligation,
+        constraint = obligation.owner == owner.key() @ SetDepositCollateralError::InvalidObligationOwner,
+        seeds = [
+            Obligation::SEED_PREFIX,
+            lending_market.key().as_ref(),
+            owner.key().as_ref(),
+            Obligation::id_seed(&obligation.obligation_id),
+        ],
+        bump = obligation.bump
+    )]
+    pub obligation: Account<'info, Obligation>,
+}
+
+/// Enable or disable a deposit as borrow collateral
+///
+/// A deposit disabled as collateral keeps earning interest but adds no
+/// borrowing power and cannot be seized, capping the owner's liquidation
+/// exposure to their remaining collateral.
+///
+/// Disabling always removes the deposit's contribution from the cached
+/// values, using the higher of its base and e-mode parameters, and blocks
+/// borrows until the next refresh_obligation. With outstanding debt it also
+/// requires a fresh obligation and must keep the position above the minimum
+/// health factor. Enabling takes effect on the next refresh.
+///
+/// # Arguments
+/// * `ctx` - The context containing all accounts
+/// * `use_as_collateral` - true to count the deposit as collateral
+pub fn handler(ctx: Context<SetDepositCollateral>, use_as_collateral: bool) -> Result<()> {
+    let reserve = &ctx.accounts.reserve;
+    let obligation = &mut ctx.accounts.obligation;
+    let clock = Clock::get()?;
+    let reserve_key = reserve.key();
+
+    let deposit_index = obligation
+        .find_deposit(&reserve_key)
+        .ok_or(SetDepositCollateralError::NoDepositFound)?;
+
+    if obligation.deposits[deposit_index].use_as_collateral == use_as_collateral {
+        msg!("Deposit collateral usage unchanged: {}", use_as_collateral);
+        return Ok(());
+    }
+
+    if !use_as_collateral {
+        // Remove at least as much as the deposit could have contributed,
+        // whether or not there is debt, so cached values never overstate
+        // collateral
+        let (ltv_bps, liq_threshold_bps) = reserve.config.risk_params(true);
+        let deposit_value_usd = obligation.deposits[deposit_index].market_value_usd;
+        let allowed_usd = mul_div_ceil(deposit_value_usd, ltv_bps as u128, 10000)
+            .ok_or(SetDepositCollateralError::MathOverflow)?;
+        let unhealthy_usd = mul_div_ceil(deposit_value_usd, liq_threshold_bps as u128, 10000)
+            .ok_or(SetDepositCollateralError::MathOverflow)?;
+
+        let new_allowed_borrow_value_usd = obligation.allowed_borrow_value_usd.saturating_sub(allowed_usd);
+        let new_unhealthy_borrow_value_usd = obligation.unhealthy_borrow_value_usd.saturating_sub(unhealthy_usd);
+
+        if obligation.has_borrows() {
+            require!(
+                clock.slot <= obligation.last_update_slot.saturating_add(MAX_OBLIGATION_STALENESS_SLOTS),
+                SetDepositCollateralError::ObligationStale
+            );
+
+            require!(
+                obligation.borrowed_value_usd <= new_allowed_borrow_value_usd,
+                SetDepositCollateralError::InsufficientBorrowCapacity
+            );
+
+            let new_health_factor =
+                health_factor_bps(new_unhealthy_borrow_value_usd, obligation.borrowed_value_usd)
+                    .unwrap_or(u64::MAX);
+            require!(
+                new_health_factor >= MIN_HEALTH_FACTOR_AFTER_BORROW,
+                SetDepositCollateralError::HealthFactorTooLow
+            );
+        }
+
+        obligation.allowed_borrow_value_usd = new_allowed_borrow_value_usd;
+        obligation.unhealthy_borrow_value_usd = new_unhealthy_borrow_value_usd;
+
+        // Block borrows on the adjusted cached values until the next refresh
+        obligation.last_withdraw_slot = clock.slot;
+    }
+
+    obligation.deposits[deposit_index].use_as_collateral = use_as_collateral;
+
+    // Emit event
+    emit!(DepositCollateralChanged {
+        obligation: obligation.key(),
+        reserve: reserve_key,
+        owner: obligation.owner,
+        use_as_collateral,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Deposit in reserve {} used as collateral: {}", reserve.token_mint, use_as_collateral);
+
+    Ok(())
+}
+
+/// Set deposit collateral errors
+#[error_code]
+pub enum SetDepositCollateralError {
+    #[msg("Reserve does not belong to this lending market")]
+    InvalidReserve,
+
+    #[msg("Obligation does not belong to this lending market")]
+    InvalidObligation,
+
+    #[msg("Obligation owner mismatch")]
+    InvalidObligationOwner,
+
+    #[msg("No deposit found for this reserve")]
+    NoDepositFound,
+
+    #[msg("Obligation data is stale, refresh required")]
+    ObligationStale,
+
+    #[msg("Insufficient borrowing capacity without this collateral")]
+    InsufficientBorrowCapacity,
+
+    #[msg("Health factor would be too low without this collateral")]
+    HealthFactorTooLow,
+
+    #[msg("Math overflow")]
+    MathOverflow,
+}
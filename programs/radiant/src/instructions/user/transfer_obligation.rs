@@ -0,0 +1,124 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{LendingMarket, Obligation};
+use crate::events::ObligationTransferred;
+
+/// Accounts for transferring an obligation to a new owner
+#[derive(Accounts)]
+pub struct TransferObligation<'info> {
+    /// Current owner of the obligation (receives the old account's rent)
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// New owner acknowledging the transfer (pays for the new account)
+    #[account(mut)]
+    pub new_owner: Signer<'info>,
+
+    /// The lending market
+    #[account(
+        seeds = [LendingMarket::SEED_PREFIX, lending_market.authority.as_ref()],
+        bump = lending_market.bump
+    )]
+    pub lending_market: Account<'info, LendingMarket>,
+
+    /// The obligation being transferred (closed after migration)
+    #[account(
+        mut,
+        close = owner,
+        constraint = obligation.lending_market == lending_market.key() @ TransferObligationError::InvalidObligation,
+        constraint = obligation.owner == owner.key() @ TransferObligationError::InvalidObligationOwner,
+        seeds = [
+            Obligation::SEED_PREFIX,
+            lending_market.key().as_ref(),
+            owner.key().as_ref(),
+            Obligation::id_seed(&obligation.obligation_id),
+        ],
+        bump = obligation.bump
+    )]
+    pub obligation: Account<'info, Obligation>,
+
+    /// The new owner's obligation, keeping the same obligation id
+    /// PDA: ["obligation", lending_market, new_owner, obligation_id]
+    #[account(
+        init,
+        payer = new_owner,
+        space = 8 + Obligation::INIT_SPACE,
+        seeds = [
+            Obligation::SEED_PREFIX,
+            lending_market.key().as_ref(),
+            new_owner.key().as_ref(),
+            Obligation::id_seed(&obligation.obligation_id),
+        ],
+        bump
+    )]
+    pub new_obligation: Account<'info, Obligation>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+/// Transfer an entire obligation to a new owner
+///
+/// Because obligation PDAs are seeded by owner, the position is migrated
+/// into a new PDA for the recipient and the old account is closed.
+/// All deposits, borrows and cached values carry over unchanged.
+/// Both the current owner and the recipient must sign.
+///
+/// # Arguments
+/// * `ctx` - The context containing all accounts
+pub fn handler(ctx: Context<TransferObligation>) -> Result<()> {
+    let old_owner = ctx.accounts.owner.key();
+    let new_owner = ctx.accounts.new_owner.key();
+
+    require_keys_neq!(old_owner, new_owner, TransferObligationError::SameOwner);
+
+    let obligation = &ctx.accounts.obligation;
+
+    // Migrate position state; only ownership and PDA bump change
+    let mut migrated = Obligation::clone(obligation);
+    migrated.owner = new_owner;
+    migrated.bump = ctx.bumps.new_obligation;
+
+    let new_obligation = &mut ctx.accounts.new_obligation;
+    new_obligation.set_inner(migrated);
+
+    // Verify position integrity
+    require!(
+        new_obligation.deposits.len() == obligation.deposits.len()
+            && new_obligation.borrows.len() == obligation.borrows.len()
+            && new_obligation.calculate_health_factor() == obligation.calculate_health_factor(),
+        TransferObligationError::PositionMismatch
+    );
+
+    // Emit event
+    emit!(ObligationTransferred {
+        lending_market: new_obligation.lending_market,
+        old_obligation: obligation.key(),
+        new_obligation: new_obligation.key(),
+        old_owner,
+        new_owner,
+        deposits_count: new_obligation.deposits.len() as u8,
+        borrows_count: new_obligation.borrows.len() as u8,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Obligation transferred from {} to {}", old_owner, new_owner);
+
+    Ok(())
+}
+
+/// Transfer obligation errors
+#[error_code]
+pub enum TransferObligationError {
+    #[msg("Obligation does not belong to this lending market")]
+    InvalidObligation,
+
+    #[msg("Obligation owner mismatch")]
+    InvalidObligationOwner,
+
+    #[msg("New owner must differ from current owner")]
+    SameOwner,
+
+    #[msg("Migrated position does not match the original")]
+    PositionMismatch,
+}
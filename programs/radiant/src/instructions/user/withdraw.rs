@@ -2,8 +2,10 @@ use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 
 use crate::state::{LendingMarket, Reserve, Obligation};
-use crate::constants::{VAULT_SEED, MIN_HEALTH_FACTOR_AFTER_BORROW, MAX_RESERVE_STALENESS_SLOTS};
+use crate::constants::{VAULT_SEED, MIN_HEALTH_FACTOR_AFTER_BORROW};
 use crate::events::WithdrawEvent;
+use crate::math::health_factor_bps;
+use crate::instructions::permissionless::refresh_reserve::accrue_interest;
 
 /// Accounts for withdrawing collateral
 #[derive(Accounts)]
@@ -30,7 +32,12 @@ pub struct Withdraw<'info> {
         mut,
         constraint = obligation.lending_market == lending_market.key() @ WithdrawError::InvalidObligation,
         constraint = obligation.owner == owner.key() @ WithdrawError::InvalidObligationOwner,
-        seeds = [Obligation::SEED_PREFIX, lending_market.key().as_ref(), owner.key().as_ref()],
+        seeds = [
+            Obligation::SEED_PREFIX,
+            lending_market.key().as_ref(),
+            owner.key().as_ref(),
+            Obligation::id_seed(&obligation.obligation_id),
+        ],
         bump = obligation.bump
     )]
     pub obligation: Account<'info, Obligation>,
@@ -52,12 +59,16 @@ pub struct Withdraw<'info> {
     )]
     pub user_token_account: Account<'info, TokenAccount>,
 
-    /// Token program
+    /// Token program (must match the reserve's pinned token program)
+    #[account(
+        constraint = token_program.key() == reserve.token_program_id @ WithdrawError::InvalidTokenProgram,
+    )]
     pub token_program: Program<'info, Token>,
 }
 
 /// Withdraw collateral from the reserve
 ///
+/// Accrues reserve interest first, so withdrawals are always up to date.
 /// Transfers tokens from reserve vault to user.
 /// Validates that withdrawal doesn't make position unhealthy.
 ///
@@ -70,11 +81,8 @@ pub fn handler(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
     let reserve_key = reserve.key();
     let clock = Clock::get()?;
 
-    // Check reserve is not stale
-    require!(
-        !reserve.is_stale(clock.slot, MAX_RESERVE_STALENESS_SLOTS),
-        WithdrawError::ReserveStale
-    );
+    // Accrue interest inline so the withdrawal includes all interest to this slot
+    accrue_interest(reserve, clock.slot, clock.unix_timestamp)?;
 
     // Find user's deposit in this reserve
     let deposit_index = obligation
@@ -123,7 +131,8 @@ pub fn handler(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
         .ok_or(WithdrawError::MathOverflow)?;
 
     // If user has borrows, validate health factor after withdrawal
-    if obligation.has_borrows() {
+    // Deposits not used as collateral don't back the debt
+    if obligation.has_borrows() && obligation.deposits[deposit_index].use_as_collateral {
         // Calculate the USD value being withdrawn (simplified - in production use oracle)
         // This is an approximation using cached deposit market value
         let deposit = &obligation.deposits[deposit_index];
@@ -153,11 +162,10 @@ pub fn handler(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
         );
 
         // Calculate health factor after withdrawal
-        let new_health_factor = if obligation.borrowed_value_usd > 0 {
-            ((new_unhealthy_borrow_value_usd * 10000) / obligation.borrowed_value_usd) as u64
-        } else {
-            u64::MAX // No debt = infinite health
-        };
+        // No debt = infinite health
+        let new_health_factor =
+            health_factor_bps(new_unhealthy_borrow_value_usd, obligation.borrowed_value_usd)
+                .unwrap_or(u64::MAX);
 
         // Require health factor stays above minimum threshold
         require!(
@@ -201,17 +209,22 @@ pub fn handler(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
     if remaining_deposit == 0 {
         // Remove the deposit entry
         obligation.deposits.remove(deposit_index);
+        reserve.liquidity.depositor_count = reserve.liquidity.depositor_count.saturating_sub(1);
     } else {
         // Update the deposit with remaining amount
         let deposit = &mut obligation.deposits[deposit_index];
         deposit.deposited_amount = remaining_deposit;
         deposit.supply_index_snapshot = current_supply_index;
+        // Removed collateral comes out of the matured part first
+        deposit.pending_amount = deposit.pending_amount.min(remaining_deposit);
     }
 
     // Update timestamps
     reserve.last_update_slot = clock.slot;
     reserve.last_update_timestamp = clock.unix_timestamp;
-    obligation.last_update_slot = clock.slot;
+
+    // Block borrows until the next refresh
+    obligation.last_withdraw_slot = clock.slot;
 
     // Emit withdraw event
     emit!(WithdrawEvent {
@@ -221,6 +234,7 @@ pub fn handler(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
         owner: ctx.accounts.owner.key(),
         amount: withdraw_amount,
         remaining_deposit,
+        depositor_count: reserve.liquidity.depositor_count,
         timestamp: clock.unix_timestamp,
     });
 
@@ -244,6 +258,9 @@ pub enum WithdrawError {
     #[msg("Invalid vault account")]
     InvalidVault,
 
+    #[msg("Token program does not match the reserve's token program")]
+    InvalidTokenProgram,
+
     #[msg("Token mint mismatch")]
     InvalidTokenMint,
 
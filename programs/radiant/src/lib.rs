@@ -3,6 +3,8 @@ use anchor_lang::prelude::*;
 pub mod constants;
 pub mod events;
 pub mod instructions;
+pub mod math;
+pub mod oracle;
 pub mod state;
 
 use instructions::*;
@@ -41,6 +43,14 @@ pub mod radiant {
         instructions::admin::update_reserve_config::handler(ctx, params)
     }
 
+    /// Update lending market configuration
+    pub fn update_lending_market(
+        ctx: Context<UpdateLendingMarket>,
+        params: UpdateLendingMarketParams,
+    ) -> Result<()> {
+        instructions::admin::update_lending_market::handler(ctx, params)
+    }
+
     /// Set emergency mode on/off
     pub fn set_emergency_mode(
         ctx: Context<SetEmergencyMode>,
@@ -49,9 +59,68 @@ pub mod radiant {
         instructions::admin::set_emergency_mode::handler(ctx, emergency)
     }
 
+    /// Halt or resume liquidations
+    pub fn set_liquidations_halted(
+        ctx: Context<SetLiquidationsHalted>,
+        halted: bool,
+    ) -> Result<()> {
+        instructions::admin::set_liquidations_halted::handler(ctx, halted)
+    }
+
+    /// Allow or pause creation of new obligations
+    pub fn set_allow_new_obligations(
+        ctx: Context<SetAllowNewObligations>,
+        allowed: bool,
+    ) -> Result<()> {
+        instructions::admin::set_allow_new_obligations::handler(ctx, allowed)
+    }
+
     /// Collect accumulated protocol fees from a reserve
-    pub fn collect_fees(ctx: Context<CollectFees>, amount: u64) -> Result<()> {
-        instructions::admin::collect_fees::handler(ctx, amount)
+    pub fn collect_fees(
+        ctx: Context<CollectFees>,
+        amount: u64,
+        amount_usd: Option<u64>,
+    ) -> Result<()> {
+        instructions::admin::collect_fees::handler(ctx, amount, amount_usd)
+    }
+
+    /// Sweep tokens sent to a reserve vault outside of deposits to the treasury
+    pub fn sweep_surplus(ctx: Context<SweepSurplus>) -> Result<()> {
+        instructions::admin::sweep_surplus::handler(ctx)
+    }
+
+    /// Compare (and optionally repair) reserve totals against obligation positions
+    pub fn reconcile_reserve<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ReconcileReserve<'info>>,
+        repair: bool,
+    ) -> Result<()> {
+        instructions::admin::reconcile_reserve::handler(ctx, repair)
+    }
+
+    /// Deposit treasury tokens into a reserve as protocol-owned liquidity
+    pub fn seed_protocol_liquidity(
+        ctx: Context<SeedProtocolLiquidity>,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::admin::seed_protocol_liquidity::handler(ctx, amount)
+    }
+
+    /// Withdraw protocol-owned liquidity back to the treasury
+    pub fn withdraw_protocol_liquidity(
+        ctx: Context<WithdrawProtocolLiquidity>,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::admin::withdraw_protocol_liquidity::handler(ctx, amount)
+    }
+
+    /// Whitelist a depositor on a permissioned reserve
+    pub fn add_whitelisted_depositor(ctx: Context<AddWhitelistedDepositor>) -> Result<()> {
+        instructions::admin::add_whitelisted_depositor::handler(ctx)
+    }
+
+    /// Remove a depositor from a reserve's whitelist
+    pub fn remove_whitelisted_depositor(ctx: Context<RemoveWhitelistedDepositor>) -> Result<()> {
+        instructions::admin::remove_whitelisted_depositor::handler(ctx)
     }
 
     // ============================================================================
@@ -59,13 +128,18 @@ pub mod radiant {
     // ============================================================================
 
     /// Initialize a user's obligation account
-    pub fn initialize_obligation(ctx: Context<InitializeObligation>) -> Result<()> {
-        instructions::user::initialize_obligation::handler(ctx)
+    pub fn initialize_obligation(ctx: Context<InitializeObligation>, obligation_id: u8) -> Result<()> {
+        instructions::user::initialize_obligation::handler(ctx, obligation_id)
     }
 
-    /// Deposit collateral into a reserve
-    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
-        instructions::user::deposit::handler(ctx, amount)
+    /// Deposit collateral into a reserve, optionally creating the obligation
+    pub fn deposit(
+        ctx: Context<Deposit>,
+        amount: u64,
+        obligation_id: u8,
+        create_obligation: bool,
+    ) -> Result<()> {
+        instructions::user::deposit::handler(ctx, amount, obligation_id, create_obligation)
     }
 
     /// Withdraw collateral from a reserve
@@ -79,8 +153,26 @@ pub mod radiant {
     }
 
     /// Repay borrowed tokens
-    pub fn repay(ctx: Context<Repay>, amount: u64) -> Result<()> {
-        instructions::user::repay::handler(ctx, amount)
+    pub fn repay(ctx: Context<Repay>, amount: u64, close_obligation: bool) -> Result<()> {
+        instructions::user::repay::handler(ctx, amount, close_obligation)
+    }
+
+    /// Repay just enough debt to reach a target health factor
+    pub fn repay_to_health(ctx: Context<RepayToHealth>, target_health_bps: u64) -> Result<u64> {
+        instructions::user::repay_to_health::handler(ctx, target_health_bps)
+    }
+
+    /// Transfer an obligation to a new owner
+    pub fn transfer_obligation(ctx: Context<TransferObligation>) -> Result<()> {
+        instructions::user::transfer_obligation::handler(ctx)
+    }
+
+    /// Enable or disable a deposit as borrow collateral
+    pub fn set_deposit_collateral(
+        ctx: Context<SetDepositCollateral>,
+        use_as_collateral: bool,
+    ) -> Result<()> {
+        instructions::user::set_deposit_collateral::handler(ctx, use_as_collateral)
     }
 
     // ============================================================================
@@ -93,7 +185,7 @@ pub mod radiant {
     }
 
     /// Refresh obligation state (update USD values)
-    pub fn refresh_obligation(ctx: Context<RefreshObligation>) -> Result<()> {
+    pub fn refresh_obligation(ctx: Context<RefreshObligation>) -> Result<ObligationRefreshResult> {
         instructions::permissionless::refresh_obligation::handler(ctx)
     }
 
@@ -101,4 +193,53 @@ pub mod radiant {
     pub fn liquidate(ctx: Context<Liquidate>, repay_amount: u64) -> Result<()> {
         instructions::permissionless::liquidate::handler(ctx, repay_amount)
     }
+
+    /// Preview an obligation's current debt in a reserve, including unrefreshed interest
+    pub fn preview_debt(ctx: Context<PreviewDebt>) -> Result<u64> {
+        instructions::permissionless::preview_debt::handler(ctx)
+    }
+
+    /// Read a reserve's key metrics for composing programs
+    pub fn get_reserve_state(ctx: Context<GetReserveState>) -> Result<ReserveState> {
+        instructions::permissionless::get_reserve_state::handler(ctx)
+    }
+
+    /// Accrue interest and credit vault rounding dust to protocol fees
+    pub fn sweep_rounding_dust(ctx: Context<SweepRoundingDust>) -> Result<()> {
+        instructions::permissionless::sweep_rounding_dust::handler(ctx)
+    }
+
+    /// Sum all reserves' deposits and borrows in USD
+    pub fn market_totals<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MarketTotals<'info>>,
+    ) -> Result<MarketTotalsResult> {
+        instructions::permissionless::market_totals::handler(ctx)
+    }
+
+    /// Emit the market-wide collateralization ratio
+    pub fn market_solvency<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MarketSolvency<'info>>,
+    ) -> Result<()> {
+        instructions::permissionless::market_solvency::handler(ctx)
+    }
+
+    /// Sort an obligation's positions by reserve (layout migration)
+    pub fn normalize_obligation(ctx: Context<NormalizeObligation>) -> Result<()> {
+        instructions::permissionless::normalize_obligation::handler(ctx)
+    }
+
+    /// Rewrite a version 1 lending market in the current layout
+    pub fn migrate_lending_market(ctx: Context<MigrateLendingMarket>) -> Result<()> {
+        instructions::permissionless::migrate_lending_market::handler(ctx)
+    }
+
+    /// Rewrite a version 1 reserve in the current layout
+    pub fn migrate_reserve(ctx: Context<MigrateReserve>) -> Result<()> {
+        instructions::permissionless::migrate_reserve::handler(ctx)
+    }
+
+    /// Rewrite a version 1 obligation in the current layout
+    pub fn migrate_obligation(ctx: Context<MigrateObligation>) -> Result<()> {
+        instructions::permissionless::migrate_obligation::handler(ctx)
+    }
 }
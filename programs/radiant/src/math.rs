@@ -0,0 +1,139 @@
+//! Radiant fixed-point math helpers
+//!
+//! Rounding policy:
+//! - Amounts credited to users (supply interest, supply index growth)
+//!   round DOWN, so depositors never gain at the protocol's expense.
+//! - Amounts owed by users (borrow interest, borrow index growth,
+//!   protocol cut of interest) round UP, so borrowers never owe less
+//!   than they should.
+//!
+//! Precision:
+//! - Indexes and compound factors are scaled by INDEX_ONE (10^18)
+//! - Rates and ratios are in BPS (10^4)
+//! - USD values are scaled by USD_SCALE (10^6)
+//!
+//! All helpers return None on overflow or division by zero.
+
+use crate::constants::{BPS_DENOMINATOR, HEALTH_FACTOR_ONE, INDEX_ONE};
+
+/// Calculate a * b / denominator, rounding down
+pub fn mul_div_floor(a: u128, b: u128, denominator: u128) -> Option<u128> {
+    if denominator == 0 {
+        return None;
+    }
+    a.checked_mul(b)?.checked_div(denominator)
+}
+
+/// Calculate a * b / denominator, rounding up
+pub fn mul_div_ceil(a: u128, b: u128, denominator: u128) -> Option<u128> {
+    if denominator == 0 {
+        return None;
+    }
+    let product = a.checked_mul(b)?;
+    let quotient = product / denominator;
+    if product % denominator == 0 {
+        Some(quotient)
+    } else {
+        quotient.checked_add(1)
+    }
+}
+
+/// Apply a BPS ratio to an amount, rounding down
+pub fn mul_bps_floor(amount: u64, bps: u64) -> Option<u64> {
+    let result = mul_div_floor(amount as u128, bps as u128, BPS_DENOMINATOR as u128)?;
+    u64::try_from(result).ok()
+}
+
+/// Apply a BPS ratio to an amount, rounding up
+pub fn mul_bps_ceil(amount: u64, bps: u64) -> Option<u64> {
+    let result = mul_div_ceil(amount as u128, bps as u128, BPS_DENOMINATOR as u128)?;
+    u64::try_from(result).ok()
+}
+
+/// Calculate a health factor scaled by HEALTH_FACTOR_ONE, rounding down
+///
+/// health = unhealthy_value * HEALTH_FACTOR_ONE / borrowed_value, where
+/// unhealthy_value is collateral weighted by liquidation threshold.
+/// This is the single definition every health comparison must use, so
+/// results are directly comparable with HEALTH_FACTOR_ONE and
+/// MIN_HEALTH_FACTOR_AFTER_BORROW.
+///
+/// Returns None when there is no debt (infinite health). Saturates at
+/// u64::MAX rather than overflowing.
+pub fn health_factor_bps(unhealthy_value: u128, borrowed_value: u128) -> Option<u64> {
+    if borrowed_value == 0 {
+        return None;
+    }
+    let health = mul_div_floor(unhealthy_value, HEALTH_FACTOR_ONE as u128, borrowed_value)
+        .unwrap_or(u128::MAX);
+    Some(u64::try_from(health).unwrap_or(u64::MAX))
+}
+
+/// Calculate a * b / denominator, rounding down, without requiring a * b
+/// to fit in u128
+///
+/// Splits `a` into whole multiples of the denominator and a remainder, so
+/// only (denominator - 1) * b must fit. Use when `a` can be a full u128.
+pub fn mul_div_floor_wide(a: u128, b: u128, denominator: u128) -> Option<u128> {
+    let whole = a.checked_div(denominator)?.checked_mul(b)?;
+    let fraction = a.checked_rem(denominator)?.checked_mul(b)?.checked_div(denominator)?;
+    whole.checked_add(fraction)
+}
+
+/// Calculate index * factor / INDEX_ONE, rounding down
+///
+/// Splits the index into whole and fractional parts so the intermediate
+/// product stays within u128 for any index up to MAX_CUMULATIVE_INDEX.
+pub fn scale_index_floor(index: u128, factor: u128) -> Option<u128> {
+    let whole = (index / INDEX_ONE).checked_mul(factor)?;
+    let fraction = (index % INDEX_ONE).checked_mul(factor)? / INDEX_ONE;
+    whole.checked_add(fraction)
+}
+
+/// Calculate index * factor / INDEX_ONE, rounding up
+pub fn scale_index_ceil(index: u128, factor: u128) -> Option<u128> {
+    let whole = (index / INDEX_ONE).checked_mul(factor)?;
+    let fraction = mul_div_ceil(index % INDEX_ONE, factor, INDEX_ONE)?;
+    whole.checked_add(fraction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wide_mul_div_matches_plain_mul_div() {
+        for (a, b, d) in [(0, 5, 3), (10, 3, 4), (999, 1_000_000, 7), (u64::MAX as u128, 10_500, 10_000)] {
+            assert_eq!(mul_div_floor_wide(a, b, d), mul_div_floor(a, b, d));
+        }
+    }
+
+    #[test]
+    fn wide_mul_div_handles_products_beyond_u128() {
+        let a = u128::MAX / 3;
+        assert_eq!(mul_div_floor(a, 6, 9), None);
+        assert_eq!(mul_div_floor_wide(a, 6, 9), Some(a / 9 * 6 + (a % 9) * 6 / 9));
+    }
+
+    #[test]
+    fn wide_mul_div_rejects_zero_denominator_and_overflowing_results() {
+        assert_eq!(mul_div_floor_wide(1, 1, 0), None);
+        assert_eq!(mul_div_floor_wide(u128::MAX, 2, 1), None);
+    }
+
+    #[test]
+    fn health_factor_matches_inline_withdraw_formula() {
+        // The formula withdraw used before sharing this helper
+        for (unhealthy, borrowed) in [(85_000, 80_000), (1, 3), (10_000, 10_000), (7_999_999, 8_000_000)] {
+            assert_eq!(health_factor_bps(unhealthy, borrowed), Some((unhealthy * 10_000 / borrowed) as u64));
+        }
+        assert_eq!(health_factor_bps(85_000, 80_000), Some(10_625));
+    }
+
+    #[test]
+    fn health_factor_handles_no_debt_and_saturates() {
+        assert_eq!(health_factor_bps(1_000, 0), None);
+        assert_eq!(health_factor_bps(0, 0), None);
+        assert_eq!(health_factor_bps(u128::MAX, 1), Some(u64::MAX));
+    }
+}
@@ -0,0 +1,455 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::USD_DECIMALS;
+use crate::state::Reserve;
+
+// ============================================================================
+// PYTH PRICE ACCOUNT LAYOUT
+// ============================================================================
+
+/// Magic number at the start of every Pyth account
+const PYTH_MAGIC: u32 = 0xa1b2c3d4;
+
+/// Pyth account type for price accounts
+const PYTH_ACCOUNT_TYPE_PRICE: u32 = 3;
+
+/// Pyth aggregate price status: trading (price is valid)
+const PYTH_STATUS_TRADING: u32 = 1;
+
+/// Byte offsets into a Pyth v2 price account
+const MAGIC_OFFSET: usize = 0;
+const ACCOUNT_TYPE_OFFSET: usize = 8;
+const EXPONENT_OFFSET: usize = 20;
+const AGG_PRICE_OFFSET: usize = 208;
+const AGG_STATUS_OFFSET: usize = 224;
+const AGG_PUBLISH_SLOT_OFFSET: usize = 232;
+
+/// Minimum length of a Pyth price account we can read
+const PRICE_ACCOUNT_MIN_LEN: usize = 240;
+
+/// Largest absolute price exponent accepted from a feed
+/// Pyth feeds use small negative exponents; anything beyond this is corrupt
+const MAX_ORACLE_EXPONENT: i32 = 18;
+
+/// Raw oracle price: value = mantissa * 10^exponent
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OraclePrice {
+    /// Price mantissa (always positive)
+    pub mantissa: u64,
+
+    /// Decimal exponent applied to the mantissa
+    pub exponent: i32,
+
+    /// Slot at which the price was published
+    pub publish_slot: u64,
+}
+
+/// Read and validate a price from a Pyth price account
+///
+/// Rejects accounts that are not Pyth price accounts, prices that are
+/// not currently trading, non-positive prices, out of range exponents and
+/// stale prices.
+pub fn read_oracle_price(
+    oracle: &AccountInfo,
+    current_slot: u64,
+    max_staleness_slots: u64,
+) -> Result<OraclePrice> {
+    let data = oracle.try_borrow_data()?;
+    require!(
+        data.len() >= PRICE_ACCOUNT_MIN_LEN,
+        OracleError::InvalidOracleData
+    );
+
+    require!(
+        read_u32(&data, MAGIC_OFFSET) == PYTH_MAGIC
+            && read_u32(&data, ACCOUNT_TYPE_OFFSET) == PYTH_ACCOUNT_TYPE_PRICE,
+        OracleError::InvalidOracleData
+    );
+
+    require!(
+        read_u32(&data, AGG_STATUS_OFFSET) == PYTH_STATUS_TRADING,
+        OracleError::PriceNotTrading
+    );
+
+    let price = read_i64(&data, AGG_PRICE_OFFSET);
+    require!(price > 0, OracleError::InvalidOraclePrice);
+
+    let exponent = read_i32(&data, EXPONENT_OFFSET);
+    require!(
+        (-MAX_ORACLE_EXPONENT..=MAX_ORACLE_EXPONENT).contains(&exponent),
+        OracleError::InvalidOracleExponent
+    );
+
+    let publish_slot = read_u64(&data, AGG_PUBLISH_SLOT_OFFSET);
+    require!(
+        current_slot.saturating_sub(publish_slot) <= max_staleness_slots,
+        OracleError::StaleOraclePrice
+    );
+
+    Ok(OraclePrice {
+        mantissa: price as u64,
+        exponent,
+        publish_slot,
+    })
+}
+
+/// Get a reserve's token price in USD (scaled by 10^6 per whole token)
+///
+/// If the reserve has a `price_quote_oracle`, its main oracle is quoted in
+/// another asset (e.g. TOKEN/SOL) and the quote oracle converts that asset
+/// to USD (e.g. SOL/USD). The two prices are composed:
+///
+/// price_usd = (asset / quote) * (quote / USD)
+///
+/// Both prices must be within the reserve's oracle staleness tolerance.
+pub fn get_reserve_price_usd(
+    reserve: &Reserve,
+    oracle: &AccountInfo,
+    quote_oracle: Option<&AccountInfo>,
+    current_slot: u64,
+) -> Result<u64> {
+    require_keys_eq!(oracle.key(), reserve.oracle, OracleError::InvalidOracleAccount);
+
+    let max_staleness_slots = reserve.config.oracle_staleness_slots();
+    let price = read_oracle_price(oracle, current_slot, max_staleness_slots)?;
+
+    match reserve.price_quote_oracle {
+        None => scale_price_to_usd(price.mantissa as u128, price.exponent),
+        Some(expected_quote_oracle) => {
+            let quote_oracle = quote_oracle.ok_or(OracleError::MissingQuoteOracle)?;
+            require_keys_eq!(
+                quote_oracle.key(),
+                expected_quote_oracle,
+                OracleError::InvalidQuoteOracle
+            );
+
+            let quote_price =
+                read_oracle_price(quote_oracle, current_slot, max_staleness_slots)?;
+            compose_prices(price, quote_price)
+        }
+    }
+}
+
+/// Compose an asset/quote price with a quote/USD price into a USD price
+/// Returns the price scaled by 10^6
+pub fn compose_prices(base: OraclePrice, quote: OraclePrice) -> Result<u64> {
+    // (m1 * 10^e1) * (m2 * 10^e2) = (m1 * m2) * 10^(e1 + e2)
+    let mantissa = (base.mantissa as u128)
+        .checked_mul(quote.mantissa as u128)
+        .ok_or(OracleError::MathOverflow)?;
+    let exponent = base
+        .exponent
+        .checked_add(quote.exponent)
+        .ok_or(OracleError::MathOverflow)?;
+
+    scale_price_to_usd(mantissa, exponent)
+}
+
+/// Scale a price `mantissa * 10^exponent` to USD_SCALE (10^6)
+///
+/// With shift = exponent + 6 the result is mantissa * 10^shift for
+/// shift >= 0 (zero and positive exponents) and mantissa / 10^-shift
+/// otherwise. Errors if the result does not fit a u64 or rounds to zero.
+pub fn scale_price_to_usd(mantissa: u128, exponent: i32) -> Result<u64> {
+    let shift = exponent
+        .checked_add(USD_DECIMALS as i32)
+        .ok_or(OracleError::MathOverflow)?;
+
+    let scaled = if shift >= 0 {
+        10u128
+            .checked_pow(shift as u32)
+            .and_then(|factor| mantissa.checked_mul(factor))
+            .ok_or(OracleError::MathOverflow)?
+    } else {
+        // A divisor beyond u128 range scales any mantissa to zero
+        match 10u128.checked_pow(shift.unsigned_abs()) {
+            Some(divisor) => mantissa / divisor,
+            None => 0,
+        }
+    };
+
+    require!(scaled > 0, OracleError::InvalidOraclePrice);
+    u64::try_from(scaled).map_err(|_| error!(OracleError::MathOverflow))
+}
+
+/// Convert a native token amount to USD (scaled by 10^6)
+///
+/// usd_value = amount * price_usd / 10^decimals
+pub fn token_amount_to_usd(amount: u64, price_usd: u64, decimals: u8) -> Result<u128> {
+    let decimals_factor = 10u128
+        .checked_pow(decimals as u32)
+        .ok_or(OracleError::MathOverflow)?;
+
+    Ok((amount as u128)
+        .checked_mul(price_usd as u128)
+        .ok_or(OracleError::MathOverflow)?
+        / decimals_factor)
+}
+
+/// Convert a USD value (scaled by 10^6) to a native token amount
+///
+/// amount = usd_value * 10^decimals / price_usd, rounded down
+pub fn usd_to_token_amount(usd_value: u128, price_usd: u64, decimals: u8) -> Result<u64> {
+    require!(price_usd > 0, OracleError::InvalidOraclePrice);
+    let decimals_factor = 10u128
+        .checked_pow(decimals as u32)
+        .ok_or(OracleError::MathOverflow)?;
+
+    let amount = usd_value
+        .checked_mul(decimals_factor)
+        .ok_or(OracleError::MathOverflow)?
+        / price_usd as u128;
+    u64::try_from(amount).map_err(|_| error!(OracleError::MathOverflow))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_i32(data: &[u8], offset: usize) -> i32 {
+    i32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_i64(data: &[u8], offset: usize) -> i64 {
+    i64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+/// Oracle errors
+#[error_code]
+pub enum OracleError {
+    #[msg("Oracle account does not match reserve oracle")]
+    InvalidOracleAccount,
+
+    #[msg("Oracle account is not a valid price feed")]
+    InvalidOracleData,
+
+    #[msg("Oracle price is not currently trading")]
+    PriceNotTrading,
+
+    #[msg("Invalid oracle price")]
+    InvalidOraclePrice,
+
+    #[msg("Oracle price exponent is out of range")]
+    InvalidOracleExponent,
+
+    #[msg("Oracle price is stale")]
+    StaleOraclePrice,
+
+    #[msg("Quote oracle account is required for this reserve")]
+    MissingQuoteOracle,
+
+    #[msg("Quote oracle account does not match reserve quote oracle")]
+    InvalidQuoteOracle,
+
+    #[msg("Math overflow")]
+    MathOverflow,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price_account_data(price: i64, exponent: i32, publish_slot: u64) -> Vec<u8> {
+        let mut data = vec![0u8; PRICE_ACCOUNT_MIN_LEN];
+        data[MAGIC_OFFSET..MAGIC_OFFSET + 4].copy_from_slice(&PYTH_MAGIC.to_le_bytes());
+        data[ACCOUNT_TYPE_OFFSET..ACCOUNT_TYPE_OFFSET + 4]
+            .copy_from_slice(&PYTH_ACCOUNT_TYPE_PRICE.to_le_bytes());
+        data[EXPONENT_OFFSET..EXPONENT_OFFSET + 4].copy_from_slice(&exponent.to_le_bytes());
+        data[AGG_PRICE_OFFSET..AGG_PRICE_OFFSET + 8].copy_from_slice(&price.to_le_bytes());
+        data[AGG_STATUS_OFFSET..AGG_STATUS_OFFSET + 4]
+            .copy_from_slice(&PYTH_STATUS_TRADING.to_le_bytes());
+        data[AGG_PUBLISH_SLOT_OFFSET..AGG_PUBLISH_SLOT_OFFSET + 8]
+            .copy_from_slice(&publish_slot.to_le_bytes());
+        data
+    }
+
+    /// Backing storage for an oracle AccountInfo
+    struct TestOracle {
+        key: Pubkey,
+        owner: Pubkey,
+        lamports: u64,
+        data: Vec<u8>,
+    }
+
+    impl TestOracle {
+        fn new(data: Vec<u8>) -> Self {
+            Self {
+                key: Pubkey::new_unique(),
+                owner: Pubkey::new_unique(),
+                lamports: 0,
+                data,
+            }
+        }
+
+        fn info(&mut self) -> AccountInfo<'_> {
+            AccountInfo::new(
+                &self.key,
+                false,
+                false,
+                &mut self.lamports,
+                &mut self.data,
+                &self.owner,
+                false,
+                0,
+            )
+        }
+    }
+
+    fn read(data: Vec<u8>, current_slot: u64) -> Result<OraclePrice> {
+        let mut oracle = TestOracle::new(data);
+        read_oracle_price(&oracle.info(), current_slot, 25)
+    }
+
+    fn zeroed_reserve(oracle: Pubkey, price_quote_oracle: Option<Pubkey>) -> Reserve {
+        let data = vec![0u8; Reserve::INIT_SPACE];
+        let mut reserve = Reserve::deserialize(&mut data.as_slice()).unwrap();
+        reserve.oracle = oracle;
+        reserve.price_quote_oracle = price_quote_oracle;
+        reserve
+    }
+
+    #[test]
+    fn reads_a_trading_price() {
+        let price = read(price_account_data(12_345, -2, 100), 110).unwrap();
+        assert_eq!(price, OraclePrice { mantissa: 12_345, exponent: -2, publish_slot: 100 });
+    }
+
+    #[test]
+    fn rejects_malformed_accounts() {
+        assert_eq!(read(vec![0u8; PRICE_ACCOUNT_MIN_LEN - 1], 100), Err(OracleError::InvalidOracleData.into()));
+
+        let mut data = price_account_data(100, -2, 100);
+        data[MAGIC_OFFSET] ^= 1;
+        assert_eq!(read(data, 100), Err(OracleError::InvalidOracleData.into()));
+
+        let mut data = price_account_data(100, -2, 100);
+        data[ACCOUNT_TYPE_OFFSET] = 2;
+        assert_eq!(read(data, 100), Err(OracleError::InvalidOracleData.into()));
+    }
+
+    #[test]
+    fn rejects_unusable_prices() {
+        let mut halted = price_account_data(100, -2, 100);
+        halted[AGG_STATUS_OFFSET] = 2;
+        assert_eq!(read(halted, 100), Err(OracleError::PriceNotTrading.into()));
+
+        assert_eq!(read(price_account_data(0, -2, 100), 100), Err(OracleError::InvalidOraclePrice.into()));
+        assert_eq!(read(price_account_data(-5, -2, 100), 100), Err(OracleError::InvalidOraclePrice.into()));
+        assert_eq!(read(price_account_data(100, -19, 100), 100), Err(OracleError::InvalidOracleExponent.into()));
+        assert_eq!(read(price_account_data(100, 19, 100), 100), Err(OracleError::InvalidOracleExponent.into()));
+        assert!(read(price_account_data(100, 18, 100), 100).is_ok());
+    }
+
+    #[test]
+    fn staleness_is_measured_from_the_publish_slot() {
+        assert!(read(price_account_data(100, -2, 100), 125).is_ok());
+        assert_eq!(read(price_account_data(100, -2, 100), 126), Err(OracleError::StaleOraclePrice.into()));
+        // A publish slot ahead of the clock is not stale
+        assert!(read(price_account_data(100, -2, 200), 100).is_ok());
+    }
+
+    #[test]
+    fn scales_negative_exponents_down() {
+        // $123.45678901 at expo -8
+        assert_eq!(scale_price_to_usd(12_345_678_901, -8).unwrap(), 123_456_789);
+        assert_eq!(scale_price_to_usd(1_000_000, -6).unwrap(), 1_000_000);
+        assert_eq!(scale_price_to_usd(5, -7), Err(OracleError::InvalidOraclePrice.into()));
+        // Divisors beyond u128 range round to zero rather than overflow
+        assert_eq!(scale_price_to_usd(u128::MAX, -60), Err(OracleError::InvalidOraclePrice.into()));
+    }
+
+    #[test]
+    fn scales_zero_and_positive_exponents_up() {
+        assert_eq!(scale_price_to_usd(42, 0).unwrap(), 42_000_000);
+        assert_eq!(scale_price_to_usd(3, 2).unwrap(), 300_000_000);
+        assert_eq!(scale_price_to_usd(1, 13).unwrap(), 10u64.pow(19));
+    }
+
+    #[test]
+    fn rejects_scaled_prices_beyond_u64() {
+        assert_eq!(scale_price_to_usd(1, 14), Err(OracleError::MathOverflow.into()));
+        assert_eq!(scale_price_to_usd(u64::MAX as u128 + 1, -6), Err(OracleError::MathOverflow.into()));
+        assert_eq!(scale_price_to_usd(u128::MAX, 0), Err(OracleError::MathOverflow.into()));
+        assert_eq!(scale_price_to_usd(1, 40), Err(OracleError::MathOverflow.into()));
+        assert_eq!(scale_price_to_usd(1, i32::MAX), Err(OracleError::MathOverflow.into()));
+    }
+
+    #[test]
+    fn composes_a_two_hop_price() {
+        // TOKEN/SOL = 0.05 (expo -8), SOL/USD = $150 (expo -8): TOKEN = $7.50
+        let base = OraclePrice { mantissa: 5_000_000, exponent: -8, publish_slot: 0 };
+        let quote = OraclePrice { mantissa: 15_000_000_000, exponent: -8, publish_slot: 0 };
+        assert_eq!(compose_prices(base, quote).unwrap(), 7_500_000);
+
+        // Mixed exponents: 2 * 10^1 quote units at $0.5
+        let base = OraclePrice { mantissa: 2, exponent: 1, publish_slot: 0 };
+        let quote = OraclePrice { mantissa: 5, exponent: -1, publish_slot: 0 };
+        assert_eq!(compose_prices(base, quote).unwrap(), 10_000_000);
+
+        let huge = OraclePrice { mantissa: u64::MAX, exponent: 0, publish_slot: 0 };
+        assert_eq!(compose_prices(huge, huge), Err(OracleError::MathOverflow.into()));
+    }
+
+    #[test]
+    fn reserve_price_uses_the_quote_oracle_when_configured() {
+        let mut oracle = TestOracle::new(price_account_data(5_000_000, -8, 100));
+        let mut quote = TestOracle::new(price_account_data(15_000_000_000, -8, 100));
+        let mut other = TestOracle::new(price_account_data(15_000_000_000, -8, 100));
+
+        let direct = zeroed_reserve(oracle.key, None);
+        assert_eq!(get_reserve_price_usd(&direct, &oracle.info(), None, 100).unwrap(), 50_000);
+
+        let composed = zeroed_reserve(oracle.key, Some(quote.key));
+        assert_eq!(
+            get_reserve_price_usd(&composed, &oracle.info(), Some(&quote.info()), 100).unwrap(),
+            7_500_000
+        );
+        assert_eq!(
+            get_reserve_price_usd(&composed, &oracle.info(), None, 100),
+            Err(OracleError::MissingQuoteOracle.into())
+        );
+        assert_eq!(
+            get_reserve_price_usd(&composed, &oracle.info(), Some(&other.info()), 100),
+            Err(OracleError::InvalidQuoteOracle.into())
+        );
+        assert_eq!(
+            get_reserve_price_usd(&composed, &other.info(), Some(&quote.info()), 100),
+            Err(OracleError::InvalidOracleAccount.into())
+        );
+    }
+
+    #[test]
+    fn stale_quote_price_is_rejected() {
+        let mut oracle = TestOracle::new(price_account_data(5_000_000, -8, 1_000));
+        let mut quote = TestOracle::new(price_account_data(15_000_000_000, -8, 100));
+        let reserve = zeroed_reserve(oracle.key, Some(quote.key));
+        assert_eq!(
+            get_reserve_price_usd(&reserve, &oracle.info(), Some(&quote.info()), 1_000),
+            Err(OracleError::StaleOraclePrice.into())
+        );
+    }
+
+    #[test]
+    fn token_amounts_convert_to_usd_and_back() {
+        // 1.5 SOL at $100
+        assert_eq!(token_amount_to_usd(1_500_000_000, 100_000_000, 9).unwrap(), 150_000_000);
+        assert_eq!(usd_to_token_amount(150_000_000, 100_000_000, 9).unwrap(), 1_500_000_000);
+        // Rounds down in both directions
+        assert_eq!(token_amount_to_usd(1, 100_000_000, 9).unwrap(), 0);
+        assert_eq!(usd_to_token_amount(1, 3_000_000, 6).unwrap(), 0);
+        assert_eq!(usd_to_token_amount(10, 3_000_000, 6).unwrap(), 3);
+    }
+
+    #[test]
+    fn token_conversions_reject_overflow_and_zero_prices() {
+        assert_eq!(token_amount_to_usd(u64::MAX, u64::MAX, 0).unwrap(), u64::MAX as u128 * u64::MAX as u128);
+        assert_eq!(token_amount_to_usd(1, 1, 39), Err(OracleError::MathOverflow.into()));
+        assert_eq!(usd_to_token_amount(1, 0, 6), Err(OracleError::InvalidOraclePrice.into()));
+        assert_eq!(usd_to_token_amount(u64::MAX as u128 + 1, 1, 0), Err(OracleError::MathOverflow.into()));
+        assert_eq!(usd_to_token_amount(u128::MAX, 1, 1), Err(OracleError::MathOverflow.into()));
+    }
+}
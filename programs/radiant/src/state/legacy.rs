@@ -0,0 +1,175 @@
+//! Account layouts written by version 1 of the program
+//!
+//! Version 1 accounts were allocated with `8 + INIT_SPACE` of these layouts,
+//! so a program-owned account carrying the current discriminator at exactly
+//! that length still holds a version 1 layout. The migrate_* instructions
+//! read them with these structs and rewrite them in the current layout.
+
+use anchor_lang::prelude::*;
+
+use super::{MAX_BORROWS, MAX_DEPOSITS};
+
+/// Version 1 layout of `LendingMarket`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct LendingMarketV1 {
+    pub version: u8,
+    pub bump: u8,
+    pub authority: Pubkey,
+    pub treasury: Pubkey,
+    pub emergency_mode: bool,
+    pub close_factor_bps: u16,
+    pub liquidation_bonus_bps: u16,
+    pub protocol_fee_bps: u16,
+    pub reserves_count: u8,
+    pub _padding: [u8; 128],
+}
+
+/// Version 1 layout of `Reserve`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct ReserveV1 {
+    pub version: u8,
+    pub bump: u8,
+    pub lending_market: Pubkey,
+    pub token_mint: Pubkey,
+    pub token_decimals: u8,
+    pub token_vault: Pubkey,
+    pub fee_receiver: Pubkey,
+    pub oracle: Pubkey,
+    pub last_update_slot: u64,
+    pub last_update_timestamp: i64,
+    pub config: ReserveConfigV1,
+    pub liquidity: ReserveLiquidityV1,
+    pub _padding: [u8; 128],
+}
+
+/// Version 1 layout of `ReserveConfig`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace, Default)]
+pub struct ReserveConfigV1 {
+    pub ltv_bps: u16,
+    pub liquidation_threshold_bps: u16,
+    pub deposit_limit: u64,
+    pub borrow_limit: u64,
+    pub deposits_enabled: bool,
+    pub borrows_enabled: bool,
+    pub interest_rate_config: InterestRateConfigV1,
+}
+
+/// Version 1 layout of `InterestRateConfig`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace, Default)]
+pub struct InterestRateConfigV1 {
+    pub optimal_utilization_bps: u16,
+    pub base_rate_bps: u16,
+    pub slope1_bps: u16,
+    pub slope2_bps: u16,
+    pub reserve_factor_bps: u16,
+}
+
+/// Version 1 layout of `ReserveLiquidity`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace, Default)]
+pub struct ReserveLiquidityV1 {
+    pub total_deposits: u64,
+    pub total_borrows: u64,
+    pub accumulated_protocol_fees: u64,
+    pub cumulative_borrow_index: u128,
+    pub cumulative_supply_index: u128,
+    pub current_borrow_rate_bps: u64,
+    pub current_supply_rate_bps: u64,
+}
+
+/// Version 1 layout of `Obligation`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct ObligationV1 {
+    pub version: u8,
+    pub bump: u8,
+    pub lending_market: Pubkey,
+    pub owner: Pubkey,
+    pub last_update_slot: u64,
+    #[max_len(MAX_DEPOSITS)]
+    pub deposits: Vec<ObligationCollateralV1>,
+    #[max_len(MAX_BORROWS)]
+    pub borrows: Vec<ObligationLiquidityV1>,
+    pub deposited_value_usd: u128,
+    pub borrowed_value_usd: u128,
+    pub allowed_borrow_value_usd: u128,
+    pub unhealthy_borrow_value_usd: u128,
+    pub _padding: [u8; 64],
+}
+
+/// Version 1 layout of `ObligationCollateral`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace, Default)]
+pub struct ObligationCollateralV1 {
+    pub reserve: Pubkey,
+    pub deposited_amount: u64,
+    pub supply_index_snapshot: u128,
+    pub market_value_usd: u128,
+}
+
+/// Version 1 layout of `ObligationLiquidity`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace, Default)]
+pub struct ObligationLiquidityV1 {
+    pub reserve: Pubkey,
+    pub borrowed_amount: u64,
+    pub borrow_index_snapshot: u128,
+    pub market_value_usd: u128,
+}
+
+/// Read a version 1 account of type `T` stored under `discriminator`
+///
+/// Fails with `AlreadyMigrated` when the account already has the current
+/// length `current_len` and `InvalidLegacyAccount` when it is not a
+/// program-owned version 1 account.
+pub fn read_legacy_account<T: AnchorDeserialize + Space>(
+    account: &AccountInfo,
+    discriminator: &[u8],
+    current_len: usize,
+) -> Result<T> {
+    require_keys_eq!(*account.owner, crate::ID, MigrationError::InvalidLegacyAccount);
+
+    let data = account.try_borrow_data()?;
+    require!(data.starts_with(discriminator), MigrationError::InvalidLegacyAccount);
+    require!(data.len() != current_len, MigrationError::AlreadyMigrated);
+    require!(data.len() == 8 + T::INIT_SPACE, MigrationError::InvalidLegacyAccount);
+
+    T::deserialize(&mut &data[8..]).map_err(|_| error!(MigrationError::InvalidLegacyAccount))
+}
+
+/// Grow a program-owned account to `new_len`, topping up rent from `payer`
+pub fn grow_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    new_len: usize,
+) -> Result<()> {
+    let required_lamports = Rent::get()?.minimum_balance(new_len);
+    let shortfall = required_lamports.saturating_sub(account.lamports());
+    if shortfall > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                anchor_lang::system_program::Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    account.resize(new_len)?;
+    Ok(())
+}
+
+/// Account migration errors
+#[error_code]
+pub enum MigrationError {
+    #[msg("Account is not a version 1 account of the expected type")]
+    InvalidLegacyAccount,
+
+    #[msg("Account already uses the current layout")]
+    AlreadyMigrated,
+
+    #[msg("Token vault does not match the reserve")]
+    InvalidTokenVault,
+
+    #[msg("Math overflow")]
+    MathOverflow,
+}
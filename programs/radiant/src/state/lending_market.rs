@@ -1,5 +1,12 @@
 use anchor_lang::prelude::*;
 
+use crate::constants::{
+    MAX_LIQUIDATION_BONUS_BPS,
+    MAX_OBLIGATION_BORROWS,
+    MAX_OBLIGATION_DEPOSITS,
+    MAX_OBLIGATION_STALENESS_SLOTS,
+};
+
 /// Global configuration for the lending protocol
 /// PDA Seeds: ["lending_market", authority]
 #[account]
@@ -20,6 +27,10 @@ pub struct LendingMarket {
     /// Emergency mode - when true, only withdrawals and repayments allowed
     pub emergency_mode: bool,
 
+    /// Liquidation halt - when true, liquidations are rejected
+    /// Independent of emergency mode, for oracle incidents
+    pub halt_liquidations: bool,
+
     /// Close factor in BPS (max % of debt that can be liquidated at once)
     /// e.g., 5000 = 50%
     pub close_factor_bps: u16,
@@ -35,6 +46,74 @@ pub struct LendingMarket {
     /// Number of reserves in this market
     pub reserves_count: u8,
 
+    /// Allow borrowing the same asset that is an obligation's only collateral
+    /// (single-asset looping). Disabled by default.
+    pub allow_looping: bool,
+
+    /// Maximum liquidations of a single obligation per slot (0 = unlimited)
+    pub max_liquidations_per_slot: u8,
+
+    /// Route liquidation protocol fees into the collateral reserve's
+    /// accumulated_protocol_fees (collected via collect_fees) instead of
+    /// transferring them to the reserve fee receiver
+    pub route_liquidation_fees_to_reserve: bool,
+
+    /// Minimum slots between config updates of a reserve (0 = no limit)
+    /// Not enforced in emergency mode
+    pub min_config_update_interval_slots: u64,
+
+    /// Maximum borrowed value per obligation in USD, scaled by 10^6 (0 = unlimited)
+    pub max_obligation_borrow_usd: u64,
+
+    /// Allow creation of new obligations. When false, existing obligations
+    /// keep working but initialize_obligation is rejected (phased rollout).
+    pub allow_new_obligations: bool,
+
+    /// Liquidation hysteresis band in health BPS (0 = disabled)
+    /// A liquidation may restore health up to 1.0 + band, and an obligation
+    /// restored above 1.0 cannot be liquidated again until its health falls
+    /// below 1.0 - band. Prevents repeated dust liquidations while the price
+    /// wicks across the threshold.
+    pub liquidation_hysteresis_bps: u16,
+
+    /// Require borrows to be preceded by a refresh_obligation of the same
+    /// obligation earlier in the same transaction. Off by default so clients
+    /// that refresh in a prior transaction keep working.
+    pub require_refresh_in_transaction: bool,
+
+    /// Maximum obligation refresh age accepted by liquidate, in slots
+    /// (0 = MAX_OBLIGATION_STALENESS_SLOTS). At most the general staleness window.
+    pub max_liquidation_refresh_age_slots: u64,
+
+    /// Emit ActionBlocked when deposits or borrows are rejected by emergency
+    /// mode or a disabled reserve. Off by default to keep logs quiet.
+    pub emit_action_blocked_events: bool,
+
+    /// Share of allowed borrow value held back at borrow time, in BPS
+    /// (0 = none). Absorbs rounding differences between the borrow check and
+    /// the next refresh so a max borrow is not immediately over capacity.
+    pub borrow_capacity_buffer_bps: u16,
+
+    /// Health factor in BPS below which a borrow emits RiskyBorrow (0 = off)
+    /// Borrows are still allowed down to the reserve's minimum health factor.
+    pub borrow_warning_health_bps: u16,
+
+    /// Extra liquidation bonus in BPS paid when an obligation first becomes
+    /// liquidatable. It decays linearly to zero over
+    /// `liquidation_bonus_decay_slots`, rewarding liquidators who act quickly.
+    pub liquidation_bonus_decay_extra_bps: u16,
+
+    /// Slots over which the extra liquidation bonus decays (0 = no decay bonus)
+    pub liquidation_bonus_decay_slots: u64,
+
+    /// Maximum distinct deposit reserves per obligation
+    /// (0 = MAX_OBLIGATION_DEPOSITS). Lower limits keep obligations cheap
+    /// to refresh and liquidate.
+    pub max_obligation_deposits: u8,
+
+    /// Maximum distinct borrow reserves per obligation (0 = MAX_OBLIGATION_BORROWS)
+    pub max_obligation_borrows: u8,
+
     /// Reserved space for future upgrades (128 bytes)
     pub _padding: [u8; 128],
 }
@@ -42,6 +121,9 @@ pub struct LendingMarket {
 impl LendingMarket {
     pub const SEED_PREFIX: &'static [u8] = b"lending_market";
 
+    /// Layout version written by this program (1 = legacy layout, see `legacy`)
+    pub const CURRENT_VERSION: u8 = 2;
+
     /// Check if market is in emergency mode
     pub fn is_emergency(&self) -> bool {
         self.emergency_mode
@@ -52,6 +134,50 @@ impl LendingMarket {
         close_factor_bps <= 10000
     }
 
+    /// Get the maximum obligation refresh age accepted by liquidate
+    pub fn liquidation_refresh_age_slots(&self) -> u64 {
+        if self.max_liquidation_refresh_age_slots == 0 {
+            MAX_OBLIGATION_STALENESS_SLOTS
+        } else {
+            self.max_liquidation_refresh_age_slots
+        }
+    }
+
+    /// Get the maximum number of deposit positions per obligation
+    pub fn obligation_deposits_limit(&self) -> usize {
+        if self.max_obligation_deposits == 0 {
+            MAX_OBLIGATION_DEPOSITS
+        } else {
+            self.max_obligation_deposits as usize
+        }
+    }
+
+    /// Get the maximum number of borrow positions per obligation
+    pub fn obligation_borrows_limit(&self) -> usize {
+        if self.max_obligation_borrows == 0 {
+            MAX_OBLIGATION_BORROWS
+        } else {
+            self.max_obligation_borrows as usize
+        }
+    }
+
+    /// Get the liquidation bonus for an obligation liquidatable for `unhealthy_slots`
+    ///
+    /// bonus = base + extra * (decay_slots - unhealthy_slots) / decay_slots,
+    /// capped at MAX_LIQUIDATION_BONUS_BPS. Without a recorded unhealthy slot
+    /// the base bonus applies.
+    pub fn decayed_liquidation_bonus_bps(&self, unhealthy_slots: Option<u64>) -> u16 {
+        let decay_slots = self.liquidation_bonus_decay_slots;
+        let extra_bps = match unhealthy_slots {
+            Some(elapsed) if decay_slots > 0 && elapsed < decay_slots => {
+                self.liquidation_bonus_decay_extra_bps as u64 * (decay_slots - elapsed) / decay_slots
+            }
+            _ => 0,
+        };
+
+        (self.liquidation_bonus_bps as u64 + extra_bps).min(MAX_LIQUIDATION_BONUS_BPS as u64) as u16
+    }
+
     /// Validate liquidation bonus is within acceptable range (0-25%)
     pub fn validate_liquidation_bonus(bonus_bps: u16) -> bool {
         bonus_bps <= 2500
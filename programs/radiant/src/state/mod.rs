@@ -1,7 +1,10 @@
 pub mod lending_market;
 pub mod reserve;
 pub mod obligation;
+pub mod whitelisted_depositor;
+pub mod legacy;
 
 pub use lending_market::*;
 pub use reserve::*;
 pub use obligation::*;
+pub use whitelisted_depositor::*;
@@ -1,5 +1,9 @@
 use anchor_lang::prelude::*;
 
+use crate::constants::{HEALTH_FACTOR_ONE, INDEX_ONE, PRICE_MOVE_CHECK_WINDOW_SLOTS};
+use crate::math::{health_factor_bps, mul_div_ceil, mul_div_floor};
+use super::InterestModel;
+
 /// Maximum number of deposits per obligation
 pub const MAX_DEPOSITS: usize = 8;
 
@@ -7,7 +11,8 @@ pub const MAX_DEPOSITS: usize = 8;
 pub const MAX_BORROWS: usize = 8;
 
 /// User's position in the lending market
-/// PDA Seeds: ["obligation", lending_market, owner]
+/// PDA Seeds: ["obligation", lending_market, owner, obligation_id]
+/// (obligation 0 omits the id seed, see `Obligation::id_seed`)
 #[account]
 #[derive(InitSpace)]
 pub struct Obligation {
@@ -24,6 +29,8 @@ pub struct Obligation {
     pub owner: Pubkey,
 
     /// Last slot when obligation was refreshed
+    /// Only refresh_obligation moves it, so no other instruction (e.g. a
+    /// tiny third-party repay) can make stale cached values look fresh
     pub last_update_slot: u64,
 
     /// Deposited assets used as collateral
@@ -50,6 +57,28 @@ pub struct Obligation {
     /// = sum(deposit_value * liquidation_threshold) for each deposit
     pub unhealthy_borrow_value_usd: u128,
 
+    /// Slot of the most recent liquidation
+    pub last_liquidation_slot: u64,
+
+    /// Number of liquidations in `last_liquidation_slot`
+    pub liquidations_in_slot: u8,
+
+    /// Slot of the most recent withdrawal not yet followed by a refresh
+    /// (0 = refreshed since the last withdrawal)
+    pub last_withdraw_slot: u64,
+
+    /// Index distinguishing an owner's obligations in one market
+    /// Lets a user hold separate, isolated positions
+    pub obligation_id: u8,
+
+    /// Estimated health factor after the most recent liquidation
+    /// (0 = never liquidated); drives the liquidation hysteresis band
+    pub last_liquidation_health_bps: u64,
+
+    /// Slot at which a refresh first found the obligation liquidatable
+    /// (0 = healthy at the last refresh); drives liquidation bonus decay
+    pub unhealthy_since_slot: u64,
+
     /// Reserved space for future upgrades (64 bytes)
     pub _padding: [u8; 64],
 }
@@ -69,15 +98,41 @@ pub struct ObligationCollateral {
 
     /// Cached market value in USD (scaled by 10^6)
     pub market_value_usd: u128,
+
+    /// Oracle price used for the cached market value (USD scaled by 10^6)
+    pub market_price_usd: u64,
+
+    /// Slot of the most recent deposit into this position
+    pub last_deposit_slot: u64,
+
+    /// Amount deposited since this position last matured, activating at
+    /// `last_deposit_slot` (in native token units). Only this amount is
+    /// subject to the reserve's initial borrow haircut and activation
+    /// delay; the rest of the position backs borrows at the full LTV.
+    pub pending_amount: u64,
+
+    /// Whether this deposit counts as borrow collateral
+    /// Non-collateral deposits still earn interest but add no borrowing
+    /// power and cannot be seized in liquidation. Version 1 deposits always
+    /// backed borrows, so migrate_obligation enables it for all of them.
+    pub use_as_collateral: bool,
 }
 
 /// Liquidity borrowed by user
+///
+/// Interest model: debt compounds through the reserve's cumulative borrow
+/// index, so interest accrues on previously accrued interest as well as on
+/// principal. On every update the current debt (including interest) is
+/// rebased into `borrowed_amount` with a fresh index snapshot; this is the
+/// same compounding the index already applies and does not change the debt.
+/// Principal is tracked separately so repayments can be split accurately.
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace, Default)]
 pub struct ObligationLiquidity {
     /// Reserve account this borrow is from
     pub reserve: Pubkey,
 
-    /// Amount borrowed (principal, in native token units)
+    /// Debt at `borrow_index_snapshot` (principal plus interest accrued up to
+    /// the last update, in native token units)
     pub borrowed_amount: u64,
 
     /// Borrow index snapshot when loan was taken
@@ -86,14 +141,39 @@ pub struct ObligationLiquidity {
 
     /// Cached market value in USD (scaled by 10^6)
     pub market_value_usd: u128,
+
+    /// Oracle price used for the cached market value (USD scaled by 10^6)
+    pub market_price_usd: u64,
+
+    /// Outstanding principal, excluding any accrued interest
+    /// (in native token units)
+    pub principal: u64,
 }
 
 impl Obligation {
     pub const SEED_PREFIX: &'static [u8] = b"obligation";
 
-    /// Calculate health factor (scaled by 10000 for precision)
+    /// First version whose deposits and borrows are kept sorted by reserve
+    pub const SORTED_VERSION: u8 = 2;
+
+    /// PDA seed for an obligation id
+    ///
+    /// Obligation 0 uses an empty seed, which leaves the address derived
+    /// from ["obligation", lending_market, owner] as in version 1, where
+    /// each owner had a single obligation. Migrated version 1 obligations
+    /// therefore keep their address as obligation 0.
+    pub fn id_seed(obligation_id: &u8) -> &[u8] {
+        if *obligation_id == 0 {
+            &[]
+        } else {
+            std::slice::from_ref(obligation_id)
+        }
+    }
+
+    /// Calculate health factor (scaled by HEALTH_FACTOR_ONE = 10000)
     ///
     /// Formula: Health = unhealthy_borrow_value_usd / borrowed_value_usd
+    /// (see math::health_factor_bps)
     ///
     /// Where unhealthy_borrow_value_usd = sum(deposit_value * liquidation_threshold)
     /// This is pre-calculated during refresh_obligation
@@ -103,22 +183,15 @@ impl Obligation {
     /// - Some(>10000) = Healthy (e.g., 12000 = 1.2 health factor)
     /// - Some(<=10000) = Liquidatable (e.g., 9500 = 0.95 health factor)
     pub fn calculate_health_factor(&self) -> Option<u64> {
-        if self.borrowed_value_usd == 0 {
-            return None; // No debt = infinite health
-        }
-
-        // health_factor = (unhealthy_borrow_value / borrowed_value) * 10000
         // Example: $85,000 threshold / $80,000 debt = 1.0625 → 10625
-        Some(
-            ((self.unhealthy_borrow_value_usd * 10000) / self.borrowed_value_usd) as u64
-        )
+        health_factor_bps(self.unhealthy_borrow_value_usd, self.borrowed_value_usd)
     }
 
     /// Check if obligation is healthy (health factor > 1.0)
     pub fn is_healthy(&self) -> bool {
         match self.calculate_health_factor() {
             None => true, // No debt = healthy
-            Some(health) => health > 10000,
+            Some(health) => health > HEALTH_FACTOR_ONE,
         }
     }
 
@@ -127,12 +200,263 @@ impl Obligation {
         !self.is_healthy()
     }
 
+    /// Record whether the obligation is liquidatable as of `current_slot`
+    ///
+    /// Keeps the first unhealthy slot while the obligation stays liquidatable
+    /// and clears it once health is restored.
+    pub fn update_unhealthy_since(&mut self, current_slot: u64) {
+        if !self.is_liquidatable() {
+            self.unhealthy_since_slot = 0;
+        } else if self.unhealthy_since_slot == 0 {
+            self.unhealthy_since_slot = current_slot;
+        }
+    }
+
+    /// Get the number of slots the obligation has been liquidatable
+    /// (None if no refresh has recorded it as unhealthy)
+    pub fn unhealthy_slots(&self, current_slot: u64) -> Option<u64> {
+        if self.unhealthy_since_slot == 0 {
+            None
+        } else {
+            Some(current_slot.saturating_sub(self.unhealthy_since_slot))
+        }
+    }
+
+    /// Check if the obligation is inside the liquidation hysteresis band
+    ///
+    /// True when the last liquidation restored health above 1.0 and health
+    /// has not since fallen below 1.0 - hysteresis_bps.
+    pub fn in_liquidation_hysteresis_band(&self, hysteresis_bps: u16) -> bool {
+        if hysteresis_bps == 0 || self.last_liquidation_health_bps <= HEALTH_FACTOR_ONE {
+            return false;
+        }
+        let rearm_below = HEALTH_FACTOR_ONE.saturating_sub(hysteresis_bps as u64);
+        match self.calculate_health_factor() {
+            None => true,
+            Some(health) => health >= rearm_below,
+        }
+    }
+
+    /// Estimate health after a liquidation using cached values
+    ///
+    /// Removes the repaid debt value from the borrowed value and the seized
+    /// collateral's threshold-weighted value from the unhealthy value.
+    /// Returns u64::MAX when no debt would remain.
+    pub fn health_after_liquidation(
+        &self,
+        repay_value_usd: u128,
+        seized_unhealthy_value_usd: u128,
+    ) -> u64 {
+        health_factor_bps(
+            self.unhealthy_borrow_value_usd.saturating_sub(seized_unhealthy_value_usd),
+            self.borrowed_value_usd.saturating_sub(repay_value_usd),
+        )
+        .unwrap_or(u64::MAX)
+    }
+
+    /// Apply a liquidation to the cached values and return the recomputed health
+    ///
+    /// Removes `repaid_value_usd` from the borrow position and borrowed value,
+    /// and `seized_value_usd` from the deposit position and deposited value,
+    /// with its LTV and threshold weighted shares (rounded up) from the
+    /// allowed and unhealthy values. Returns u64::MAX when no debt remains.
+    pub fn apply_liquidation_to_cache(
+        &mut self,
+        borrow_index: usize,
+        deposit_index: usize,
+        repaid_value_usd: u128,
+        seized_value_usd: u128,
+        ltv_bps: u16,
+        liquidation_threshold_bps: u16,
+    ) -> Option<u64> {
+        let seized_allowed_usd = mul_div_ceil(seized_value_usd, ltv_bps as u128, 10000)?;
+        let seized_unhealthy_usd = mul_div_ceil(seized_value_usd, liquidation_threshold_bps as u128, 10000)?;
+
+        let borrow = self.borrows.get_mut(borrow_index)?;
+        borrow.market_value_usd = borrow.market_value_usd.saturating_sub(repaid_value_usd);
+        let deposit = self.deposits.get_mut(deposit_index)?;
+        deposit.market_value_usd = deposit.market_value_usd.saturating_sub(seized_value_usd);
+
+        self.borrowed_value_usd = self.borrowed_value_usd.saturating_sub(repaid_value_usd);
+        self.deposited_value_usd = self.deposited_value_usd.saturating_sub(seized_value_usd);
+        self.allowed_borrow_value_usd = self.allowed_borrow_value_usd.saturating_sub(seized_allowed_usd);
+        self.unhealthy_borrow_value_usd = self.unhealthy_borrow_value_usd.saturating_sub(seized_unhealthy_usd);
+
+        Some(self.calculate_health_factor().unwrap_or(u64::MAX))
+    }
+
+    /// Record a liquidation in the given slot
+    /// Returns false if the per-slot limit (0 = unlimited) is already reached
+    pub fn record_liquidation(&mut self, slot: u64, max_per_slot: u8) -> bool {
+        if self.last_liquidation_slot != slot {
+            self.last_liquidation_slot = slot;
+            self.liquidations_in_slot = 0;
+        }
+
+        if max_per_slot > 0 && self.liquidations_in_slot >= max_per_slot {
+            return false;
+        }
+
+        self.liquidations_in_slot = self.liquidations_in_slot.saturating_add(1);
+        true
+    }
+
+    /// Find the liquidation that maximizes liquidator profit
+    ///
+    /// Considers every (borrow, collateral deposit) pair, valuing positions in
+    /// USD from index-adjusted amounts. Deposits that are not collateral or
+    /// whose reserve is not seizable are skipped. For each pair the repay is
+    /// limited by the close factor and by the collateral available to seize
+    /// at `repay_value * (1 + bonus)`. Profit is the bonus portion in USD.
+    ///
+    /// Returns (repay_reserve, collateral_reserve, repay_amount), or None if
+    /// the obligation is healthy or no pair has a price.
+    pub fn most_profitable_liquidation(
+        &self,
+        prices: &[PositionPrice],
+        bonus_bps: u16,
+        close_factor_bps: u16,
+    ) -> Option<(Pubkey, Pubkey, u64)> {
+        if !self.is_liquidatable() {
+            return None;
+        }
+
+        let find_price = |reserve: &Pubkey| prices.iter().find(|p| &p.reserve == reserve);
+        let bonus_multiplier = 10000 + bonus_bps as u128;
+
+        let mut best: Option<(Pubkey, Pubkey, u64)> = None;
+        let mut best_profit_usd: u128 = 0;
+
+        for borrow in self.borrows.iter() {
+            let Some(borrow_price) = find_price(&borrow.reserve) else { continue };
+            if borrow_price.price_usd == 0 {
+                continue;
+            }
+            let borrow_scale = 10u128.checked_pow(borrow_price.decimals as u32)?;
+
+            let current_borrow = borrow.current_amount(
+                borrow_price.cumulative_borrow_index,
+                borrow_price.interest_model,
+            )?;
+            let max_repay = mul_div_floor(current_borrow as u128, close_factor_bps as u128, 10000)?;
+            let max_repay_usd = mul_div_floor(max_repay, borrow_price.price_usd as u128, borrow_scale)?;
+
+            for deposit in self.deposits.iter() {
+                if !deposit.use_as_collateral {
+                    continue;
+                }
+                let Some(deposit_price) = find_price(&deposit.reserve) else { continue };
+                if !deposit_price.seizable {
+                    continue;
+                }
+                let deposit_scale = 10u128.checked_pow(deposit_price.decimals as u32)?;
+
+                let current_deposit = deposit.current_amount(deposit_price.cumulative_supply_index)?;
+                let deposit_usd =
+                    mul_div_floor(current_deposit as u128, deposit_price.price_usd as u128, deposit_scale)?;
+
+                // Repay is bounded by the collateral that can cover it plus bonus
+                let repay_usd = max_repay_usd.min(mul_div_floor(deposit_usd, 10000, bonus_multiplier)?);
+                let profit_usd = mul_div_floor(repay_usd, bonus_bps as u128, 10000)?;
+
+                if repay_usd == 0 || (best.is_some() && profit_usd <= best_profit_usd) {
+                    continue;
+                }
+
+                let repay_amount =
+                    mul_div_floor(repay_usd, borrow_scale, borrow_price.price_usd as u128)?.min(max_repay);
+
+                best_profit_usd = profit_usd;
+                best = Some((borrow.reserve, deposit.reserve, u64::try_from(repay_amount).ok()?));
+            }
+        }
+
+        best
+    }
+
+    /// Check if collateral was withdrawn since the last refresh
+    /// Borrows stay blocked until refresh_obligation clears the marker
+    pub fn withdrawn_since_refresh(&self) -> bool {
+        self.last_withdraw_slot != 0
+    }
+
+    /// Calculate the USD repayment needed to reach a target health factor
+    ///
+    /// Uses cached values: collateral is unchanged by a repay, so the target
+    /// borrowed value is unhealthy_borrow_value_usd * 10000 / target.
+    /// Returns 0 if the obligation is already at or above the target,
+    /// None if the target is zero.
+    pub fn repay_to_target_health(&self, target_health_bps: u64) -> Option<u128> {
+        if target_health_bps == 0 {
+            return None;
+        }
+
+        let target_borrowed_value_usd =
+            self.unhealthy_borrow_value_usd.checked_mul(10000)? / target_health_bps as u128;
+
+        Some(self.borrowed_value_usd.saturating_sub(target_borrowed_value_usd))
+    }
+
+    /// Estimate the collateral price at which the obligation hits health 1.0
+    ///
+    /// Holds debt and all other collateral constant:
+    /// price = (borrowed_value - other_collateral_value) * 10000 * 10^decimals
+    ///         / (current_deposit_amount * liq_threshold_bps)
+    ///
+    /// `other_collateral_value_usd` is the threshold-weighted value of the
+    /// obligation's other deposits (their contribution to
+    /// unhealthy_borrow_value_usd). The deposit includes supply interest up
+    /// to `current_supply_index`, matching the index-adjusted debt cached at
+    /// refresh. Uses cached values, so refresh first.
+    /// Returns the price in USD scaled by 10^6, rounded up; Some(0) if the
+    /// other collateral alone covers the debt; None if there is no deposit
+    /// in the reserve, the threshold is zero, or on overflow.
+    pub fn liquidation_price(
+        &self,
+        collateral_reserve: &Pubkey,
+        current_supply_index: u128,
+        liq_threshold_bps: u16,
+        other_collateral_value_usd: u128,
+        decimals: u8,
+    ) -> Option<u64> {
+        let deposit = &self.deposits[self.find_deposit(collateral_reserve)?];
+        let deposit_amount = deposit.current_amount(current_supply_index)?;
+        if deposit_amount == 0 || liq_threshold_bps == 0 {
+            return None;
+        }
+
+        let uncovered_debt_usd = self.borrowed_value_usd.saturating_sub(other_collateral_value_usd);
+        let scale = 10u128.checked_pow(decimals as u32)?.checked_mul(10000)?;
+        let weighted_amount = (deposit_amount as u128).checked_mul(liq_threshold_bps as u128)?;
+        let price = mul_div_ceil(uncovered_debt_usd, scale, weighted_amount)?;
+        u64::try_from(price).ok()
+    }
+
+    /// Get net equity in USD (deposits - borrows), negative when insolvent
+    pub fn net_equity_usd(&self) -> i128 {
+        let deposited = i128::try_from(self.deposited_value_usd).unwrap_or(i128::MAX);
+        let borrowed = i128::try_from(self.borrowed_value_usd).unwrap_or(i128::MAX);
+        deposited.saturating_sub(borrowed)
+    }
+
     /// Get remaining borrow capacity in USD
     pub fn remaining_borrow_capacity_usd(&self) -> u128 {
         self.allowed_borrow_value_usd
             .saturating_sub(self.borrowed_value_usd)
     }
 
+    /// Get remaining borrow capacity in USD after holding back `buffer_bps`
+    /// of the allowed borrow value
+    pub fn remaining_borrow_capacity_with_buffer_usd(&self, buffer_bps: u16) -> u128 {
+        let buffered_allowed = mul_div_floor(
+            self.allowed_borrow_value_usd,
+            10000u128.saturating_sub(buffer_bps as u128),
+            10000,
+        )
+        .unwrap_or(0);
+        buffered_allowed.saturating_sub(self.borrowed_value_usd)
+    }
+
     /// Find deposit index for a given reserve
     pub fn find_deposit(&self, reserve: &Pubkey) -> Option<usize> {
         self.deposits.iter().position(|d| &d.reserve == reserve)
@@ -143,6 +467,36 @@ impl Obligation {
         self.borrows.iter().position(|b| &b.reserve == reserve)
     }
 
+    /// Insert a deposit keeping deposits sorted by reserve
+    pub fn insert_deposit(&mut self, deposit: ObligationCollateral) {
+        let index = self.deposits.partition_point(|d| d.reserve < deposit.reserve);
+        self.deposits.insert(index, deposit);
+    }
+
+    /// Insert a borrow keeping borrows sorted by reserve
+    pub fn insert_borrow(&mut self, borrow: ObligationLiquidity) {
+        let index = self.borrows.partition_point(|b| b.reserve < borrow.reserve);
+        self.borrows.insert(index, borrow);
+    }
+
+    /// Sort deposits and borrows by reserve and mark the obligation as sorted
+    /// Idempotent; returns true if any position moved.
+    pub fn normalize(&mut self) -> bool {
+        let deposits_sorted = self.deposits.windows(2).all(|w| w[0].reserve <= w[1].reserve);
+        let borrows_sorted = self.borrows.windows(2).all(|w| w[0].reserve <= w[1].reserve);
+
+        self.deposits.sort_by_key(|d| d.reserve);
+        self.borrows.sort_by_key(|b| b.reserve);
+        self.version = self.version.max(Self::SORTED_VERSION);
+
+        !(deposits_sorted && borrows_sorted)
+    }
+
+    /// Get the reserves this obligation currently borrows from
+    pub fn borrowed_reserves(&self) -> Vec<Pubkey> {
+        self.borrows.iter().map(|b| b.reserve).collect()
+    }
+
     /// Check if user has any deposits
     pub fn has_deposits(&self) -> bool {
         !self.deposits.is_empty()
@@ -192,16 +546,103 @@ impl Obligation {
     }
 }
 
+/// Price of a reserve's token, used for off-cache position valuation
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct PositionPrice {
+    /// Reserve the price applies to
+    pub reserve: Pubkey,
+
+    /// Token price in USD (scaled by 10^6 per whole token)
+    pub price_usd: u64,
+
+    /// Token decimals
+    pub decimals: u8,
+
+    /// Reserve's current cumulative borrow index
+    pub cumulative_borrow_index: u128,
+
+    /// Reserve's interest model, which determines how the borrow index applies
+    pub interest_model: InterestModel,
+
+    /// Reserve's current cumulative supply index
+    pub cumulative_supply_index: u128,
+
+    /// Whether the reserve's collateral may be seized in liquidation
+    pub seizable: bool,
+}
+
+/// Check a new price against the previously cached one
+/// Returns false on a move larger than `max_multiplier` in either direction
+/// A zero cached price (never refreshed) always passes, as does a cached price
+/// older than `PRICE_MOVE_CHECK_WINDOW_SLOTS`, so a genuine large move cannot
+/// leave the obligation permanently unrefreshable
+pub fn is_plausible_price_move(
+    cached_price: u64,
+    new_price: u64,
+    max_multiplier: u64,
+    slots_since_cache: u64,
+) -> bool {
+    if cached_price == 0 || slots_since_cache > PRICE_MOVE_CHECK_WINDOW_SLOTS {
+        return true;
+    }
+
+    let cached = cached_price as u128;
+    let new = new_price as u128;
+    let multiplier = max_multiplier as u128;
+
+    new <= cached * multiplier && new * multiplier >= cached
+}
+
 impl ObligationCollateral {
     /// Create new collateral entry
-    pub fn new(reserve: Pubkey, amount: u64, supply_index: u128) -> Self {
+    pub fn new(reserve: Pubkey, amount: u64, supply_index: u128, deposit_slot: u64) -> Self {
         Self {
             reserve,
             deposited_amount: amount,
             supply_index_snapshot: supply_index,
             market_value_usd: 0,
+            market_price_usd: 0,
+            last_deposit_slot: deposit_slot,
+            pending_amount: amount,
+            use_as_collateral: true,
+        }
+    }
+
+    /// Record a top-up of `amount` at `deposit_slot`
+    ///
+    /// A pending amount older than `maturity_slots` has matured and is
+    /// replaced; otherwise the top-up joins it and restarts its window.
+    /// Matured collateral is never pulled back into a window.
+    pub fn add_pending(&mut self, amount: u64, deposit_slot: u64, maturity_slots: u64) {
+        if deposit_slot.saturating_sub(self.last_deposit_slot) >= maturity_slots {
+            self.pending_amount = amount;
+        } else {
+            self.pending_amount = self.pending_amount.saturating_add(amount);
+        }
+        self.last_deposit_slot = deposit_slot;
+    }
+
+    /// Part of `current_amount` still pending at `current_slot`
+    pub fn pending_at(&self, current_amount: u64, current_slot: u64, maturity_slots: u64) -> u64 {
+        if current_slot.saturating_sub(self.last_deposit_slot) >= maturity_slots {
+            0
+        } else {
+            self.pending_amount.min(current_amount)
         }
     }
+
+    /// Deposit including supply interest accrued since the snapshot (rounds down)
+    pub fn current_amount(&self, current_supply_index: u128) -> Option<u64> {
+        if self.supply_index_snapshot == 0 {
+            return Some(self.deposited_amount);
+        }
+        let amount = mul_div_floor(
+            self.deposited_amount as u128,
+            current_supply_index,
+            self.supply_index_snapshot,
+        )?;
+        u64::try_from(amount).ok()
+    }
 }
 
 impl ObligationLiquidity {
@@ -212,6 +653,375 @@ impl ObligationLiquidity {
             borrowed_amount: amount,
             borrow_index_snapshot: borrow_index,
             market_value_usd: 0,
+            market_price_usd: 0,
+            principal: amount,
+        }
+    }
+
+    /// Debt including borrow interest accrued since the snapshot (rounds up)
+    ///
+    /// Compound: the stored debt scaled by the index growth. Simple: the
+    /// stored debt plus interest on the outstanding principal only.
+    pub fn current_amount(&self, current_borrow_index: u128, interest_model: InterestModel) -> Option<u64> {
+        if self.borrow_index_snapshot == 0 {
+            return Some(self.borrowed_amount);
         }
+        let amount = match interest_model {
+            InterestModel::Compound => mul_div_ceil(
+                self.borrowed_amount as u128,
+                current_borrow_index,
+                self.borrow_index_snapshot,
+            )?,
+            InterestModel::Simple => {
+                let interest = mul_div_ceil(
+                    self.principal as u128,
+                    current_borrow_index.saturating_sub(self.borrow_index_snapshot),
+                    INDEX_ONE,
+                )?;
+                (self.borrowed_amount as u128).checked_add(interest)?
+            }
+        };
+        u64::try_from(amount).ok()
+    }
+
+    /// Split a repayment into (principal_repaid, interest_repaid)
+    ///
+    /// Accrued interest is the difference between the current index-adjusted
+    /// debt and the outstanding principal. Repayments cover interest first.
+    pub fn split_repayment(&self, current_amount: u64, repay_amount: u64) -> (u64, u64) {
+        let accrued_interest = current_amount.saturating_sub(self.principal);
+        let interest_repaid = repay_amount.min(accrued_interest);
+        let principal_repaid = repay_amount - interest_repaid;
+
+        (principal_repaid, interest_repaid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::INDEX_ONE;
+
+    const USDC: Pubkey = Pubkey::new_from_array([1; 32]);
+    const SOL: Pubkey = Pubkey::new_from_array([2; 32]);
+    const ETH: Pubkey = Pubkey::new_from_array([3; 32]);
+
+    fn obligation(
+        deposits: Vec<ObligationCollateral>,
+        borrows: Vec<ObligationLiquidity>,
+        unhealthy_usd: u128,
+        borrowed_usd: u128,
+    ) -> Obligation {
+        Obligation {
+            version: Obligation::SORTED_VERSION,
+            bump: 0,
+            lending_market: Pubkey::default(),
+            owner: Pubkey::default(),
+            last_update_slot: 0,
+            deposits,
+            borrows,
+            deposited_value_usd: 0,
+            borrowed_value_usd: borrowed_usd,
+            allowed_borrow_value_usd: 0,
+            unhealthy_borrow_value_usd: unhealthy_usd,
+            last_liquidation_slot: 0,
+            liquidations_in_slot: 0,
+            last_withdraw_slot: 0,
+            obligation_id: 0,
+            last_liquidation_health_bps: 0,
+            unhealthy_since_slot: 0,
+            _padding: [0; 64],
+        }
+    }
+
+    fn price(reserve: Pubkey, price_usd: u64, decimals: u8) -> PositionPrice {
+        PositionPrice {
+            reserve,
+            price_usd,
+            decimals,
+            cumulative_borrow_index: INDEX_ONE,
+            interest_model: InterestModel::Compound,
+            cumulative_supply_index: INDEX_ONE,
+            seizable: true,
+        }
+    }
+
+    fn standard_prices() -> Vec<PositionPrice> {
+        vec![
+            price(USDC, 1_000_000, 6),
+            price(SOL, 100_000_000, 9),
+            price(ETH, 2_000_000_000, 8),
+        ]
+    }
+
+    #[test]
+    fn healthy_obligation_has_no_liquidation() {
+        let o = obligation(
+            vec![ObligationCollateral::new(SOL, 5_000_000_000, INDEX_ONE, 0)],
+            vec![ObligationLiquidity::new(USDC, 100_000_000, INDEX_ONE)],
+            400_000_000,
+            100_000_000,
+        );
+        assert_eq!(o.most_profitable_liquidation(&standard_prices(), 500, 5000), None);
+    }
+
+    #[test]
+    fn picks_collateral_by_usd_value_across_decimals() {
+        // $1000 USDC debt; 5 SOL ($500) and 0.1 ETH ($200) of collateral
+        let o = obligation(
+            vec![
+                ObligationCollateral::new(SOL, 5_000_000_000, INDEX_ONE, 0),
+                ObligationCollateral::new(ETH, 10_000_000, INDEX_ONE, 0),
+            ],
+            vec![ObligationLiquidity::new(USDC, 1_000_000_000, INDEX_ONE)],
+            600_000_000,
+            1_000_000_000,
+        );
+        let (repay, collateral, amount) =
+            o.most_profitable_liquidation(&standard_prices(), 500, 5000).unwrap();
+        assert_eq!(repay, USDC);
+        assert_eq!(collateral, SOL);
+        // $500 of SOL covers $476.19 of repay at a 5% bonus
+        assert_eq!(amount, 476_190_476);
+    }
+
+    #[test]
+    fn repay_uses_index_adjusted_debt() {
+        // 400 USDC borrowed at index 1.0, now 2.0: debt is 800, close factor allows 400
+        let o = obligation(
+            vec![ObligationCollateral::new(SOL, 100_000_000_000, INDEX_ONE, 0)],
+            vec![ObligationLiquidity::new(USDC, 400_000_000, INDEX_ONE)],
+            700_000_000,
+            800_000_000,
+        );
+        let mut prices = standard_prices();
+        prices[0].cumulative_borrow_index = 2 * INDEX_ONE;
+        let (_, _, amount) = o.most_profitable_liquidation(&prices, 500, 5000).unwrap();
+        assert_eq!(amount, 400_000_000);
+    }
+
+    #[test]
+    fn deposit_value_includes_supply_interest() {
+        // 3 SOL at supply index 2.0 ($600) beats 0.25 ETH ($500)
+        let o = obligation(
+            vec![
+                ObligationCollateral::new(SOL, 3_000_000_000, INDEX_ONE, 0),
+                ObligationCollateral::new(ETH, 25_000_000, INDEX_ONE, 0),
+            ],
+            vec![ObligationLiquidity::new(USDC, 2_000_000_000, INDEX_ONE)],
+            1_000_000_000,
+            2_000_000_000,
+        );
+        let mut prices = standard_prices();
+        prices[1].cumulative_supply_index = 2 * INDEX_ONE;
+        let (_, collateral, _) = o.most_profitable_liquidation(&prices, 500, 5000).unwrap();
+        assert_eq!(collateral, SOL);
+    }
+
+    #[test]
+    fn skips_non_collateral_deposits() {
+        let mut sol = ObligationCollateral::new(SOL, 5_000_000_000, INDEX_ONE, 0);
+        sol.use_as_collateral = false;
+        let o = obligation(
+            vec![sol, ObligationCollateral::new(ETH, 10_000_000, INDEX_ONE, 0)],
+            vec![ObligationLiquidity::new(USDC, 1_000_000_000, INDEX_ONE)],
+            600_000_000,
+            1_000_000_000,
+        );
+        let (_, collateral, _) = o.most_profitable_liquidation(&standard_prices(), 500, 5000).unwrap();
+        assert_eq!(collateral, ETH);
+    }
+
+    #[test]
+    fn skips_non_seizable_reserves() {
+        let o = obligation(
+            vec![
+                ObligationCollateral::new(SOL, 5_000_000_000, INDEX_ONE, 0),
+                ObligationCollateral::new(ETH, 10_000_000, INDEX_ONE, 0),
+            ],
+            vec![ObligationLiquidity::new(USDC, 1_000_000_000, INDEX_ONE)],
+            600_000_000,
+            1_000_000_000,
+        );
+        let mut prices = standard_prices();
+        prices[1].seizable = false;
+        let (_, collateral, _) = o.most_profitable_liquidation(&prices, 500, 5000).unwrap();
+        assert_eq!(collateral, ETH);
+
+        prices[2].seizable = false;
+        assert_eq!(o.most_profitable_liquidation(&prices, 500, 5000), None);
+    }
+
+    fn liquidatable_position() -> Obligation {
+        // $1000 debt against $1237.50 of collateral at an 80% threshold: health 0.99
+        let mut sol = ObligationCollateral::new(SOL, 12_375_000_000, INDEX_ONE, 0);
+        sol.market_value_usd = 1_237_500_000;
+        let mut usdc = ObligationLiquidity::new(USDC, 1_000_000_000, INDEX_ONE);
+        usdc.market_value_usd = 1_000_000_000;
+        let mut o = obligation(vec![sol], vec![usdc], 990_000_000, 1_000_000_000);
+        o.deposited_value_usd = 1_237_500_000;
+        o.allowed_borrow_value_usd = 928_125_000;
+        o
+    }
+
+    #[test]
+    fn liquidation_recomputes_cached_health() {
+        let mut o = liquidatable_position();
+        assert_eq!(o.calculate_health_factor(), Some(9900));
+
+        // Repay $500, seize $525 of collateral (5% bonus)
+        let health = o
+            .apply_liquidation_to_cache(0, 0, 500_000_000, 525_000_000, 7500, 8000)
+            .unwrap();
+
+        assert_eq!(o.borrowed_value_usd, 500_000_000);
+        assert_eq!(o.borrows[0].market_value_usd, 500_000_000);
+        assert_eq!(o.deposited_value_usd, 712_500_000);
+        assert_eq!(o.deposits[0].market_value_usd, 712_500_000);
+        assert_eq!(o.allowed_borrow_value_usd, 534_375_000);
+        assert_eq!(o.unhealthy_borrow_value_usd, 570_000_000);
+        assert_eq!(health, 11400);
+        assert_eq!(o.calculate_health_factor(), Some(health));
+    }
+
+    #[test]
+    fn liquidation_repaying_all_debt_reports_infinite_health() {
+        let mut o = liquidatable_position();
+        let health = o
+            .apply_liquidation_to_cache(0, 0, 1_000_000_000, 1_050_000_000, 7500, 8000)
+            .unwrap();
+        assert_eq!(health, u64::MAX);
+    }
+
+    #[test]
+    fn cached_and_projected_health_share_one_formula() {
+        let o = obligation(vec![], vec![], 85_000_000, 80_000_000);
+        assert_eq!(o.calculate_health_factor(), health_factor_bps(85_000_000, 80_000_000));
+        assert_eq!(o.health_after_liquidation(0, 0), 10_625);
+        assert_eq!(o.health_after_liquidation(10_000_000, 5_000_000), health_factor_bps(80_000_000, 70_000_000).unwrap());
+        assert_eq!(obligation(vec![], vec![], 85_000_000, 0).calculate_health_factor(), None);
+    }
+
+    #[test]
+    fn liquidation_price_with_a_single_collateral() {
+        // 10 SOL backing $680 of debt at an 80% threshold: liquidated at $85
+        let o = obligation(
+            vec![ObligationCollateral::new(SOL, 10_000_000_000, INDEX_ONE, 0)],
+            vec![ObligationLiquidity::new(USDC, 680_000_000, INDEX_ONE)],
+            800_000_000,
+            680_000_000,
+        );
+        assert_eq!(o.liquidation_price(&SOL, INDEX_ONE, 8000, 0, 9), Some(85_000_000));
+        assert_eq!(o.liquidation_price(&SOL, INDEX_ONE, 0, 0, 9), None);
+        assert_eq!(o.liquidation_price(&ETH, INDEX_ONE, 8000, 0, 8), None);
+    }
+
+    #[test]
+    fn liquidation_price_counts_other_collateral() {
+        // 0.1 ETH worth $160 at threshold covers part of the $680 debt
+        let o = obligation(
+            vec![
+                ObligationCollateral::new(SOL, 10_000_000_000, INDEX_ONE, 0),
+                ObligationCollateral::new(ETH, 10_000_000, INDEX_ONE, 0),
+            ],
+            vec![ObligationLiquidity::new(USDC, 680_000_000, INDEX_ONE)],
+            960_000_000,
+            680_000_000,
+        );
+        assert_eq!(o.liquidation_price(&SOL, INDEX_ONE, 8000, 160_000_000, 9), Some(65_000_000));
+
+        // At that price health is exactly 1.0
+        let sol_value = 10 * 65_000_000 * 8000 / 10000;
+        assert_eq!(health_factor_bps(sol_value + 160_000_000, 680_000_000), Some(10_000));
+
+        // Other collateral alone covering the debt never liquidates on SOL
+        assert_eq!(o.liquidation_price(&SOL, INDEX_ONE, 8000, 700_000_000, 9), Some(0));
+    }
+
+    #[test]
+    fn liquidation_price_includes_accrued_supply_interest() {
+        // 10 SOL deposited at index 1.0 have grown to 10.2 SOL at index 1.02
+        let o = obligation(
+            vec![ObligationCollateral::new(SOL, 10_000_000_000, INDEX_ONE, 0)],
+            vec![ObligationLiquidity::new(USDC, 680_000_000, INDEX_ONE)],
+            816_000_000,
+            680_000_000,
+        );
+        let index = INDEX_ONE * 102 / 100;
+        assert_eq!(o.liquidation_price(&SOL, index, 8000, 0, 9), Some(83_333_334));
+        assert!(o.liquidation_price(&SOL, index, 8000, 0, 9) < o.liquidation_price(&SOL, INDEX_ONE, 8000, 0, 9));
+    }
+
+    #[test]
+    fn hysteresis_band_blocks_a_shallow_wick_after_liquidation() {
+        let mut o = liquidatable_position();
+        o.last_liquidation_health_bps = 10200;
+
+        // Refresh during a wick finds health just under 1.0: still in the band
+        o.unhealthy_borrow_value_usd = 990_000_000;
+        assert!(o.in_liquidation_hysteresis_band(300));
+
+        // At exactly 1.0 - band the band still holds
+        o.unhealthy_borrow_value_usd = 970_000_000;
+        assert!(o.in_liquidation_hysteresis_band(300));
+    }
+
+    #[test]
+    fn hysteresis_band_rearms_on_a_deep_wick() {
+        let mut o = liquidatable_position();
+        o.last_liquidation_health_bps = 10200;
+
+        // Health falls below 1.0 - band: liquidation is allowed again
+        o.unhealthy_borrow_value_usd = 969_000_000;
+        assert!(!o.in_liquidation_hysteresis_band(300));
+    }
+
+    #[test]
+    fn hysteresis_band_inactive_without_a_restoring_liquidation() {
+        let mut o = liquidatable_position();
+        assert!(!o.in_liquidation_hysteresis_band(300));
+
+        // A liquidation that left health at or below 1.0 does not arm the band
+        o.last_liquidation_health_bps = HEALTH_FACTOR_ONE;
+        assert!(!o.in_liquidation_hysteresis_band(300));
+
+        // Nor does a market without a band
+        o.last_liquidation_health_bps = 10200;
+        assert!(!o.in_liquidation_hysteresis_band(0));
+    }
+
+    #[test]
+    fn withdrawal_blocks_borrows_until_refresh_regardless_of_elapsed_slots() {
+        let mut o = obligation(vec![], vec![], 0, 0);
+        assert!(!o.withdrawn_since_refresh());
+
+        // No amount of elapsed slots lifts the block on its own
+        o.last_withdraw_slot = 100;
+        assert!(o.withdrawn_since_refresh());
+
+        // refresh_obligation clears the marker
+        o.last_withdraw_slot = 0;
+        assert!(!o.withdrawn_since_refresh());
+    }
+
+    #[test]
+    fn price_move_within_multiplier_is_plausible() {
+        assert!(is_plausible_price_move(100, 1_000, 10, 0));
+        assert!(is_plausible_price_move(1_000, 100, 10, 0));
+        assert!(is_plausible_price_move(0, 5_000_000, 10, 0));
+    }
+
+    #[test]
+    fn price_move_beyond_multiplier_is_rejected_inside_window() {
+        assert!(!is_plausible_price_move(100, 1_001, 10, 0));
+        assert!(!is_plausible_price_move(1_000, 99, 10, PRICE_MOVE_CHECK_WINDOW_SLOTS));
+    }
+
+    #[test]
+    fn genuine_large_move_passes_once_cache_is_old() {
+        // A 100x crash blocks refreshes only until the window lapses
+        let elapsed = PRICE_MOVE_CHECK_WINDOW_SLOTS + 1;
+        assert!(is_plausible_price_move(10_000, 100, 10, elapsed));
+        assert!(is_plausible_price_move(100, 10_000, 10, elapsed));
     }
 }
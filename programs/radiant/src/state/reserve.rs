@@ -1,4 +1,18 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+
+use crate::constants::{
+    INDEX_ONE,
+    MAX_LIQUIDATION_BONUS_BPS,
+    MAX_LIQUIDATION_THRESHOLD_BPS,
+    MAX_LTV_BPS,
+    MAX_NEGATIVE_SUPPLY_RATE_BPS,
+    MAX_ORACLE_STALENESS_SLOTS,
+    MAX_RESERVE_FACTOR_BPS,
+    MAX_RESERVE_MIN_HEALTH_FACTOR_BPS,
+    MIN_HEALTH_FACTOR_AFTER_BORROW,
+};
+use crate::math::{mul_div_floor, scale_index_ceil};
 
 /// Per-asset liquidity pool configuration and state
 /// PDA Seeds: ["reserve", lending_market, token_mint]
@@ -29,6 +43,10 @@ pub struct Reserve {
     /// Pyth oracle price feed for this asset
     pub oracle: Pubkey,
 
+    /// Optional oracle converting the main oracle's quote asset to USD
+    /// Set when the main feed is not USD-quoted (e.g. TOKEN/SOL with SOL/USD)
+    pub price_quote_oracle: Option<Pubkey>,
+
     /// Last slot when reserve was refreshed
     pub last_update_slot: u64,
 
@@ -41,8 +59,39 @@ pub struct Reserve {
     /// Current liquidity state
     pub liquidity: ReserveLiquidity,
 
-    /// Reserved space for future upgrades (128 bytes)
-    pub _padding: [u8; 128],
+    /// Slot of the last config update (0 = never updated)
+    pub last_config_update_slot: u64,
+
+    /// Treasury receiving this reserve's collected fees
+    /// None = use the lending market treasury
+    pub fee_treasury: Option<Pubkey>,
+
+    /// Token program that owns the mint and vault, pinned at initialization
+    /// Every token-moving instruction must pass this program
+    pub token_program_id: Pubkey,
+
+    /// How borrow interest accrues, fixed at initialization
+    pub interest_model: InterestModel,
+
+    /// Outstanding borrow principal, excluding accrued interest
+    /// (in native token units). Simple interest accrues on this total.
+    pub total_borrow_principal: u64,
+
+    /// Reserved space for future upgrades (119 bytes)
+    pub _padding: [u8; 119],
+}
+
+/// Interest accrual model for a reserve
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Default, Debug)]
+pub enum InterestModel {
+    /// Debt grows with the borrow index, so accrued interest earns interest
+    #[default]
+    Compound,
+
+    /// Interest accrues on outstanding principal only:
+    /// principal * rate * time / year. The borrow index then grows
+    /// linearly and measures accrued interest per unit of principal.
+    Simple,
 }
 
 /// Configuration parameters for a reserve
@@ -62,16 +111,119 @@ pub struct ReserveConfig {
     /// Maximum borrow limit for this reserve (0 = unlimited)
     pub borrow_limit: u64,
 
+    /// Maximum utilization in BPS that borrows may push the reserve to (0 = no cap)
+    pub max_utilization_bps: u16,
+
+    /// Share of total deposits in BPS kept free for withdrawals (0 = none)
+    /// Borrows cannot use it; withdrawals can, so depositors can always exit
+    /// at least this portion.
+    pub withdrawal_reserve_bps: u16,
+
+    /// Utilization in BPS above which borrows pay a one-time utilization fee
+    pub utilization_fee_threshold_bps: u16,
+
+    /// Utilization fee in BPS of the borrowed amount at 100% utilization,
+    /// scaling linearly from 0 at the threshold (0 = disabled)
+    pub utilization_fee_max_bps: u16,
+
+    /// Maximum amount for a single deposit instruction (0 = unlimited)
+    pub max_single_deposit: u64,
+
+    /// Maximum amount for a single borrow instruction (0 = unlimited)
+    pub max_single_borrow: u64,
+
     /// Whether deposits are enabled
     pub deposits_enabled: bool,
 
     /// Whether borrows are enabled
     pub borrows_enabled: bool,
 
+    /// When borrows are re-enabled, restart accrual from that moment instead
+    /// of charging interest retroactively over the disabled period (opt-in)
+    pub reset_accrual_on_borrow_enable: bool,
+
+    /// Whether this reserve offers flash loans (off by default; intended
+    /// only for deep, stable reserves)
+    pub flash_loans_enabled: bool,
+
+    /// Restrict deposits to whitelisted depositors (permissioned pool).
+    /// Existing positions can still withdraw, borrow and repay.
+    pub permissioned: bool,
+
+    /// Whether liquidators may seize deposits of this reserve (default true).
+    /// Clearing it protects a frozen collateral; liquidators must then seize
+    /// other collateral of the obligation.
+    pub seizable: bool,
+
+    /// Efficiency mode category (0 = none)
+    /// Obligations whose collateral and debt all share the same nonzero
+    /// category use the e-mode LTV and liquidation threshold below
+    pub emode_category: u8,
+
+    /// LTV in BPS applied when the obligation is in this e-mode category
+    pub emode_ltv_bps: u16,
+
+    /// Liquidation threshold in BPS applied when the obligation is in this e-mode category
+    pub emode_liquidation_threshold_bps: u16,
+
+    /// Liquidation bonus in BPS when debt and this collateral share the e-mode
+    /// category (e.g. stable-stable); never exceeds the market bonus
+    pub emode_liquidation_bonus_bps: u16,
+
+    /// Minimum health factor in BPS after borrowing this asset (0 = market default)
+    /// The stricter of this and the market minimum applies
+    pub min_health_factor_for_borrow_bps: u16,
+
+    /// Maximum oracle price age in slots for this reserve (0 = global default)
+    pub max_oracle_staleness_slots: u32,
+
+    /// LTV reduction in BPS applied to collateral deposited within the
+    /// last `initial_borrow_haircut_slots` slots (0 = no haircut)
+    pub initial_borrow_ltv_haircut_bps: u16,
+
+    /// Number of slots after a deposit during which the LTV haircut applies
+    pub initial_borrow_haircut_slots: u64,
+
+    /// Slots after a deposit before it backs borrows (0 = immediately)
+    /// Until then it adds no borrowing power but still counts toward the
+    /// liquidation threshold. Each new deposit restarts the delay.
+    pub collateral_activation_delay_slots: u64,
+
+    /// Protocol fee in BPS on liquidation bonuses when this reserve is seized
+    /// Overrides the market protocol fee when set
+    pub liquidation_protocol_fee_bps: Option<u16>,
+
+    /// Close factor in BPS when this reserve's debt is liquidated
+    /// Overrides the market close factor when set (1-10000)
+    pub close_factor_bps: Option<u16>,
+
+    /// Opt-in negative supply rate in BPS per year (0 = disabled)
+    /// When set, each accrual moves this share of deposits to protocol fees
+    /// and lowers the supply index accordingly, so depositors' balances
+    /// shrink. The index never drops below INDEX_ONE. Risks: deposits can
+    /// lose value, collateral values fall (obligations may become
+    /// liquidatable without any price move), and integrators assuming a
+    /// monotonic supply index will misreport balances. Only settable via
+    /// update_reserve_config and capped at MAX_NEGATIVE_SUPPLY_RATE_BPS.
+    pub negative_supply_rate_bps: u16,
+
     /// Interest rate model configuration
     pub interest_rate_config: InterestRateConfig,
 }
 
+/// Maximum number of extra rate curve kinks above the optimal utilization
+pub const MAX_EXTRA_RATE_KINKS: usize = 2;
+
+/// Extra kink of the interest rate curve above the optimal utilization
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace, Default, Debug)]
+pub struct RateKink {
+    /// Utilization in BPS where this segment starts (0 = unused)
+    pub utilization_bps: u16,
+
+    /// Rate increase across this segment, up to the next kink or 100% (BPS)
+    pub slope_bps: u16,
+}
+
 /// Kinked interest rate model configuration
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace, Default)]
 pub struct InterestRateConfig {
@@ -94,6 +246,19 @@ pub struct InterestRateConfig {
     /// Reserve factor in BPS (protocol's cut of interest)
     /// e.g., 1000 = 10%
     pub reserve_factor_bps: u16,
+
+    /// Utilization in BPS below which the supply subsidy applies
+    pub low_util_threshold_bps: u16,
+
+    /// Extra supply rate in BPS (annualized) paid to depositors below the
+    /// threshold, funded from accumulated protocol fees (0 = disabled)
+    pub low_util_supply_subsidy_bps: u16,
+
+    /// Optional kinks above the optimal utilization, in ascending order
+    /// With none set, slope2 spans optimal to 100% (the two-slope model);
+    /// otherwise slope2 ends at the first kink and each kink's slope spans
+    /// up to the next kink or 100%
+    pub extra_kinks: [RateKink; MAX_EXTRA_RATE_KINKS],
 }
 
 /// Current liquidity state of a reserve
@@ -123,19 +288,98 @@ pub struct ReserveLiquidity {
 
     /// Current supply rate in BPS (annualized)
     pub current_supply_rate_bps: u64,
+
+    /// Seed liquidity deposited by the authority at initialization
+    /// Included in total_deposits but not owned by any obligation, so it can
+    /// never be withdrawn. Protects the supply index against first-depositor
+    /// inflation attacks.
+    pub locked_seed_deposit: u64,
+
+    /// Number of obligations with a deposit in this reserve
+    pub depositor_count: u32,
+
+    /// Number of obligations with a borrow from this reserve
+    pub borrower_count: u32,
+
+    /// Total interest accrued by borrowers over the reserve's lifetime
+    /// (in native token units)
+    pub lifetime_borrow_interest_paid: u128,
+
+    /// Protocol-owned liquidity seeded from the treasury, as of the snapshot
+    /// below. Included in total_deposits, earns supply interest, and is not
+    /// tied to any obligation so it can never be liquidated.
+    pub protocol_owned_deposits: u64,
+
+    /// Supply index when protocol_owned_deposits was last updated
+    pub protocol_deposit_index_snapshot: u128,
+
+    /// Timestamp of the last user borrow (0 = never borrowed)
+    pub last_borrow_timestamp: i64,
+
+    /// Timestamp of the last user deposit (0 = never deposited)
+    pub last_deposit_timestamp: i64,
+
+    /// Debt of this reserve repaid by liquidators over the reserve's lifetime
+    /// (in native token units)
+    pub lifetime_liquidation_repaid: u128,
+
+    /// Collateral of this reserve seized by liquidators over the reserve's
+    /// lifetime, including protocol fees (in native token units)
+    pub lifetime_liquidation_seized: u128,
+
+    /// Number of liquidations that repaid or seized this reserve
+    pub liquidation_count: u32,
+
+    /// Borrow interest accrued but below one native unit, scaled by INDEX_ONE
+    /// Carried into the next accrual so total_borrows rounds down without loss
+    pub borrow_interest_remainder: u128,
 }
 
 impl Reserve {
     pub const SEED_PREFIX: &'static [u8] = b"reserve";
 
+    /// Layout version written by this program (1 = legacy layout, see `legacy`)
+    pub const CURRENT_VERSION: u8 = 2;
+
     /// Calculate current utilization rate in BPS
+    ///
+    /// Borrows without deposits is a broken accounting state; it reads as
+    /// full utilization (10000) so rates and monitoring reflect it instead
+    /// of collapsing to zero.
     pub fn calculate_utilization_bps(&self) -> u64 {
         if self.liquidity.total_deposits == 0 {
-            return 0;
+            return if self.has_borrows_without_deposits() { 10000 } else { 0 };
         }
 
         // utilization = borrows / deposits * 10000
-        ((self.liquidity.total_borrows as u128 * 10000) / self.liquidity.total_deposits as u128) as u64
+        let utilization = (self.liquidity.total_borrows as u128 * 10000) / self.liquidity.total_deposits as u128;
+        u64::try_from(utilization).unwrap_or(u64::MAX)
+    }
+
+    /// Amount borrow interest accrues on: total debt when compounding,
+    /// outstanding principal under simple interest
+    pub fn interest_bearing_borrows(&self) -> u64 {
+        match self.interest_model {
+            InterestModel::Compound => self.liquidity.total_borrows,
+            InterestModel::Simple => self.total_borrow_principal,
+        }
+    }
+
+    /// Borrow index growth for an accrual with the given rate * time factor
+    ///
+    /// Compound: the index grows in proportion to itself (rounded up).
+    /// Simple: the index grows by the factor itself, so it sums rate * time
+    /// across accruals.
+    pub fn borrow_index_growth(&self, factor: u128) -> Option<u128> {
+        match self.interest_model {
+            InterestModel::Compound => scale_index_ceil(self.liquidity.cumulative_borrow_index, factor),
+            InterestModel::Simple => Some(factor),
+        }
+    }
+
+    /// Check for the inconsistent state of outstanding borrows with zero deposits
+    pub fn has_borrows_without_deposits(&self) -> bool {
+        self.liquidity.total_deposits == 0 && self.liquidity.total_borrows > 0
     }
 
     /// Get available liquidity for borrowing
@@ -145,6 +389,161 @@ impl Reserve {
             .saturating_sub(self.liquidity.total_borrows)
     }
 
+    /// Get the liquidity borrows may use: available liquidity minus the
+    /// withdrawal reserve carved out of total deposits
+    pub fn borrowable_liquidity(&self) -> u64 {
+        let withdrawal_reserve = mul_div_floor(
+            self.liquidity.total_deposits as u128,
+            self.config.withdrawal_reserve_bps as u128,
+            10000,
+        )
+        .unwrap_or(0) as u64;
+        self.available_liquidity().saturating_sub(withdrawal_reserve)
+    }
+
+    /// Get the maximum amount that can be borrowed from this reserve right now
+    ///
+    /// The minimum of borrowable liquidity, the remaining borrow limit and the
+    /// headroom below the utilization cap.
+    pub fn max_borrowable(&self) -> u64 {
+        let mut max_borrowable = self.borrowable_liquidity();
+
+        if self.config.borrow_limit > 0 {
+            let borrow_limit_headroom = self.config.borrow_limit
+                .saturating_sub(self.liquidity.total_borrows);
+            max_borrowable = max_borrowable.min(borrow_limit_headroom);
+        }
+
+        if self.config.max_utilization_bps > 0 {
+            // borrows <= deposits * max_utilization / 10000
+            let max_total_borrows = mul_div_floor(
+                self.liquidity.total_deposits as u128,
+                self.config.max_utilization_bps as u128,
+                10000,
+            )
+            .unwrap_or(0) as u64;
+            let utilization_headroom = max_total_borrows
+                .saturating_sub(self.liquidity.total_borrows);
+            max_borrowable = max_borrowable.min(utilization_headroom);
+        }
+
+        max_borrowable
+    }
+
+    /// Get the utilization ceiling implied by the reserve's caps, in BPS
+    ///
+    /// The tightest of full utilization less the withdrawal reserve, the
+    /// utilization cap and the borrow limit relative to current deposits.
+    /// Rates above this point on the curve are unreachable through new
+    /// borrows. Returns 0 with no deposits.
+    pub fn effective_max_utilization_bps(&self) -> u64 {
+        let total_deposits = self.liquidity.total_deposits;
+        if total_deposits == 0 {
+            return 0;
+        }
+
+        let mut max_utilization_bps: u64 = 10000u64.saturating_sub(self.config.withdrawal_reserve_bps as u64);
+
+        if self.config.max_utilization_bps > 0 {
+            max_utilization_bps = max_utilization_bps.min(self.config.max_utilization_bps as u64);
+        }
+
+        if self.config.borrow_limit > 0 {
+            // borrow_limit / deposits * 10000
+            let borrow_limit_bps = mul_div_floor(
+                self.config.borrow_limit as u128,
+                10000,
+                total_deposits as u128,
+            )
+            .unwrap_or(u128::MAX);
+            max_utilization_bps = max_utilization_bps.min(borrow_limit_bps.min(10000) as u64);
+        }
+
+        max_utilization_bps
+    }
+
+    /// Get supply index growth as a fraction of borrow index growth (in BPS)
+    ///
+    /// Supply interest is borrow interest minus the reserve factor, spread
+    /// over all deposits, so supply growth is at most borrow growth scaled by
+    /// utilization and (1 - reserve factor). A ratio at or above 10000 means
+    /// depositors have been credited more than borrowers paid, indicating an
+    /// accounting bug or manipulation. Returns 0 before any borrow interest.
+    pub fn index_divergence_ratio(&self) -> u64 {
+        let borrow_growth = self.liquidity.cumulative_borrow_index.saturating_sub(INDEX_ONE);
+        if borrow_growth == 0 {
+            return 0;
+        }
+
+        let supply_growth = self.liquidity.cumulative_supply_index.saturating_sub(INDEX_ONE);
+        mul_div_floor(supply_growth, 10000, borrow_growth)
+            .and_then(|ratio| u64::try_from(ratio).ok())
+            .unwrap_or(u64::MAX)
+    }
+
+    /// Get the vault balance implied by tracked accounting
+    ///
+    /// = deposits (including credited interest) + unclaimed protocol fees
+    ///   - tokens currently lent out
+    pub fn expected_vault_balance(&self) -> u64 {
+        self.liquidity
+            .total_deposits
+            .saturating_add(self.liquidity.accumulated_protocol_fees)
+            .saturating_sub(self.liquidity.total_borrows)
+    }
+
+    /// Get protocol-owned deposits including supply interest since the snapshot
+    ///
+    /// Rounds down like any other credited deposit.
+    pub fn current_protocol_owned_deposits(&self) -> Option<u64> {
+        let deposits = self.liquidity.protocol_owned_deposits;
+        let snapshot = self.liquidity.protocol_deposit_index_snapshot;
+        if deposits == 0 || snapshot == 0 {
+            return Some(deposits);
+        }
+        let current = mul_div_floor(
+            deposits as u128,
+            self.liquidity.cumulative_supply_index,
+            snapshot,
+        )?;
+        u64::try_from(current).ok()
+    }
+
+    /// Get the treasury that receives this reserve's collected fees
+    pub fn fee_treasury_or(&self, market_treasury: Pubkey) -> Pubkey {
+        self.fee_treasury.unwrap_or(market_treasury)
+    }
+
+    /// Re-assert the invariants of the reserve's token accounts before moving funds
+    ///
+    /// The vault must be the reserve's vault, hold the reserve's mint and be
+    /// owned by the reserve PDA. The fee receiver, when provided, must be the
+    /// reserve's fee receiver, hold the same mint, be owned by the lending
+    /// market, and never be the vault itself.
+    pub fn validate_accounts(
+        &self,
+        reserve_key: &Pubkey,
+        vault: &Account<TokenAccount>,
+        fee_receiver: Option<&Account<TokenAccount>>,
+    ) -> bool {
+        let vault_valid = vault.key() == self.token_vault
+            && vault.mint == self.token_mint
+            && vault.owner == *reserve_key
+            && self.fee_receiver != self.token_vault;
+
+        let fee_receiver_valid = match fee_receiver {
+            Some(fee_receiver) => {
+                fee_receiver.key() == self.fee_receiver
+                    && fee_receiver.key() != vault.key()
+                    && fee_receiver.mint == self.token_mint
+                    && fee_receiver.owner == self.lending_market
+            }
+            None => true,
+        };
+
+        vault_valid && fee_receiver_valid
+    }
+
     /// Check if reserve needs refresh (stale data)
     pub fn is_stale(&self, current_slot: u64, max_age_slots: u64) -> bool {
         current_slot > self.last_update_slot + max_age_slots
@@ -153,15 +552,191 @@ impl Reserve {
     /// Validate LTV is less than liquidation threshold
     pub fn validate_config(config: &ReserveConfig) -> bool {
         config.ltv_bps < config.liquidation_threshold_bps
-            && config.liquidation_threshold_bps <= 10000
-            && config.interest_rate_config.optimal_utilization_bps <= 10000
-            && config.interest_rate_config.reserve_factor_bps <= 10000
+            && config.ltv_bps <= MAX_LTV_BPS
+            && config.liquidation_threshold_bps <= MAX_LIQUIDATION_THRESHOLD_BPS
+            && config.interest_rate_config.validate_rate_curve()
+            && config.max_utilization_bps <= 10000
+            && config.withdrawal_reserve_bps <= 10000
+            && config.utilization_fee_threshold_bps <= 10000
+            && config.utilization_fee_max_bps <= 10000
+            && config.interest_rate_config.reserve_factor_bps <= MAX_RESERVE_FACTOR_BPS
+            && config.interest_rate_config.low_util_threshold_bps <= 10000
+            && config.liquidation_protocol_fee_bps.unwrap_or(0) <= 10000
+            && (1..=10000).contains(&config.close_factor_bps.unwrap_or(10000))
+            && config.initial_borrow_ltv_haircut_bps <= 10000
+            && config.negative_supply_rate_bps <= MAX_NEGATIVE_SUPPLY_RATE_BPS
+            && Self::validate_min_health_factor_for_borrow(config.min_health_factor_for_borrow_bps)
+            && Self::validate_limits(config.deposit_limit, config.borrow_limit)
+            && Self::validate_emode_config(config)
+    }
+
+    /// Validate native limits: when both are set, borrows cannot exceed deposits
+    pub fn validate_limits(deposit_limit: u64, borrow_limit: u64) -> bool {
+        deposit_limit == 0 || borrow_limit == 0 || borrow_limit <= deposit_limit
+    }
+
+    /// Validate a reserve minimum health factor to borrow (0 = unset, else 1.0-5.0)
+    pub fn validate_min_health_factor_for_borrow(min_health_factor_bps: u16) -> bool {
+        min_health_factor_bps == 0
+            || (10000..=MAX_RESERVE_MIN_HEALTH_FACTOR_BPS).contains(&min_health_factor_bps)
+    }
+
+    /// Validate e-mode parameters (only checked when a category is set)
+    /// E-mode must be at least as generous as the normal parameters
+    pub fn validate_emode_config(config: &ReserveConfig) -> bool {
+        config.emode_category == 0
+            || (config.emode_ltv_bps < config.emode_liquidation_threshold_bps
+                && config.emode_liquidation_threshold_bps <= 10000
+                && config.emode_ltv_bps >= config.ltv_bps
+                && config.emode_liquidation_threshold_bps >= config.liquidation_threshold_bps
+                && config.emode_liquidation_bonus_bps <= MAX_LIQUIDATION_BONUS_BPS)
+    }
+}
+
+impl ReserveConfig {
+    /// Get the liquidation protocol fee in BPS, falling back to the market fee
+    pub fn effective_liquidation_protocol_fee_bps(&self, market_protocol_fee_bps: u16) -> u16 {
+        self.liquidation_protocol_fee_bps.unwrap_or(market_protocol_fee_bps)
+    }
+
+    /// Get the close factor in BPS for this debt, falling back to the market close factor
+    pub fn effective_close_factor_bps(&self, market_close_factor_bps: u16) -> u16 {
+        self.close_factor_bps.unwrap_or(market_close_factor_bps)
+    }
+
+    /// Get (LTV, liquidation threshold) in BPS, using e-mode parameters when active
+    pub fn risk_params(&self, emode_active: bool) -> (u16, u16) {
+        if emode_active && self.emode_category != 0 {
+            (self.emode_ltv_bps, self.emode_liquidation_threshold_bps)
+        } else {
+            (self.ltv_bps, self.liquidation_threshold_bps)
+        }
+    }
+
+    /// Check whether two reserves share a nonzero e-mode category
+    pub fn shares_emode_category(&self, other: &ReserveConfig) -> bool {
+        self.emode_category != 0 && self.emode_category == other.emode_category
+    }
+
+    /// Get the liquidation bonus in BPS when seizing this collateral for `debt_config` debt
+    /// Same-category liquidations use the (lower) e-mode bonus
+    pub fn liquidation_bonus_bps(&self, debt_config: &ReserveConfig, market_bonus_bps: u16) -> u16 {
+        if self.shares_emode_category(debt_config) {
+            self.emode_liquidation_bonus_bps.min(market_bonus_bps)
+        } else {
+            market_bonus_bps
+        }
+    }
+
+    /// Get the one-time utilization fee in BPS for a borrow leaving the
+    /// reserve at `utilization_after_bps`
+    ///
+    /// fee = max_fee * (utilization - threshold) / (10000 - threshold)
+    pub fn utilization_fee_bps(&self, utilization_after_bps: u64) -> u64 {
+        let threshold = self.utilization_fee_threshold_bps as u64;
+        if self.utilization_fee_max_bps == 0 || utilization_after_bps <= threshold {
+            return 0;
+        }
+
+        let excess = utilization_after_bps.min(10000) - threshold;
+        let range = 10000 - threshold;
+        excess * self.utilization_fee_max_bps as u64 / range
+    }
+
+    /// Get the minimum health factor after borrowing this asset
+    /// (the stricter of the reserve override and the market minimum)
+    pub fn min_health_factor_for_borrow(&self) -> u64 {
+        (self.min_health_factor_for_borrow_bps as u64).max(MIN_HEALTH_FACTOR_AFTER_BORROW)
+    }
+
+    /// Get the oracle staleness tolerance in slots, falling back to the global default
+    pub fn oracle_staleness_slots(&self) -> u64 {
+        if self.max_oracle_staleness_slots == 0 {
+            MAX_ORACLE_STALENESS_SLOTS
+        } else {
+            self.max_oracle_staleness_slots as u64
+        }
+    }
+
+    /// Apply the initial borrow haircut to an LTV for collateral deposited at `last_deposit_slot`
+    /// The haircut ends once `initial_borrow_haircut_slots` have passed
+    pub fn apply_initial_borrow_haircut(&self, ltv_bps: u16, last_deposit_slot: u64, current_slot: u64) -> u16 {
+        if current_slot.saturating_sub(last_deposit_slot) < self.initial_borrow_haircut_slots {
+            ltv_bps.saturating_sub(self.initial_borrow_ltv_haircut_bps)
+        } else {
+            ltv_bps
+        }
+    }
+
+    /// Check whether collateral deposited at `last_deposit_slot` may back borrows yet
+    pub fn is_collateral_active(&self, last_deposit_slot: u64, current_slot: u64) -> bool {
+        current_slot.saturating_sub(last_deposit_slot) >= self.collateral_activation_delay_slots
+    }
+
+    /// Slots after a deposit until it backs borrows at the full LTV
+    pub fn collateral_maturity_slots(&self) -> u64 {
+        self.initial_borrow_haircut_slots.max(self.collateral_activation_delay_slots)
     }
 }
 
 impl InterestRateConfig {
+    /// Validate the kinked rate curve
+    ///
+    /// With unsigned slopes each segment is non-decreasing, and both segments
+    /// meet at base + slope1 at the kink. The curve is only discontinuous when
+    /// the kink sits at 0% utilization with a nonzero slope1 (the rate would
+    /// jump straight from base to base + slope1), so that is rejected. The
+    /// endpoints are also compared so the full range stays non-decreasing.
+    ///
+    /// Extra kinks must sit strictly between the optimal utilization and
+    /// 100% in ascending order, with unused entries only at the end.
+    pub fn validate_rate_curve(&self) -> bool {
+        if self.optimal_utilization_bps > 10000 {
+            return false;
+        }
+
+        let mut previous_kink_bps = self.optimal_utilization_bps;
+        let mut unused_seen = false;
+        for kink in self.extra_kinks.iter() {
+            if kink.utilization_bps == 0 {
+                unused_seen = true;
+                continue;
+            }
+            if unused_seen || kink.utilization_bps <= previous_kink_bps || kink.utilization_bps >= 10000 {
+                return false;
+            }
+            previous_kink_bps = kink.utilization_bps;
+        }
+
+        if self.optimal_utilization_bps == 0 && self.slope1_bps != 0 {
+            return false;
+        }
+
+        let rate_at_zero = self.calculate_borrow_rate(0);
+        let rate_at_kink = self.rate_at_kink();
+        let rate_at_full = self.rate_at_full();
+
+        rate_at_zero <= rate_at_kink && rate_at_kink <= rate_at_full
+    }
+
+    /// Get the borrow rate in BPS at the optimal utilization kink
+    ///
+    /// Equals `calculate_borrow_rate(optimal_utilization_bps)` for curves
+    /// passing `validate_rate_curve`.
+    pub fn rate_at_kink(&self) -> u64 {
+        self.base_rate_bps as u64 + self.slope1_bps as u64
+    }
+
+    /// Get the borrow rate in BPS at 100% utilization
+    pub fn rate_at_full(&self) -> u64 {
+        self.calculate_borrow_rate(10000)
+    }
+
     /// Calculate borrow rate based on utilization
     /// Returns rate in BPS (annualized)
+    ///
+    /// Invariant: for configs passing `validate_rate_curve`, the rate is
+    /// continuous at the kink and non-decreasing over 0-100% utilization.
     pub fn calculate_borrow_rate(&self, utilization_bps: u64) -> u64 {
         if utilization_bps <= self.optimal_utilization_bps as u64 {
             // Below optimal: base + (util / optimal) * slope1
@@ -172,26 +747,298 @@ impl InterestRateConfig {
             };
             self.base_rate_bps as u64 + slope_rate
         } else {
-            // Above optimal: base + slope1 + ((util - optimal) / (1 - optimal)) * slope2
-            let excess_utilization = utilization_bps - self.optimal_utilization_bps as u64;
-            let remaining_utilization = 10000 - self.optimal_utilization_bps as u64;
+            // Above optimal: base + slope1 + ((util - start) / (end - start)) * slope
+            // for the segment containing util, plus the full slope of every
+            // segment below it. Segments run optimal -> kinks -> 100%.
+            let mut rate = self.base_rate_bps as u64 + self.slope1_bps as u64;
+            let mut segment_start = self.optimal_utilization_bps as u64;
+            let mut segment_slope = self.slope2_bps as u64;
 
-            let steep_rate = if remaining_utilization == 0 {
-                self.slope2_bps as u64
-            } else {
-                (excess_utilization * self.slope2_bps as u64) / remaining_utilization
-            };
+            for kink in self.extra_kinks.iter().filter(|kink| kink.utilization_bps > 0) {
+                let segment_end = kink.utilization_bps as u64;
+                if utilization_bps <= segment_end {
+                    return rate
+                        + segment_rate(utilization_bps - segment_start, segment_end - segment_start, segment_slope);
+                }
+                rate += segment_slope;
+                segment_start = segment_end;
+                segment_slope = kink.slope_bps as u64;
+            }
 
-            self.base_rate_bps as u64 + self.slope1_bps as u64 + steep_rate
+            rate + segment_rate(utilization_bps - segment_start, 10000 - segment_start, segment_slope)
         }
     }
 
     /// Calculate supply rate based on borrow rate and utilization
-    /// supply_rate = borrow_rate * utilization * (1 - reserve_factor)
+    /// supply_rate = borrow_rate * utilization * (1 - reserve_factor) + subsidy
+    ///
+    /// Never exceeds borrow_rate * utilization: depositors cannot earn more
+    /// than borrowers pay. The subsidy is only paid while protocol fees can
+    /// fund it, so the realized rate may be lower when fees run out.
     pub fn calculate_supply_rate(&self, borrow_rate_bps: u64, utilization_bps: u64) -> u64 {
         // supply_rate = borrow_rate * utilization * (1 - reserve_factor) / 10000
         let gross_supply_rate = (borrow_rate_bps * utilization_bps) / 10000;
         let protocol_cut = (gross_supply_rate * self.reserve_factor_bps as u64) / 10000;
-        gross_supply_rate - protocol_cut
+        gross_supply_rate - protocol_cut + self.effective_supply_subsidy_rate(borrow_rate_bps, utilization_bps)
+    }
+
+    /// Get the supply subsidy rate in BPS after clamping, so the supply rate
+    /// stays at or below borrow_rate * utilization
+    ///
+    /// At most the reserve factor's cut of the gross supply rate; a larger
+    /// configured subsidy is clamped rather than rejected.
+    pub fn effective_supply_subsidy_rate(&self, borrow_rate_bps: u64, utilization_bps: u64) -> u64 {
+        let gross_supply_rate = (borrow_rate_bps * utilization_bps) / 10000;
+        let protocol_cut = (gross_supply_rate * self.reserve_factor_bps as u64) / 10000;
+        self.supply_subsidy_rate(utilization_bps).min(protocol_cut)
+    }
+
+    /// Get the low utilization supply subsidy rate in BPS (annualized)
+    pub fn supply_subsidy_rate(&self, utilization_bps: u64) -> u64 {
+        if utilization_bps < self.low_util_threshold_bps as u64 {
+            self.low_util_supply_subsidy_bps as u64
+        } else {
+            0
+        }
+    }
+}
+
+/// Rate added within one segment of the curve, interpolated linearly
+/// A zero-width segment (kink at 100%) contributes its full slope
+fn segment_rate(excess_bps: u64, width_bps: u64, slope_bps: u64) -> u64 {
+    (excess_bps * slope_bps).checked_div(width_bps).unwrap_or(slope_bps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn curve(extra_kinks: [RateKink; MAX_EXTRA_RATE_KINKS]) -> InterestRateConfig {
+        InterestRateConfig {
+            optimal_utilization_bps: 8000,
+            base_rate_bps: 0,
+            slope1_bps: 400,
+            slope2_bps: 1000,
+            extra_kinks,
+            ..Default::default()
+        }
+    }
+
+    fn kink(utilization_bps: u16, slope_bps: u16) -> RateKink {
+        RateKink { utilization_bps, slope_bps }
+    }
+
+    #[test]
+    fn oversized_subsidy_is_clamped_to_the_protocol_cut() {
+        let config = InterestRateConfig {
+            reserve_factor_bps: 1000,
+            low_util_threshold_bps: 5000,
+            low_util_supply_subsidy_bps: 10000,
+            ..Default::default()
+        };
+        // 10% borrow rate at 40% utilization: 4% gross, 0.4% protocol cut
+        assert_eq!(config.effective_supply_subsidy_rate(1000, 4000), 40);
+        assert_eq!(config.calculate_supply_rate(1000, 4000), 400);
+        // Above the threshold no subsidy applies
+        assert_eq!(config.calculate_supply_rate(1000, 6000), 540);
+    }
+
+    /// A reserve with `total_deposits` and the given caps, everything else zeroed
+    fn capped_reserve(total_deposits: u64, withdrawal_reserve_bps: u16, max_utilization_bps: u16, borrow_limit: u64) -> Reserve {
+        let data = vec![0u8; Reserve::INIT_SPACE];
+        let mut reserve = Reserve::deserialize(&mut data.as_slice()).unwrap();
+        reserve.liquidity.total_deposits = total_deposits;
+        reserve.config.withdrawal_reserve_bps = withdrawal_reserve_bps;
+        reserve.config.max_utilization_bps = max_utilization_bps;
+        reserve.config.borrow_limit = borrow_limit;
+        reserve
+    }
+
+    #[test]
+    fn effective_max_utilization_takes_the_tightest_cap() {
+        assert_eq!(capped_reserve(1_000_000, 0, 0, 0).effective_max_utilization_bps(), 10000);
+        assert_eq!(capped_reserve(1_000_000, 500, 0, 0).effective_max_utilization_bps(), 9500);
+        assert_eq!(capped_reserve(1_000_000, 500, 9000, 0).effective_max_utilization_bps(), 9000);
+        assert_eq!(capped_reserve(1_000_000, 500, 9000, 600_000).effective_max_utilization_bps(), 6000);
+        // A borrow limit above deposits never raises the ceiling
+        assert_eq!(capped_reserve(1_000_000, 0, 0, 5_000_000).effective_max_utilization_bps(), 10000);
+        assert_eq!(capped_reserve(0, 0, 9000, 600_000).effective_max_utilization_bps(), 0);
+    }
+
+    #[test]
+    fn two_slope_curve_without_extra_kinks() {
+        let config = curve([RateKink::default(); MAX_EXTRA_RATE_KINKS]);
+        assert_eq!(config.calculate_borrow_rate(0), 0);
+        assert_eq!(config.calculate_borrow_rate(4000), 200);
+        assert_eq!(config.calculate_borrow_rate(8000), 400);
+        assert_eq!(config.calculate_borrow_rate(9000), 900);
+        assert_eq!(config.calculate_borrow_rate(10000), 1400);
+        assert!(config.validate_rate_curve());
+    }
+
+    #[test]
+    fn extra_kinks_add_steeper_segments() {
+        let config = curve([kink(9000, 5000), kink(9500, 20000)]);
+        assert_eq!(config.calculate_borrow_rate(8000), 400);
+        assert_eq!(config.calculate_borrow_rate(8500), 900);
+        assert_eq!(config.calculate_borrow_rate(9000), 1400);
+        assert_eq!(config.calculate_borrow_rate(9250), 3900);
+        assert_eq!(config.calculate_borrow_rate(9500), 6400);
+        assert_eq!(config.calculate_borrow_rate(9750), 16400);
+        assert_eq!(config.calculate_borrow_rate(10000), 26400);
+        assert_eq!(config.rate_at_kink(), 400);
+        assert_eq!(config.rate_at_full(), 26400);
+        assert!(config.validate_rate_curve());
+    }
+
+    #[test]
+    fn single_extra_kink_leaves_the_rest_unused() {
+        let config = curve([kink(9000, 5000), RateKink::default()]);
+        assert_eq!(config.calculate_borrow_rate(9000), 1400);
+        assert_eq!(config.calculate_borrow_rate(9500), 3900);
+        assert_eq!(config.calculate_borrow_rate(10000), 6400);
+    }
+
+    #[test]
+    fn multi_kink_curve_is_continuous_and_non_decreasing() {
+        let config = curve([kink(8500, 3000), kink(9900, 30000)]);
+        let mut previous = config.calculate_borrow_rate(0);
+        for utilization in 1..=10000 {
+            let rate = config.calculate_borrow_rate(utilization);
+            assert!(rate >= previous, "rate fell at {} bps", utilization);
+            // No jump larger than the steepest segment's per-bps increase (plus rounding)
+            assert!(rate - previous <= 30000 / 100 + 1, "rate jumped at {} bps", utilization);
+            previous = rate;
+        }
+    }
+
+    #[test]
+    fn invalid_kinks_are_rejected() {
+        // Not above the previous kink
+        assert!(!curve([kink(8000, 1000), RateKink::default()]).validate_rate_curve());
+        assert!(!curve([kink(9500, 1000), kink(9000, 1000)]).validate_rate_curve());
+        // At or beyond 100%
+        assert!(!curve([kink(10000, 1000), RateKink::default()]).validate_rate_curve());
+        // A used kink after an unused one
+        assert!(!curve([RateKink::default(), kink(9000, 1000)]).validate_rate_curve());
+    }
+
+    #[test]
+    fn zero_width_segment_contributes_its_full_slope() {
+        assert_eq!(segment_rate(0, 0, 700), 700);
+        assert_eq!(segment_rate(250, 500, 700), 350);
+    }
+
+    #[test]
+    fn kink_at_zero_utilization_requires_a_flat_first_segment() {
+        let mut config = curve([RateKink::default(); MAX_EXTRA_RATE_KINKS]);
+        config.optimal_utilization_bps = 0;
+        assert!(!config.validate_rate_curve());
+
+        config.slope1_bps = 0;
+        assert!(config.validate_rate_curve());
+        assert_eq!(config.calculate_borrow_rate(0), 0);
+        assert_eq!(config.calculate_borrow_rate(5000), 500);
+    }
+
+    #[test]
+    fn kink_beyond_full_utilization_is_rejected() {
+        let mut config = curve([RateKink::default(); MAX_EXTRA_RATE_KINKS]);
+        config.optimal_utilization_bps = 10001;
+        assert!(!config.validate_rate_curve());
+
+        // A kink at 100% leaves slope2 unreachable but is valid
+        config.optimal_utilization_bps = 10000;
+        assert!(config.validate_rate_curve());
+        assert_eq!(config.calculate_borrow_rate(10000), 400);
+    }
+
+    #[test]
+    fn flat_curve_is_valid() {
+        let config = InterestRateConfig {
+            optimal_utilization_bps: 8000,
+            base_rate_bps: 300,
+            ..Default::default()
+        };
+        assert!(config.validate_rate_curve());
+        assert_eq!(config.calculate_borrow_rate(0), 300);
+        assert_eq!(config.calculate_borrow_rate(10000), 300);
+    }
+
+    proptest! {
+        #[test]
+        fn accepted_curves_never_decrease(
+            optimal_utilization_bps in 0u16..=10000,
+            base_rate_bps in any::<u16>(),
+            slope1_bps in any::<u16>(),
+            slope2_bps in any::<u16>(),
+            kink_utilization_bps in 0u16..10000,
+            kink_slope_bps in any::<u16>(),
+        ) {
+            let mut extra_kinks = [RateKink::default(); MAX_EXTRA_RATE_KINKS];
+            extra_kinks[0] = kink(kink_utilization_bps, kink_slope_bps);
+            let config = InterestRateConfig {
+                optimal_utilization_bps,
+                base_rate_bps,
+                slope1_bps,
+                slope2_bps,
+                extra_kinks,
+                ..Default::default()
+            };
+            prop_assume!(config.validate_rate_curve());
+
+            let mut previous = config.calculate_borrow_rate(0);
+            for utilization in (0..=10000).step_by(7).chain([10000]) {
+                let rate = config.calculate_borrow_rate(utilization);
+                prop_assert!(rate >= previous, "rate fell at {} bps", utilization);
+                previous = rate;
+            }
+        }
+
+        #[test]
+        fn key_points_match_the_rate_curve(
+            optimal_utilization_bps in 1u16..=10000,
+            base_rate_bps in any::<u16>(),
+            slope1_bps in any::<u16>(),
+            slope2_bps in any::<u16>(),
+        ) {
+            let config = InterestRateConfig {
+                optimal_utilization_bps,
+                base_rate_bps,
+                slope1_bps,
+                slope2_bps,
+                ..Default::default()
+            };
+            prop_assert!(config.validate_rate_curve());
+            prop_assert_eq!(config.rate_at_kink(), config.calculate_borrow_rate(optimal_utilization_bps as u64));
+            prop_assert_eq!(config.rate_at_full(), config.calculate_borrow_rate(10000));
+
+            // Neither neighbouring step jumps by more than one bps of its segment
+            let below = config.calculate_borrow_rate(optimal_utilization_bps as u64 - 1);
+            prop_assert!(config.rate_at_kink() - below <= slope1_bps as u64 / optimal_utilization_bps as u64 + 1);
+            if optimal_utilization_bps < 10000 {
+                let above = config.calculate_borrow_rate(optimal_utilization_bps as u64 + 1);
+                prop_assert!(above - config.rate_at_kink() <= slope2_bps as u64 / (10000 - optimal_utilization_bps as u64) + 1);
+            }
+        }
+
+        #[test]
+        fn supply_rate_never_exceeds_what_borrowers_pay(
+            borrow_rate_bps in 0u64..1_000_000,
+            utilization_bps in 0u64..=10000,
+            reserve_factor_bps in 0u16..=MAX_RESERVE_FACTOR_BPS,
+            low_util_threshold_bps in 0u16..=10000,
+            low_util_supply_subsidy_bps in any::<u16>(),
+        ) {
+            let config = InterestRateConfig {
+                reserve_factor_bps,
+                low_util_threshold_bps,
+                low_util_supply_subsidy_bps,
+                ..Default::default()
+            };
+            let supply_rate = config.calculate_supply_rate(borrow_rate_bps, utilization_bps);
+            prop_assert!(supply_rate <= borrow_rate_bps * utilization_bps / 10000);
+        }
     }
 }
@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+
+/// Allow-list entry for a permissioned reserve
+/// The account's existence grants the depositor access; closing it revokes it.
+/// PDA Seeds: ["whitelisted_depositor", reserve, depositor]
+#[account]
+#[derive(InitSpace)]
+pub struct WhitelistedDepositor {
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+
+    /// The reserve this entry applies to
+    pub reserve: Pubkey,
+
+    /// The whitelisted depositor
+    pub depositor: Pubkey,
+
+    /// Slot when the depositor was whitelisted
+    pub added_slot: u64,
+}
+
+impl WhitelistedDepositor {
+    pub const SEED_PREFIX: &'static [u8] = b"whitelisted_depositor";
+}
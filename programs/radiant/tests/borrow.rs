@@ -0,0 +1,575 @@
+mod common;
+
+use anchor_lang::prelude::ProgramError;
+use common::*;
+use radiant::events::RiskyBorrow;
+use radiant::instructions::{
+    BorrowError, InitializeReserveParams, UpdateConfigError, UpdateLendingMarketError, UpdateLendingMarketParams,
+    UpdateReserveConfigParams,
+};
+use radiant::state::Obligation;
+
+/// USDC reserve charging up to 10% above 50% utilization, with 1000 USDC supplied
+fn usdc_with_utilization_fee(env: &mut TestEnv) -> TestReserve {
+    let usdc = add_usdc(env);
+    env.update_reserve_config(&usdc, UpdateReserveConfigParams {
+        utilization_fee_threshold_bps: Some(5000),
+        utilization_fee_max_bps: Some(1000),
+        ..update_reserve_params()
+    })
+    .unwrap();
+    supply(env, &usdc, 1_000_000_000);
+    usdc
+}
+
+/// SOL reserve whose collateral borrows at 25% less LTV for 100 slots after a deposit
+fn sol_with_initial_haircut(env: &mut TestEnv) -> TestReserve {
+    env.add_reserve_with(9, 100_000_000, InitializeReserveParams {
+        initial_borrow_ltv_haircut_bps: Some(2500),
+        initial_borrow_haircut_slots: Some(100),
+        ..reserve_params(7500, 8000)
+    })
+    .unwrap()
+}
+
+#[test]
+fn borrow_after_withdraw_requires_refresh() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    supply(&mut env, &usdc, 1_000_000_000_000);
+
+    // 10 SOL at $100 backs $750 of borrowing
+    let borrower = borrower_with_collateral(&mut env, &sol, 10_000_000_000);
+    env.borrow(&borrower, &usdc, 100_000_000).unwrap();
+    assert_eq!(env.balance_of(&borrower, &usdc), 100_000_000);
+
+    env.advance_slots(10);
+    env.refresh(&borrower);
+    env.withdraw(&borrower, &sol, 1_000_000_000).unwrap();
+    assert!(env.obligation_state(&borrower).withdrawn_since_refresh());
+
+    // Still blocked many slots later, until the obligation is refreshed
+    env.advance_slots(100);
+    env.touch_oracles();
+    env.refresh_reserve(&usdc).unwrap();
+    assert_error(env.borrow(&borrower, &usdc, 1_000_000), BorrowError::WithdrawCooldownActive);
+
+    env.refresh(&borrower);
+    env.borrow(&borrower, &usdc, 1_000_000).unwrap();
+}
+
+#[test]
+fn utilization_fee_is_part_of_the_valued_debt() {
+    let mut env = TestEnv::new();
+    let usdc = usdc_with_utilization_fee(&mut env);
+    let sol = add_sol(&mut env);
+    let borrower = borrower_with_collateral(&mut env, &sol, 100_000_000_000);
+
+    // 900 USDC leaves utilization at 90%: fee = 10% * 4000 / 5000 = 8%
+    env.borrow(&borrower, &usdc, 900_000_000).unwrap();
+
+    let obligation = env.obligation_state(&borrower);
+    assert_eq!(obligation.borrows[0].borrowed_amount, 972_000_000);
+    assert_eq!(obligation.borrowed_value_usd, 972_000_000);
+
+    let reserve = env.reserve_state(&usdc);
+    assert_eq!(reserve.liquidity.total_borrows, 972_000_000);
+    assert_eq!(reserve.liquidity.accumulated_protocol_fees, 72_000_000);
+}
+
+#[test]
+fn borrowing_capacity_covers_the_utilization_fee() {
+    let mut env = TestEnv::new();
+    let usdc = usdc_with_utilization_fee(&mut env);
+    let dai = env.add_reserve(6, 1_000_000, 5000, 6000);
+
+    // 1900 DAI at 50% LTV allows $950 of debt
+    let borrower = borrower_with_collateral(&mut env, &dai, 1_900_000_000);
+
+    // 900 USDC fits on its own, but not with its 72 USDC fee
+    assert_error(
+        env.borrow(&borrower, &usdc, 900_000_000),
+        BorrowError::InsufficientBorrowingCapacity,
+    );
+
+    // 800 USDC pays 6%: 848 USDC of debt
+    env.borrow(&borrower, &usdc, 800_000_000).unwrap();
+    assert_eq!(env.obligation_state(&borrower).borrowed_value_usd, 848_000_000);
+}
+
+#[test]
+fn borrow_limit_covers_the_utilization_fee() {
+    let mut env = TestEnv::new();
+    let usdc = usdc_with_utilization_fee(&mut env);
+    env.update_reserve_config(&usdc, UpdateReserveConfigParams {
+        borrow_limit: Some(950_000_000),
+        ..update_reserve_params()
+    })
+    .unwrap();
+    let sol = add_sol(&mut env);
+    let borrower = borrower_with_collateral(&mut env, &sol, 100_000_000_000);
+
+    assert_error(env.borrow(&borrower, &usdc, 900_000_000), BorrowError::BorrowLimitExceeded);
+    env.borrow(&borrower, &usdc, 800_000_000).unwrap();
+}
+
+#[test]
+fn borrow_requires_a_recent_obligation_refresh() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    supply(&mut env, &usdc, 1_000_000_000_000);
+    let borrower = borrower_with_collateral(&mut env, &sol, 10_000_000_000);
+
+    // Fresh reserves do not make up for a stale obligation
+    env.advance_slots(151);
+    env.touch_oracles();
+    env.refresh_reserve(&usdc).unwrap();
+    assert_error(env.borrow(&borrower, &usdc, 1_000_000), BorrowError::ObligationStale);
+
+    env.refresh(&borrower);
+    env.borrow(&borrower, &usdc, 1_000_000).unwrap();
+}
+
+#[test]
+fn borrowing_the_only_collateral_asset_requires_looping() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    supply(&mut env, &usdc, 1_000_000_000_000);
+    let borrower = borrower_with_collateral(&mut env, &usdc, 1_000_000_000);
+
+    assert_error(env.borrow(&borrower, &usdc, 100_000_000), BorrowError::CircularBorrow);
+
+    // A second collateral asset makes the position no longer circular
+    env.deposit(&borrower, &sol, 1_000_000_000).unwrap();
+    env.refresh(&borrower);
+    env.borrow(&borrower, &usdc, 100_000_000).unwrap();
+}
+
+#[test]
+fn single_asset_looping_is_allowed_when_enabled() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    supply(&mut env, &usdc, 1_000_000_000_000);
+    let borrower = borrower_with_collateral(&mut env, &usdc, 1_000_000_000);
+    env.update_lending_market(UpdateLendingMarketParams {
+        allow_looping: Some(true),
+        ..update_market_params()
+    })
+    .unwrap();
+
+    env.borrow(&borrower, &usdc, 100_000_000).unwrap();
+}
+
+#[test]
+fn borrow_amount_is_checked_against_remaining_capacity() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    supply(&mut env, &usdc, 1_000_000_000_000);
+    let borrower = borrower_with_collateral(&mut env, &sol, 10_000_000_000);
+
+    // Capacity is positive, but not enough for the requested amount
+    assert_error(env.borrow(&borrower, &usdc, 750_000_001), BorrowError::InsufficientBorrowingCapacity);
+    env.borrow(&borrower, &usdc, 700_000_000).unwrap();
+    assert_error(env.borrow(&borrower, &usdc, 50_000_001), BorrowError::InsufficientBorrowingCapacity);
+    env.borrow(&borrower, &usdc, 50_000_000).unwrap();
+}
+
+#[test]
+fn dust_collateral_cannot_back_a_minimum_borrow() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    supply(&mut env, &usdc, 1_000_000_000_000);
+
+    // 1000 lamports are worth $0.0001, backing $0.000075 of debt
+    let borrower = borrower_with_collateral(&mut env, &sol, 1_000);
+    assert!(env.obligation_state(&borrower).allowed_borrow_value_usd > 0);
+    assert_error(env.borrow(&borrower, &usdc, 1_000), BorrowError::InsufficientBorrowingCapacity);
+}
+
+#[test]
+fn fresh_collateral_borrows_at_a_reduced_ltv() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = sol_with_initial_haircut(&mut env);
+    supply(&mut env, &usdc, 1_000_000_000_000);
+
+    // 10 SOL at $100 borrows at 50% instead of 75% inside the window
+    let borrower = borrower_with_collateral(&mut env, &sol, 10_000_000_000);
+    assert_eq!(env.obligation_state(&borrower).allowed_borrow_value_usd, 500_000_000);
+    assert_eq!(env.obligation_state(&borrower).unhealthy_borrow_value_usd, 800_000_000);
+    assert_error(env.borrow(&borrower, &usdc, 600_000_000), BorrowError::InsufficientBorrowingCapacity);
+
+    env.advance_slots(99);
+    env.refresh(&borrower);
+    assert_eq!(env.obligation_state(&borrower).allowed_borrow_value_usd, 500_000_000);
+
+    env.advance_slots(1);
+    env.refresh(&borrower);
+    assert_eq!(env.obligation_state(&borrower).allowed_borrow_value_usd, 750_000_000);
+    env.borrow(&borrower, &usdc, 600_000_000).unwrap();
+}
+
+#[test]
+fn topping_up_collateral_haircuts_only_the_top_up() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = sol_with_initial_haircut(&mut env);
+    supply(&mut env, &usdc, 1_000_000_000_000);
+    let borrower = borrower_with_collateral(&mut env, &sol, 10_000_000_000);
+    env.advance_slots(100);
+    env.refresh(&borrower);
+    env.borrow(&borrower, &usdc, 700_000_000).unwrap();
+
+    // The matured 10 SOL keep 75%; the new 10 SOL borrow at 50%
+    env.deposit(&borrower, &sol, 10_000_000_000).unwrap();
+    env.refresh(&borrower);
+    assert_eq!(env.obligation_state(&borrower).allowed_borrow_value_usd, 1_250_000_000);
+    assert_eq!(env.obligation_state(&borrower).deposits[0].pending_amount, 10_000_000_000);
+
+    // A second top-up inside the window joins the pending amount
+    env.advance_slots(50);
+    env.deposit(&borrower, &sol, 10_000_000_000).unwrap();
+    env.refresh(&borrower);
+    assert_eq!(env.obligation_state(&borrower).allowed_borrow_value_usd, 1_750_000_000);
+
+    env.advance_slots(100);
+    env.refresh(&borrower);
+    assert_eq!(env.obligation_state(&borrower).allowed_borrow_value_usd, 2_250_000_000);
+
+    // After maturing, a new top-up starts a fresh pending amount
+    env.deposit(&borrower, &sol, 10_000_000_000).unwrap();
+    env.refresh(&borrower);
+    assert_eq!(env.obligation_state(&borrower).deposits[0].pending_amount, 10_000_000_000);
+    assert_eq!(env.obligation_state(&borrower).allowed_borrow_value_usd, 2_750_000_000);
+}
+
+#[test]
+fn withdrawals_take_matured_collateral_first() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = sol_with_initial_haircut(&mut env);
+    supply(&mut env, &usdc, 1_000_000_000_000);
+    let borrower = borrower_with_collateral(&mut env, &sol, 10_000_000_000);
+    env.advance_slots(100);
+    env.deposit(&borrower, &sol, 10_000_000_000).unwrap();
+
+    // What is left after withdrawing 15 SOL is all still pending
+    env.refresh(&borrower);
+    env.withdraw(&borrower, &sol, 15_000_000_000).unwrap();
+    env.refresh(&borrower);
+    let obligation = env.obligation_state(&borrower);
+    assert_eq!(obligation.deposits[0].pending_amount, 5_000_000_000);
+    assert_eq!(obligation.allowed_borrow_value_usd, 250_000_000);
+}
+
+#[test]
+fn volatile_reserve_requires_a_larger_health_buffer() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let meme = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    env.update_reserve_config(&meme, UpdateReserveConfigParams {
+        min_health_factor_for_borrow_bps: Some(15000),
+        ..update_reserve_params()
+    })
+    .unwrap();
+    supply(&mut env, &usdc, 1_000_000_000_000);
+    supply(&mut env, &meme, 1_000_000_000_000);
+
+    // 10 SOL backs $800 of liquidation threshold: health 1.5 allows $533
+    let borrower = borrower_with_collateral(&mut env, &sol, 10_000_000_000);
+    assert_error(env.borrow(&borrower, &meme, 540_000_000), BorrowError::InsufficientHealthFactor);
+    env.borrow(&borrower, &meme, 530_000_000).unwrap();
+
+    // Borrowing a reserve without the override only needs the market minimum
+    env.borrow(&borrower, &usdc, 200_000_000).unwrap();
+    assert_error(env.borrow(&borrower, &meme, 1_000_000), BorrowError::InsufficientHealthFactor);
+}
+
+#[test]
+fn reserve_health_override_is_bounded() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    for invalid in [9_999, 50_001] {
+        assert_error(
+            env.update_reserve_config(&usdc, UpdateReserveConfigParams {
+                min_health_factor_for_borrow_bps: Some(invalid),
+                ..update_reserve_params()
+            }),
+            UpdateConfigError::InvalidMinHealthFactor,
+        );
+    }
+    for valid in [0, 10_000, 50_000] {
+        env.update_reserve_config(&usdc, UpdateReserveConfigParams {
+            min_health_factor_for_borrow_bps: Some(valid),
+            ..update_reserve_params()
+        })
+        .unwrap();
+    }
+}
+
+/// Borrow exactly the reserve's max borrowable, then check one more unit fails
+fn borrow_to_max(env: &mut TestEnv, reserve: &TestReserve, expected_max: u64, error: BorrowError) {
+    let sol = add_sol(env);
+    let borrower = borrower_with_collateral(env, &sol, 100_000_000_000);
+
+    assert_eq!(env.reserve_state(reserve).max_borrowable(), expected_max);
+    assert_error(env.borrow(&borrower, reserve, expected_max + 1), error);
+    env.borrow(&borrower, reserve, expected_max).unwrap();
+    assert_eq!(env.reserve_state(reserve).max_borrowable(), 0);
+}
+
+#[test]
+fn max_borrowable_bound_by_liquidity() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    supply(&mut env, &usdc, 1_000_000_000);
+    borrow_to_max(&mut env, &usdc, 1_000_000_000, BorrowError::InsufficientLiquidity);
+}
+
+#[test]
+fn max_borrowable_bound_by_withdrawal_reserve() {
+    let mut env = TestEnv::new();
+    let usdc = env
+        .add_reserve_with(6, 1_000_000, InitializeReserveParams {
+            withdrawal_reserve_bps: Some(2000),
+            ..reserve_params(8000, 8500)
+        })
+        .unwrap();
+    supply(&mut env, &usdc, 1_000_000_000);
+    borrow_to_max(&mut env, &usdc, 800_000_000, BorrowError::InsufficientLiquidity);
+}
+
+#[test]
+fn max_borrowable_bound_by_borrow_limit() {
+    let mut env = TestEnv::new();
+    let usdc = env
+        .add_reserve_with(6, 1_000_000, InitializeReserveParams {
+            borrow_limit: Some(300_000_000),
+            ..reserve_params(8000, 8500)
+        })
+        .unwrap();
+    supply(&mut env, &usdc, 1_000_000_000);
+    borrow_to_max(&mut env, &usdc, 300_000_000, BorrowError::BorrowLimitExceeded);
+}
+
+#[test]
+fn max_borrowable_bound_by_utilization_cap() {
+    let mut env = TestEnv::new();
+    let usdc = env
+        .add_reserve_with(6, 1_000_000, InitializeReserveParams {
+            max_utilization_bps: Some(6000),
+            ..reserve_params(8000, 8500)
+        })
+        .unwrap();
+    supply(&mut env, &usdc, 1_000_000_000);
+    borrow_to_max(&mut env, &usdc, 600_000_000, BorrowError::UtilizationCapExceeded);
+}
+
+#[test]
+fn obligation_borrow_cap_limits_total_debt() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    let eth = env.add_reserve(9, 100_000_000, 7500, 8000);
+    supply(&mut env, &usdc, 1_000_000_000_000);
+    supply(&mut env, &eth, 1_000_000_000_000);
+    env.update_lending_market(UpdateLendingMarketParams {
+        max_obligation_borrow_usd: Some(500_000_000),
+        ..update_market_params()
+    })
+    .unwrap();
+    let borrower = borrower_with_collateral(&mut env, &sol, 100_000_000_000);
+
+    env.borrow(&borrower, &usdc, 300_000_000).unwrap();
+    // $300 of USDC plus 2.01 ETH at $100 is over the $500 cap
+    assert_error(env.borrow(&borrower, &eth, 2_010_000_000), BorrowError::ObligationBorrowLimitExceeded);
+    env.borrow(&borrower, &eth, 2_000_000_000).unwrap();
+    assert_error(env.borrow(&borrower, &usdc, 1_000), BorrowError::ObligationBorrowLimitExceeded);
+
+    // Another obligation is capped separately
+    let other = borrower_with_collateral(&mut env, &sol, 100_000_000_000);
+    env.borrow(&other, &usdc, 500_000_000).unwrap();
+}
+
+#[test]
+fn required_refresh_must_precede_borrow_in_the_transaction() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    supply(&mut env, &usdc, 1_000_000_000);
+    let borrower = borrower_with_collateral(&mut env, &sol, 10_000_000_000);
+    let bystander = borrower_with_collateral(&mut env, &sol, 10_000_000_000);
+    env.update_lending_market(UpdateLendingMarketParams {
+        require_refresh_in_transaction: Some(true),
+        ..update_market_params()
+    })
+    .unwrap();
+    env.refresh(&borrower);
+    env.refresh(&bystander);
+
+    assert_error(env.borrow(&borrower, &usdc, 100_000_000), BorrowError::MissingInstructionsSysvar);
+
+    let refresh = env.refresh_obligation_instruction(&borrower);
+    let borrow = env.borrow_instruction(&borrower, &usdc, 100_000_000);
+    assert_error(
+        env.process_transaction(&[borrow.clone(), refresh.clone()]),
+        BorrowError::RefreshNotInTransaction,
+    );
+    let other_refresh = env.refresh_obligation_instruction(&bystander);
+    assert_error(
+        env.process_transaction(&[other_refresh, borrow.clone()]),
+        BorrowError::RefreshNotInTransaction,
+    );
+
+    env.process_transaction(&[refresh, borrow]).unwrap();
+    assert_eq!(env.balance_of(&borrower, &usdc), 100_000_000);
+}
+
+/// Borrow `amount` USDC against 10 SOL ($750 of capacity) under the given
+/// capacity buffer, then refresh a few slots later
+fn borrow_then_refresh(buffer_bps: u16, amount: u64) -> Result<Obligation, ProgramError> {
+    let mut env = TestEnv::new();
+    env.update_lending_market(UpdateLendingMarketParams {
+        borrow_capacity_buffer_bps: Some(buffer_bps),
+        ..update_market_params()
+    })
+    .unwrap();
+    let usdc = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    supply(&mut env, &usdc, 1_000_000_000);
+    let borrower = borrower_with_collateral(&mut env, &sol, 10_000_000_000);
+    env.borrow(&borrower, &usdc, amount)?;
+
+    env.advance_slots(10);
+    env.refresh(&borrower);
+    Ok(env.obligation_state(&borrower))
+}
+
+#[test]
+fn capacity_buffer_keeps_a_max_borrow_within_capacity() {
+    // Without a buffer a max borrow reads over capacity at the next refresh
+    let unbuffered = borrow_then_refresh(0, 750_000_000).unwrap();
+    assert_eq!(unbuffered.allowed_borrow_value_usd, 750_000_000);
+    assert!(unbuffered.borrowed_value_usd > unbuffered.allowed_borrow_value_usd);
+
+    // A 10 bps buffer holds back $0.75 of the capacity
+    assert_eq!(
+        borrow_then_refresh(10, 750_000_000).err(),
+        Some(anchor_error(BorrowError::InsufficientBorrowingCapacity))
+    );
+    let buffered = borrow_then_refresh(10, 749_250_000).unwrap();
+    assert!(buffered.borrowed_value_usd > 749_250_000);
+    assert!(buffered.borrowed_value_usd <= buffered.allowed_borrow_value_usd);
+}
+
+#[test]
+fn fresh_collateral_backs_borrows_only_after_the_activation_delay() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = env
+        .add_reserve_with(9, 100_000_000, InitializeReserveParams {
+            collateral_activation_delay_slots: Some(100),
+            ..reserve_params(7500, 8000)
+        })
+        .unwrap();
+    supply(&mut env, &usdc, 1_000_000_000);
+
+    let borrower = borrower_with_collateral(&mut env, &sol, 10_000_000_000);
+    let obligation = env.obligation_state(&borrower);
+    assert_eq!(obligation.deposited_value_usd, 1_000_000_000);
+    assert_eq!(obligation.allowed_borrow_value_usd, 0);
+    assert_error(env.borrow(&borrower, &usdc, 1_000_000), BorrowError::InsufficientBorrowingCapacity);
+
+    env.advance_slots(100);
+    env.refresh(&borrower);
+    assert_eq!(env.obligation_state(&borrower).allowed_borrow_value_usd, 750_000_000);
+    env.borrow(&borrower, &usdc, 500_000_000).unwrap();
+}
+
+#[test]
+fn topping_up_active_collateral_keeps_it_active() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = env
+        .add_reserve_with(9, 100_000_000, InitializeReserveParams {
+            collateral_activation_delay_slots: Some(100),
+            ..reserve_params(7500, 8000)
+        })
+        .unwrap();
+    supply(&mut env, &usdc, 1_000_000_000);
+    let borrower = borrower_with_collateral(&mut env, &sol, 10_000_000_000);
+    env.advance_slots(100);
+    env.refresh(&borrower);
+    env.borrow(&borrower, &usdc, 700_000_000).unwrap();
+
+    // The top-up waits for activation; the existing debt stays backed
+    env.deposit(&borrower, &sol, 1_000_000_000).unwrap();
+    env.refresh(&borrower);
+    let obligation = env.obligation_state(&borrower);
+    assert_eq!(obligation.allowed_borrow_value_usd, 750_000_000);
+    assert!(obligation.borrowed_value_usd <= obligation.allowed_borrow_value_usd);
+
+    env.advance_slots(100);
+    env.refresh(&borrower);
+    assert_eq!(env.obligation_state(&borrower).allowed_borrow_value_usd, 825_000_000);
+}
+
+#[test]
+fn borrows_in_the_warning_band_emit_risky_borrow() {
+    let mut env = TestEnv::new();
+    assert_error(
+        env.update_lending_market(UpdateLendingMarketParams {
+            borrow_warning_health_bps: Some(9_999),
+            ..update_market_params()
+        }),
+        UpdateLendingMarketError::InvalidBorrowWarningHealth,
+    );
+    env.update_lending_market(UpdateLendingMarketParams {
+        borrow_warning_health_bps: Some(12_000),
+        ..update_market_params()
+    })
+    .unwrap();
+    let usdc = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    supply(&mut env, &usdc, 1_000_000_000);
+    let borrower = borrower_with_collateral(&mut env, &sol, 10_000_000_000);
+
+    // Health 8.0: no warning
+    env.borrow(&borrower, &usdc, 100_000_000).unwrap();
+    assert!(env.events_of::<RiskyBorrow>().is_empty());
+
+    // $800 of threshold value against $700 of debt: health ~1.14, still allowed
+    env.borrow(&borrower, &usdc, 600_000_000).unwrap();
+    let warning = env.event::<RiskyBorrow>();
+    assert_eq!(warning.owner, borrower);
+    assert_eq!(warning.warning_health_bps, 12_000);
+    assert_eq!(Some(warning.health_factor), env.obligation_state(&borrower).calculate_health_factor());
+    assert!((10_000..12_000).contains(&warning.health_factor));
+}
+
+#[test]
+fn market_borrow_position_limit_applies_below_the_hard_cap() {
+    let mut env = TestEnv::new();
+    env.update_lending_market(UpdateLendingMarketParams {
+        max_obligation_borrows: Some(2),
+        ..update_market_params()
+    })
+    .unwrap();
+    let reserves: Vec<_> = (0..3).map(|_| add_usdc(&mut env)).collect();
+    let sol = add_sol(&mut env);
+    for reserve in reserves.iter() {
+        supply(&mut env, reserve, 1_000_000_000);
+    }
+    let borrower = borrower_with_collateral(&mut env, &sol, 100_000_000_000);
+    env.borrow(&borrower, &reserves[0], 10_000_000).unwrap();
+    env.borrow(&borrower, &reserves[1], 10_000_000).unwrap();
+
+    assert_error(env.borrow(&borrower, &reserves[2], 10_000_000), BorrowError::MaxBorrowsReached);
+    env.borrow(&borrower, &reserves[0], 10_000_000).unwrap();
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use common::*;
+use radiant::events::ProtocolFeesCollected;
+use radiant::instructions::{CollectFeesError, UpdateReserveConfigParams};
+
+/// A USDC reserve holding 1000 USDC with 5 USDC of protocol fees accrued
+fn usdc_with_fees(env: &mut TestEnv) -> TestReserve {
+    let usdc = add_usdc(env);
+    supply(env, &usdc, 1_000_000_000);
+    env.update_reserve(&usdc, |reserve| reserve.liquidity.accumulated_protocol_fees = 5_000_000);
+    usdc
+}
+
+#[test]
+fn fees_go_to_the_market_treasury_by_default() {
+    let mut env = TestEnv::new();
+    let usdc = usdc_with_fees(&mut env);
+    let partner = new_key();
+
+    assert_error(env.collect_fees(&usdc, &partner, 0), CollectFeesError::InvalidTreasury);
+    let market_treasury = env.treasury;
+    env.collect_fees(&usdc, &market_treasury, 0).unwrap();
+    assert_eq!(env.balance_of(&market_treasury, &usdc), 5_000_000);
+    assert_eq!(env.reserve_state(&usdc).liquidity.accumulated_protocol_fees, 0);
+}
+
+#[test]
+fn reserve_fee_treasury_overrides_the_market_treasury() {
+    let mut env = TestEnv::new();
+    let usdc = usdc_with_fees(&mut env);
+    let partner = new_key();
+    let market_treasury = env.treasury;
+    env.update_reserve_config(&usdc, UpdateReserveConfigParams {
+        fee_treasury: Some(Some(partner)),
+        ..update_reserve_params()
+    })
+    .unwrap();
+
+    assert_error(env.collect_fees(&usdc, &market_treasury, 0), CollectFeesError::InvalidTreasury);
+    env.collect_fees(&usdc, &partner, 2_000_000).unwrap();
+    assert_eq!(env.balance_of(&partner, &usdc), 2_000_000);
+
+    // Clearing the override routes the rest back to the market
+    env.update_reserve_config(&usdc, UpdateReserveConfigParams {
+        fee_treasury: Some(None),
+        ..update_reserve_params()
+    })
+    .unwrap();
+    assert_error(env.collect_fees(&usdc, &partner, 0), CollectFeesError::InvalidTreasury);
+    env.collect_fees(&usdc, &market_treasury, 0).unwrap();
+    assert_eq!(env.balance_of(&market_treasury, &usdc), 3_000_000);
+}
+
+#[test]
+fn fees_stay_put_when_reserve_accounts_fail_validation() {
+    let mut env = TestEnv::new();
+    let usdc = usdc_with_fees(&mut env);
+    let market_treasury = env.treasury;
+
+    // Vault no longer owned by the reserve PDA
+    env.set_token_owner(&usdc.vault, &new_key());
+    assert_error(env.collect_fees(&usdc, &market_treasury, 0), CollectFeesError::InvalidReserveAccounts);
+    env.set_token_owner(&usdc.vault, &usdc.key);
+
+    // Fee receiver recorded as the vault itself
+    env.update_reserve(&usdc, |reserve| reserve.fee_receiver = reserve.token_vault);
+    assert_error(env.collect_fees(&usdc, &market_treasury, 0), CollectFeesError::InvalidReserveAccounts);
+    assert_eq!(env.reserve_state(&usdc).liquidity.accumulated_protocol_fees, 5_000_000);
+}
+
+#[test]
+fn usd_amounts_convert_at_the_oracle_price() {
+    let mut env = TestEnv::new();
+    let sol = add_sol(&mut env);
+    supply(&mut env, &sol, 100_000_000_000);
+    env.update_reserve(&sol, |reserve| reserve.liquidity.accumulated_protocol_fees = 1_000_000_000);
+    let treasury = env.treasury;
+
+    // $10 of SOL at $100, then at $200
+    env.collect_fees_usd(&sol, &treasury, 10_000_000).unwrap();
+    let collected = env.event::<ProtocolFeesCollected>();
+    assert_eq!((collected.amount, collected.amount_usd), (100_000_000, 10_000_000));
+
+    env.advance_slots(1);
+    env.set_price(&sol, 200_000_000);
+    env.collect_fees_usd(&sol, &treasury, 10_000_000).unwrap();
+    let collected = env.event::<ProtocolFeesCollected>();
+    assert_eq!((collected.amount, collected.amount_usd), (50_000_000, 10_000_000));
+
+    // More than is available collects the rest
+    env.collect_fees_usd(&sol, &treasury, 1_000_000_000).unwrap();
+    let collected = env.event::<ProtocolFeesCollected>();
+    assert_eq!((collected.amount, collected.amount_usd), (850_000_000, 170_000_000));
+    assert_eq!(env.balance_of(&treasury, &sol), 1_000_000_000);
+}
+
+#[test]
+fn usd_amount_worth_no_tokens_is_rejected() {
+    let mut env = TestEnv::new();
+    let usdc = usdc_with_fees(&mut env);
+    let treasury = env.treasury;
+
+    assert_error(env.collect_fees_usd(&usdc, &treasury, 0), CollectFeesError::AmountTooSmall);
+}
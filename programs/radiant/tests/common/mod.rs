@@ -0,0 +1,1392 @@
+//! In-process test environment for the radiant program
+//!
+//! Instructions run through `radiant::entry` against an in-memory account
+//! store. Syscalls are stubbed: the clock and rent sysvars, events, return
+//! data, and CPIs to the token program (transfer, initialize_account3) and
+//! the system program (create_account, assign, transfer, allocate).
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Once;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    entrypoint::MAX_PERMITTED_DATA_INCREASE,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    program_pack::Pack,
+    program_stubs::{self, SyscallStubs},
+    sysvar,
+};
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token;
+
+use radiant::constants::{FEE_RECEIVER_SEED, VAULT_SEED};
+use radiant::instructions::{
+    InitializeLendingMarketParams,
+    InitializeReserveParams,
+    UpdateLendingMarketParams,
+    UpdateReserveConfigParams,
+};
+use radiant::state::{LendingMarket, Obligation, Reserve};
+
+/// Seconds per slot used when advancing the clock (400ms slots)
+pub const SLOT_MS: i64 = 400;
+
+/// Starting slot of every test environment
+pub const START_SLOT: u64 = 1_000;
+
+/// Starting unix timestamp of every test environment
+pub const START_TIMESTAMP: i64 = 1_700_000_000;
+
+// ============================================================================
+// SYSCALL STUBS
+// ============================================================================
+
+thread_local! {
+    static CLOCK: RefCell<Clock> = RefCell::new(Clock::default());
+    static EVENTS: RefCell<Vec<Vec<u8>>> = const { RefCell::new(Vec::new()) };
+    static LOGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    static RETURN_DATA: RefCell<Option<Vec<u8>>> = const { RefCell::new(None) };
+}
+
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_log(&self, message: &str) {
+        LOGS.with(|logs| logs.borrow_mut().push(message.to_string()));
+    }
+
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        EVENTS.with(|events| events.borrow_mut().push(fields.concat()));
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = CLOCK.with(|clock| clock.borrow().clone());
+        unsafe { *(var_addr as *mut Clock) = clock };
+        0
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        0
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        RETURN_DATA.with(|return_data| *return_data.borrow_mut() = Some(data.to_vec()));
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        RETURN_DATA.with(|return_data| return_data.borrow().clone().map(|data| (radiant::ID, data)))
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> std::result::Result<(), ProgramError> {
+        let accounts = instruction
+            .accounts
+            .iter()
+            .map(|meta| {
+                account_infos
+                    .iter()
+                    .find(|info| *info.key == meta.pubkey)
+                    .ok_or(ProgramError::NotEnoughAccountKeys)
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let is_signer = |info: &AccountInfo| {
+            info.is_signer
+                || signers_seeds.iter().any(|seeds| {
+                    Pubkey::create_program_address(seeds, &radiant::ID).ok() == Some(*info.key)
+                })
+        };
+
+        if instruction.program_id == spl_token::ID {
+            invoke_token(&instruction.data, &accounts, is_signer)
+        } else if instruction.program_id == system_program::ID {
+            invoke_system(&instruction.data, &accounts, is_signer)
+        } else {
+            Err(ProgramError::IncorrectProgramId)
+        }
+    }
+}
+
+fn invoke_token(
+    data: &[u8],
+    accounts: &[&AccountInfo],
+    is_signer: impl Fn(&AccountInfo) -> bool,
+) -> std::result::Result<(), ProgramError> {
+    match data.first() {
+        // Transfer { amount }: [source, destination, authority]
+        Some(3) => {
+            let amount = u64::from_le_bytes(data[1..9].try_into().unwrap());
+            let mut source = spl_token::state::Account::unpack(&accounts[0].try_borrow_data()?)?;
+            let mut destination = spl_token::state::Account::unpack(&accounts[1].try_borrow_data()?)?;
+
+            if source.owner != *accounts[2].key || !is_signer(accounts[2]) {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            if source.mint != destination.mint {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            if source.amount < amount {
+                return Err(ProgramError::InsufficientFunds);
+            }
+
+            if accounts[0].key != accounts[1].key {
+                source.amount -= amount;
+                destination.amount += amount;
+                spl_token::state::Account::pack(source, &mut accounts[0].try_borrow_mut_data()?)?;
+                spl_token::state::Account::pack(destination, &mut accounts[1].try_borrow_mut_data()?)?;
+            }
+            Ok(())
+        }
+        // InitializeAccount3 { owner }: [account, mint]
+        Some(18) => {
+            let owner = Pubkey::try_from(&data[1..33]).unwrap();
+            let account = spl_token::state::Account {
+                mint: *accounts[1].key,
+                owner,
+                state: spl_token::state::AccountState::Initialized,
+                ..Default::default()
+            };
+            spl_token::state::Account::pack(account, &mut accounts[0].try_borrow_mut_data()?)
+        }
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+fn invoke_system(
+    data: &[u8],
+    accounts: &[&AccountInfo],
+    is_signer: impl Fn(&AccountInfo) -> bool,
+) -> std::result::Result<(), ProgramError> {
+    let read_u64 = |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+    let move_lamports = |from: &AccountInfo, to: &AccountInfo, lamports: u64| {
+        if !is_signer(from) {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        let mut from_lamports = from.try_borrow_mut_lamports()?;
+        if **from_lamports < lamports {
+            return Err(ProgramError::InsufficientFunds);
+        }
+        **from_lamports -= lamports;
+        drop(from_lamports);
+        **to.try_borrow_mut_lamports()? += lamports;
+        Ok(())
+    };
+
+    match u32::from_le_bytes(data[0..4].try_into().unwrap()) {
+        // CreateAccount { lamports, space, owner }: [funder, new account]
+        0 => {
+            if accounts[1].lamports() > 0 || !is_signer(accounts[1]) {
+                return Err(ProgramError::AccountAlreadyInitialized);
+            }
+            move_lamports(accounts[0], accounts[1], read_u64(4))?;
+            accounts[1].resize(read_u64(12) as usize)?;
+            accounts[1].assign(&Pubkey::try_from(&data[20..52]).unwrap());
+            Ok(())
+        }
+        // Assign { owner }: [account]
+        1 => {
+            accounts[0].assign(&Pubkey::try_from(&data[4..36]).unwrap());
+            Ok(())
+        }
+        // Transfer { lamports }: [from, to]
+        2 => move_lamports(accounts[0], accounts[1], read_u64(4)),
+        // Allocate { space }: [account]
+        8 => accounts[0].resize(read_u64(4) as usize),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+fn install_stubs() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        program_stubs::set_syscall_stubs(Box::new(Stubs));
+    });
+}
+
+// ============================================================================
+// ACCOUNT STORE
+// ============================================================================
+
+/// Stored state of an account between instructions
+#[derive(Clone, Debug, Default)]
+pub struct StoredAccount {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+/// Serialized account as the runtime lays it out: the key is preceded by the
+/// original data length and the data by its current length, with room to grow
+struct RawAccount {
+    key: Box<[u8; 36]>,
+    lamports: Box<u64>,
+    data: Vec<u8>,
+    data_len: usize,
+    owner: Box<Pubkey>,
+    executable: bool,
+}
+
+impl RawAccount {
+    fn new(key: &Pubkey, account: &StoredAccount) -> Self {
+        let mut key_buf = Box::new([0u8; 36]);
+        key_buf[..4].copy_from_slice(&(account.data.len() as u32).to_le_bytes());
+        key_buf[4..].copy_from_slice(key.as_ref());
+
+        let mut data = vec![0u8; 8 + account.data.len() + MAX_PERMITTED_DATA_INCREASE];
+        data[..8].copy_from_slice(&(account.data.len() as u64).to_le_bytes());
+        data[8..8 + account.data.len()].copy_from_slice(&account.data);
+
+        Self {
+            key: key_buf,
+            lamports: Box::new(account.lamports),
+            data,
+            data_len: account.data.len(),
+            owner: Box::new(account.owner),
+            executable: account.executable,
+        }
+    }
+
+    /// Build an AccountInfo over this buffer
+    ///
+    /// The buffer must outlive the returned info and not move meanwhile.
+    unsafe fn info<'a>(&mut self, is_signer: bool, is_writable: bool) -> AccountInfo<'a> {
+        let key = &*(self.key.as_ptr().add(4) as *const Pubkey);
+        let lamports = &mut *(&mut *self.lamports as *mut u64);
+        let data = std::slice::from_raw_parts_mut(self.data.as_mut_ptr().add(8), self.data_len);
+        let owner = &*(&*self.owner as *const Pubkey);
+        AccountInfo::new(key, is_signer, is_writable, lamports, data, owner, self.executable, 0)
+    }
+}
+
+/// Convert an anchor error into the ProgramError the entrypoint returns
+pub fn anchor_error(error: impl Into<anchor_lang::error::Error>) -> ProgramError {
+    error.into().into()
+}
+
+/// Assert that an instruction failed with the given anchor error
+#[track_caller]
+pub fn assert_error(result: std::result::Result<(), ProgramError>, error: impl Into<anchor_lang::error::Error>) {
+    assert_eq!(result, Err(anchor_error(error)));
+}
+
+/// Unique address for a test account
+pub fn new_key() -> Pubkey {
+    Pubkey::new_unique()
+}
+
+// ============================================================================
+// PYTH ORACLE LAYOUT
+// ============================================================================
+
+/// Size of a Pyth v2 price account as read by the program
+pub const ORACLE_LEN: usize = 240;
+
+/// Build Pyth price account data
+pub fn oracle_data(price: i64, exponent: i32, publish_slot: u64, trading: bool) -> Vec<u8> {
+    let mut data = vec![0u8; ORACLE_LEN];
+    data[0..4].copy_from_slice(&0xa1b2c3d4u32.to_le_bytes());
+    data[8..12].copy_from_slice(&3u32.to_le_bytes());
+    data[20..24].copy_from_slice(&exponent.to_le_bytes());
+    data[208..216].copy_from_slice(&price.to_le_bytes());
+    data[224..228].copy_from_slice(&(trading as u32).to_le_bytes());
+    data[232..240].copy_from_slice(&publish_slot.to_le_bytes());
+    data
+}
+
+// ============================================================================
+// PROGRAM TEST ENVIRONMENT
+// ============================================================================
+
+/// Price feed set up for a reserve (price = mantissa * 10^exponent USD)
+#[derive(Clone, Copy, Debug)]
+pub struct OracleFeed {
+    pub mantissa: i64,
+    pub exponent: i32,
+}
+
+/// Keys of a reserve created by the environment
+#[derive(Clone, Copy, Debug)]
+pub struct TestReserve {
+    pub key: Pubkey,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub fee_receiver: Pubkey,
+    pub oracle: Pubkey,
+    pub decimals: u8,
+}
+
+/// A lending market with its authority and reserves, run in process
+pub struct TestEnv {
+    pub accounts: HashMap<Pubkey, StoredAccount>,
+    pub clock: Clock,
+    pub authority: Pubkey,
+    pub treasury: Pubkey,
+    pub market: Pubkey,
+    pub oracles: HashMap<Pubkey, OracleFeed>,
+    pub token_accounts: HashMap<(Pubkey, Pubkey), Pubkey>,
+    pub events: Vec<Vec<u8>>,
+    pub logs: Vec<String>,
+    pub return_data: Option<Vec<u8>>,
+}
+
+impl TestEnv {
+    /// Create an environment with a lending market using the default parameters
+    pub fn new() -> Self {
+        Self::with_market(InitializeLendingMarketParams {
+            close_factor_bps: None,
+            liquidation_bonus_bps: None,
+            protocol_fee_bps: None,
+        })
+    }
+
+    /// Create an environment with a lending market using `params`
+    pub fn with_market(params: InitializeLendingMarketParams) -> Self {
+        install_stubs();
+
+        let authority = new_key();
+        let treasury = new_key();
+        let (market, _) = Pubkey::find_program_address(&[b"lending_market", authority.as_ref()], &radiant::ID);
+
+        let mut env = Self {
+            accounts: HashMap::new(),
+            clock: Clock {
+                slot: START_SLOT,
+                unix_timestamp: START_TIMESTAMP,
+                ..Clock::default()
+            },
+            authority,
+            treasury,
+            market,
+            oracles: HashMap::new(),
+            token_accounts: HashMap::new(),
+            events: Vec::new(),
+            logs: Vec::new(),
+            return_data: None,
+        };
+
+        for program in [radiant::ID, spl_token::ID, system_program::ID] {
+            env.accounts.insert(program, StoredAccount {
+                lamports: 1,
+                executable: true,
+                owner: Pubkey::default(),
+                ..StoredAccount::default()
+            });
+        }
+        env.set_rent_sysvar();
+        env.fund_lamports(&authority);
+
+        env.process(
+            radiant::accounts::InitializeLendingMarket {
+                authority,
+                lending_market: market,
+                treasury,
+                system_program: system_program::ID,
+            },
+            radiant::instruction::InitializeLendingMarket { params },
+        )
+        .expect("initialize_lending_market");
+
+        env
+    }
+
+    fn set_rent_sysvar(&mut self) {
+        let rent = Rent::default();
+        let mut data = Vec::with_capacity(17);
+        data.extend_from_slice(&rent.lamports_per_byte_year.to_le_bytes());
+        data.extend_from_slice(&rent.exemption_threshold.to_le_bytes());
+        data.push(rent.burn_percent);
+        self.accounts.insert(sysvar::rent::ID, StoredAccount {
+            lamports: 1,
+            data,
+            owner: sysvar::ID,
+            executable: false,
+        });
+    }
+
+    // ------------------------------------------------------------------------
+    // Clock
+    // ------------------------------------------------------------------------
+
+    pub fn slot(&self) -> u64 {
+        self.clock.slot
+    }
+
+    /// Advance the clock by `slots` (and the matching wall time)
+    pub fn advance_slots(&mut self, slots: u64) {
+        self.clock.slot += slots;
+        self.clock.unix_timestamp += slots as i64 * SLOT_MS / 1000;
+    }
+
+    /// Advance the clock by `seconds` (and the matching number of slots)
+    pub fn advance_seconds(&mut self, seconds: i64) {
+        self.clock.unix_timestamp += seconds;
+        self.clock.slot += (seconds * 1000 / SLOT_MS) as u64;
+    }
+
+    // ------------------------------------------------------------------------
+    // Raw accounts
+    // ------------------------------------------------------------------------
+
+    pub fn account(&self, key: &Pubkey) -> Option<&StoredAccount> {
+        self.accounts.get(key)
+    }
+
+    pub fn set_account(&mut self, key: Pubkey, account: StoredAccount) {
+        self.accounts.insert(key, account);
+    }
+
+    /// Give an account enough lamports to pay for any account it creates
+    pub fn fund_lamports(&mut self, key: &Pubkey) {
+        let account = self.accounts.entry(*key).or_default();
+        account.lamports += 1_000_000_000_000;
+    }
+
+    /// Deserialize a program account
+    #[track_caller]
+    pub fn get<T: AccountDeserialize>(&self, key: &Pubkey) -> T {
+        let account = self.accounts.get(key).expect("account exists");
+        T::try_deserialize(&mut &account.data[..]).expect("account deserializes")
+    }
+
+    /// Overwrite a program account's state
+    pub fn set<T: AccountSerialize>(&mut self, key: &Pubkey, state: &T) {
+        let account = self.accounts.get_mut(key).expect("account exists");
+        let mut data = Vec::new();
+        state.try_serialize(&mut data).expect("account serializes");
+        account.data[..data.len()].copy_from_slice(&data);
+    }
+
+    /// Modify a program account's state in place
+    pub fn update<T: AccountDeserialize + AccountSerialize>(&mut self, key: &Pubkey, f: impl FnOnce(&mut T)) {
+        let mut state: T = self.get(key);
+        f(&mut state);
+        self.set(key, &state);
+    }
+
+    pub fn market_state(&self) -> LendingMarket {
+        self.get(&self.market)
+    }
+
+    pub fn reserve_state(&self, reserve: &TestReserve) -> Reserve {
+        self.get(&reserve.key)
+    }
+
+    pub fn obligation_state(&self, owner: &Pubkey) -> Obligation {
+        self.get(&self.obligation_key(owner, 0))
+    }
+
+    pub fn update_market(&mut self, f: impl FnOnce(&mut LendingMarket)) {
+        let market = self.market;
+        self.update(&market, f);
+    }
+
+    pub fn update_reserve(&mut self, reserve: &TestReserve, f: impl FnOnce(&mut Reserve)) {
+        self.update(&reserve.key, f);
+    }
+
+    pub fn update_obligation(&mut self, owner: &Pubkey, f: impl FnOnce(&mut Obligation)) {
+        let key = self.obligation_key(owner, 0);
+        self.update(&key, f);
+    }
+
+    // ------------------------------------------------------------------------
+    // Tokens
+    // ------------------------------------------------------------------------
+
+    /// Create an initialized mint
+    pub fn create_mint(&mut self, decimals: u8) -> Pubkey {
+        let mint = new_key();
+        let mut data = vec![0u8; spl_token::state::Mint::LEN];
+        spl_token::state::Mint::pack(
+            spl_token::state::Mint {
+                mint_authority: Some(self.authority).into(),
+                supply: 0,
+                decimals,
+                is_initialized: true,
+                freeze_authority: None.into(),
+            },
+            &mut data,
+        )
+        .unwrap();
+        self.accounts.insert(mint, StoredAccount {
+            lamports: 1_000_000,
+            data,
+            owner: spl_token::ID,
+            executable: false,
+        });
+        mint
+    }
+
+    /// Create a token account at `key` holding `amount`
+    pub fn create_token_account_at(&mut self, key: Pubkey, mint: &Pubkey, owner: &Pubkey, amount: u64) {
+        let mut data = vec![0u8; spl_token::state::Account::LEN];
+        spl_token::state::Account::pack(
+            spl_token::state::Account {
+                mint: *mint,
+                owner: *owner,
+                amount,
+                state: spl_token::state::AccountState::Initialized,
+                ..Default::default()
+            },
+            &mut data,
+        )
+        .unwrap();
+        self.accounts.insert(key, StoredAccount {
+            lamports: 2_039_280,
+            data,
+            owner: spl_token::ID,
+            executable: false,
+        });
+    }
+
+    /// Get (or create) `owner`'s token account for `mint`
+    pub fn token_account(&mut self, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+        if let Some(key) = self.token_accounts.get(&(*owner, *mint)) {
+            return *key;
+        }
+        let key = new_key();
+        self.create_token_account_at(key, mint, owner, 0);
+        self.token_accounts.insert((*owner, *mint), key);
+        key
+    }
+
+    /// Credit `amount` tokens of the reserve's mint to `owner`
+    pub fn mint_to(&mut self, owner: &Pubkey, reserve: &TestReserve, amount: u64) -> Pubkey {
+        let key = self.token_account(owner, &reserve.mint);
+        self.set_token_balance(&key, self.token_balance(&key) + amount);
+        key
+    }
+
+    pub fn token_balance(&self, key: &Pubkey) -> u64 {
+        spl_token::state::Account::unpack(&self.accounts[key].data).unwrap().amount
+    }
+
+    pub fn set_token_balance(&mut self, key: &Pubkey, amount: u64) {
+        let account = self.accounts.get_mut(key).unwrap();
+        let mut state = spl_token::state::Account::unpack(&account.data).unwrap();
+        state.amount = amount;
+        spl_token::state::Account::pack(state, &mut account.data).unwrap();
+    }
+
+    pub fn set_token_owner(&mut self, key: &Pubkey, owner: &Pubkey) {
+        let account = self.accounts.get_mut(key).unwrap();
+        let mut state = spl_token::state::Account::unpack(&account.data).unwrap();
+        state.owner = *owner;
+        spl_token::state::Account::pack(state, &mut account.data).unwrap();
+    }
+
+    /// Balance of `owner`'s token account for the reserve's mint
+    pub fn balance_of(&mut self, owner: &Pubkey, reserve: &TestReserve) -> u64 {
+        let key = self.token_account(owner, &reserve.mint);
+        self.token_balance(&key)
+    }
+
+    // ------------------------------------------------------------------------
+    // Oracles
+    // ------------------------------------------------------------------------
+
+    /// Create a Pyth price account published at the current slot
+    pub fn create_oracle(&mut self, mantissa: i64, exponent: i32) -> Pubkey {
+        let oracle = new_key();
+        self.write_oracle(oracle, OracleFeed { mantissa, exponent });
+        oracle
+    }
+
+    fn write_oracle(&mut self, oracle: Pubkey, feed: OracleFeed) {
+        self.oracles.insert(oracle, feed);
+        self.accounts.insert(oracle, StoredAccount {
+            lamports: 1_000_000,
+            data: oracle_data(feed.mantissa, feed.exponent, self.clock.slot, true),
+            owner: new_key(),
+            executable: false,
+        });
+    }
+
+    /// Set a reserve's price in USD (scaled by 10^6), published now
+    pub fn set_price(&mut self, reserve: &TestReserve, price_usd: u64) {
+        self.write_oracle(reserve.oracle, OracleFeed { mantissa: price_usd as i64, exponent: -6 });
+    }
+
+    /// Republish every oracle at the current slot with unchanged prices
+    pub fn touch_oracles(&mut self) {
+        let feeds: Vec<_> = self.oracles.iter().map(|(key, feed)| (*key, *feed)).collect();
+        for (oracle, feed) in feeds {
+            self.write_oracle(oracle, feed);
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // Execution
+    // ------------------------------------------------------------------------
+
+    /// Run an instruction with the given accounts
+    pub fn process(
+        &mut self,
+        accounts: impl ToAccountMetas,
+        instruction: impl InstructionData,
+    ) -> std::result::Result<(), ProgramError> {
+        self.process_with_remaining(accounts, instruction, &[])
+    }
+
+    /// Run an instruction with extra remaining accounts (read-only)
+    pub fn process_with_remaining(
+        &mut self,
+        accounts: impl ToAccountMetas,
+        instruction: impl InstructionData,
+        remaining: &[Pubkey],
+    ) -> std::result::Result<(), ProgramError> {
+        let mut metas = accounts.to_account_metas(None);
+        metas.extend(remaining.iter().map(|key| AccountMeta::new_readonly(*key, false)));
+        self.process_instruction(&metas, &instruction.data())
+    }
+
+    /// Run instructions in order as one transaction, exposing them through
+    /// the instructions sysvar
+    ///
+    /// Stops at the first failure; earlier instructions are not rolled back.
+    pub fn process_transaction(&mut self, instructions: &[Instruction]) -> std::result::Result<(), ProgramError> {
+        for (index, instruction) in instructions.iter().enumerate() {
+            self.write_instructions_sysvar(instructions, index as u16);
+            self.process_instruction(&instruction.accounts, &instruction.data)?;
+        }
+        Ok(())
+    }
+
+    /// Store the instructions sysvar in the runtime's layout: the instruction
+    /// count and offsets, each instruction's metas, program and data, then
+    /// the current instruction index
+    fn write_instructions_sysvar(&mut self, instructions: &[Instruction], current_index: u16) {
+        let mut data = (instructions.len() as u16).to_le_bytes().to_vec();
+        data.resize(2 + 2 * instructions.len(), 0);
+        for (index, instruction) in instructions.iter().enumerate() {
+            let offset = (data.len() as u16).to_le_bytes();
+            data[2 + 2 * index..4 + 2 * index].copy_from_slice(&offset);
+            data.extend((instruction.accounts.len() as u16).to_le_bytes());
+            for meta in &instruction.accounts {
+                data.push(meta.is_signer as u8 | (meta.is_writable as u8) << 1);
+                data.extend(meta.pubkey.as_ref());
+            }
+            data.extend(instruction.program_id.as_ref());
+            data.extend((instruction.data.len() as u16).to_le_bytes());
+            data.extend(&instruction.data);
+        }
+        data.extend(current_index.to_le_bytes());
+        self.accounts.insert(
+            sysvar::instructions::ID,
+            StoredAccount { lamports: 1, data, owner: sysvar::ID, executable: false },
+        );
+    }
+
+    /// Run raw instruction data against account metas
+    pub fn process_instruction(
+        &mut self,
+        metas: &[AccountMeta],
+        data: &[u8],
+    ) -> std::result::Result<(), ProgramError> {
+        CLOCK.with(|clock| *clock.borrow_mut() = self.clock.clone());
+        EVENTS.with(|events| events.borrow_mut().clear());
+        LOGS.with(|logs| logs.borrow_mut().clear());
+        RETURN_DATA.with(|return_data| *return_data.borrow_mut() = None);
+
+        // One buffer per distinct key; duplicate metas share the same info
+        let mut keys: Vec<Pubkey> = Vec::new();
+        let mut raws: Vec<RawAccount> = Vec::new();
+        for meta in metas {
+            if !keys.contains(&meta.pubkey) {
+                let stored = self.accounts.get(&meta.pubkey).cloned().unwrap_or_default();
+                keys.push(meta.pubkey);
+                raws.push(RawAccount::new(&meta.pubkey, &stored));
+            }
+        }
+
+        let mut unique_infos: Vec<AccountInfo> = Vec::new();
+        for (index, raw) in raws.iter_mut().enumerate() {
+            let is_signer = metas.iter().any(|meta| meta.pubkey == keys[index] && meta.is_signer);
+            let is_writable = metas.iter().any(|meta| meta.pubkey == keys[index] && meta.is_writable);
+            unique_infos.push(unsafe { raw.info(is_signer, is_writable) });
+        }
+        let infos: Vec<AccountInfo> = metas
+            .iter()
+            .map(|meta| unique_infos[keys.iter().position(|key| *key == meta.pubkey).unwrap()].clone())
+            .collect();
+
+        let result = radiant::entry(&radiant::ID, &infos, data);
+
+        self.events = EVENTS.with(|events| events.borrow_mut().drain(..).collect());
+        self.logs = LOGS.with(|logs| logs.borrow_mut().drain(..).collect());
+        self.return_data = RETURN_DATA.with(|return_data| return_data.borrow_mut().take());
+
+        if result.is_ok() {
+            for (index, info) in unique_infos.iter().enumerate() {
+                if !info.is_writable {
+                    continue;
+                }
+                let data = info.try_borrow_data().unwrap().to_vec();
+                self.accounts.insert(keys[index], StoredAccount {
+                    lamports: info.lamports(),
+                    data,
+                    owner: *info.owner,
+                    executable: info.executable,
+                });
+            }
+        }
+
+        result
+    }
+
+    /// Decode the events of the last instruction of type `E`
+    pub fn events_of<E: anchor_lang::Event + AnchorDeserialize>(&self) -> Vec<E> {
+        self.events
+            .iter()
+            .filter(|data| data.starts_with(E::DISCRIMINATOR))
+            .map(|data| E::deserialize(&mut &data[E::DISCRIMINATOR.len()..]).unwrap())
+            .collect()
+    }
+
+    /// Decode the single event of type `E` from the last instruction
+    #[track_caller]
+    pub fn event<E: anchor_lang::Event + AnchorDeserialize>(&self) -> E {
+        let mut events = self.events_of::<E>();
+        assert_eq!(events.len(), 1, "expected exactly one event");
+        events.remove(0)
+    }
+
+    /// Decode the return data of the last instruction
+    #[track_caller]
+    pub fn returned<T: AnchorDeserialize>(&self) -> T {
+        let data = self.return_data.as_ref().expect("instruction returned data");
+        T::deserialize(&mut &data[..]).unwrap()
+    }
+
+    // ------------------------------------------------------------------------
+    // Reserves
+    // ------------------------------------------------------------------------
+
+    /// Add a reserve priced at `price_usd` (scaled by 10^6) with the given LTV
+    /// and liquidation threshold
+    pub fn add_reserve(&mut self, decimals: u8, price_usd: u64, ltv_bps: u16, liquidation_threshold_bps: u16) -> TestReserve {
+        self.add_reserve_with(decimals, price_usd, reserve_params(ltv_bps, liquidation_threshold_bps))
+            .expect("initialize_reserve")
+    }
+
+    /// Add a reserve with explicit initialization parameters
+    pub fn add_reserve_with(
+        &mut self,
+        decimals: u8,
+        price_usd: u64,
+        params: InitializeReserveParams,
+    ) -> std::result::Result<TestReserve, ProgramError> {
+        let mint = self.create_mint(decimals);
+        let oracle = self.create_oracle(price_usd as i64, -6);
+        let (key, _) = Pubkey::find_program_address(
+            &[b"reserve", self.market.as_ref(), mint.as_ref()],
+            &radiant::ID,
+        );
+        let (vault, _) = Pubkey::find_program_address(&[VAULT_SEED, key.as_ref()], &radiant::ID);
+        let (fee_receiver, _) = Pubkey::find_program_address(&[FEE_RECEIVER_SEED, key.as_ref()], &radiant::ID);
+
+        let reserve = TestReserve { key, mint, vault, fee_receiver, oracle, decimals };
+        let authority_token_account = if params.initial_seed_deposit.unwrap_or(0) > 0 {
+            let authority = self.authority;
+            Some(self.mint_to(&authority, &reserve, params.initial_seed_deposit.unwrap()))
+        } else {
+            None
+        };
+
+        self.process(
+            radiant::accounts::InitializeReserve {
+                authority: self.authority,
+                lending_market: self.market,
+                reserve: key,
+                token_mint: mint,
+                token_vault: vault,
+                fee_receiver,
+                oracle,
+                authority_token_account,
+                token_program: spl_token::ID,
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            },
+            radiant::instruction::InitializeReserve { params },
+        )?;
+
+        Ok(reserve)
+    }
+
+    // ------------------------------------------------------------------------
+    // Admin instructions
+    // ------------------------------------------------------------------------
+
+    pub fn update_reserve_config(
+        &mut self,
+        reserve: &TestReserve,
+        params: UpdateReserveConfigParams,
+    ) -> std::result::Result<(), ProgramError> {
+        self.process(
+            radiant::accounts::UpdateReserveConfig {
+                authority: self.authority,
+                lending_market: self.market,
+                reserve: reserve.key,
+            },
+            radiant::instruction::UpdateReserveConfig { params },
+        )
+    }
+
+    pub fn update_lending_market(&mut self, params: UpdateLendingMarketParams) -> std::result::Result<(), ProgramError> {
+        self.process(
+            radiant::accounts::UpdateLendingMarket {
+                authority: self.authority,
+                lending_market: self.market,
+            },
+            radiant::instruction::UpdateLendingMarket { params },
+        )
+    }
+
+    pub fn set_emergency_mode(&mut self, emergency: bool) -> std::result::Result<(), ProgramError> {
+        self.process(
+            radiant::accounts::SetEmergencyMode {
+                authority: self.authority,
+                lending_market: self.market,
+            },
+            radiant::instruction::SetEmergencyMode { emergency },
+        )
+    }
+
+    pub fn set_liquidations_halted(&mut self, halted: bool) -> std::result::Result<(), ProgramError> {
+        self.process(
+            radiant::accounts::SetLiquidationsHalted {
+                authority: self.authority,
+                lending_market: self.market,
+            },
+            radiant::instruction::SetLiquidationsHalted { halted },
+        )
+    }
+
+    pub fn set_allow_new_obligations(&mut self, allowed: bool) -> std::result::Result<(), ProgramError> {
+        self.process(
+            radiant::accounts::SetAllowNewObligations {
+                authority: self.authority,
+                lending_market: self.market,
+            },
+            radiant::instruction::SetAllowNewObligations { allowed },
+        )
+    }
+
+    pub fn add_whitelisted_depositor(&mut self, reserve: &TestReserve, depositor: &Pubkey) -> std::result::Result<(), ProgramError> {
+        self.process(
+            radiant::accounts::AddWhitelistedDepositor {
+                authority: self.authority,
+                lending_market: self.market,
+                reserve: reserve.key,
+                depositor: *depositor,
+                whitelisted_depositor: self.whitelist_key(reserve, depositor),
+                system_program: system_program::ID,
+            },
+            radiant::instruction::AddWhitelistedDepositor {},
+        )
+    }
+
+    pub fn remove_whitelisted_depositor(&mut self, reserve: &TestReserve, depositor: &Pubkey) -> std::result::Result<(), ProgramError> {
+        self.process(
+            radiant::accounts::RemoveWhitelistedDepositor {
+                authority: self.authority,
+                lending_market: self.market,
+                reserve: reserve.key,
+                whitelisted_depositor: self.whitelist_key(reserve, depositor),
+            },
+            radiant::instruction::RemoveWhitelistedDepositor {},
+        )
+    }
+
+    pub fn whitelist_key(&self, reserve: &TestReserve, depositor: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"whitelisted_depositor", reserve.key.as_ref(), depositor.as_ref()],
+            &radiant::ID,
+        )
+        .0
+    }
+
+    /// Collect `amount` of the reserve's protocol fees to `treasury`'s token account
+    pub fn collect_fees(
+        &mut self,
+        reserve: &TestReserve,
+        treasury: &Pubkey,
+        amount: u64,
+    ) -> std::result::Result<(), ProgramError> {
+        let accounts = self.collect_fees_accounts(reserve, treasury, None);
+        self.process(accounts, radiant::instruction::CollectFees { amount, amount_usd: None })
+    }
+
+    /// Collect `amount_usd` worth of the reserve's protocol fees at the oracle price
+    pub fn collect_fees_usd(
+        &mut self,
+        reserve: &TestReserve,
+        treasury: &Pubkey,
+        amount_usd: u64,
+    ) -> std::result::Result<(), ProgramError> {
+        let accounts = self.collect_fees_accounts(reserve, treasury, Some(reserve.oracle));
+        self.process(accounts, radiant::instruction::CollectFees { amount: 0, amount_usd: Some(amount_usd) })
+    }
+
+    fn collect_fees_accounts(
+        &mut self,
+        reserve: &TestReserve,
+        treasury: &Pubkey,
+        oracle: Option<Pubkey>,
+    ) -> radiant::accounts::CollectFees {
+        radiant::accounts::CollectFees {
+            authority: self.authority,
+            lending_market: self.market,
+            reserve: reserve.key,
+            reserve_vault: reserve.vault,
+            treasury_token_account: self.token_account(treasury, &reserve.mint),
+            treasury: *treasury,
+            token_program: spl_token::ID,
+            oracle,
+            quote_oracle: None,
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // User instructions
+    // ------------------------------------------------------------------------
+
+    /// Create a funded user
+    pub fn create_user(&mut self) -> Pubkey {
+        let user = new_key();
+        self.fund_lamports(&user);
+        user
+    }
+
+    pub fn obligation_key(&self, owner: &Pubkey, obligation_id: u8) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"obligation", self.market.as_ref(), owner.as_ref(), Obligation::id_seed(&obligation_id)],
+            &radiant::ID,
+        )
+        .0
+    }
+
+    pub fn initialize_obligation(&mut self, owner: &Pubkey, obligation_id: u8) -> std::result::Result<(), ProgramError> {
+        self.process(
+            radiant::accounts::InitializeObligation {
+                owner: *owner,
+                lending_market: self.market,
+                obligation: self.obligation_key(owner, obligation_id),
+                system_program: system_program::ID,
+            },
+            radiant::instruction::InitializeObligation { obligation_id },
+        )
+    }
+
+    /// Deposit into obligation 0, creating it if needed
+    pub fn deposit(&mut self, owner: &Pubkey, reserve: &TestReserve, amount: u64) -> std::result::Result<(), ProgramError> {
+        self.deposit_into(owner, reserve, amount, 0, true)
+    }
+
+    pub fn deposit_into(
+        &mut self,
+        owner: &Pubkey,
+        reserve: &TestReserve,
+        amount: u64,
+        obligation_id: u8,
+        create_obligation: bool,
+    ) -> std::result::Result<(), ProgramError> {
+        let user_token_account = self.token_account(owner, &reserve.mint);
+        if self.token_balance(&user_token_account) < amount {
+            self.set_token_balance(&user_token_account, amount);
+        }
+        // Pass the owner's whitelist entry while it exists
+        let whitelisted_depositor = Some(self.whitelist_key(reserve, owner))
+            .filter(|key| self.accounts.get(key).is_some_and(|account| account.lamports > 0));
+        self.process(
+            radiant::accounts::Deposit {
+                owner: *owner,
+                lending_market: self.market,
+                reserve: reserve.key,
+                obligation: self.obligation_key(owner, obligation_id),
+                user_token_account,
+                token_vault: reserve.vault,
+                token_program: spl_token::ID,
+                whitelisted_depositor,
+                system_program: system_program::ID,
+            },
+            radiant::instruction::Deposit { amount, obligation_id, create_obligation },
+        )
+    }
+
+    pub fn borrow(&mut self, owner: &Pubkey, reserve: &TestReserve, amount: u64) -> std::result::Result<(), ProgramError> {
+        let accounts = self.borrow_accounts(owner, reserve, None);
+        self.process(accounts, radiant::instruction::Borrow { amount })
+    }
+
+    /// Borrow instruction passing the instructions sysvar, for `process_transaction`
+    pub fn borrow_instruction(&mut self, owner: &Pubkey, reserve: &TestReserve, amount: u64) -> Instruction {
+        let accounts = self.borrow_accounts(owner, reserve, Some(sysvar::instructions::ID));
+        Instruction {
+            program_id: radiant::ID,
+            accounts: accounts.to_account_metas(None),
+            data: radiant::instruction::Borrow { amount }.data(),
+        }
+    }
+
+    fn borrow_accounts(
+        &mut self,
+        owner: &Pubkey,
+        reserve: &TestReserve,
+        instructions_sysvar: Option<Pubkey>,
+    ) -> radiant::accounts::Borrow {
+        radiant::accounts::Borrow {
+            owner: *owner,
+            lending_market: self.market,
+            reserve: reserve.key,
+            obligation: self.obligation_key(owner, 0),
+            token_vault: reserve.vault,
+            user_token_account: self.token_account(owner, &reserve.mint),
+            oracle: reserve.oracle,
+            quote_oracle: None,
+            instructions_sysvar,
+            token_program: spl_token::ID,
+        }
+    }
+
+    pub fn withdraw(&mut self, owner: &Pubkey, reserve: &TestReserve, amount: u64) -> std::result::Result<(), ProgramError> {
+        let user_token_account = self.token_account(owner, &reserve.mint);
+        self.process(
+            radiant::accounts::Withdraw {
+                owner: *owner,
+                lending_market: self.market,
+                reserve: reserve.key,
+                obligation: self.obligation_key(owner, 0),
+                token_vault: reserve.vault,
+                user_token_account,
+                token_program: spl_token::ID,
+            },
+            radiant::instruction::Withdraw { amount },
+        )
+    }
+
+    /// Repay `owner`'s debt from `payer`, minting the payer the tokens needed
+    pub fn repay(
+        &mut self,
+        payer: &Pubkey,
+        owner: &Pubkey,
+        reserve: &TestReserve,
+        amount: u64,
+        close_obligation: bool,
+    ) -> std::result::Result<(), ProgramError> {
+        let payer_token_account = self.token_account(payer, &reserve.mint);
+        let balance = self.token_balance(&payer_token_account);
+        self.set_token_balance(&payer_token_account, balance.max(amount).max(1_000_000_000_000));
+        self.process(
+            radiant::accounts::Repay {
+                payer: *payer,
+                lending_market: self.market,
+                reserve: reserve.key,
+                obligation: self.obligation_key(owner, 0),
+                payer_token_account,
+                token_vault: reserve.vault,
+                token_program: spl_token::ID,
+            },
+            radiant::instruction::Repay { amount, close_obligation },
+        )
+    }
+
+    pub fn set_deposit_collateral(
+        &mut self,
+        owner: &Pubkey,
+        reserve: &TestReserve,
+        use_as_collateral: bool,
+    ) -> std::result::Result<(), ProgramError> {
+        self.process(
+            radiant::accounts::SetDepositCollateral {
+                owner: *owner,
+                lending_market: self.market,
+                reserve: reserve.key,
+                obligation: self.obligation_key(owner, 0),
+            },
+            radiant::instruction::SetDepositCollateral { use_as_collateral },
+        )
+    }
+
+    // ------------------------------------------------------------------------
+    // Permissionless instructions
+    // ------------------------------------------------------------------------
+
+    pub fn refresh_reserve(&mut self, reserve: &TestReserve) -> std::result::Result<(), ProgramError> {
+        self.process(
+            radiant::accounts::RefreshReserve {
+                lending_market: self.market,
+                reserve: reserve.key,
+                oracle: Some(reserve.oracle),
+            },
+            radiant::instruction::RefreshReserve {},
+        )
+    }
+
+    /// Remaining accounts for refreshing an obligation: each position's
+    /// reserve and oracle, deposits first
+    pub fn refresh_accounts(&self, obligation: &Obligation) -> Vec<Pubkey> {
+        obligation
+            .deposits
+            .iter()
+            .map(|deposit| deposit.reserve)
+            .chain(obligation.borrows.iter().map(|borrow| borrow.reserve))
+            .flat_map(|reserve| {
+                let state: Reserve = self.get(&reserve);
+                [reserve, state.oracle]
+            })
+            .collect()
+    }
+
+    pub fn refresh_obligation(&mut self, owner: &Pubkey) -> std::result::Result<(), ProgramError> {
+        let key = self.obligation_key(owner, 0);
+        let remaining = self.refresh_accounts(&self.get(&key));
+        self.process_with_remaining(
+            radiant::accounts::RefreshObligation {
+                lending_market: self.market,
+                obligation: key,
+            },
+            radiant::instruction::RefreshObligation {},
+            &remaining,
+        )
+    }
+
+    /// Refresh instruction for `owner`'s obligation, for `process_transaction`
+    pub fn refresh_obligation_instruction(&self, owner: &Pubkey) -> Instruction {
+        let key = self.obligation_key(owner, 0);
+        let mut accounts = radiant::accounts::RefreshObligation {
+            lending_market: self.market,
+            obligation: key,
+        }
+        .to_account_metas(None);
+        accounts.extend(
+            self.refresh_accounts(&self.get(&key))
+                .into_iter()
+                .map(|key| AccountMeta::new_readonly(key, false)),
+        );
+        Instruction {
+            program_id: radiant::ID,
+            accounts,
+            data: radiant::instruction::RefreshObligation {}.data(),
+        }
+    }
+
+    /// Republish oracles, refresh every reserve of the obligation, then the obligation
+    #[track_caller]
+    pub fn refresh(&mut self, owner: &Pubkey) {
+        self.touch_oracles();
+        let obligation = self.obligation_state(owner);
+        let mut reserves: Vec<Pubkey> = obligation.deposits.iter().map(|deposit| deposit.reserve).collect();
+        reserves.extend(obligation.borrows.iter().map(|borrow| borrow.reserve));
+        reserves.dedup();
+        for reserve in reserves {
+            let oracle = self.get::<Reserve>(&reserve).oracle;
+            self.process(
+                radiant::accounts::RefreshReserve {
+                    lending_market: self.market,
+                    reserve,
+                    oracle: Some(oracle),
+                },
+                radiant::instruction::RefreshReserve {},
+            )
+            .expect("refresh_reserve");
+        }
+        self.refresh_obligation(owner).expect("refresh_obligation");
+    }
+
+    /// Liquidate `owner`'s obligation, minting the liquidator the repay tokens
+    pub fn liquidate(
+        &mut self,
+        liquidator: &Pubkey,
+        owner: &Pubkey,
+        repay_reserve: &TestReserve,
+        collateral_reserve: &TestReserve,
+        repay_amount: u64,
+    ) -> std::result::Result<(), ProgramError> {
+        let liquidator_repay_account = self.token_account(liquidator, &repay_reserve.mint);
+        let balance = self.token_balance(&liquidator_repay_account);
+        self.set_token_balance(&liquidator_repay_account, balance.max(1_000_000_000_000_000));
+        let liquidator_collateral_account = self.token_account(liquidator, &collateral_reserve.mint);
+        self.process(
+            radiant::accounts::Liquidate {
+                liquidator: *liquidator,
+                lending_market: self.market,
+                repay_reserve: repay_reserve.key,
+                collateral_reserve: collateral_reserve.key,
+                obligation: self.obligation_key(owner, 0),
+                repay_vault: repay_reserve.vault,
+                collateral_vault: collateral_reserve.vault,
+                collateral_fee_receiver: collateral_reserve.fee_receiver,
+                liquidator_repay_account,
+                liquidator_collateral_account,
+                token_program: spl_token::ID,
+            },
+            radiant::instruction::Liquidate { repay_amount },
+        )
+    }
+}
+
+impl Default for TestEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ============================================================================
+// PARAMETER DEFAULTS
+// ============================================================================
+
+/// Reserve initialization parameters with every optional setting unset
+pub fn reserve_params(ltv_bps: u16, liquidation_threshold_bps: u16) -> InitializeReserveParams {
+    InitializeReserveParams {
+        ltv_bps,
+        liquidation_threshold_bps,
+        deposit_limit: None,
+        borrow_limit: None,
+        max_utilization_bps: None,
+        withdrawal_reserve_bps: None,
+        utilization_fee_threshold_bps: None,
+        utilization_fee_max_bps: None,
+        max_single_deposit: None,
+        max_single_borrow: None,
+        reset_accrual_on_borrow_enable: None,
+        flash_loans_enabled: None,
+        permissioned: None,
+        seizable: None,
+        interest_rate_config: None,
+        emode_category: None,
+        emode_ltv_bps: None,
+        emode_liquidation_threshold_bps: None,
+        emode_liquidation_bonus_bps: None,
+        min_health_factor_for_borrow_bps: None,
+        max_oracle_staleness_slots: None,
+        initial_borrow_ltv_haircut_bps: None,
+        initial_borrow_haircut_slots: None,
+        collateral_activation_delay_slots: None,
+        liquidation_protocol_fee_bps: None,
+        close_factor_bps: None,
+        price_quote_oracle: None,
+        fee_treasury: None,
+        initial_seed_deposit: None,
+        interest_model: None,
+    }
+}
+
+/// Reserve config update parameters that change nothing
+pub fn update_reserve_params() -> UpdateReserveConfigParams {
+    UpdateReserveConfigParams {
+        ltv_bps: None,
+        liquidation_threshold_bps: None,
+        deposit_limit: None,
+        borrow_limit: None,
+        force_limits: None,
+        max_utilization_bps: None,
+        withdrawal_reserve_bps: None,
+        utilization_fee_threshold_bps: None,
+        utilization_fee_max_bps: None,
+        max_single_deposit: None,
+        max_single_borrow: None,
+        deposits_enabled: None,
+        borrows_enabled: None,
+        reset_accrual_on_borrow_enable: None,
+        flash_loans_enabled: None,
+        permissioned: None,
+        seizable: None,
+        emode_category: None,
+        emode_ltv_bps: None,
+        emode_liquidation_threshold_bps: None,
+        emode_liquidation_bonus_bps: None,
+        min_health_factor_for_borrow_bps: None,
+        max_oracle_staleness_slots: None,
+        initial_borrow_ltv_haircut_bps: None,
+        initial_borrow_haircut_slots: None,
+        collateral_activation_delay_slots: None,
+        liquidation_protocol_fee_bps: None,
+        close_factor_bps: None,
+        fee_treasury: None,
+        negative_supply_rate_bps: None,
+        optimal_utilization_bps: None,
+        base_rate_bps: None,
+        slope1_bps: None,
+        slope2_bps: None,
+        reserve_factor_bps: None,
+        low_util_threshold_bps: None,
+        low_util_supply_subsidy_bps: None,
+        extra_kinks: None,
+    }
+}
+
+/// Lending market update parameters that change nothing
+pub fn update_market_params() -> UpdateLendingMarketParams {
+    UpdateLendingMarketParams {
+        close_factor_bps: None,
+        liquidation_bonus_bps: None,
+        protocol_fee_bps: None,
+        allow_looping: None,
+        max_liquidations_per_slot: None,
+        route_liquidation_fees_to_reserve: None,
+        min_config_update_interval_slots: None,
+        max_obligation_borrow_usd: None,
+        liquidation_hysteresis_bps: None,
+        require_refresh_in_transaction: None,
+        max_liquidation_refresh_age_slots: None,
+        emit_action_blocked_events: None,
+        borrow_capacity_buffer_bps: None,
+        borrow_warning_health_bps: None,
+        liquidation_bonus_decay_extra_bps: None,
+        liquidation_bonus_decay_slots: None,
+        max_obligation_deposits: None,
+        max_obligation_borrows: None,
+    }
+}
+
+// ============================================================================
+// COMMON SCENARIOS
+// ============================================================================
+
+/// USDC-like stable reserve: 6 decimals, $1, 80% LTV, 85% threshold
+pub fn add_usdc(env: &mut TestEnv) -> TestReserve {
+    env.add_reserve(6, 1_000_000, 8000, 8500)
+}
+
+/// SOL-like volatile reserve: 9 decimals, $100, 75% LTV, 80% threshold
+pub fn add_sol(env: &mut TestEnv) -> TestReserve {
+    env.add_reserve(9, 100_000_000, 7500, 8000)
+}
+
+/// Stablecoin reserve in e-mode category 1: like `add_usdc`, but 95% LTV,
+/// 97% threshold and a 1% liquidation bonus against other stables
+pub fn add_stable(env: &mut TestEnv) -> TestReserve {
+    env.add_reserve_with(6, 1_000_000, InitializeReserveParams {
+        emode_category: Some(1),
+        emode_ltv_bps: Some(9500),
+        emode_liquidation_threshold_bps: Some(9700),
+        emode_liquidation_bonus_bps: Some(100),
+        ..reserve_params(8000, 8500)
+    })
+    .expect("initialize_reserve")
+}
+
+/// Fund a reserve with liquidity from a fresh depositor
+pub fn supply(env: &mut TestEnv, reserve: &TestReserve, amount: u64) -> Pubkey {
+    let depositor = env.create_user();
+    env.deposit(&depositor, reserve, amount).expect("supply deposit");
+    depositor
+}
+
+/// A borrower with `collateral_amount` of `collateral` deposited and refreshed
+pub fn borrower_with_collateral(env: &mut TestEnv, collateral: &TestReserve, collateral_amount: u64) -> Pubkey {
+    let borrower = env.create_user();
+    env.deposit(&borrower, collateral, collateral_amount).expect("collateral deposit");
+    env.refresh(&borrower);
+    borrower
+}
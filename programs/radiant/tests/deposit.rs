@@ -0,0 +1,254 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::program_error::ProgramError;
+use anchor_lang::system_program;
+use anchor_spl::token::spl_token;
+use common::*;
+use radiant::constants::MAX_OBLIGATION_DEPOSITS;
+use radiant::events::ObligationInitialized;
+use radiant::instructions::{
+    DepositError, InitializeLendingMarketParams, UpdateLendingMarketError, UpdateLendingMarketParams,
+    UpdateReserveConfigParams,
+};
+use radiant::state::Obligation;
+
+/// Deposit signed by `owner` into an arbitrary obligation account
+fn deposit_into_account(
+    env: &mut TestEnv,
+    owner: &Pubkey,
+    reserve: &TestReserve,
+    obligation: Pubkey,
+    amount: u64,
+) -> Result<(), ProgramError> {
+    let user_token_account = env.mint_to(owner, reserve, amount);
+    env.process(
+        radiant::accounts::Deposit {
+            owner: *owner,
+            lending_market: env.market,
+            reserve: reserve.key,
+            obligation,
+            user_token_account,
+            token_vault: reserve.vault,
+            token_program: spl_token::ID,
+            whitelisted_depositor: None,
+            system_program: system_program::ID,
+        },
+        radiant::instruction::Deposit { amount, obligation_id: 0, create_obligation: true },
+    )
+}
+
+#[test]
+fn first_deposit_creates_the_obligation() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let owner = env.create_user();
+
+    env.deposit_into(&owner, &usdc, 1_000_000, 3, true).unwrap();
+
+    let key = env.obligation_key(&owner, 3);
+    let event: ObligationInitialized = env.event();
+    assert_eq!(event.obligation, key);
+    assert_eq!(event.obligation_id, 3);
+
+    let obligation: Obligation = env.get(&key);
+    assert_eq!(obligation.owner, owner);
+    assert_eq!(obligation.lending_market, env.market);
+    assert_eq!(obligation.obligation_id, 3);
+    assert_eq!(obligation.deposits.len(), 1);
+    assert_eq!(obligation.deposits[0].deposited_amount, 1_000_000);
+    assert_eq!(env.reserve_state(&usdc).liquidity.depositor_count, 1);
+
+    // A second deposit reuses the obligation without reinitializing it
+    env.deposit_into(&owner, &usdc, 2_000_000, 3, true).unwrap();
+    assert!(env.events_of::<ObligationInitialized>().is_empty());
+    let obligation: Obligation = env.get(&key);
+    assert_eq!(obligation.deposits[0].deposited_amount, 3_000_000);
+    assert_eq!(env.reserve_state(&usdc).liquidity.depositor_count, 1);
+}
+
+#[test]
+fn deposit_without_create_requires_an_existing_obligation() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let owner = env.create_user();
+
+    assert_error(
+        env.deposit_into(&owner, &usdc, 1_000_000, 0, false),
+        DepositError::ObligationNotInitialized,
+    );
+    env.deposit_into(&owner, &usdc, 1_000_000, 0, true).unwrap();
+    env.deposit_into(&owner, &usdc, 1_000_000, 0, false).unwrap();
+}
+
+#[test]
+fn deposit_creation_respects_paused_obligations() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let owner = env.create_user();
+    env.update_market(|market| market.allow_new_obligations = false);
+
+    assert_error(env.deposit(&owner, &usdc, 1_000_000), DepositError::NewObligationsPaused);
+}
+
+#[test]
+fn deposit_rejects_another_owners_obligation() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let victim = env.create_user();
+    let attacker = env.create_user();
+    env.deposit(&victim, &usdc, 1_000_000).unwrap();
+
+    // Not the attacker's PDA
+    let victim_obligation = env.obligation_key(&victim, 0);
+    assert_error(
+        deposit_into_account(&mut env, &attacker, &usdc, victim_obligation, 1_000_000),
+        anchor_lang::error::ErrorCode::ConstraintSeeds,
+    );
+
+    // An obligation at the attacker's address recording another owner
+    env.deposit(&attacker, &usdc, 1_000_000).unwrap();
+    env.update_obligation(&attacker, |obligation| obligation.owner = victim);
+    assert_error(env.deposit(&attacker, &usdc, 1_000_000), DepositError::InvalidObligationOwner);
+}
+
+#[test]
+fn deposit_rejects_another_markets_obligation() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let owner = env.create_user();
+    env.deposit(&owner, &usdc, 1_000_000).unwrap();
+
+    // The same owner's obligation in a second market
+    let other_authority = env.create_user();
+    let (other_market, _) =
+        Pubkey::find_program_address(&[b"lending_market", other_authority.as_ref()], &radiant::ID);
+    env.process(
+        radiant::accounts::InitializeLendingMarket {
+            authority: other_authority,
+            lending_market: other_market,
+            treasury: new_key(),
+            system_program: system_program::ID,
+        },
+        radiant::instruction::InitializeLendingMarket {
+            params: InitializeLendingMarketParams {
+                close_factor_bps: None,
+                liquidation_bonus_bps: None,
+                protocol_fee_bps: None,
+            },
+        },
+    )
+    .unwrap();
+    let other_obligation = Pubkey::find_program_address(
+        &[b"obligation", other_market.as_ref(), owner.as_ref(), &[0]],
+        &radiant::ID,
+    )
+    .0;
+    assert_error(
+        deposit_into_account(&mut env, &owner, &usdc, other_obligation, 1_000_000),
+        anchor_lang::error::ErrorCode::ConstraintSeeds,
+    );
+
+    // An obligation at this market's address recording the other market
+    env.update_obligation(&owner, |obligation| obligation.lending_market = other_market);
+    assert_error(env.deposit(&owner, &usdc, 1_000_000), DepositError::InvalidObligation);
+}
+
+#[test]
+fn one_owner_can_hold_several_obligations() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    let owner = env.create_user();
+
+    env.deposit_into(&owner, &usdc, 1_000_000, 0, true).unwrap();
+    env.deposit_into(&owner, &sol, 2_000_000, 1, true).unwrap();
+
+    let first_key = env.obligation_key(&owner, 0);
+    let second_key = env.obligation_key(&owner, 1);
+    assert_ne!(first_key, second_key);
+
+    let first: Obligation = env.get(&first_key);
+    let second: Obligation = env.get(&second_key);
+    assert_eq!((first.obligation_id, second.obligation_id), (0, 1));
+    assert_eq!(first.owner, second.owner);
+    assert_eq!(first.deposits.len(), 1);
+    assert_eq!(first.deposits[0].reserve, usdc.key);
+    assert_eq!(second.deposits.len(), 1);
+    assert_eq!(second.deposits[0].reserve, sol.key);
+
+    // Positions stay isolated: withdrawing from the first leaves the second alone
+    env.withdraw(&owner, &usdc, 0).unwrap();
+    assert!(env.get::<Obligation>(&first_key).deposits.is_empty());
+    assert_eq!(env.get::<Obligation>(&second_key).deposits[0].deposited_amount, 2_000_000);
+}
+
+#[test]
+fn permissioned_reserve_accepts_only_whitelisted_depositors() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let existing = supply(&mut env, &usdc, 100_000_000);
+    env.update_reserve_config(&usdc, UpdateReserveConfigParams {
+        permissioned: Some(true),
+        ..update_reserve_params()
+    })
+    .unwrap();
+    let applicant = env.create_user();
+
+    assert_error(env.deposit(&applicant, &usdc, 1_000_000), DepositError::DepositorNotWhitelisted);
+    assert_error(
+        env.deposit_into(&existing, &usdc, 1_000_000, 0, false),
+        DepositError::DepositorNotWhitelisted,
+    );
+    // Existing positions can still leave
+    env.withdraw(&existing, &usdc, 50_000_000).unwrap();
+
+    env.add_whitelisted_depositor(&usdc, &applicant).unwrap();
+    env.deposit(&applicant, &usdc, 1_000_000).unwrap();
+
+    env.remove_whitelisted_depositor(&usdc, &applicant).unwrap();
+    assert_error(
+        env.deposit_into(&applicant, &usdc, 1_000_000, 0, false),
+        DepositError::DepositorNotWhitelisted,
+    );
+}
+
+#[test]
+fn deposits_past_the_position_value_cap_are_rejected() {
+    let mut env = TestEnv::new();
+    // Whole-token reserve at $1: 10^12 tokens is the largest valued position
+    let dollar = env.add_reserve(0, 1_000_000, 5000, 6000);
+    let owner = env.create_user();
+    env.deposit(&owner, &dollar, 1_000_000_000_000).unwrap();
+    env.refresh(&owner);
+
+    assert_error(env.deposit(&owner, &dollar, 1_000), DepositError::ValueTooLarge);
+    let obligation = env.obligation_state(&owner);
+    assert_eq!(obligation.deposits[0].deposited_amount, 1_000_000_000_000);
+}
+
+#[test]
+fn market_deposit_position_limit_applies_below_the_hard_cap() {
+    let mut env = TestEnv::new();
+    env.update_lending_market(UpdateLendingMarketParams {
+        max_obligation_deposits: Some(2),
+        ..update_market_params()
+    })
+    .unwrap();
+    let reserves: Vec<_> = (0..3).map(|_| add_usdc(&mut env)).collect();
+    let owner = env.create_user();
+    env.deposit(&owner, &reserves[0], 1_000_000).unwrap();
+    env.deposit(&owner, &reserves[1], 1_000_000).unwrap();
+
+    assert_error(env.deposit(&owner, &reserves[2], 1_000_000), DepositError::MaxDepositsReached);
+    // Topping up an existing position is not a new position
+    env.deposit(&owner, &reserves[0], 1_000_000).unwrap();
+
+    assert_error(
+        env.update_lending_market(UpdateLendingMarketParams {
+            max_obligation_deposits: Some(MAX_OBLIGATION_DEPOSITS as u8 + 1),
+            ..update_market_params()
+        }),
+        UpdateLendingMarketError::InvalidObligationPositionLimit,
+    );
+}
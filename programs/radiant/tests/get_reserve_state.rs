@@ -0,0 +1,64 @@
+mod common;
+
+use anchor_lang::prelude::{ProgramError, Pubkey};
+use common::*;
+use radiant::instructions::{GetReserveStateError, ReserveState};
+
+fn get_reserve_state(
+    env: &mut TestEnv,
+    reserve: &TestReserve,
+    oracle: Option<Pubkey>,
+) -> Result<ReserveState, ProgramError> {
+    env.process(
+        radiant::accounts::GetReserveState {
+            reserve: reserve.key,
+            oracle,
+            quote_oracle: None,
+        },
+        radiant::instruction::GetReserveState {},
+    )?;
+    Ok(env.returned())
+}
+
+#[test]
+fn snapshot_reports_reserve_metrics() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    supply(&mut env, &sol, 100_000_000_000);
+    let borrower = borrower_with_collateral(&mut env, &usdc, 10_000_000_000);
+    env.borrow(&borrower, &sol, 25_000_000_000).unwrap();
+
+    let state = get_reserve_state(&mut env, &sol, Some(sol.oracle)).unwrap();
+    let reserve = env.reserve_state(&sol);
+    assert_eq!(state.utilization_bps, 2500);
+    assert_eq!(state.total_deposits, 100_000_000_000);
+    assert_eq!(state.total_borrows, 25_000_000_000);
+    assert_eq!(state.available_liquidity, 75_000_000_000);
+    assert_eq!(
+        state.borrow_rate_bps,
+        reserve.liquidity.current_borrow_rate_bps
+    );
+    assert_eq!(
+        state.supply_rate_bps,
+        reserve.liquidity.current_supply_rate_bps
+    );
+    assert_eq!(state.price_usd, 100_000_000);
+    assert_eq!(state.last_update_slot, reserve.last_update_slot);
+}
+
+#[test]
+fn snapshot_price_is_optional() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+
+    assert_eq!(
+        get_reserve_state(&mut env, &sol, None).unwrap().price_usd,
+        0
+    );
+    assert_eq!(
+        get_reserve_state(&mut env, &sol, Some(usdc.oracle)).err(),
+        Some(anchor_error(GetReserveStateError::InvalidOracle))
+    );
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use common::*;
+use radiant::instructions::{DepositError, InitializeObligationError};
+
+#[test]
+fn paused_market_rejects_new_obligations_only() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    supply(&mut env, &usdc, 1_000_000_000);
+    let existing = env.create_user();
+    env.initialize_obligation(&existing, 0).unwrap();
+    let newcomer = env.create_user();
+
+    env.set_allow_new_obligations(false).unwrap();
+    assert!(!env.market_state().allow_new_obligations);
+    assert_error(
+        env.initialize_obligation(&newcomer, 0),
+        InitializeObligationError::NewObligationsPaused,
+    );
+    assert_error(env.deposit(&newcomer, &usdc, 1_000_000), DepositError::NewObligationsPaused);
+    assert_error(
+        env.initialize_obligation(&existing, 1),
+        InitializeObligationError::NewObligationsPaused,
+    );
+
+    // Existing obligations keep operating
+    env.deposit_into(&existing, &usdc, 100_000_000, 0, false).unwrap();
+    env.withdraw(&existing, &usdc, 50_000_000).unwrap();
+
+    env.set_allow_new_obligations(true).unwrap();
+    env.initialize_obligation(&newcomer, 0).unwrap();
+    assert_eq!(env.obligation_state(&newcomer).owner, newcomer);
+}
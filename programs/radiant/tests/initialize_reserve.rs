@@ -0,0 +1,186 @@
+mod common;
+
+use anchor_spl::token::spl_token;
+use common::*;
+use radiant::constants::{MAX_LIQUIDATION_THRESHOLD_BPS, MAX_LTV_BPS, MAX_RESERVE_FACTOR_BPS};
+use radiant::instructions::{
+    BorrowError, DepositError, InitializeReserveParams, InterestRateConfigParams, RepayError, ReserveError,
+    UpdateConfigError, UpdateReserveConfigParams, WithdrawError,
+};
+
+fn add_seeded_usdc(env: &mut TestEnv, seed: u64) -> TestReserve {
+    env.add_reserve_with(6, 1_000_000, InitializeReserveParams {
+        initial_seed_deposit: Some(seed),
+        ..reserve_params(8000, 8500)
+    })
+    .unwrap()
+}
+
+#[test]
+fn seed_deposit_is_locked_in_the_reserve() {
+    let mut env = TestEnv::new();
+    let usdc = add_seeded_usdc(&mut env, 1_000_000);
+
+    let reserve = env.reserve_state(&usdc);
+    assert_eq!(reserve.liquidity.total_deposits, 1_000_000);
+    assert_eq!(reserve.liquidity.locked_seed_deposit, 1_000_000);
+    assert_eq!(reserve.liquidity.depositor_count, 0);
+    assert_eq!(env.token_balance(&usdc.vault), 1_000_000);
+}
+
+#[test]
+fn first_depositor_cannot_withdraw_the_seed() {
+    let mut env = TestEnv::new();
+    let usdc = add_seeded_usdc(&mut env, 1_000_000);
+    let sol = add_sol(&mut env);
+
+    // A minimum first deposit, then interest accrues on borrowed seed liquidity
+    let depositor = env.create_user();
+    env.deposit(&depositor, &usdc, 1_000).unwrap();
+    let borrower = borrower_with_collateral(&mut env, &sol, 10_000_000_000);
+    env.borrow(&borrower, &usdc, 500_000).unwrap();
+    env.advance_seconds(365 * 86_400);
+    env.touch_oracles();
+    env.refresh_reserve(&usdc).unwrap();
+    env.repay(&borrower, &borrower, &usdc, 0, false).unwrap();
+
+    // The dust depositor earns only its own share, not the seed's interest
+    env.refresh(&depositor);
+    env.withdraw(&depositor, &usdc, 0).unwrap();
+    let withdrawn = env.balance_of(&depositor, &usdc);
+    assert!((1_000..1_100).contains(&withdrawn));
+
+    let reserve = env.reserve_state(&usdc);
+    assert!(reserve.liquidity.total_deposits >= 1_000_000);
+    assert!(env.token_balance(&usdc.vault) >= 1_000_000);
+}
+
+#[test]
+fn token_moving_handlers_reject_an_unpinned_token_program() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    assert_eq!(env.reserve_state(&usdc).token_program_id, spl_token::ID);
+    supply(&mut env, &usdc, 1_000_000_000);
+    let borrower = borrower_with_collateral(&mut env, &sol, 10_000_000_000);
+    env.borrow(&borrower, &usdc, 100_000_000).unwrap();
+
+    // Pinned to another token program, the classic program is refused
+    env.update_reserve(&usdc, |reserve| reserve.token_program_id = new_key());
+    assert_error(env.deposit(&borrower, &usdc, 1_000_000), DepositError::InvalidTokenProgram);
+    assert_error(env.borrow(&borrower, &usdc, 1_000_000), BorrowError::InvalidTokenProgram);
+    assert_error(
+        env.repay(&borrower, &borrower, &usdc, 1_000_000, false),
+        RepayError::InvalidTokenProgram,
+    );
+    env.update_reserve(&sol, |reserve| reserve.token_program_id = new_key());
+    assert_error(env.withdraw(&borrower, &sol, 1_000_000), WithdrawError::InvalidTokenProgram);
+}
+
+fn params_with_reserve_factor(ltv_bps: u16, liquidation_threshold_bps: u16, reserve_factor_bps: u16) -> InitializeReserveParams {
+    InitializeReserveParams {
+        interest_rate_config: Some(InterestRateConfigParams {
+            optimal_utilization_bps: 8000,
+            base_rate_bps: 0,
+            slope1_bps: 400,
+            slope2_bps: 30_000,
+            reserve_factor_bps,
+            low_util_threshold_bps: 0,
+            low_util_supply_subsidy_bps: 0,
+            extra_kinks: None,
+        }),
+        ..reserve_params(ltv_bps, liquidation_threshold_bps)
+    }
+}
+
+#[test]
+fn initialization_enforces_protocol_maxima() {
+    let mut env = TestEnv::new();
+    let cases = [
+        (MAX_LTV_BPS + 1, MAX_LIQUIDATION_THRESHOLD_BPS, MAX_RESERVE_FACTOR_BPS, ReserveError::InvalidLtv),
+        (9000, MAX_LIQUIDATION_THRESHOLD_BPS + 1, MAX_RESERVE_FACTOR_BPS, ReserveError::InvalidLiquidationThreshold),
+        (9000, 9500, MAX_RESERVE_FACTOR_BPS + 1, ReserveError::InvalidReserveFactor),
+    ];
+    for (ltv, threshold, reserve_factor, error) in cases {
+        let params = params_with_reserve_factor(ltv, threshold, reserve_factor);
+        assert_eq!(env.add_reserve_with(6, 1_000_000, params).err(), Some(anchor_error(error)));
+    }
+    env.add_reserve_with(
+        6,
+        1_000_000,
+        params_with_reserve_factor(MAX_LTV_BPS, MAX_LIQUIDATION_THRESHOLD_BPS, MAX_RESERVE_FACTOR_BPS),
+    )
+    .unwrap();
+}
+
+#[test]
+fn config_updates_enforce_protocol_maxima() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let cases = [
+        (
+            UpdateReserveConfigParams {
+                ltv_bps: Some(MAX_LTV_BPS + 1),
+                liquidation_threshold_bps: Some(MAX_LIQUIDATION_THRESHOLD_BPS),
+                ..update_reserve_params()
+            },
+            UpdateConfigError::InvalidLtv,
+        ),
+        (
+            UpdateReserveConfigParams {
+                liquidation_threshold_bps: Some(MAX_LIQUIDATION_THRESHOLD_BPS + 1),
+                ..update_reserve_params()
+            },
+            UpdateConfigError::InvalidLiquidationThreshold,
+        ),
+        (
+            UpdateReserveConfigParams {
+                reserve_factor_bps: Some(MAX_RESERVE_FACTOR_BPS + 1),
+                ..update_reserve_params()
+            },
+            UpdateConfigError::InvalidReserveFactor,
+        ),
+    ];
+    for (params, error) in cases {
+        assert_error(env.update_reserve_config(&usdc, params), error);
+    }
+    assert_eq!(env.reserve_state(&usdc).config.ltv_bps, 8000);
+}
+
+#[test]
+fn borrow_limit_may_not_exceed_deposit_limit() {
+    let mut env = TestEnv::new();
+    let limits = |deposit_limit, borrow_limit| InitializeReserveParams {
+        deposit_limit: Some(deposit_limit),
+        borrow_limit: Some(borrow_limit),
+        ..reserve_params(8000, 8500)
+    };
+    assert_eq!(
+        env.add_reserve_with(6, 1_000_000, limits(1_000, 1_001)).err(),
+        Some(anchor_error(ReserveError::BorrowLimitAboveDepositLimit))
+    );
+    // Zero means unlimited on either side
+    env.add_reserve_with(6, 1_000_000, limits(0, 1_001)).unwrap();
+    let usdc = env.add_reserve_with(6, 1_000_000, limits(1_000, 1_000)).unwrap();
+
+    assert_error(
+        env.update_reserve_config(&usdc, UpdateReserveConfigParams {
+            borrow_limit: Some(1_001),
+            ..update_reserve_params()
+        }),
+        UpdateConfigError::BorrowLimitAboveDepositLimit,
+    );
+    assert_error(
+        env.update_reserve_config(&usdc, UpdateReserveConfigParams {
+            deposit_limit: Some(999),
+            ..update_reserve_params()
+        }),
+        UpdateConfigError::BorrowLimitAboveDepositLimit,
+    );
+    env.update_reserve_config(&usdc, UpdateReserveConfigParams {
+        deposit_limit: Some(2_000),
+        borrow_limit: Some(1_500),
+        ..update_reserve_params()
+    })
+    .unwrap();
+}
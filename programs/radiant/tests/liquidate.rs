@@ -0,0 +1,470 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use radiant::constants::{DEFAULT_LIQUIDATION_BONUS_BPS, MAX_RESERVE_STALENESS_SLOTS};
+use radiant::events::{LiquidationEvent, ReserveRefreshed};
+use radiant::instructions::{
+    LiquidateError, UpdateConfigError, UpdateLendingMarketParams, UpdateReserveConfigParams,
+};
+
+/// Borrower with 10 SOL of collateral and 700 USDC of debt, made unhealthy
+/// by SOL falling to $85 and refreshed at the current slot
+fn unhealthy_borrower(env: &mut TestEnv) -> (TestReserve, TestReserve, Pubkey) {
+    let usdc = add_usdc(env);
+    let sol = add_sol(env);
+    supply(env, &usdc, 1_000_000_000_000);
+
+    let borrower = borrower_with_collateral(env, &sol, 10_000_000_000);
+    env.borrow(&borrower, &usdc, 700_000_000).unwrap();
+
+    env.advance_slots(1);
+    env.set_price(&sol, 85_000_000);
+    env.refresh(&borrower);
+    (usdc, sol, borrower)
+}
+
+#[test]
+fn tiny_repay_does_not_block_liquidation() {
+    let mut env = TestEnv::new();
+    let (usdc, sol, borrower) = unhealthy_borrower(&mut env);
+    let refreshed_slot = env.obligation_state(&borrower).last_update_slot;
+
+    // A third party front-runs the liquidator with a dust repay
+    env.advance_slots(2);
+    env.touch_oracles();
+    env.refresh_reserve(&usdc).unwrap();
+    env.refresh_reserve(&sol).unwrap();
+    let ally = env.create_user();
+    env.repay(&ally, &borrower, &usdc, 1, false).unwrap();
+
+    let obligation = env.obligation_state(&borrower);
+    assert_eq!(obligation.last_update_slot, refreshed_slot);
+    assert!(obligation.borrowed_value_usd < 700_000_000);
+
+    let liquidator = env.create_user();
+    env.liquidate(&liquidator, &borrower, &usdc, &sol, 100_000_000).unwrap();
+    assert!(env.balance_of(&liquidator, &sol) > 0);
+}
+
+#[test]
+fn repay_does_not_extend_the_liquidation_refresh_window() {
+    let mut env = TestEnv::new();
+    let (usdc, sol, borrower) = unhealthy_borrower(&mut env);
+
+    // Past the refresh window, a repay must not make the obligation fresh again
+    env.advance_slots(151);
+    env.touch_oracles();
+    env.refresh_reserve(&usdc).unwrap();
+    env.refresh_reserve(&sol).unwrap();
+    let ally = env.create_user();
+    env.repay(&ally, &borrower, &usdc, 1, false).unwrap();
+
+    let liquidator = env.create_user();
+    assert_error(
+        env.liquidate(&liquidator, &borrower, &usdc, &sol, 100_000_000),
+        LiquidateError::ObligationStale,
+    );
+
+    env.refresh(&borrower);
+    env.liquidate(&liquidator, &borrower, &usdc, &sol, 100_000_000).unwrap();
+}
+
+#[test]
+fn liquidations_per_slot_are_limited() {
+    let mut env = TestEnv::new();
+    let (usdc, sol, borrower) = unhealthy_borrower(&mut env);
+    env.update_lending_market(UpdateLendingMarketParams {
+        max_liquidations_per_slot: Some(2),
+        ..update_market_params()
+    })
+    .unwrap();
+
+    let liquidator = env.create_user();
+    let keeper = env.create_user();
+    env.liquidate(&liquidator, &borrower, &usdc, &sol, 10_000_000).unwrap();
+    env.liquidate(&keeper, &borrower, &usdc, &sol, 10_000_000).unwrap();
+    assert_error(
+        env.liquidate(&liquidator, &borrower, &usdc, &sol, 10_000_000),
+        LiquidateError::LiquidationLimitReached,
+    );
+
+    // The counter resets in the next slot
+    env.advance_slots(1);
+    env.liquidate(&liquidator, &borrower, &usdc, &sol, 10_000_000).unwrap();
+    assert_eq!(env.obligation_state(&borrower).liquidations_in_slot, 1);
+}
+
+#[test]
+fn halted_liquidations_are_rejected() {
+    let mut env = TestEnv::new();
+    let (usdc, sol, borrower) = unhealthy_borrower(&mut env);
+    let liquidator = env.create_user();
+
+    env.set_liquidations_halted(true).unwrap();
+    assert_error(
+        env.liquidate(&liquidator, &borrower, &usdc, &sol, 100_000_000),
+        LiquidateError::LiquidationsHalted,
+    );
+
+    // Emergency mode alone keeps liquidations enabled
+    env.set_liquidations_halted(false).unwrap();
+    env.set_emergency_mode(true).unwrap();
+    env.liquidate(&liquidator, &borrower, &usdc, &sol, 100_000_000).unwrap();
+}
+
+#[test]
+fn liquidation_requires_fresh_reserves() {
+    let mut env = TestEnv::new();
+    let (usdc, sol, borrower) = unhealthy_borrower(&mut env);
+    let liquidator = env.create_user();
+    env.advance_slots(MAX_RESERVE_STALENESS_SLOTS + 1);
+    env.refresh(&borrower);
+    let stale_slot = env.slot() - MAX_RESERVE_STALENESS_SLOTS - 1;
+
+    // An old borrow index would understate the debt being repaid
+    env.update_reserve(&usdc, |reserve| reserve.last_update_slot = stale_slot);
+    assert_error(
+        env.liquidate(&liquidator, &borrower, &usdc, &sol, 100_000_000),
+        LiquidateError::ReserveStale,
+    );
+    env.refresh_reserve(&usdc).unwrap();
+
+    env.update_reserve(&sol, |reserve| reserve.last_update_slot = stale_slot);
+    assert_error(
+        env.liquidate(&liquidator, &borrower, &usdc, &sol, 100_000_000),
+        LiquidateError::ReserveStale,
+    );
+    env.refresh_reserve(&sol).unwrap();
+
+    env.liquidate(&liquidator, &borrower, &usdc, &sol, 100_000_000).unwrap();
+}
+
+#[test]
+fn liquidation_accrues_interest_since_the_last_refresh() {
+    let mut env = TestEnv::new();
+    let (usdc, sol, borrower) = unhealthy_borrower(&mut env);
+    let before = env.reserve_state(&usdc);
+
+    // Reserves still fresh enough to liquidate, but interest has built up
+    env.advance_seconds(60);
+    env.touch_oracles();
+    env.refresh_obligation(&borrower).unwrap();
+    let liquidator = env.create_user();
+    env.liquidate(&liquidator, &borrower, &usdc, &sol, 100_000_000).unwrap();
+
+    let after = env.reserve_state(&usdc);
+    assert!(after.liquidity.cumulative_borrow_index > before.liquidity.cumulative_borrow_index);
+    assert!(after.liquidity.lifetime_borrow_interest_paid > before.liquidity.lifetime_borrow_interest_paid);
+    assert_eq!(after.last_update_slot, env.slot());
+
+    // Nothing left to accrue for the elapsed period
+    env.refresh_reserve(&usdc).unwrap();
+    assert_eq!(
+        env.reserve_state(&usdc).liquidity.cumulative_borrow_index,
+        after.liquidity.cumulative_borrow_index
+    );
+}
+
+#[test]
+fn stable_pairs_liquidate_at_the_reduced_bonus() {
+    let mut env = TestEnv::new();
+    let usdc = add_stable(&mut env);
+    let dai = add_stable(&mut env);
+    supply(&mut env, &dai, 1_000_000_000_000);
+
+    // 1000 USDC backs 950 DAI in e-mode; DAI at $1.03 breaks the 97% threshold
+    let borrower = borrower_with_collateral(&mut env, &usdc, 1_000_000_000);
+    env.borrow(&borrower, &dai, 950_000_000).unwrap();
+    env.advance_slots(1);
+    env.set_price(&dai, 1_030_000);
+    env.refresh(&borrower);
+
+    let liquidator = env.create_user();
+    env.liquidate(&liquidator, &borrower, &dai, &usdc, 100_000_000).unwrap();
+    let event: LiquidationEvent = env.event();
+    assert_eq!(event.liquidation_bonus_bps, 100);
+    // 100 DAI at $1.03 plus 1%
+    assert_eq!(event.collateral_seized, 104_030_000);
+}
+
+#[test]
+fn stable_volatile_pairs_keep_the_market_bonus() {
+    let mut env = TestEnv::new();
+    let usdc = add_stable(&mut env);
+    let sol = add_sol(&mut env);
+    supply(&mut env, &usdc, 1_000_000_000_000);
+
+    let borrower = borrower_with_collateral(&mut env, &sol, 10_000_000_000);
+    env.borrow(&borrower, &usdc, 700_000_000).unwrap();
+    env.advance_slots(1);
+    env.set_price(&sol, 85_000_000);
+    env.refresh(&borrower);
+
+    let liquidator = env.create_user();
+    env.liquidate(&liquidator, &borrower, &usdc, &sol, 85_000_000).unwrap();
+    let event: LiquidationEvent = env.event();
+    assert_eq!(event.liquidation_bonus_bps, DEFAULT_LIQUIDATION_BONUS_BPS);
+    // $85 of debt buys 1 SOL plus 5%
+    assert_eq!(event.collateral_seized, 1_050_000_000);
+}
+
+#[test]
+fn liquidation_fee_goes_to_the_fee_receiver_by_default() {
+    let mut env = TestEnv::new();
+    let (usdc, sol, borrower) = unhealthy_borrower(&mut env);
+    let fees_before = env.reserve_state(&sol).liquidity.accumulated_protocol_fees;
+
+    let liquidator = env.create_user();
+    env.liquidate(&liquidator, &borrower, &usdc, &sol, 100_000_000).unwrap();
+    let event: LiquidationEvent = env.event();
+
+    assert!(event.protocol_fee > 0);
+    assert_eq!(env.token_balance(&sol.fee_receiver), event.protocol_fee);
+    assert_eq!(env.reserve_state(&sol).liquidity.accumulated_protocol_fees, fees_before);
+}
+
+#[test]
+fn liquidation_fee_can_be_routed_to_reserve_fees() {
+    let mut env = TestEnv::new();
+    let (usdc, sol, borrower) = unhealthy_borrower(&mut env);
+    env.update_lending_market(UpdateLendingMarketParams {
+        route_liquidation_fees_to_reserve: Some(true),
+        ..update_market_params()
+    })
+    .unwrap();
+    let fees_before = env.reserve_state(&sol).liquidity.accumulated_protocol_fees;
+    let vault_before = env.token_balance(&sol.vault);
+
+    let liquidator = env.create_user();
+    env.liquidate(&liquidator, &borrower, &usdc, &sol, 100_000_000).unwrap();
+    let event: LiquidationEvent = env.event();
+
+    // The fee stays in the vault, collectable through collect_fees
+    assert!(event.protocol_fee > 0);
+    assert_eq!(env.token_balance(&sol.fee_receiver), 0);
+    assert_eq!(env.reserve_state(&sol).liquidity.accumulated_protocol_fees, fees_before + event.protocol_fee);
+    assert_eq!(env.token_balance(&sol.vault), vault_before - event.collateral_seized + event.protocol_fee);
+}
+
+#[test]
+fn liquidation_rejects_misowned_reserve_accounts() {
+    let mut env = TestEnv::new();
+    let (usdc, sol, borrower) = unhealthy_borrower(&mut env);
+    let liquidator = env.create_user();
+    let market = env.market;
+
+    env.set_token_owner(&sol.fee_receiver, &liquidator);
+    assert_error(
+        env.liquidate(&liquidator, &borrower, &usdc, &sol, 100_000_000),
+        LiquidateError::InvalidReserveAccounts,
+    );
+    env.set_token_owner(&sol.fee_receiver, &market);
+
+    env.set_token_owner(&usdc.vault, &liquidator);
+    assert_error(
+        env.liquidate(&liquidator, &borrower, &usdc, &sol, 100_000_000),
+        LiquidateError::InvalidReserveAccounts,
+    );
+    env.set_token_owner(&usdc.vault, &usdc.key);
+    env.liquidate(&liquidator, &borrower, &usdc, &sol, 100_000_000).unwrap();
+}
+
+#[test]
+fn hysteresis_restores_health_and_waits_for_a_deeper_drop() {
+    let mut env = TestEnv::new();
+    env.update_lending_market(UpdateLendingMarketParams {
+        close_factor_bps: Some(1000),
+        liquidation_hysteresis_bps: Some(200),
+        ..update_market_params()
+    })
+    .unwrap();
+    let (usdc, sol, borrower) = unhealthy_borrower(&mut env);
+    let liquidator = env.create_user();
+
+    // Past the 10% close factor, up to the repay restoring health to 1.02
+    env.liquidate(&liquidator, &borrower, &usdc, &sol, 700_000_000).unwrap();
+    let repaid = env.event::<LiquidationEvent>().repay_amount;
+    assert!(repaid > 70_000_000);
+    let restored = env.obligation_state(&borrower).last_liquidation_health_bps;
+    assert!(restored.abs_diff(10_200) <= 1, "restored to {restored}");
+
+    // A wick to ~0.99 stays inside the band
+    env.advance_slots(1);
+    env.set_price(&sol, 82_500_000);
+    env.refresh(&borrower);
+    assert!(env.obligation_state(&borrower).is_liquidatable());
+    assert_error(
+        env.liquidate(&liquidator, &borrower, &usdc, &sol, 700_000_000),
+        LiquidateError::WithinHysteresisBand,
+    );
+
+    // Below 0.98 liquidation re-arms
+    env.advance_slots(1);
+    env.set_price(&sol, 80_000_000);
+    env.refresh(&borrower);
+    env.liquidate(&liquidator, &borrower, &usdc, &sol, 700_000_000).unwrap();
+}
+
+/// Amount repaid by one oversized liquidation with a 20% market close factor
+/// and the given close factor overrides on the USDC and SOL reserves
+fn repaid_with_close_factors(usdc_close_factor: Option<u16>, sol_close_factor: Option<u16>) -> u64 {
+    let mut env = TestEnv::new();
+    env.update_lending_market(UpdateLendingMarketParams {
+        close_factor_bps: Some(2000),
+        ..update_market_params()
+    })
+    .unwrap();
+    let (usdc, sol, borrower) = unhealthy_borrower(&mut env);
+    for (reserve, close_factor) in [(&usdc, usdc_close_factor), (&sol, sol_close_factor)] {
+        env.update_reserve_config(reserve, UpdateReserveConfigParams {
+            close_factor_bps: Some(close_factor),
+            ..update_reserve_params()
+        })
+        .unwrap();
+    }
+    let liquidator = env.create_user();
+    env.liquidate(&liquidator, &borrower, &usdc, &sol, 700_000_000).unwrap();
+    env.event::<LiquidationEvent>().repay_amount
+}
+
+#[test]
+fn repay_reserve_close_factor_overrides_the_market() {
+    assert_eq!(repaid_with_close_factors(None, None), 140_000_000);
+    assert_eq!(repaid_with_close_factors(Some(4000), None), 280_000_000);
+    // Only the repay reserve's override applies
+    assert_eq!(repaid_with_close_factors(None, Some(4000)), 140_000_000);
+
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    for invalid in [0, 10_001] {
+        assert_error(
+            env.update_reserve_config(&usdc, UpdateReserveConfigParams {
+                close_factor_bps: Some(Some(invalid)),
+                ..update_reserve_params()
+            }),
+            UpdateConfigError::InvalidCloseFactor,
+        );
+    }
+}
+
+#[test]
+fn seizing_all_collateral_leaves_the_rest_as_bad_debt() {
+    let mut env = TestEnv::new();
+    env.update_lending_market(UpdateLendingMarketParams {
+        close_factor_bps: Some(10_000),
+        ..update_market_params()
+    })
+    .unwrap();
+    let (usdc, sol, borrower) = unhealthy_borrower(&mut env);
+
+    // 10 SOL at $50 cannot cover the $700 debt plus bonus
+    env.advance_slots(1);
+    env.set_price(&sol, 50_000_000);
+    env.refresh(&borrower);
+    let liquidator = env.create_user();
+    env.liquidate(&liquidator, &borrower, &usdc, &sol, 700_000_000).unwrap();
+
+    let event = env.event::<LiquidationEvent>();
+    assert_eq!(event.collateral_seized, 10_000_000_000);
+    assert!(event.repay_amount < 500_000_000);
+    assert_eq!(event.residual_bad_debt, 700_000_000 - event.repay_amount);
+
+    let obligation = env.obligation_state(&borrower);
+    assert!(obligation.deposits.is_empty());
+    assert_eq!(obligation.borrows.len(), 1);
+    assert_eq!(obligation.borrows[0].borrowed_amount, event.residual_bad_debt);
+}
+
+#[test]
+fn liquidations_accumulate_on_both_reserves() {
+    let mut env = TestEnv::new();
+    let (usdc, sol, borrower) = unhealthy_borrower(&mut env);
+    let liquidator = env.create_user();
+
+    let mut repaid = 0;
+    let mut seized = 0;
+    for _ in 0..2 {
+        env.liquidate(&liquidator, &borrower, &usdc, &sol, 50_000_000).unwrap();
+        let event = env.event::<LiquidationEvent>();
+        repaid += event.repay_amount as u128;
+        seized += event.collateral_seized as u128;
+        assert_eq!(event.repay_reserve_lifetime_liquidation_repaid, repaid);
+        assert_eq!(event.collateral_reserve_lifetime_liquidation_seized, seized);
+        env.advance_slots(1);
+        env.refresh(&borrower);
+    }
+
+    let usdc_liquidity = env.reserve_state(&usdc).liquidity;
+    assert_eq!(usdc_liquidity.lifetime_liquidation_repaid, 100_000_000);
+    assert_eq!(usdc_liquidity.liquidation_count, 2);
+    let sol_liquidity = env.reserve_state(&sol).liquidity;
+    assert_eq!(sol_liquidity.lifetime_liquidation_seized, seized);
+    assert_eq!(sol_liquidity.liquidation_count, 2);
+
+    env.advance_slots(1);
+    env.refresh_reserve(&usdc).unwrap();
+    let refreshed = env.event::<ReserveRefreshed>();
+    assert_eq!(refreshed.lifetime_liquidation_repaid, 100_000_000);
+    assert_eq!(refreshed.liquidation_count, 2);
+}
+
+#[test]
+fn non_seizable_collateral_forces_another_collateral() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    let eth = env.add_reserve(8, 2_000_000_000, 7500, 8000);
+    supply(&mut env, &usdc, 1_000_000_000_000);
+    let borrower = borrower_with_collateral(&mut env, &sol, 10_000_000_000);
+    env.deposit(&borrower, &eth, 10_000_000).unwrap();
+    env.refresh(&borrower);
+    env.borrow(&borrower, &usdc, 850_000_000).unwrap();
+    env.update_reserve_config(&sol, UpdateReserveConfigParams {
+        seizable: Some(false),
+        ..update_reserve_params()
+    })
+    .unwrap();
+
+    env.advance_slots(1);
+    env.set_price(&sol, 85_000_000);
+    env.refresh(&borrower);
+
+    let liquidator = env.create_user();
+    assert_error(
+        env.liquidate(&liquidator, &borrower, &usdc, &sol, 50_000_000),
+        LiquidateError::CollateralNotSeizable,
+    );
+    env.liquidate(&liquidator, &borrower, &usdc, &eth, 50_000_000).unwrap();
+    assert!(env.balance_of(&liquidator, &eth) > 0);
+    assert_eq!(env.balance_of(&liquidator, &sol), 0);
+}
+
+/// Liquidation bonus paid `slots` after the borrower became liquidatable,
+/// with 400 BPS of extra bonus decaying over 100 slots
+fn bonus_after_unhealthy_slots(slots: u64) -> u16 {
+    let mut env = TestEnv::new();
+    let (usdc, sol, borrower) = unhealthy_borrower(&mut env);
+    env.update_lending_market(UpdateLendingMarketParams {
+        liquidation_bonus_decay_extra_bps: Some(400),
+        liquidation_bonus_decay_slots: Some(100),
+        ..update_market_params()
+    })
+    .unwrap();
+
+    env.advance_slots(slots);
+    env.refresh(&borrower);
+    let liquidator = env.create_user();
+    env.liquidate(&liquidator, &borrower, &usdc, &sol, 10_000_000).unwrap();
+    env.event::<LiquidationEvent>().liquidation_bonus_bps
+}
+
+#[test]
+fn liquidation_bonus_decays_to_the_base() {
+    let base = DEFAULT_LIQUIDATION_BONUS_BPS;
+    assert_eq!(bonus_after_unhealthy_slots(0), base + 400);
+    assert_eq!(bonus_after_unhealthy_slots(25), base + 300);
+    assert_eq!(bonus_after_unhealthy_slots(50), base + 200);
+    assert_eq!(bonus_after_unhealthy_slots(100), base);
+    assert_eq!(bonus_after_unhealthy_slots(1_000), base);
+}
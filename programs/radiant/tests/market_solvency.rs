@@ -0,0 +1,95 @@
+mod common;
+
+use anchor_lang::prelude::{ProgramError, Pubkey};
+use common::*;
+use radiant::constants::MAX_OBLIGATION_STALENESS_SLOTS;
+use radiant::events::MarketSolvency;
+use radiant::instructions::{MarketSolvencyError, MarketTotalsError};
+
+fn market_solvency(
+    env: &mut TestEnv,
+    reserves: &[&TestReserve],
+    owners: &[&Pubkey],
+) -> Result<MarketSolvency, ProgramError> {
+    let mut remaining: Vec<_> = reserves.iter().flat_map(|reserve| [reserve.key, reserve.oracle]).collect();
+    remaining.extend(owners.iter().map(|owner| env.obligation_key(owner, 0)));
+    env.touch_oracles();
+    env.process_with_remaining(
+        radiant::accounts::MarketSolvency { lending_market: env.market },
+        radiant::instruction::MarketSolvency {},
+        &remaining,
+    )?;
+    Ok(env.event())
+}
+
+/// $1000 of USDC supplied and $500 of it borrowed against $1000 of SOL,
+/// returning the lender and the borrower
+fn half_borrowed_market(env: &mut TestEnv) -> (TestReserve, TestReserve, Pubkey, Pubkey) {
+    let usdc = add_usdc(env);
+    let sol = add_sol(env);
+    let lender = supply(env, &usdc, 1_000_000_000);
+    let borrower = borrower_with_collateral(env, &sol, 10_000_000_000);
+    env.borrow(&borrower, &usdc, 500_000_000).unwrap();
+    env.refresh(&lender);
+    env.refresh(&borrower);
+    (usdc, sol, lender, borrower)
+}
+
+#[test]
+fn borrower_collateral_covering_debt_is_solvent() {
+    let mut env = TestEnv::new();
+    let (usdc, sol, lender, borrower) = half_borrowed_market(&mut env);
+
+    // The lender's supply backs no debt and is not counted as collateral
+    let report = market_solvency(&mut env, &[&usdc, &sol], &[&lender, &borrower]).unwrap();
+    assert_eq!(report.total_collateral_usd, 1_000_000_000);
+    assert_eq!(report.total_debt_usd, 500_000_000);
+    assert_eq!(report.collateralization_ratio_bps, 20_000);
+    assert!(report.solvent);
+
+    assert_eq!(
+        market_solvency(&mut env, &[&usdc], &[]).err(),
+        Some(anchor_error(MarketTotalsError::IncompleteReserves))
+    );
+}
+
+#[test]
+fn collateral_price_drop_below_debt_is_reported_insolvent() {
+    let mut env = TestEnv::new();
+    let (usdc, sol, _, borrower) = half_borrowed_market(&mut env);
+
+    env.advance_slots(1);
+    env.set_price(&sol, 40_000_000);
+    env.refresh(&borrower);
+
+    let report = market_solvency(&mut env, &[&usdc, &sol], &[&borrower]).unwrap();
+    assert_eq!(report.total_collateral_usd, 400_000_000);
+    assert_eq!(report.total_debt_usd, 500_000_000);
+    assert_eq!(report.collateralization_ratio_bps, 8_000);
+    assert!(!report.solvent);
+}
+
+#[test]
+fn obligations_must_be_fresh_and_passed_once() {
+    let mut env = TestEnv::new();
+    let (usdc, sol, _, borrower) = half_borrowed_market(&mut env);
+
+    // Leaving the borrower out understates collateral rather than debt
+    let report = market_solvency(&mut env, &[&usdc, &sol], &[]).unwrap();
+    assert_eq!(report.total_collateral_usd, 0);
+    assert!(!report.solvent);
+
+    assert_eq!(
+        market_solvency(&mut env, &[&usdc, &sol], &[&borrower, &borrower]).err(),
+        Some(anchor_error(MarketSolvencyError::DuplicateObligation))
+    );
+
+    env.advance_slots(MAX_OBLIGATION_STALENESS_SLOTS + 1);
+    env.touch_oracles();
+    env.refresh_reserve(&usdc).unwrap();
+    env.refresh_reserve(&sol).unwrap();
+    assert_eq!(
+        market_solvency(&mut env, &[&usdc, &sol], &[&borrower]).err(),
+        Some(anchor_error(MarketSolvencyError::ObligationStale))
+    );
+}
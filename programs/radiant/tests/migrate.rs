@@ -0,0 +1,318 @@
+mod common;
+
+use anchor_lang::prelude::{ProgramError, Pubkey, Rent};
+use anchor_lang::{system_program, AnchorSerialize, Discriminator, Space};
+use common::*;
+use radiant::constants::MAX_ORACLE_STALENESS_SLOTS;
+use radiant::events::AccountMigrated;
+use radiant::state::legacy::*;
+use radiant::state::{LendingMarket, Obligation, Reserve};
+
+/// Replace a program account with `state` in its version 1 layout
+fn downgrade<T: AnchorSerialize + Space>(env: &mut TestEnv, key: &Pubkey, discriminator: &[u8], state: &T) {
+    let mut data = discriminator.to_vec();
+    state.serialize(&mut data).unwrap();
+    data.resize(8 + T::INIT_SPACE, 0);
+    let account = StoredAccount {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: radiant::ID,
+        executable: false,
+    };
+    env.set_account(*key, account);
+}
+
+fn legacy_market(market: &LendingMarket) -> LendingMarketV1 {
+    LendingMarketV1 {
+        version: 1,
+        bump: market.bump,
+        authority: market.authority,
+        treasury: market.treasury,
+        emergency_mode: market.emergency_mode,
+        close_factor_bps: market.close_factor_bps,
+        liquidation_bonus_bps: market.liquidation_bonus_bps,
+        protocol_fee_bps: market.protocol_fee_bps,
+        reserves_count: market.reserves_count,
+        _padding: [0u8; 128],
+    }
+}
+
+fn legacy_reserve(reserve: &Reserve) -> ReserveV1 {
+    let rates = reserve.config.interest_rate_config;
+    ReserveV1 {
+        version: 1,
+        bump: reserve.bump,
+        lending_market: reserve.lending_market,
+        token_mint: reserve.token_mint,
+        token_decimals: reserve.token_decimals,
+        token_vault: reserve.token_vault,
+        fee_receiver: reserve.fee_receiver,
+        oracle: reserve.oracle,
+        last_update_slot: reserve.last_update_slot,
+        last_update_timestamp: reserve.last_update_timestamp,
+        config: ReserveConfigV1 {
+            ltv_bps: reserve.config.ltv_bps,
+            liquidation_threshold_bps: reserve.config.liquidation_threshold_bps,
+            deposit_limit: reserve.config.deposit_limit,
+            borrow_limit: reserve.config.borrow_limit,
+            deposits_enabled: reserve.config.deposits_enabled,
+            borrows_enabled: reserve.config.borrows_enabled,
+            interest_rate_config: InterestRateConfigV1 {
+                optimal_utilization_bps: rates.optimal_utilization_bps,
+                base_rate_bps: rates.base_rate_bps,
+                slope1_bps: rates.slope1_bps,
+                slope2_bps: rates.slope2_bps,
+                reserve_factor_bps: rates.reserve_factor_bps,
+            },
+        },
+        liquidity: ReserveLiquidityV1 {
+            total_deposits: reserve.liquidity.total_deposits,
+            total_borrows: reserve.liquidity.total_borrows,
+            accumulated_protocol_fees: reserve.liquidity.accumulated_protocol_fees,
+            cumulative_borrow_index: reserve.liquidity.cumulative_borrow_index,
+            cumulative_supply_index: reserve.liquidity.cumulative_supply_index,
+            current_borrow_rate_bps: reserve.liquidity.current_borrow_rate_bps,
+            current_supply_rate_bps: reserve.liquidity.current_supply_rate_bps,
+        },
+        _padding: [0u8; 128],
+    }
+}
+
+fn legacy_obligation(obligation: &Obligation) -> ObligationV1 {
+    ObligationV1 {
+        version: 1,
+        bump: obligation.bump,
+        lending_market: obligation.lending_market,
+        owner: obligation.owner,
+        last_update_slot: obligation.last_update_slot,
+        deposits: obligation.deposits.iter()
+            .map(|deposit| ObligationCollateralV1 {
+                reserve: deposit.reserve,
+                deposited_amount: deposit.deposited_amount,
+                supply_index_snapshot: deposit.supply_index_snapshot,
+                market_value_usd: deposit.market_value_usd,
+            })
+            .collect(),
+        borrows: obligation.borrows.iter()
+            .map(|borrow| ObligationLiquidityV1 {
+                reserve: borrow.reserve,
+                borrowed_amount: borrow.borrowed_amount,
+                borrow_index_snapshot: borrow.borrow_index_snapshot,
+                market_value_usd: borrow.market_value_usd,
+            })
+            .collect(),
+        deposited_value_usd: obligation.deposited_value_usd,
+        borrowed_value_usd: obligation.borrowed_value_usd,
+        allowed_borrow_value_usd: obligation.allowed_borrow_value_usd,
+        unhealthy_borrow_value_usd: obligation.unhealthy_borrow_value_usd,
+        _padding: [0u8; 64],
+    }
+}
+
+fn migrate_market(env: &mut TestEnv, payer: &Pubkey) -> Result<AccountMigrated, ProgramError> {
+    env.process(
+        radiant::accounts::MigrateLendingMarket {
+            payer: *payer,
+            lending_market: env.market,
+            system_program: system_program::ID,
+        },
+        radiant::instruction::MigrateLendingMarket {},
+    )?;
+    Ok(env.event())
+}
+
+fn migrate_reserve(env: &mut TestEnv, payer: &Pubkey, reserve: &TestReserve, vault: &Pubkey) -> Result<AccountMigrated, ProgramError> {
+    env.process(
+        radiant::accounts::MigrateReserve {
+            payer: *payer,
+            reserve: reserve.key,
+            token_vault: *vault,
+            system_program: system_program::ID,
+        },
+        radiant::instruction::MigrateReserve {},
+    )?;
+    Ok(env.event())
+}
+
+fn migrate_obligation(env: &mut TestEnv, payer: &Pubkey, obligation: &Pubkey) -> Result<AccountMigrated, ProgramError> {
+    env.process(
+        radiant::accounts::MigrateObligation {
+            payer: *payer,
+            obligation: *obligation,
+            system_program: system_program::ID,
+        },
+        radiant::instruction::MigrateObligation {},
+    )?;
+    Ok(env.event())
+}
+
+#[test]
+fn legacy_market_migrates_with_initialization_defaults() {
+    let mut env = TestEnv::new();
+    add_usdc(&mut env);
+    let market = env.market_state();
+    let key = env.market;
+    downgrade(&mut env, &key, LendingMarket::DISCRIMINATOR, &legacy_market(&market));
+    let payer = env.create_user();
+
+    let event = migrate_market(&mut env, &payer).unwrap();
+    assert_eq!((event.from_version, event.to_version), (1, LendingMarket::CURRENT_VERSION));
+    assert_eq!(event.data_len as usize, 8 + LendingMarket::INIT_SPACE);
+
+    let account = env.account(&env.market).unwrap();
+    assert_eq!(account.data.len(), 8 + LendingMarket::INIT_SPACE);
+    assert!(Rent::default().is_exempt(account.lamports, account.data.len()));
+
+    let migrated = env.market_state();
+    assert_eq!(migrated.authority, market.authority);
+    assert_eq!(migrated.close_factor_bps, market.close_factor_bps);
+    assert_eq!(migrated.reserves_count, 1);
+    assert!(migrated.allow_new_obligations);
+    assert!(!migrated.halt_liquidations);
+
+    // The current layout is left alone
+    assert_eq!(migrate_market(&mut env, &payer).err(), Some(anchor_error(MigrationError::AlreadyMigrated)));
+}
+
+#[test]
+fn legacy_reserve_rebuilds_total_deposits_from_the_vault() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    let lender = supply(&mut env, &usdc, 1_000_000_000);
+    let borrower = borrower_with_collateral(&mut env, &sol, 10_000_000_000);
+    env.borrow(&borrower, &usdc, 500_000_000).unwrap();
+    env.advance_seconds(365 * 24 * 60 * 60);
+    env.touch_oracles();
+    env.refresh_reserve(&usdc).unwrap();
+
+    // Version 1 left supply interest out of total_deposits
+    let reserve = env.reserve_state(&usdc);
+    let mut legacy = legacy_reserve(&reserve);
+    let supply_interest = 10_000_000;
+    legacy.liquidity.total_deposits -= supply_interest;
+    downgrade(&mut env, &usdc.key, Reserve::DISCRIMINATOR, &legacy);
+    let payer = env.create_user();
+
+    assert_eq!(
+        migrate_reserve(&mut env, &payer, &usdc, &sol.vault).err(),
+        Some(anchor_error(MigrationError::InvalidTokenVault))
+    );
+    assert_eq!(
+        migrate_reserve(&mut env, &payer, &sol, &sol.vault).err(),
+        Some(anchor_error(MigrationError::AlreadyMigrated))
+    );
+
+    let event = migrate_reserve(&mut env, &payer, &usdc, &usdc.vault).unwrap();
+    assert_eq!((event.from_version, event.to_version), (1, Reserve::CURRENT_VERSION));
+
+    let migrated = env.reserve_state(&usdc);
+    assert_eq!(migrated.liquidity.total_deposits, reserve.liquidity.total_deposits);
+    assert_eq!(migrated.liquidity.total_borrows, reserve.liquidity.total_borrows);
+    assert_eq!(migrated.liquidity.cumulative_supply_index, reserve.liquidity.cumulative_supply_index);
+    assert_eq!(migrated.config.ltv_bps, reserve.config.ltv_bps);
+    assert!(migrated.config.seizable);
+    assert_eq!(migrated.config.max_oracle_staleness_slots, MAX_ORACLE_STALENESS_SLOTS as u32);
+    assert_eq!(migrated.token_program_id, anchor_spl::token::ID);
+
+    // Depositors can withdraw everything they earned
+    env.refresh(&lender);
+    env.mint_to(&borrower, &usdc, 100_000_000);
+    env.repay(&borrower, &borrower, &usdc, 0, false).unwrap();
+    env.refresh(&lender);
+    env.withdraw(&lender, &usdc, 0).unwrap();
+    assert_eq!(env.balance_of(&lender, &usdc), reserve.liquidity.total_deposits);
+    assert_eq!(env.reserve_state(&usdc).liquidity.total_deposits, 0);
+}
+
+#[test]
+fn legacy_obligation_migrates_to_a_refreshable_obligation() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    supply(&mut env, &usdc, 1_000_000_000);
+    let borrower = borrower_with_collateral(&mut env, &sol, 10_000_000_000);
+    env.borrow(&borrower, &usdc, 500_000_000).unwrap();
+    let obligation = env.obligation_state(&borrower);
+    let key = env.obligation_key(&borrower, 0);
+    downgrade(&mut env, &key, Obligation::DISCRIMINATOR, &legacy_obligation(&obligation));
+    let payer = env.create_user();
+
+    let event = migrate_obligation(&mut env, &payer, &key).unwrap();
+    assert_eq!((event.from_version, event.to_version), (1, Obligation::SORTED_VERSION));
+    assert_eq!(env.account(&key).unwrap().data.len(), 8 + Obligation::INIT_SPACE);
+
+    let migrated = env.obligation_state(&borrower);
+    assert_eq!(migrated.obligation_id, 0);
+    assert_eq!(migrated.last_update_slot, 0);
+    assert_eq!(migrated.deposits[0].deposited_amount, 10_000_000_000);
+    assert!(migrated.deposits[0].use_as_collateral);
+    assert_eq!(migrated.deposits[0].pending_amount, 0);
+    assert_eq!(migrated.borrows[0].borrowed_amount, 500_000_000);
+    assert_eq!(migrated.borrows[0].principal, 500_000_000);
+
+    env.refresh(&borrower);
+    assert_eq!(env.obligation_state(&borrower).borrowed_value_usd, obligation.borrowed_value_usd);
+
+    assert_eq!(
+        migrate_obligation(&mut env, &payer, &usdc.key).err(),
+        Some(anchor_error(MigrationError::InvalidLegacyAccount))
+    );
+}
+
+#[test]
+fn migrated_obligation_keeps_its_version_1_address_as_obligation_0() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    supply(&mut env, &usdc, 1_000_000_000);
+    let borrower = borrower_with_collateral(&mut env, &sol, 10_000_000_000);
+
+    // Version 1 derived the address without an id
+    let (legacy_key, legacy_bump) = Pubkey::find_program_address(
+        &[b"obligation", env.market.as_ref(), borrower.as_ref()],
+        &radiant::ID,
+    );
+    assert_eq!(env.obligation_key(&borrower, 0), legacy_key);
+    assert_ne!(env.obligation_key(&borrower, 1), legacy_key);
+
+    let obligation = env.obligation_state(&borrower);
+    assert_eq!(obligation.bump, legacy_bump);
+    downgrade(&mut env, &legacy_key, Obligation::DISCRIMINATOR, &legacy_obligation(&obligation));
+    let payer = env.create_user();
+    migrate_obligation(&mut env, &payer, &legacy_key).unwrap();
+
+    // Every instruction finds the migrated obligation at its old address
+    env.refresh(&borrower);
+    env.borrow(&borrower, &usdc, 100_000_000).unwrap();
+    env.repay(&borrower, &borrower, &usdc, 0, false).unwrap();
+    env.refresh(&borrower);
+    env.withdraw(&borrower, &sol, 0).unwrap();
+    assert_eq!(env.balance_of(&borrower, &sol), 10_000_000_000);
+}
+
+#[test]
+fn migrated_deposits_count_as_collateral_until_disabled() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    let owner = supply(&mut env, &usdc, 1_000_000_000);
+    env.deposit(&owner, &sol, 10_000_000_000).unwrap();
+    env.refresh(&owner);
+    let obligation = env.obligation_state(&owner);
+
+    let key = env.obligation_key(&owner, 0);
+    downgrade(&mut env, &key, Obligation::DISCRIMINATOR, &legacy_obligation(&obligation));
+    let payer = env.create_user();
+    migrate_obligation(&mut env, &payer, &key).unwrap();
+    assert!(env.obligation_state(&owner).deposits.iter().all(|deposit| deposit.use_as_collateral));
+
+    // Borrowing power is what it was before the migration
+    env.refresh(&owner);
+    assert_eq!(env.obligation_state(&owner).allowed_borrow_value_usd, obligation.allowed_borrow_value_usd);
+
+    env.set_deposit_collateral(&owner, &sol, false).unwrap();
+    env.refresh(&owner);
+    let usdc_only = env.obligation_state(&owner).allowed_borrow_value_usd;
+    assert!(usdc_only > 0 && usdc_only < obligation.allowed_borrow_value_usd);
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use anchor_lang::prelude::{ProgramError, Pubkey};
+use common::*;
+use radiant::events::ObligationNormalized;
+use radiant::state::Obligation;
+
+fn normalize(env: &mut TestEnv, owner: &Pubkey) -> Result<ObligationNormalized, ProgramError> {
+    env.process(
+        radiant::accounts::NormalizeObligation {
+            lending_market: env.market,
+            obligation: env.obligation_key(owner, 0),
+        },
+        radiant::instruction::NormalizeObligation {},
+    )?;
+    Ok(env.event())
+}
+
+#[test]
+fn normalization_sorts_positions_and_is_idempotent() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    let eth = env.add_reserve(8, 2_000_000_000, 7500, 8000);
+    supply(&mut env, &usdc, 1_000_000_000);
+    let owner = borrower_with_collateral(&mut env, &sol, 10_000_000_000);
+    env.deposit(&owner, &eth, 100_000_000).unwrap();
+    env.refresh(&owner);
+    env.borrow(&owner, &usdc, 100_000_000).unwrap();
+
+    // An obligation from before positions were kept sorted
+    env.update_obligation(&owner, |obligation| {
+        obligation.deposits.sort_by_key(|deposit| std::cmp::Reverse(deposit.reserve));
+        obligation.version = 1;
+    });
+    let unsorted: Obligation = env.obligation_state(&owner);
+
+    assert!(normalize(&mut env, &owner).unwrap().reordered);
+    let normalized = env.obligation_state(&owner);
+    assert_eq!(normalized.version, Obligation::SORTED_VERSION);
+    assert!(normalized.deposits.windows(2).all(|pair| pair[0].reserve < pair[1].reserve));
+    let mut expected: Vec<_> = unsorted.deposits.iter().map(|d| (d.reserve, d.deposited_amount)).collect();
+    expected.sort();
+    let actual: Vec<_> = normalized.deposits.iter().map(|d| (d.reserve, d.deposited_amount)).collect();
+    assert_eq!(actual, expected);
+    assert_eq!(normalized.borrows[0].borrowed_amount, unsorted.borrows[0].borrowed_amount);
+
+    // Running it again changes nothing
+    let data = env.account(&env.obligation_key(&owner, 0)).unwrap().data.clone();
+    let again = normalize(&mut env, &owner).unwrap();
+    assert!(!again.reordered);
+    assert_eq!(again.version, Obligation::SORTED_VERSION);
+    assert_eq!(env.account(&env.obligation_key(&owner, 0)).unwrap().data, data);
+
+    env.refresh(&owner);
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use anchor_lang::prelude::{ProgramError, Pubkey};
+use common::*;
+use radiant::instructions::PreviewDebtError;
+
+fn preview_debt(env: &mut TestEnv, owner: &Pubkey, reserve: &TestReserve) -> Result<u64, ProgramError> {
+    env.process(
+        radiant::accounts::PreviewDebt {
+            reserve: reserve.key,
+            obligation: env.obligation_key(owner, 0),
+        },
+        radiant::instruction::PreviewDebt {},
+    )?;
+    Ok(env.returned())
+}
+
+#[test]
+fn preview_includes_unrefreshed_accrual() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    supply(&mut env, &usdc, 1_000_000_000);
+    let borrower = borrower_with_collateral(&mut env, &sol, 100_000_000_000);
+    env.borrow(&borrower, &usdc, 500_000_000).unwrap();
+    assert_eq!(preview_debt(&mut env, &borrower, &usdc).unwrap(), 500_000_000);
+
+    env.advance_seconds(30 * 86_400);
+    let reserve_before = env.account(&usdc.key).unwrap().data.clone();
+    let previewed = preview_debt(&mut env, &borrower, &usdc).unwrap();
+    assert!(previewed > 500_000_000);
+    assert_eq!(env.account(&usdc.key).unwrap().data, reserve_before);
+
+    // Matches the debt once the reserve is actually refreshed
+    env.refresh_reserve(&usdc).unwrap();
+    assert_eq!(preview_debt(&mut env, &borrower, &usdc).unwrap(), previewed);
+}
+
+#[test]
+fn preview_requires_a_borrow_in_the_reserve() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    let borrower = borrower_with_collateral(&mut env, &sol, 1_000_000_000);
+
+    assert_eq!(
+        preview_debt(&mut env, &borrower, &usdc),
+        Err(anchor_error(PreviewDebtError::NoBorrowFound))
+    );
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::program_error::ProgramError;
+use common::*;
+use radiant::events::ReserveReconciled;
+use radiant::instructions::ReconcileReserveError;
+
+fn reconcile(env: &mut TestEnv, reserve: &TestReserve, obligations: &[Pubkey], repair: bool) -> Result<(), ProgramError> {
+    env.process_with_remaining(
+        radiant::accounts::ReconcileReserve {
+            authority: env.authority,
+            lending_market: env.market,
+            reserve: reserve.key,
+        },
+        radiant::instruction::ReconcileReserve { repair },
+        obligations,
+    )
+}
+
+/// Two depositors and one borrower in USDC, with a year of interest pending
+fn reserve_with_positions(env: &mut TestEnv) -> (TestReserve, Vec<Pubkey>) {
+    let usdc = add_usdc(env);
+    let sol = add_sol(env);
+    let first = supply(env, &usdc, 1_000_000_000);
+    let second = supply(env, &usdc, 500_000_000);
+    let borrower = borrower_with_collateral(env, &sol, 100_000_000_000);
+    env.borrow(&borrower, &usdc, 600_000_000).unwrap();
+    env.advance_seconds(365 * 24 * 3600);
+
+    let obligations = [first, second, borrower]
+        .iter()
+        .map(|owner| env.obligation_key(owner, 0))
+        .collect();
+    (usdc, obligations)
+}
+
+#[test]
+fn reconcile_accrues_before_comparing() {
+    let mut env = TestEnv::new();
+    let (usdc, obligations) = reserve_with_positions(&mut env);
+    let borrows_before = env.reserve_state(&usdc).liquidity.total_borrows;
+
+    reconcile(&mut env, &usdc, &obligations, false).unwrap();
+
+    let event: ReserveReconciled = env.event();
+    assert!(event.stored_borrows > borrows_before);
+    assert!(event.computed_borrows.abs_diff(event.stored_borrows) <= 1);
+    assert!(event.computed_deposits <= event.stored_deposits);
+    assert!(event.stored_deposits - event.computed_deposits <= 2);
+    assert_eq!(env.reserve_state(&usdc).liquidity.total_borrows, event.stored_borrows);
+}
+
+#[test]
+fn repair_requires_every_position() {
+    let mut env = TestEnv::new();
+    let (usdc, obligations) = reserve_with_positions(&mut env);
+
+    // Leaving out a depositor would wipe its deposit from the totals
+    assert_error(
+        reconcile(&mut env, &usdc, &obligations[1..], true),
+        ReconcileReserveError::IncompleteObligationSet,
+    );
+    // Leaving out the borrower would wipe its debt
+    assert_error(
+        reconcile(&mut env, &usdc, &obligations[..2], true),
+        ReconcileReserveError::IncompleteObligationSet,
+    );
+    // A partial set is still fine for a read-only comparison
+    reconcile(&mut env, &usdc, &obligations[1..], false).unwrap();
+}
+
+#[test]
+fn repair_restores_drifted_totals() {
+    let mut env = TestEnv::new();
+    let (usdc, obligations) = reserve_with_positions(&mut env);
+    reconcile(&mut env, &usdc, &obligations, false).unwrap();
+    let expected: ReserveReconciled = env.event();
+
+    env.update_reserve(&usdc, |reserve| {
+        reserve.liquidity.total_deposits += 12_345;
+        reserve.liquidity.total_borrows -= 6_789;
+    });
+    reconcile(&mut env, &usdc, &obligations, true).unwrap();
+
+    let liquidity = env.reserve_state(&usdc).liquidity;
+    assert_eq!(liquidity.total_deposits, expected.computed_deposits);
+    assert_eq!(liquidity.total_borrows, expected.computed_borrows);
+}
+
+#[test]
+fn reconcile_rejects_duplicate_obligations() {
+    let mut env = TestEnv::new();
+    let (usdc, obligations) = reserve_with_positions(&mut env);
+
+    let duplicated = [obligations[0], obligations[1], obligations[2], obligations[0]];
+    assert_error(
+        reconcile(&mut env, &usdc, &duplicated, true),
+        ReconcileReserveError::DuplicateObligation,
+    );
+}
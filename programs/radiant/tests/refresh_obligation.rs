@@ -0,0 +1,228 @@
+mod common;
+
+use anchor_lang::prelude::AccountMeta;
+use common::*;
+use radiant::constants::{
+    MAX_OBLIGATION_DEPOSITS, MAX_OBLIGATION_VALUE_USD, MAX_REFRESH_REMAINING_ACCOUNTS, MAX_RESERVE_STALENESS_SLOTS,
+};
+use radiant::events::ObligationRefreshed;
+use radiant::instructions::{InitializeReserveParams, RefreshObligationError, UpdateReserveConfigParams};
+use radiant::oracle::OracleError;
+
+#[test]
+fn emode_applies_when_every_position_shares_the_category() {
+    let mut env = TestEnv::new();
+    let usdc = add_stable(&mut env);
+    let dai = add_stable(&mut env);
+    supply(&mut env, &dai, 1_000_000_000_000);
+
+    let borrower = borrower_with_collateral(&mut env, &usdc, 1_000_000_000);
+    env.borrow(&borrower, &dai, 900_000_000).unwrap();
+    env.refresh(&borrower);
+
+    let obligation = env.obligation_state(&borrower);
+    assert_eq!(obligation.allowed_borrow_value_usd, 950_000_000);
+    assert_eq!(obligation.unhealthy_borrow_value_usd, 970_000_000);
+}
+
+#[test]
+fn mixed_categories_fall_back_to_normal_parameters() {
+    let mut env = TestEnv::new();
+    let usdc = add_stable(&mut env);
+    let dai = add_stable(&mut env);
+    let sol = add_sol(&mut env);
+    supply(&mut env, &dai, 1_000_000_000_000);
+    supply(&mut env, &sol, 1_000_000_000_000);
+
+    let borrower = borrower_with_collateral(&mut env, &usdc, 1_000_000_000);
+    env.borrow(&borrower, &dai, 100_000_000).unwrap();
+    env.refresh(&borrower);
+    assert_eq!(env.obligation_state(&borrower).allowed_borrow_value_usd, 950_000_000);
+
+    // Uncategorized debt drops the whole obligation to normal parameters
+    env.borrow(&borrower, &sol, 100_000_000).unwrap();
+    env.refresh(&borrower);
+    let obligation = env.obligation_state(&borrower);
+    assert_eq!(obligation.allowed_borrow_value_usd, 800_000_000);
+    assert_eq!(obligation.unhealthy_borrow_value_usd, 850_000_000);
+}
+
+#[test]
+fn uncategorized_collateral_disables_emode() {
+    let mut env = TestEnv::new();
+    let usdc = add_stable(&mut env);
+    let dai = add_stable(&mut env);
+    let sol = add_sol(&mut env);
+    supply(&mut env, &dai, 1_000_000_000_000);
+
+    let borrower = borrower_with_collateral(&mut env, &usdc, 1_000_000_000);
+    env.deposit(&borrower, &sol, 1_000_000_000).unwrap();
+    env.refresh(&borrower);
+    env.borrow(&borrower, &dai, 100_000_000).unwrap();
+    env.refresh(&borrower);
+
+    // 1000 USDC at 80% plus 1 SOL ($100) at 75%
+    assert_eq!(env.obligation_state(&borrower).allowed_borrow_value_usd, 875_000_000);
+}
+
+fn add_sol_with_staleness(env: &mut TestEnv, max_oracle_staleness_slots: u32) -> TestReserve {
+    env.add_reserve_with(9, 100_000_000, InitializeReserveParams {
+        max_oracle_staleness_slots: Some(max_oracle_staleness_slots),
+        ..reserve_params(7500, 8000)
+    })
+    .unwrap()
+}
+
+#[test]
+fn short_oracle_tolerance_rejects_a_lagging_price() {
+    let mut env = TestEnv::new();
+    let sol = add_sol_with_staleness(&mut env, 5);
+    let owner = env.create_user();
+    env.deposit(&owner, &sol, 1_000_000_000).unwrap();
+
+    env.advance_slots(6);
+    env.refresh_reserve(&sol).unwrap();
+    assert_error(env.refresh_obligation(&owner), OracleError::StaleOraclePrice);
+
+    // The tolerance is tunable after listing
+    env.update_reserve_config(&sol, UpdateReserveConfigParams {
+        max_oracle_staleness_slots: Some(10),
+        ..update_reserve_params()
+    })
+    .unwrap();
+    env.refresh_obligation(&owner).unwrap();
+}
+
+#[test]
+fn long_oracle_tolerance_accepts_a_slow_feed() {
+    let mut env = TestEnv::new();
+    let sol = add_sol_with_staleness(&mut env, 100);
+    let slow = add_sol_with_staleness(&mut env, 1_000);
+    let owner = env.create_user();
+    env.deposit(&owner, &slow, 1_000_000_000).unwrap();
+    let other = env.create_user();
+    env.deposit(&other, &sol, 1_000_000_000).unwrap();
+
+    // Past the global default, but within the slow feed's tolerance
+    env.advance_slots(200);
+    env.refresh_reserve(&slow).unwrap();
+    env.refresh_obligation(&owner).unwrap();
+
+    env.refresh_reserve(&sol).unwrap();
+    assert_error(env.refresh_obligation(&other), OracleError::StaleOraclePrice);
+}
+
+#[test]
+fn max_size_positions_aggregate_without_wrapping() {
+    let mut env = TestEnv::new();
+    // Whole-token reserves at $1: 10^12 tokens is the largest valued position
+    let reserves: Vec<_> = (0..MAX_OBLIGATION_DEPOSITS).map(|_| env.add_reserve(0, 1_000_000, 5000, 6000)).collect();
+    let owner = env.create_user();
+    for reserve in reserves.iter() {
+        env.deposit(&owner, reserve, 1_000_000_000_000).unwrap();
+    }
+
+    env.refresh(&owner);
+    let obligation = env.obligation_state(&owner);
+    assert_eq!(obligation.deposited_value_usd, MAX_OBLIGATION_VALUE_USD);
+    assert_eq!(obligation.allowed_borrow_value_usd, MAX_OBLIGATION_VALUE_USD / 2);
+
+    // A price rise past the position cap fails the refresh instead of wrapping
+    env.advance_slots(1);
+    env.set_price(&reserves[0], 2_000_000);
+    env.touch_oracles();
+    for reserve in reserves.iter() {
+        env.refresh_reserve(reserve).unwrap();
+    }
+    assert_error(env.refresh_obligation(&owner), RefreshObligationError::ValueTooLarge);
+}
+
+#[test]
+fn refresh_reports_signed_net_equity() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    supply(&mut env, &usdc, 1_000_000_000_000);
+    let borrower = borrower_with_collateral(&mut env, &sol, 10_000_000_000);
+    env.borrow(&borrower, &usdc, 700_000_000).unwrap();
+
+    env.refresh(&borrower);
+    let event: ObligationRefreshed = env.event();
+    assert_eq!(event.net_equity_usd, 300_000_000);
+    assert_eq!(env.obligation_state(&borrower).net_equity_usd(), 300_000_000);
+
+    // Underwater: $500 of SOL against $700 of debt
+    env.advance_slots(1);
+    env.set_price(&sol, 50_000_000);
+    env.refresh(&borrower);
+    let event: ObligationRefreshed = env.event();
+    assert_eq!(event.net_equity_usd, -200_000_000);
+}
+
+#[test]
+fn accrued_borrow_interest_lowers_health() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    supply(&mut env, &usdc, 1_000_000_000);
+    let borrower = borrower_with_collateral(&mut env, &sol, 10_000_000_000);
+    env.borrow(&borrower, &usdc, 700_000_000).unwrap();
+    env.refresh(&borrower);
+    let before = env.obligation_state(&borrower);
+    let index_before = env.reserve_state(&usdc).liquidity.cumulative_borrow_index;
+    assert_eq!(before.borrowed_value_usd, 700_000_000);
+
+    env.advance_seconds(365 * 86_400);
+    env.refresh(&borrower);
+    let after = env.obligation_state(&borrower);
+    let index_after = env.reserve_state(&usdc).liquidity.cumulative_borrow_index;
+    assert!(index_after > index_before);
+
+    // The cached debt value follows the reserve's borrow index
+    let accrued_debt = 700_000_000 * index_after / index_before;
+    assert!(after.borrowed_value_usd.abs_diff(accrued_debt) <= 1);
+    assert_eq!(after.deposited_value_usd, before.deposited_value_usd);
+    assert!(after.calculate_health_factor() < before.calculate_health_factor());
+}
+
+#[test]
+fn accounts_beyond_the_positions_are_rejected() {
+    let mut env = TestEnv::new();
+    let sol = add_sol(&mut env);
+    let owner = borrower_with_collateral(&mut env, &sol, 10_000_000_000);
+    env.touch_oracles();
+    let junk = AccountMeta::new_readonly(sol.key, false);
+
+    // One account past the last position
+    let mut refresh = env.refresh_obligation_instruction(&owner);
+    refresh.accounts.push(junk.clone());
+    assert_error(env.process_transaction(&[refresh]), RefreshObligationError::UnexpectedAccounts);
+
+    // A padded list is rejected before any position is read
+    let mut refresh = env.refresh_obligation_instruction(&owner);
+    let positions = refresh.accounts.len() - 2;
+    refresh.accounts.extend(std::iter::repeat_n(junk, MAX_REFRESH_REMAINING_ACCOUNTS + 1 - positions));
+    assert_error(env.process_transaction(&[refresh]), RefreshObligationError::TooManyAccounts);
+
+    let refresh = env.refresh_obligation_instruction(&owner);
+    env.process_transaction(&[refresh]).unwrap();
+}
+
+#[test]
+fn refresh_rejects_a_stale_reserve() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    supply(&mut env, &usdc, 1_000_000_000);
+    let owner = borrower_with_collateral(&mut env, &sol, 10_000_000_000);
+    env.borrow(&owner, &usdc, 100_000_000).unwrap();
+
+    // The USDC borrow index has not moved for longer than the tolerance
+    env.advance_slots(MAX_RESERVE_STALENESS_SLOTS + 1);
+    env.touch_oracles();
+    env.refresh_reserve(&sol).unwrap();
+    assert_error(env.refresh_obligation(&owner), RefreshObligationError::ReserveStale);
+
+    env.refresh_reserve(&usdc).unwrap();
+    env.refresh_obligation(&owner).unwrap();
+}
@@ -0,0 +1,363 @@
+mod common;
+
+use common::*;
+use radiant::constants::{INDEX_ONE, MAX_NEGATIVE_SUPPLY_RATE_BPS};
+use radiant::events::{BorrowsWithoutDeposits, RepayEvent};
+use radiant::instructions::{
+    InitializeReserveParams,
+    InterestRateConfigParams,
+    UpdateConfigError,
+    UpdateReserveConfigParams,
+};
+use radiant::state::InterestModel;
+
+const SECONDS_PER_MONTH: i64 = 365 * 86_400 / 12;
+
+#[test]
+fn debt_compounds_across_refreshes() {
+    let mut env = TestEnv::new();
+    let monthly = add_usdc(&mut env);
+    let yearly = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    supply(&mut env, &monthly, 1_000_000_000);
+    supply(&mut env, &yearly, 1_000_000_000);
+
+    // The same 50% utilization in both reserves
+    let borrower = borrower_with_collateral(&mut env, &sol, 100_000_000_000);
+    env.borrow(&borrower, &monthly, 500_000_000).unwrap();
+    env.borrow(&borrower, &yearly, 500_000_000).unwrap();
+    let rate_bps = env.reserve_state(&yearly).liquidity.current_borrow_rate_bps;
+    assert!(rate_bps > 0);
+
+    for _ in 0..12 {
+        env.advance_seconds(SECONDS_PER_MONTH);
+        env.touch_oracles();
+        env.refresh_reserve(&monthly).unwrap();
+    }
+    env.refresh_reserve(&yearly).unwrap();
+
+    // A single refresh accrues one year of simple interest on the index
+    let yearly_debt = env.reserve_state(&yearly).liquidity.total_borrows;
+    let simple_interest = 500_000_000 * rate_bps / 10_000;
+    assert!(yearly_debt.abs_diff(500_000_000 + simple_interest) <= 1);
+
+    // Monthly refreshes compound interest on interest
+    let monthly_debt = env.reserve_state(&monthly).liquidity.total_borrows;
+    assert!(monthly_debt > yearly_debt);
+}
+
+/// USDC reserve with a flat 10% borrow rate under `interest_model`
+fn flat_rate_usdc(env: &mut TestEnv, interest_model: InterestModel) -> TestReserve {
+    env.add_reserve_with(6, 1_000_000, InitializeReserveParams {
+        interest_rate_config: Some(InterestRateConfigParams {
+            optimal_utilization_bps: 8000,
+            base_rate_bps: 1000,
+            slope1_bps: 0,
+            slope2_bps: 0,
+            reserve_factor_bps: 0,
+            low_util_threshold_bps: 0,
+            low_util_supply_subsidy_bps: 0,
+            extra_kinks: None,
+        }),
+        interest_model: Some(interest_model),
+        ..reserve_params(8000, 8500)
+    })
+    .expect("initialize_reserve")
+}
+
+#[test]
+fn simple_interest_accrues_on_principal_only() {
+    let mut env = TestEnv::new();
+    let simple = flat_rate_usdc(&mut env, InterestModel::Simple);
+    let compound = flat_rate_usdc(&mut env, InterestModel::Compound);
+    let sol = add_sol(&mut env);
+    supply(&mut env, &simple, 1_000_000_000);
+    supply(&mut env, &compound, 1_000_000_000);
+
+    let borrower = borrower_with_collateral(&mut env, &sol, 100_000_000_000);
+    env.borrow(&borrower, &simple, 500_000_000).unwrap();
+    env.borrow(&borrower, &compound, 500_000_000).unwrap();
+
+    for _ in 0..12 {
+        env.advance_seconds(SECONDS_PER_MONTH);
+        env.touch_oracles();
+        env.refresh_reserve(&simple).unwrap();
+        env.refresh_reserve(&compound).unwrap();
+    }
+
+    // A year at 10% on the principal, however often the reserve refreshed
+    let simple_total = env.reserve_state(&simple).liquidity.total_borrows;
+    assert!(simple_total.abs_diff(550_000_000) <= 1);
+    assert_eq!(env.reserve_state(&simple).total_borrow_principal, 500_000_000);
+
+    env.repay(&borrower, &borrower, &simple, 0, false).unwrap();
+    let simple_debt: RepayEvent = env.event();
+    assert!(simple_debt.amount.abs_diff(550_000_000) <= 1);
+    assert_eq!(simple_debt.principal_repaid, 500_000_000);
+    assert!(simple_debt.amount.abs_diff(simple_total) <= 1);
+    assert_eq!(env.reserve_state(&simple).total_borrow_principal, 0);
+
+    // Compounding monthly charges interest on interest
+    env.repay(&borrower, &borrower, &compound, 0, false).unwrap();
+    let compound_debt: RepayEvent = env.event();
+    assert!(compound_debt.amount > simple_debt.amount + 2_000_000);
+}
+
+/// Two USDC reserves with 1000 USDC supplied each, the first subsidizing
+/// depositors by up to 1% below 50% utilization
+fn subsidized_and_plain_usdc(env: &mut TestEnv) -> (TestReserve, TestReserve) {
+    let subsidized = add_usdc(env);
+    let plain = add_usdc(env);
+    env.update_reserve_config(&subsidized, UpdateReserveConfigParams {
+        low_util_threshold_bps: Some(5000),
+        low_util_supply_subsidy_bps: Some(100),
+        ..update_reserve_params()
+    })
+    .unwrap();
+    supply(env, &subsidized, 1_000_000_000);
+    supply(env, &plain, 1_000_000_000);
+    (subsidized, plain)
+}
+
+/// Accrue a year of interest in a single refresh of each reserve
+fn accrue_a_year(env: &mut TestEnv, reserves: &[&TestReserve]) {
+    env.advance_seconds(12 * SECONDS_PER_MONTH);
+    for reserve in reserves {
+        env.refresh_reserve(reserve).unwrap();
+    }
+}
+
+#[test]
+fn low_utilization_subsidy_moves_fees_to_depositors() {
+    let mut env = TestEnv::new();
+    let (subsidized, plain) = subsidized_and_plain_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    let borrower = borrower_with_collateral(&mut env, &sol, 100_000_000_000);
+    env.borrow(&borrower, &subsidized, 200_000_000).unwrap();
+    env.borrow(&borrower, &plain, 200_000_000).unwrap();
+
+    accrue_a_year(&mut env, &[&subsidized, &plain]);
+
+    let subsidized = env.reserve_state(&subsidized).liquidity;
+    let plain = env.reserve_state(&plain).liquidity;
+    assert!(subsidized.cumulative_supply_index > plain.cumulative_supply_index);
+    assert!(subsidized.accumulated_protocol_fees < plain.accumulated_protocol_fees);
+
+    // The subsidy only moves fees: nothing is created
+    let subsidized_total = subsidized.total_deposits + subsidized.accumulated_protocol_fees;
+    let plain_total = plain.total_deposits + plain.accumulated_protocol_fees;
+    assert!(subsidized_total.abs_diff(plain_total) <= 1);
+}
+
+#[test]
+fn no_subsidy_above_the_utilization_threshold() {
+    let mut env = TestEnv::new();
+    let (subsidized, plain) = subsidized_and_plain_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    let borrower = borrower_with_collateral(&mut env, &sol, 100_000_000_000);
+    env.borrow(&borrower, &subsidized, 600_000_000).unwrap();
+    env.borrow(&borrower, &plain, 600_000_000).unwrap();
+
+    accrue_a_year(&mut env, &[&subsidized, &plain]);
+
+    let subsidized = env.reserve_state(&subsidized).liquidity;
+    let plain = env.reserve_state(&plain).liquidity;
+    assert_eq!(subsidized.cumulative_supply_index, plain.cumulative_supply_index);
+    assert_eq!(subsidized.accumulated_protocol_fees, plain.accumulated_protocol_fees);
+}
+
+#[test]
+fn subsidy_never_exceeds_the_fees_earned() {
+    let mut env = TestEnv::new();
+    let subsidized = add_usdc(&mut env);
+    env.update_reserve_config(&subsidized, UpdateReserveConfigParams {
+        low_util_threshold_bps: Some(5000),
+        low_util_supply_subsidy_bps: Some(5000),
+        ..update_reserve_params()
+    })
+    .unwrap();
+    supply(&mut env, &subsidized, 1_000_000_000);
+    let sol = add_sol(&mut env);
+    let borrower = borrower_with_collateral(&mut env, &sol, 100_000_000_000);
+    env.borrow(&borrower, &subsidized, 200_000_000).unwrap();
+
+    // With no fees banked, a 50% subsidy can only pay out this year's fees
+    env.update_reserve(&subsidized, |reserve| reserve.liquidity.accumulated_protocol_fees = 0);
+    let before = env.reserve_state(&subsidized).liquidity;
+    env.advance_seconds(12 * SECONDS_PER_MONTH);
+    env.refresh_reserve(&subsidized).unwrap();
+    let after = env.reserve_state(&subsidized).liquidity;
+
+    // Depositors never earn more than borrowers pay
+    let interest_paid = after.total_borrows - before.total_borrows;
+    let depositor_earnings = after.total_deposits - before.total_deposits;
+    assert!(depositor_earnings > 0);
+    assert!(depositor_earnings + after.accumulated_protocol_fees <= interest_paid + 1);
+}
+
+/// A USDC reserve whose borrow rate starts at 20% APR
+fn expensive_usdc(env: &mut TestEnv) -> TestReserve {
+    let usdc = add_usdc(env);
+    env.update_reserve_config(&usdc, UpdateReserveConfigParams {
+        base_rate_bps: Some(2000),
+        ..update_reserve_params()
+    })
+    .unwrap();
+    usdc
+}
+
+#[test]
+fn a_long_gap_books_at_most_the_capped_interest() {
+    let mut env = TestEnv::new();
+    let frequent = expensive_usdc(&mut env);
+    let lazy = expensive_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    supply(&mut env, &frequent, 1_000_000_000);
+    supply(&mut env, &lazy, 1_000_000_000);
+    let borrower = borrower_with_collateral(&mut env, &sol, 100_000_000_000);
+    env.borrow(&borrower, &frequent, 500_000_000).unwrap();
+    env.borrow(&borrower, &lazy, 500_000_000).unwrap();
+    assert!(env.reserve_state(&lazy).liquidity.current_borrow_rate_bps > 2000);
+
+    for _ in 0..12 {
+        env.advance_seconds(SECONDS_PER_MONTH);
+        env.touch_oracles();
+        env.refresh_reserve(&frequent).unwrap();
+    }
+    env.refresh_reserve(&lazy).unwrap();
+
+    // A year of simple interest would exceed 20%; one refresh books 10%
+    assert_eq!(env.reserve_state(&lazy).liquidity.total_borrows, 550_000_000);
+    assert!(env.reserve_state(&frequent).liquidity.total_borrows > 600_000_000);
+}
+
+#[test]
+fn negative_supply_rate_shrinks_the_index_but_never_below_one() {
+    let mut env = TestEnv::new();
+    let negative = add_usdc(&mut env);
+    let plain = add_usdc(&mut env);
+    assert_error(
+        env.update_reserve_config(&negative, UpdateReserveConfigParams {
+            negative_supply_rate_bps: Some(MAX_NEGATIVE_SUPPLY_RATE_BPS + 1),
+            ..update_reserve_params()
+        }),
+        UpdateConfigError::InvalidNegativeSupplyRate,
+    );
+    env.update_reserve_config(&negative, UpdateReserveConfigParams {
+        negative_supply_rate_bps: Some(MAX_NEGATIVE_SUPPLY_RATE_BPS),
+        ..update_reserve_params()
+    })
+    .unwrap();
+    let grown_index = INDEX_ONE * 105 / 100;
+    for reserve in [&negative, &plain] {
+        supply(&mut env, reserve, 1_000_000_000);
+        env.update_reserve(reserve, |reserve| reserve.liquidity.cumulative_supply_index = grown_index);
+    }
+
+    // A month at -10% charges depositors into protocol fees
+    env.advance_seconds(SECONDS_PER_MONTH);
+    env.refresh_reserve(&negative).unwrap();
+    env.refresh_reserve(&plain).unwrap();
+    let liquidity = env.reserve_state(&negative).liquidity;
+    assert!(liquidity.cumulative_supply_index < grown_index);
+    assert!(liquidity.cumulative_supply_index > INDEX_ONE);
+    assert_eq!(liquidity.total_deposits + liquidity.accumulated_protocol_fees, 1_000_000_000);
+    assert_eq!(env.reserve_state(&plain).liquidity.cumulative_supply_index, grown_index);
+
+    // A year later the index is clamped at one and stays there
+    for _ in 0..2 {
+        env.advance_seconds(12 * SECONDS_PER_MONTH);
+        env.refresh_reserve(&negative).unwrap();
+        assert_eq!(env.reserve_state(&negative).liquidity.cumulative_supply_index, INDEX_ONE);
+    }
+}
+
+#[test]
+fn borrows_without_deposits_read_as_full_utilization() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    env.update_reserve(&usdc, |reserve| reserve.liquidity.total_borrows = 1_000_000);
+
+    env.advance_slots(1);
+    env.refresh_reserve(&usdc).unwrap();
+    let event = env.event::<BorrowsWithoutDeposits>();
+    assert_eq!(event.reserve, usdc.key);
+    assert!(event.total_borrows >= 1_000_000);
+
+    let reserve = env.reserve_state(&usdc);
+    assert_eq!(reserve.calculate_utilization_bps(), 10_000);
+    assert_eq!(
+        reserve.liquidity.current_borrow_rate_bps,
+        reserve.config.interest_rate_config.calculate_borrow_rate(10_000)
+    );
+}
+
+#[test]
+fn tiny_borrows_accrue_the_same_through_frequent_refreshes() {
+    let mut env = TestEnv::new();
+    let frequent = add_usdc(&mut env);
+    let lazy = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    supply(&mut env, &frequent, 1_000_000);
+    supply(&mut env, &lazy, 1_000_000);
+    let borrower = borrower_with_collateral(&mut env, &sol, 10_000_000_000);
+    env.borrow(&borrower, &frequent, 1_000).unwrap();
+    env.borrow(&borrower, &lazy, 1_000).unwrap();
+
+    // Each daily period earns well under one unit
+    for _ in 0..365 {
+        env.advance_seconds(86_400);
+        env.touch_oracles();
+        env.refresh_reserve(&frequent).unwrap();
+    }
+    env.refresh(&borrower);
+
+    let obligation = env.obligation_state(&borrower);
+    let mut outcomes = Vec::new();
+    for (reserve, borrow) in [(&frequent, &obligation.borrows[0]), (&lazy, &obligation.borrows[1])] {
+        let liquidity = env.reserve_state(reserve).liquidity;
+        let debt = (borrow.borrowed_amount as u128 * liquidity.cumulative_borrow_index)
+            .div_ceil(borrow.borrow_index_snapshot) as u64;
+        let interest = liquidity.total_borrows - 1_000;
+        let depositor_share = (liquidity.total_deposits as u128 * liquidity.cumulative_supply_index / INDEX_ONE) as u64
+            - liquidity.total_deposits;
+
+        // Sub-unit interest is carried, and the reserve books what the borrower owes
+        assert!(interest > 0);
+        assert!(debt.abs_diff(liquidity.total_borrows) <= 1);
+        // Depositors earn their share rather than losing it all to per-period rounding
+        assert!(depositor_share > 0);
+        assert!(liquidity.accumulated_protocol_fees + depositor_share <= interest);
+        outcomes.push((interest, liquidity.accumulated_protocol_fees, depositor_share));
+    }
+    assert_eq!(outcomes[0], outcomes[1]);
+}
+
+#[test]
+fn total_deposits_track_what_depositors_can_withdraw() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    let depositor = supply(&mut env, &usdc, 1_000_000_000);
+    let borrower = borrower_with_collateral(&mut env, &sol, 100_000_000_000);
+    env.borrow(&borrower, &usdc, 500_000_000).unwrap();
+
+    env.advance_seconds(365 * 86_400);
+    env.touch_oracles();
+    env.refresh_reserve(&usdc).unwrap();
+    let reserve = env.reserve_state(&usdc);
+    let supply_interest = reserve.liquidity.total_borrows - 500_000_000 - reserve.liquidity.accumulated_protocol_fees;
+    assert_eq!(reserve.liquidity.total_deposits, 1_000_000_000 + supply_interest);
+    // Utilization is measured against the grown deposits
+    assert_eq!(
+        reserve.calculate_utilization_bps(),
+        reserve.liquidity.total_borrows * 10000 / reserve.liquidity.total_deposits
+    );
+
+    // The sole depositor withdraws principal plus interest without underflowing the total
+    env.repay(&borrower, &borrower, &usdc, 0, false).unwrap();
+    env.refresh(&depositor);
+    env.withdraw(&depositor, &usdc, 0).unwrap();
+    assert_eq!(env.balance_of(&depositor, &usdc), 1_000_000_000 + supply_interest);
+    assert_eq!(env.reserve_state(&usdc).liquidity.total_deposits, 0);
+}
@@ -0,0 +1,160 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use radiant::events::RepayEvent;
+use radiant::instructions::RepayError;
+
+const SECONDS_PER_YEAR: i64 = 365 * 86_400;
+
+/// Borrower owing 500 USDC of principal plus a year of interest
+fn borrower_with_accrued_interest(env: &mut TestEnv) -> (TestReserve, Pubkey) {
+    let usdc = add_usdc(env);
+    let sol = add_sol(env);
+    supply(env, &usdc, 1_000_000_000);
+    let borrower = borrower_with_collateral(env, &sol, 100_000_000_000);
+    env.borrow(&borrower, &usdc, 500_000_000).unwrap();
+
+    env.advance_seconds(SECONDS_PER_YEAR);
+    env.touch_oracles();
+    env.refresh_reserve(&usdc).unwrap();
+    (usdc, borrower)
+}
+
+#[test]
+fn repayment_covers_interest_before_principal() {
+    let mut env = TestEnv::new();
+    let (usdc, borrower) = borrower_with_accrued_interest(&mut env);
+    let debt = env.reserve_state(&usdc).liquidity.total_borrows;
+    let interest = debt - 500_000_000;
+    assert!(interest > 1_000_000);
+
+    env.repay(&borrower, &borrower, &usdc, 1_000_000, false).unwrap();
+    let event: RepayEvent = env.event();
+    assert_eq!(event.amount, 1_000_000);
+    assert_eq!(event.interest_repaid, 1_000_000);
+    assert_eq!(event.principal_repaid, 0);
+    assert_eq!(env.obligation_state(&borrower).borrows[0].principal, 500_000_000);
+
+    // Crossing from interest into principal
+    env.repay(&borrower, &borrower, &usdc, interest, false).unwrap();
+    let event: RepayEvent = env.event();
+    assert_eq!(event.interest_repaid + event.principal_repaid, event.amount);
+    assert_eq!(event.interest_repaid, interest - 1_000_000);
+    assert_eq!(event.principal_repaid, 1_000_000);
+    assert_eq!(env.obligation_state(&borrower).borrows[0].principal, 499_000_000);
+}
+
+#[test]
+fn full_repayment_splits_the_whole_debt() {
+    let mut env = TestEnv::new();
+    let (usdc, borrower) = borrower_with_accrued_interest(&mut env);
+
+    env.repay(&borrower, &borrower, &usdc, 0, false).unwrap();
+    let event: RepayEvent = env.event();
+    assert_eq!(event.remaining_borrow, 0);
+    assert_eq!(event.principal_repaid, 500_000_000);
+    assert_eq!(event.interest_repaid + event.principal_repaid, event.amount);
+    assert!(env.obligation_state(&borrower).borrows.is_empty());
+}
+
+/// Borrower owing 100 USDC with no deposits left, as after a liquidation
+/// seized all of its collateral
+fn borrower_without_collateral(env: &mut TestEnv) -> (TestReserve, Pubkey) {
+    let usdc = add_usdc(env);
+    let sol = add_sol(env);
+    supply(env, &usdc, 1_000_000_000);
+    let borrower = borrower_with_collateral(env, &sol, 10_000_000_000);
+    env.borrow(&borrower, &usdc, 100_000_000).unwrap();
+    env.update_obligation(&borrower, |obligation| obligation.deposits.clear());
+    (usdc, borrower)
+}
+
+#[test]
+fn owner_repay_can_close_the_emptied_obligation() {
+    let mut env = TestEnv::new();
+    let (usdc, borrower) = borrower_without_collateral(&mut env);
+    let key = env.obligation_key(&borrower, 0);
+    let rent = env.account(&key).unwrap().lamports;
+    let owner_lamports = env.account(&borrower).unwrap().lamports;
+
+    env.repay(&borrower, &borrower, &usdc, 0, true).unwrap();
+
+    assert_eq!(env.account(&key).unwrap().lamports, 0);
+    assert_eq!(env.account(&borrower).unwrap().lamports, owner_lamports + rent);
+}
+
+#[test]
+fn closing_on_repay_requires_the_owner() {
+    let mut env = TestEnv::new();
+    let (usdc, borrower) = borrower_without_collateral(&mut env);
+    let helper = env.create_user();
+
+    assert_error(env.repay(&helper, &borrower, &usdc, 0, true), RepayError::CloseRequiresOwner);
+
+    // Without the flag a third party may clear the debt; the account stays
+    env.repay(&helper, &borrower, &usdc, 0, false).unwrap();
+    let obligation = env.obligation_state(&borrower);
+    assert!(obligation.borrows.is_empty() && obligation.deposits.is_empty());
+}
+
+#[test]
+fn partial_repay_keeps_the_obligation_open() {
+    let mut env = TestEnv::new();
+    let (usdc, borrower) = borrower_without_collateral(&mut env);
+
+    env.repay(&borrower, &borrower, &usdc, 50_000_000, true).unwrap();
+    assert_eq!(env.obligation_state(&borrower).borrows.len(), 1);
+}
+
+#[test]
+fn unpaid_interest_keeps_compounding_but_stays_out_of_principal() {
+    let mut env = TestEnv::new();
+    let (usdc, borrower) = borrower_with_accrued_interest(&mut env);
+    let interest = env.reserve_state(&usdc).liquidity.total_borrows - 500_000_000;
+
+    // Pay half the interest: the rest stays owed, but not as principal
+    env.repay(&borrower, &borrower, &usdc, interest / 2, false).unwrap();
+    let borrow = env.obligation_state(&borrower).borrows[0];
+    let remaining = borrow.borrowed_amount;
+    assert_eq!(borrow.principal, 500_000_000);
+    assert_eq!(remaining, 500_000_000 + interest - interest / 2);
+
+    // The next year's interest accrues on the whole remaining debt
+    env.advance_seconds(SECONDS_PER_YEAR);
+    env.touch_oracles();
+    env.refresh_reserve(&usdc).unwrap();
+    let index = env.reserve_state(&usdc).liquidity.cumulative_borrow_index;
+    let expected = (remaining as u128 * index / borrow.borrow_index_snapshot) as u64;
+
+    env.repay(&borrower, &borrower, &usdc, 0, false).unwrap();
+    let event: RepayEvent = env.event();
+    assert_eq!(event.amount, expected);
+    assert_eq!(event.principal_repaid, 500_000_000);
+    assert_eq!(event.interest_repaid, expected - 500_000_000);
+}
+
+#[test]
+fn repaying_the_wrong_reserve_reports_the_borrowed_ones() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    supply(&mut env, &usdc, 1_000_000_000);
+    let borrower = borrower_with_collateral(&mut env, &sol, 10_000_000_000);
+    env.borrow(&borrower, &usdc, 100_000_000).unwrap();
+
+    // SOL is the obligation's deposit, not a borrow
+    assert_error(
+        env.repay(&borrower, &borrower, &sol, 1_000_000, false),
+        RepayError::NoBorrowFound,
+    );
+    assert_eq!(env.returned::<Vec<Pubkey>>(), vec![usdc.key]);
+
+    // An obligation without borrows reports an empty list
+    let depositor = borrower_with_collateral(&mut env, &sol, 1_000_000_000);
+    assert_error(
+        env.repay(&depositor, &depositor, &usdc, 1_000_000, false),
+        RepayError::NoBorrowFound,
+    );
+    assert!(env.returned::<Vec<Pubkey>>().is_empty());
+}
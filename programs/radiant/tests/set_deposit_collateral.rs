@@ -0,0 +1,108 @@
+mod common;
+
+use common::*;
+use radiant::instructions::{BorrowError, LiquidateError, SetDepositCollateralError};
+
+struct Setup {
+    env: TestEnv,
+    usdc: TestReserve,
+    sol: TestReserve,
+    dai: TestReserve,
+    borrower: anchor_lang::prelude::Pubkey,
+}
+
+/// Borrower with 10 SOL ($1000, 75% LTV) and 1000 DAI ($1000, 50% LTV)
+fn setup() -> Setup {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    let dai = env.add_reserve(6, 1_000_000, 5000, 6000);
+    supply(&mut env, &usdc, 10_000_000_000);
+
+    let borrower = env.create_user();
+    env.deposit(&borrower, &sol, 10_000_000_000).unwrap();
+    env.deposit(&borrower, &dai, 1_000_000_000).unwrap();
+    env.refresh(&borrower);
+
+    Setup { env, usdc, sol, dai, borrower }
+}
+
+#[test]
+fn disabling_without_debt_removes_contribution_and_blocks_borrows() {
+    let Setup { mut env, usdc, sol, dai, borrower } = setup();
+    env.set_deposit_collateral(&borrower, &dai, false).unwrap();
+
+    // Only the SOL deposit still counts: $750 allowed, $800 threshold value
+    let obligation = env.obligation_state(&borrower);
+    assert_eq!(obligation.allowed_borrow_value_usd, 750_000_000);
+    assert_eq!(obligation.unhealthy_borrow_value_usd, 800_000_000);
+    assert!(!obligation.deposits[obligation.find_deposit(&dai.key).unwrap()].use_as_collateral);
+
+    // Borrows wait for a refresh, which then prices DAI at zero capacity
+    assert_error(env.borrow(&borrower, &usdc, 1_000_000), BorrowError::WithdrawCooldownActive);
+    env.refresh(&borrower);
+    assert_eq!(env.obligation_state(&borrower).allowed_borrow_value_usd, 750_000_000);
+    assert_error(
+        env.borrow(&borrower, &usdc, 760_000_000),
+        BorrowError::InsufficientBorrowingCapacity,
+    );
+
+    env.set_deposit_collateral(&borrower, &sol, false).unwrap();
+    env.refresh(&borrower);
+    assert_eq!(env.obligation_state(&borrower).allowed_borrow_value_usd, 0);
+    assert_error(env.borrow(&borrower, &usdc, 1_000_000), BorrowError::InsufficientBorrowingCapacity);
+}
+
+#[test]
+fn disabling_with_debt_keeps_the_position_covered() {
+    let Setup { mut env, usdc, sol, dai, borrower } = setup();
+    env.borrow(&borrower, &usdc, 400_000_000).unwrap();
+    env.refresh(&borrower);
+
+    // SOL alone would leave $500 allowed against $400 of debt
+    env.set_deposit_collateral(&borrower, &sol, false).unwrap();
+    assert_eq!(env.obligation_state(&borrower).allowed_borrow_value_usd, 500_000_000);
+
+    // Nothing would be left to back the debt
+    assert_error(
+        env.set_deposit_collateral(&borrower, &dai, false),
+        SetDepositCollateralError::InsufficientBorrowCapacity,
+    );
+}
+
+#[test]
+fn disabling_with_debt_requires_a_fresh_obligation() {
+    let Setup { mut env, usdc, sol, borrower, .. } = setup();
+    env.borrow(&borrower, &usdc, 100_000_000).unwrap();
+
+    env.advance_slots(200);
+    assert_error(
+        env.set_deposit_collateral(&borrower, &sol, false),
+        SetDepositCollateralError::ObligationStale,
+    );
+
+    env.refresh(&borrower);
+    env.set_deposit_collateral(&borrower, &sol, false).unwrap();
+}
+
+#[test]
+fn non_collateral_deposit_cannot_be_seized() {
+    let Setup { mut env, usdc, sol, dai, borrower } = setup();
+    env.set_deposit_collateral(&borrower, &sol, false).unwrap();
+    env.refresh(&borrower);
+    env.borrow(&borrower, &usdc, 450_000_000).unwrap();
+
+    // DAI depegs to $0.70: $420 of threshold value against $450 of debt
+    env.set_price(&dai, 700_000);
+    env.refresh(&borrower);
+    assert!(env.obligation_state(&borrower).is_liquidatable());
+
+    let liquidator = env.create_user();
+    assert_error(
+        env.liquidate(&liquidator, &borrower, &usdc, &sol, 100_000_000),
+        LiquidateError::NotCollateral,
+    );
+    env.liquidate(&liquidator, &borrower, &usdc, &dai, 100_000_000).unwrap();
+    assert!(env.balance_of(&liquidator, &dai) > 0);
+    assert_eq!(env.balance_of(&liquidator, &sol), 0);
+}
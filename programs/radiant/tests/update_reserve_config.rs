@@ -0,0 +1,186 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use radiant::instructions::{BorrowError, UpdateConfigError, UpdateLendingMarketParams, UpdateReserveConfigParams};
+
+/// USDC reserve with 1000 supplied and 400 borrowed
+fn usdc_in_use(env: &mut TestEnv) -> (TestReserve, Pubkey) {
+    let usdc = add_usdc(env);
+    let sol = add_sol(env);
+    supply(env, &usdc, 1_000_000_000);
+    let borrower = borrower_with_collateral(env, &sol, 100_000_000_000);
+    env.borrow(&borrower, &usdc, 400_000_000).unwrap();
+    (usdc, borrower)
+}
+
+#[test]
+fn limits_below_current_usage_are_rejected() {
+    let mut env = TestEnv::new();
+    let (usdc, _) = usdc_in_use(&mut env);
+
+    assert_error(
+        env.update_reserve_config(&usdc, UpdateReserveConfigParams {
+            borrow_limit: Some(300_000_000),
+            ..update_reserve_params()
+        }),
+        UpdateConfigError::LimitBelowCurrentUsage,
+    );
+    assert_error(
+        env.update_reserve_config(&usdc, UpdateReserveConfigParams {
+            deposit_limit: Some(900_000_000),
+            ..update_reserve_params()
+        }),
+        UpdateConfigError::LimitBelowCurrentUsage,
+    );
+
+    // Limits at current usage are not violated
+    env.update_reserve_config(&usdc, UpdateReserveConfigParams {
+        deposit_limit: Some(1_000_000_000),
+        borrow_limit: Some(400_000_000),
+        ..update_reserve_params()
+    })
+    .unwrap();
+}
+
+#[test]
+fn forced_limits_apply_below_current_usage() {
+    let mut env = TestEnv::new();
+    let (usdc, borrower) = usdc_in_use(&mut env);
+
+    env.update_reserve_config(&usdc, UpdateReserveConfigParams {
+        borrow_limit: Some(300_000_000),
+        force_limits: Some(true),
+        ..update_reserve_params()
+    })
+    .unwrap();
+    assert_eq!(env.reserve_state(&usdc).config.borrow_limit, 300_000_000);
+
+    // New borrows wait until usage falls under the limit; repaying still works
+    assert_error(env.borrow(&borrower, &usdc, 1_000_000), BorrowError::BorrowLimitExceeded);
+    env.repay(&borrower, &borrower, &usdc, 150_000_000, false).unwrap();
+    env.borrow(&borrower, &usdc, 40_000_000).unwrap();
+}
+
+#[test]
+fn reenabling_borrows_can_skip_idle_accrual() {
+    let mut env = TestEnv::new();
+    let reset = add_usdc(&mut env);
+    let retroactive = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    supply(&mut env, &reset, 1_000_000_000);
+    supply(&mut env, &retroactive, 1_000_000_000);
+    let borrower = borrower_with_collateral(&mut env, &sol, 100_000_000_000);
+    env.borrow(&borrower, &reset, 400_000_000).unwrap();
+    env.borrow(&borrower, &retroactive, 400_000_000).unwrap();
+
+    for (reserve, reset_accrual) in [(&reset, true), (&retroactive, false)] {
+        env.update_reserve_config(reserve, UpdateReserveConfigParams {
+            borrows_enabled: Some(false),
+            reset_accrual_on_borrow_enable: Some(reset_accrual),
+            ..update_reserve_params()
+        })
+        .unwrap();
+    }
+    let index_before = env.reserve_state(&reset).liquidity.cumulative_borrow_index;
+
+    // A year idle, then borrows are switched back on
+    env.advance_seconds(365 * 86_400);
+    for reserve in [&reset, &retroactive] {
+        env.update_reserve_config(reserve, UpdateReserveConfigParams {
+            borrows_enabled: Some(true),
+            ..update_reserve_params()
+        })
+        .unwrap();
+        env.refresh_reserve(reserve).unwrap();
+    }
+
+    assert_eq!(env.reserve_state(&reset).liquidity.cumulative_borrow_index, index_before);
+    assert_eq!(env.reserve_state(&reset).last_update_slot, env.slot());
+    assert!(env.reserve_state(&retroactive).liquidity.cumulative_borrow_index > index_before);
+}
+
+#[test]
+fn config_updates_are_rate_limited_outside_emergency_mode() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    env.update_lending_market(UpdateLendingMarketParams {
+        min_config_update_interval_slots: Some(100),
+        ..update_market_params()
+    })
+    .unwrap();
+    let ltv = |ltv_bps| UpdateReserveConfigParams {
+        ltv_bps: Some(ltv_bps),
+        ..update_reserve_params()
+    };
+
+    env.advance_slots(1);
+    env.update_reserve_config(&usdc, ltv(7500)).unwrap();
+    assert_eq!(env.reserve_state(&usdc).last_config_update_slot, env.slot());
+
+    env.advance_slots(99);
+    assert_error(env.update_reserve_config(&usdc, ltv(7000)), UpdateConfigError::ConfigUpdateTooSoon);
+    assert_eq!(env.reserve_state(&usdc).config.ltv_bps, 7500);
+
+    env.set_emergency_mode(true).unwrap();
+    env.update_reserve_config(&usdc, ltv(7000)).unwrap();
+    env.set_emergency_mode(false).unwrap();
+    assert_error(env.update_reserve_config(&usdc, ltv(6500)), UpdateConfigError::ConfigUpdateTooSoon);
+
+    env.advance_slots(100);
+    env.update_reserve_config(&usdc, ltv(6500)).unwrap();
+    assert_eq!(env.reserve_state(&usdc).config.ltv_bps, 6500);
+}
+
+#[test]
+fn flash_loans_default_off_and_toggle_through_config() {
+    let mut env = TestEnv::new();
+    let usdc = add_usdc(&mut env);
+    assert!(!env.reserve_state(&usdc).config.flash_loans_enabled);
+
+    for enabled in [true, false] {
+        env.update_reserve_config(&usdc, UpdateReserveConfigParams {
+            flash_loans_enabled: Some(enabled),
+            ..update_reserve_params()
+        })
+        .unwrap();
+        assert_eq!(env.reserve_state(&usdc).config.flash_loans_enabled, enabled);
+    }
+}
+
+#[test]
+fn rate_changes_settle_the_elapsed_period_at_the_old_rate() {
+    let mut env = TestEnv::new();
+    let changed = add_usdc(&mut env);
+    let settled_first = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    supply(&mut env, &changed, 1_000_000_000);
+    supply(&mut env, &settled_first, 1_000_000_000);
+    let borrower = borrower_with_collateral(&mut env, &sol, 100_000_000_000);
+    env.borrow(&borrower, &changed, 400_000_000).unwrap();
+    env.borrow(&borrower, &settled_first, 400_000_000).unwrap();
+    let raise_rate = UpdateReserveConfigParams {
+        base_rate_bps: Some(2000),
+        ..update_reserve_params()
+    };
+
+    // Half a year passes; one reserve is refreshed before the rate rises
+    env.advance_seconds(182 * 86_400);
+    env.touch_oracles();
+    env.refresh_reserve(&settled_first).unwrap();
+    env.update_reserve_config(&settled_first, raise_rate.clone()).unwrap();
+    env.update_reserve_config(&changed, raise_rate).unwrap();
+
+    let index = |env: &TestEnv, reserve| env.reserve_state(reserve).liquidity.cumulative_borrow_index;
+    let old_rate_index = index(&env, &settled_first);
+    assert_eq!(index(&env, &changed), old_rate_index);
+    assert_eq!(env.reserve_state(&changed).last_update_slot, env.slot());
+
+    // Only the period after the change is charged at the new rate
+    env.advance_seconds(182 * 86_400);
+    env.touch_oracles();
+    env.refresh_reserve(&changed).unwrap();
+    env.refresh_reserve(&settled_first).unwrap();
+    assert_eq!(index(&env, &changed), index(&env, &settled_first));
+    assert!(index(&env, &changed) > old_rate_index);
+}
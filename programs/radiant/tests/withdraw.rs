@@ -0,0 +1,56 @@
+mod common;
+
+use common::*;
+use radiant::instructions::{BorrowError, InitializeReserveParams};
+
+#[test]
+fn withdraw_accrues_interest_up_to_the_current_slot() {
+    let mut env = TestEnv::new();
+    let unrefreshed = add_usdc(&mut env);
+    let refreshed = add_usdc(&mut env);
+    let sol = add_sol(&mut env);
+    supply(&mut env, &unrefreshed, 1_000_000_000);
+    supply(&mut env, &refreshed, 1_000_000_000);
+    let lender = env.create_user();
+    env.deposit(&lender, &unrefreshed, 100_000_000).unwrap();
+    env.deposit(&lender, &refreshed, 100_000_000).unwrap();
+
+    let borrower = borrower_with_collateral(&mut env, &sol, 100_000_000_000);
+    env.borrow(&borrower, &unrefreshed, 500_000_000).unwrap();
+    env.borrow(&borrower, &refreshed, 500_000_000).unwrap();
+
+    // A year later, withdraw from one reserve without refreshing it first
+    env.advance_seconds(365 * 86_400);
+    env.refresh_reserve(&refreshed).unwrap();
+    env.withdraw(&lender, &refreshed, 0).unwrap();
+    env.withdraw(&lender, &unrefreshed, 0).unwrap();
+
+    let expected = env.balance_of(&lender, &refreshed);
+    assert!(expected > 100_000_000);
+    assert_eq!(env.balance_of(&lender, &unrefreshed), expected);
+    assert_eq!(env.reserve_state(&unrefreshed).last_update_slot, env.slot());
+}
+
+#[test]
+fn withdrawals_can_use_the_liquidity_borrows_cannot() {
+    let mut env = TestEnv::new();
+    let usdc = env
+        .add_reserve_with(6, 1_000_000, InitializeReserveParams {
+            withdrawal_reserve_bps: Some(2000),
+            ..reserve_params(8000, 8500)
+        })
+        .unwrap();
+    let sol = add_sol(&mut env);
+    let depositor = supply(&mut env, &usdc, 1_000_000_000);
+    let borrower = borrower_with_collateral(&mut env, &sol, 100_000_000_000);
+
+    // 20% of deposits is held back from borrowers
+    assert_error(env.borrow(&borrower, &usdc, 800_000_001), BorrowError::InsufficientLiquidity);
+    env.borrow(&borrower, &usdc, 800_000_000).unwrap();
+    assert_eq!(env.reserve_state(&usdc).borrowable_liquidity(), 0);
+
+    // ...but the depositor can still take it out
+    env.withdraw(&depositor, &usdc, 200_000_000).unwrap();
+    assert_eq!(env.balance_of(&depositor, &usdc), 200_000_000);
+    assert_eq!(env.token_balance(&usdc.vault), 0);
+}